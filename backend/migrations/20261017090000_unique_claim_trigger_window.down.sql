@@ -0,0 +1,3 @@
+-- Remove trigger window uniqueness from policy_claims
+ALTER TABLE policy_claims
+DROP CONSTRAINT IF EXISTS unique_policy_claim_trigger_window;
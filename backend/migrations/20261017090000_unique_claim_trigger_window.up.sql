@@ -0,0 +1,4 @@
+-- Prevent the claims processor from filing the same trigger window twice
+ALTER TABLE policy_claims
+ADD CONSTRAINT unique_policy_claim_trigger_window
+UNIQUE (policy_id, trigger_period_start, trigger_period_end);
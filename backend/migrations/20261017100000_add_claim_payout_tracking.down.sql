@@ -0,0 +1,7 @@
+-- Remove oracle payout tracking from policy_claims
+DROP INDEX IF EXISTS idx_policy_claims_pending_payout;
+
+ALTER TABLE policy_claims
+DROP COLUMN IF EXISTS payout_last_error,
+DROP COLUMN IF EXISTS payout_submitted_at,
+DROP COLUMN IF EXISTS payout_attempts;
@@ -0,0 +1,9 @@
+-- Track on-chain payout attempts made by the oracle settlement daemon
+ALTER TABLE policy_claims
+ADD COLUMN payout_attempts INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN payout_submitted_at TIMESTAMP NULL,
+ADD COLUMN payout_last_error TEXT NULL;
+
+-- Oracle polls approved claims that have not been paid out yet
+CREATE INDEX idx_policy_claims_pending_payout ON policy_claims(claim_status)
+WHERE payout_block_number IS NULL;
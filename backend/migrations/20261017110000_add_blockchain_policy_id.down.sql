@@ -0,0 +1,6 @@
+-- Remove on-chain policy id from insurance_policies
+ALTER TABLE insurance_policies
+DROP CONSTRAINT IF EXISTS unique_blockchain_policy_id;
+
+ALTER TABLE insurance_policies
+DROP COLUMN IF EXISTS blockchain_policy_id;
@@ -0,0 +1,8 @@
+-- Link each policy to its record in the WeatherInsurance contract
+ALTER TABLE insurance_policies
+ADD COLUMN blockchain_policy_id BIGINT NULL;
+
+-- An on-chain policy can back at most one row per contract
+ALTER TABLE insurance_policies
+ADD CONSTRAINT unique_blockchain_policy_id
+UNIQUE (smart_contract_address, blockchain_policy_id);
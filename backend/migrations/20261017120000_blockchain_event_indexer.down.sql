@@ -0,0 +1,4 @@
+-- Remove blockchain event indexer tables
+DROP TABLE IF EXISTS blockchain_reconciliation_issues;
+DROP TABLE IF EXISTS blockchain_events;
+DROP TABLE IF EXISTS blockchain_indexer_checkpoints;
@@ -0,0 +1,42 @@
+-- Blockchain event indexer: per-contract checkpoint, indexed contract events
+-- and reconciliation issues found while matching them against the database
+
+CREATE TABLE blockchain_indexer_checkpoints (
+    contract_address VARCHAR(42) PRIMARY KEY,
+    last_indexed_block BIGINT NOT NULL,
+    last_block_hash VARCHAR(66) NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE blockchain_events (
+    id SERIAL PRIMARY KEY,
+    contract_address VARCHAR(42) NOT NULL,
+    event_name VARCHAR(50) NOT NULL, -- 'PolicyCreated', 'PolicyTriggered'
+    blockchain_policy_id BIGINT NOT NULL,
+    transaction_hash VARCHAR(66) NOT NULL,
+    log_index INTEGER NOT NULL,
+    block_number BIGINT NOT NULL,
+    block_hash VARCHAR(66) NOT NULL,
+    event_data JSONB NOT NULL, -- Decoded event fields
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_blockchain_event_log UNIQUE (transaction_hash, log_index)
+);
+
+CREATE TABLE blockchain_reconciliation_issues (
+    id SERIAL PRIMARY KEY,
+    event_id INTEGER NOT NULL REFERENCES blockchain_events(id) ON DELETE CASCADE,
+    issue_type VARCHAR(50) NOT NULL, -- 'orphan_policy', 'payout_mismatch', 'unrecorded_payout', ...
+    policy_id INTEGER REFERENCES insurance_policies(id) ON DELETE SET NULL,
+    claim_id INTEGER REFERENCES policy_claims(id) ON DELETE SET NULL,
+    details JSONB,
+    resolved BOOLEAN DEFAULT false,
+    resolved_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT unique_reconciliation_issue UNIQUE (event_id, issue_type)
+);
+
+CREATE INDEX idx_blockchain_events_contract_block ON blockchain_events(contract_address, block_number);
+CREATE INDEX idx_blockchain_events_policy ON blockchain_events(blockchain_policy_id);
+CREATE INDEX idx_blockchain_reconciliation_unresolved ON blockchain_reconciliation_issues(resolved);
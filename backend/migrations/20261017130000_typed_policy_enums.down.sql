@@ -0,0 +1,9 @@
+-- Remove the enum CHECK constraints. Normalised values are left as they are.
+ALTER TABLE policy_conditions DROP CONSTRAINT IF EXISTS policy_conditions_measurement_period_check;
+ALTER TABLE policy_conditions DROP CONSTRAINT IF EXISTS policy_conditions_measurement_unit_check;
+ALTER TABLE policy_conditions DROP CONSTRAINT IF EXISTS policy_conditions_operator_check;
+ALTER TABLE policy_conditions DROP CONSTRAINT IF EXISTS policy_conditions_condition_type_check;
+ALTER TABLE policy_claims DROP CONSTRAINT IF EXISTS policy_claims_claim_status_check;
+ALTER TABLE insurance_policies DROP CONSTRAINT IF EXISTS insurance_policies_status_check;
+ALTER TABLE insurance_policies DROP CONSTRAINT IF EXISTS insurance_policies_policy_type_check;
+ALTER TABLE policy_templates DROP CONSTRAINT IF EXISTS policy_templates_policy_type_check;
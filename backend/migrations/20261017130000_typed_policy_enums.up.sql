@@ -0,0 +1,66 @@
+-- Limit free-form policy, claim and condition columns to the values the
+-- backend's enums understand. Existing rows are normalised to the canonical
+-- spelling first.
+
+UPDATE policy_templates SET policy_type = LOWER(TRIM(policy_type));
+UPDATE insurance_policies SET policy_type = LOWER(TRIM(policy_type)), status = LOWER(TRIM(status));
+UPDATE policy_claims SET claim_status = LOWER(TRIM(claim_status));
+
+UPDATE policy_conditions SET
+    condition_type = CASE LOWER(TRIM(condition_type))
+        WHEN 'precipitation' THEN 'rainfall'
+        WHEN 'temperature' THEN 'temperature_avg'
+        WHEN 'pressure' THEN 'atmospheric_pressure'
+        ELSE LOWER(TRIM(condition_type))
+    END,
+    operator = CASE TRIM(operator)
+        WHEN '=' THEN '=='
+        WHEN '<>' THEN '!='
+        ELSE TRIM(operator)
+    END,
+    measurement_unit = CASE LOWER(TRIM(measurement_unit))
+        WHEN 'inch' THEN 'in'
+        WHEN 'inches' THEN 'in'
+        WHEN 'c' THEN 'celsius'
+        WHEN 'f' THEN 'fahrenheit'
+        WHEN 'kmh' THEN 'km/h'
+        WHEN 'kph' THEN 'km/h'
+        WHEN 'percent' THEN '%'
+        WHEN 'mbar' THEN 'hpa'
+        ELSE LOWER(TRIM(measurement_unit))
+    END,
+    measurement_period = LOWER(TRIM(measurement_period));
+
+ALTER TABLE policy_templates
+ADD CONSTRAINT policy_templates_policy_type_check
+CHECK (policy_type IN ('drought', 'rain', 'temperature', 'freeze', 'heat', 'wind', 'storm'));
+
+ALTER TABLE insurance_policies
+ADD CONSTRAINT insurance_policies_policy_type_check
+CHECK (policy_type IN ('drought', 'rain', 'temperature', 'freeze', 'heat', 'wind', 'storm'));
+
+ALTER TABLE insurance_policies
+ADD CONSTRAINT insurance_policies_status_check
+CHECK (status IN ('active', 'expired', 'claimed', 'cancelled'));
+
+ALTER TABLE policy_claims
+ADD CONSTRAINT policy_claims_claim_status_check
+CHECK (claim_status IN ('pending', 'approved', 'rejected', 'paid'));
+
+ALTER TABLE policy_conditions
+ADD CONSTRAINT policy_conditions_condition_type_check
+CHECK (condition_type IN ('rainfall', 'temperature_min', 'temperature_max', 'temperature_avg',
+                          'wind_speed', 'humidity', 'atmospheric_pressure'));
+
+ALTER TABLE policy_conditions
+ADD CONSTRAINT policy_conditions_operator_check
+CHECK (operator IN ('>', '<', '>=', '<=', '==', '!='));
+
+ALTER TABLE policy_conditions
+ADD CONSTRAINT policy_conditions_measurement_unit_check
+CHECK (measurement_unit IN ('mm', 'cm', 'in', 'celsius', 'fahrenheit', 'km/h', 'mph', 'm/s',
+                            '%', 'hpa'));
+
+ALTER TABLE policy_conditions
+ADD CONSTRAINT policy_conditions_measurement_period_check
+CHECK (measurement_period IN ('daily', 'weekly', 'monthly', 'cumulative'));
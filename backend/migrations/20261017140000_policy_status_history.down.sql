@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS policy_status_history;
+
+UPDATE insurance_policies SET status = 'active' WHERE status = 'pending_verification';
+ALTER TABLE insurance_policies DROP CONSTRAINT IF EXISTS insurance_policies_status_check;
+ALTER TABLE insurance_policies
+ADD CONSTRAINT insurance_policies_status_check
+CHECK (status IN ('active', 'expired', 'claimed', 'cancelled'));
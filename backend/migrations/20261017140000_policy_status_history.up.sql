@@ -0,0 +1,20 @@
+-- Policies that are not yet verified on chain wait in pending_verification.
+ALTER TABLE insurance_policies DROP CONSTRAINT insurance_policies_status_check;
+ALTER TABLE insurance_policies
+ADD CONSTRAINT insurance_policies_status_check
+CHECK (status IN ('pending_verification', 'active', 'expired', 'claimed', 'cancelled'));
+
+-- Every status transition, with who made it and why.
+CREATE TABLE policy_status_history (
+    id SERIAL PRIMARY KEY,
+    policy_id INTEGER NOT NULL REFERENCES insurance_policies(id) ON DELETE CASCADE,
+    from_status VARCHAR(50) NOT NULL
+        CHECK (from_status IN ('pending_verification', 'active', 'expired', 'claimed', 'cancelled')),
+    to_status VARCHAR(50) NOT NULL
+        CHECK (to_status IN ('pending_verification', 'active', 'expired', 'claimed', 'cancelled')),
+    actor VARCHAR(100) NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_policy_status_history_policy_id ON policy_status_history(policy_id, created_at);
@@ -0,0 +1,4 @@
+ALTER TABLE insurance_policies
+DROP COLUMN IF EXISTS refund_transaction_hash,
+DROP COLUMN IF EXISTS refund_amount,
+DROP COLUMN IF EXISTS cancelled_at;
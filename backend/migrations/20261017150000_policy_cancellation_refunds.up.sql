@@ -0,0 +1,7 @@
+-- Refunds for cancelled policies. refund_amount is in the policy currency and
+-- refund_transaction_hash is the contract's cancel transaction, when the
+-- policy exists on chain.
+ALTER TABLE insurance_policies
+ADD COLUMN cancelled_at TIMESTAMP,
+ADD COLUMN refund_amount DECIMAL(30,18),
+ADD COLUMN refund_transaction_hash VARCHAR(66);
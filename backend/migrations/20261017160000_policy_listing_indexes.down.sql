@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_insurance_policies_h3_prefix;
+DROP INDEX IF EXISTS idx_insurance_policies_user_type;
+DROP INDEX IF EXISTS idx_insurance_policies_user_status;
+DROP INDEX IF EXISTS idx_insurance_policies_user_coverage;
+DROP INDEX IF EXISTS idx_insurance_policies_user_end_date;
+DROP INDEX IF EXISTS idx_insurance_policies_user_created;
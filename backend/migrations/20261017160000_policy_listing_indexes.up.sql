@@ -0,0 +1,12 @@
+-- Keyset pagination of a user's policies. Each sort has an index matching its
+-- ORDER BY, with id breaking ties.
+CREATE INDEX idx_insurance_policies_user_created
+    ON insurance_policies(user_id, (COALESCE(created_at, 'epoch'::timestamp)), id);
+CREATE INDEX idx_insurance_policies_user_end_date ON insurance_policies(user_id, end_date, id);
+CREATE INDEX idx_insurance_policies_user_coverage ON insurance_policies(user_id, coverage_amount, id);
+
+-- Filters
+CREATE INDEX idx_insurance_policies_user_status ON insurance_policies(user_id, status);
+CREATE INDEX idx_insurance_policies_user_type ON insurance_policies(user_id, policy_type);
+CREATE INDEX idx_insurance_policies_h3_prefix
+    ON insurance_policies(location_h3_index text_pattern_ops);
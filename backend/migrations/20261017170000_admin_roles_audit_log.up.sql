@@ -0,0 +1,18 @@
+-- Roles gate the admin API. Oracle accounts are for automated services.
+ALTER TABLE users
+ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT 'user'
+CHECK (role IN ('user', 'admin', 'oracle'));
+
+-- Every change made through the admin API, with what it changed.
+CREATE TABLE admin_audit_log (
+    id SERIAL PRIMARY KEY,
+    admin_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    action VARCHAR(100) NOT NULL,
+    target_type VARCHAR(50) NOT NULL,
+    target_id INTEGER NOT NULL,
+    details JSONB,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_admin_audit_log_created_at ON admin_audit_log(created_at);
+CREATE INDEX idx_admin_audit_log_target ON admin_audit_log(target_type, target_id);
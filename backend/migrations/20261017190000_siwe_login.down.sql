@@ -0,0 +1,16 @@
+-- Refuse to revert while accounts without an email or a password exist,
+-- rather than deleting them and the policies they bought
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM users WHERE email IS NULL OR password_hash IS NULL) THEN
+        RAISE EXCEPTION 'Cannot revert Sign-In With Ethereum: % account(s) have no email or password',
+            (SELECT COUNT(*) FROM users WHERE email IS NULL OR password_hash IS NULL);
+    END IF;
+END $$;
+
+DROP TABLE IF EXISTS auth_nonces;
+
+DROP INDEX IF EXISTS idx_users_wallet_address_lower;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_or_wallet;
+ALTER TABLE users ALTER COLUMN password_hash SET NOT NULL;
+ALTER TABLE users ALTER COLUMN email SET NOT NULL;
@@ -0,0 +1,18 @@
+-- Sign-In With Ethereum. Accounts created through it have a wallet address
+-- but neither an email nor a password.
+ALTER TABLE users ALTER COLUMN email DROP NOT NULL;
+ALTER TABLE users ALTER COLUMN password_hash DROP NOT NULL;
+ALTER TABLE users ADD CONSTRAINT users_email_or_wallet
+    CHECK (email IS NOT NULL OR wallet_address IS NOT NULL);
+
+CREATE INDEX idx_users_wallet_address_lower ON users(LOWER(wallet_address));
+
+-- Nonces handed out for SIWE messages. Each can be used for one sign-in.
+CREATE TABLE auth_nonces (
+    nonce VARCHAR(64) PRIMARY KEY,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_auth_nonces_expires_at ON auth_nonces(expires_at);
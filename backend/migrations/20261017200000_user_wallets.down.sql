@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS users_wallet_address_key;
+CREATE INDEX idx_users_wallet_address_lower ON users(LOWER(wallet_address));
+
+DROP TABLE IF EXISTS user_wallets;
@@ -0,0 +1,29 @@
+-- Wallets users have proven they own by signing a challenge. A wallet belongs
+-- to at most one user; users.wallet_address is the user's primary wallet and
+-- is always one of theirs.
+CREATE TABLE user_wallets (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wallet_address VARCHAR(42) NOT NULL,
+    verified_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX user_wallets_wallet_address_key ON user_wallets(LOWER(wallet_address));
+CREATE INDEX idx_user_wallets_user_id ON user_wallets(user_id);
+
+-- Accounts created through Sign-In With Ethereum proved their wallet by
+-- signing in with it
+INSERT INTO user_wallets (user_id, wallet_address, verified_at)
+SELECT id, wallet_address, COALESCE(created_at, CURRENT_TIMESTAMP)
+FROM users
+WHERE email IS NULL AND wallet_address IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+-- Other addresses were set without any proof of ownership, so they have to be
+-- linked again
+UPDATE users SET wallet_address = NULL
+WHERE email IS NOT NULL AND wallet_address IS NOT NULL;
+
+DROP INDEX IF EXISTS idx_users_wallet_address_lower;
+CREATE UNIQUE INDEX users_wallet_address_key ON users(LOWER(wallet_address));
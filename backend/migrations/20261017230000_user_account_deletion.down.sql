@@ -0,0 +1,12 @@
+ALTER TABLE insurance_policies DROP CONSTRAINT insurance_policies_user_id_fkey;
+ALTER TABLE insurance_policies ADD CONSTRAINT insurance_policies_user_id_fkey
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+
+-- Keep deleted accounts, and their policies, with an address nobody owns
+UPDATE users SET email = 'deleted-user-' || id || '@deleted.invalid'
+WHERE email IS NULL AND wallet_address IS NULL;
+ALTER TABLE users DROP CONSTRAINT users_email_or_wallet;
+ALTER TABLE users ADD CONSTRAINT users_email_or_wallet
+    CHECK (email IS NOT NULL OR wallet_address IS NOT NULL);
+
+ALTER TABLE users DROP COLUMN IF EXISTS deleted_at;
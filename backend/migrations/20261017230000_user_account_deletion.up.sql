@@ -0,0 +1,12 @@
+-- Deleted accounts are anonymised rather than removed, so the policies and
+-- claims they bought stay on record. They have neither an email nor a wallet.
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMP;
+
+ALTER TABLE users DROP CONSTRAINT users_email_or_wallet;
+ALTER TABLE users ADD CONSTRAINT users_email_or_wallet
+    CHECK (email IS NOT NULL OR wallet_address IS NOT NULL OR deleted_at IS NOT NULL);
+
+-- Removing a user must never take their insurance history with it
+ALTER TABLE insurance_policies DROP CONSTRAINT insurance_policies_user_id_fkey;
+ALTER TABLE insurance_policies ADD CONSTRAINT insurance_policies_user_id_fkey
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE RESTRICT;
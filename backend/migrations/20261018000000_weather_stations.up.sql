@@ -0,0 +1,12 @@
+-- Where each WeatherXM station is, so quotes can find the stations near a
+-- location before any policy there uses them. h3_index is the resolution 7
+-- cell the station is in, the resolution policy locations are given in.
+CREATE TABLE weather_stations (
+    id VARCHAR(100) PRIMARY KEY,
+    latitude DECIMAL(10,8) NOT NULL,
+    longitude DECIMAL(11,8) NOT NULL,
+    h3_index VARCHAR(20) NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_weather_stations_h3_index ON weather_stations(h3_index);
@@ -0,0 +1,5 @@
+UPDATE policy_templates
+SET default_conditions = default_conditions
+    #- '{conditions,0,min_threshold}'
+    #- '{conditions,0,max_threshold}'
+WHERE default_conditions #> '{conditions,0}' IS NOT NULL;
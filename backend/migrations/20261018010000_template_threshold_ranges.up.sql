@@ -0,0 +1,15 @@
+-- The range policies of the sample templates may move the threshold within.
+-- Conditions without a range keep the template's threshold.
+UPDATE policy_templates
+SET default_conditions = jsonb_set(
+    jsonb_set(default_conditions, '{conditions,0,min_threshold}', to_jsonb(ranges.min_threshold)),
+    '{conditions,0,max_threshold}', to_jsonb(ranges.max_threshold))
+FROM (VALUES
+    ('Drought Protection', 1, 10),
+    ('Rain Event Insurance', 5, 50),
+    ('Freeze Protection', -10, 0),
+    ('Storm Insurance', 40, 120)
+) AS ranges (template_name, min_threshold, max_threshold)
+WHERE policy_templates.template_name = ranges.template_name
+  AND (default_conditions #>> '{conditions,0,threshold}')::numeric
+      BETWEEN ranges.min_threshold AND ranges.max_threshold;
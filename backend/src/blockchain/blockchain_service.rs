@@ -1,7 +1,13 @@
-use crate::blockchain::contract_abi::{BlockchainPolicy, BuyPolicyTransaction, WeatherInsurance};
-use crate::db::models::CreateInsurancePolicyRequest;
+use crate::blockchain::contract_abi::{
+    BlockchainPolicy, BuyPolicyCall, BuyPolicyTransaction, PolicyCreatedFilter, WeatherInsurance,
+};
+use crate::db::models::{CreateInsurancePolicyRequest, CreatePolicyCondition};
+use contract_events::event_type_for_policy_type;
+use ethers::abi::AbiDecode;
 use ethers::prelude::*;
+use ethers::utils::{format_ether, parse_ether};
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -33,13 +39,15 @@ pub struct VerificationResult {
     pub blockchain_policy: Option<BlockchainPolicy>,
 }
 
-// Configuration for blockchain service
-#[derive(Debug, Clone)]
+// Configuration for blockchain service. The owner key is only needed to send
+// refunds, since `cancel` is owner-only. Not Debug, so it is never logged.
+#[derive(Clone)]
 pub struct BlockchainConfig {
     pub rpc_url: String,
     pub contract_address: String,
     pub verification_enabled: bool,
     pub timeout_seconds: u64,
+    pub owner_private_key: Option<String>,
 }
 
 impl Default for BlockchainConfig {
@@ -49,6 +57,7 @@ impl Default for BlockchainConfig {
             contract_address: "".to_string(),
             verification_enabled: true,
             timeout_seconds: 30,
+            owner_private_key: None,
         }
     }
 }
@@ -75,12 +84,18 @@ impl BlockchainService {
         })
     }
 
+    // The contract policies are bought from, formatted as stored in the database
+    pub fn contract_address(&self) -> String {
+        format!("{:?}", self.contract_address)
+    }
+
     // Main verification function - simplified for hackathon
     pub async fn verify_policy_transaction(
         &self,
         tx_hash: &str,
-        user_wallet_address: &str,
+        user_wallet_addresses: &[String],
         policy_request: &CreateInsurancePolicyRequest,
+        conditions: &[CreatePolicyCondition],
     ) -> Result<VerificationResult, BlockchainError> {
         if !self.config.verification_enabled {
             return Ok(VerificationResult {
@@ -91,17 +106,21 @@ impl BlockchainService {
             });
         }
 
-        // Step 1: Verify transaction exists and is confirmed
-        let (tx_receipt, block_number) = self.verify_transaction_confirmed(tx_hash).await?;
+        // Step 1: Verify transaction exists, is confirmed and created a policy
+        let (_tx_receipt, block_number, policy_created) =
+            self.verify_transaction_confirmed(tx_hash).await?;
 
         // Step 2: Get transaction details
         let transaction = self.get_transaction_details(tx_hash).await?;
 
-        // Step 3: Validate user address matches transaction sender
-        let user_addr = Address::from_str(user_wallet_address)
+        // Step 3: Validate transaction sender is one of the user's verified wallets
+        let user_addrs = user_wallet_addresses
+            .iter()
+            .map(|address| Address::from_str(address))
+            .collect::<Result<Vec<_>, _>>()
             .map_err(|e| BlockchainError::ParseError(format!("Invalid user address: {}", e)))?;
 
-        if transaction.from != user_addr {
+        if !user_addrs.contains(&transaction.from) {
             return Ok(VerificationResult {
                 verified: false,
                 block_number: Some(block_number),
@@ -132,21 +151,32 @@ impl BlockchainService {
             });
         }
 
-        // For hackathon: simplified verification passes if basic checks are met
-        // In production, you would decode the full transaction parameters
+        // Step 6: Decode the buyPolicy call and compare it with the request
+        let buy_policy = decode_buy_policy_transaction(&transaction)?;
+        verify_buy_policy_parameters(&buy_policy, policy_request, conditions)?;
+        if !user_addrs.contains(&policy_created.user) {
+            return Err(BlockchainError::ParameterMismatch(format!(
+                "Policy {} was created for {:?}, not the user wallet",
+                policy_created.policy_id, policy_created.user
+            )));
+        }
+
+        // Step 7: Read the on-chain record created by this transaction
+        let blockchain_policy = self.get_blockchain_policy(policy_created.policy_id).await?;
+
         Ok(VerificationResult {
             verified: true,
             block_number: Some(block_number),
             error_message: None,
-            blockchain_policy: None, // Could populate this by querying the contract
+            blockchain_policy: Some(blockchain_policy),
         })
     }
 
-    // Verify transaction exists and is confirmed
+    // Verify transaction exists, is confirmed and emitted PolicyCreated
     async fn verify_transaction_confirmed(
         &self,
         tx_hash: &str,
-    ) -> Result<(TransactionReceipt, u64), BlockchainError> {
+    ) -> Result<(TransactionReceipt, u64, PolicyCreatedFilter), BlockchainError> {
         let tx_hash = H256::from_str(tx_hash)
             .map_err(|e| BlockchainError::ParseError(format!("Invalid transaction hash: {}", e)))?;
 
@@ -173,7 +203,91 @@ impl BlockchainService {
             .ok_or_else(|| BlockchainError::TransactionNotConfirmed)?
             .as_u64();
 
-        Ok((receipt, block_number))
+        let policy_created =
+            parse_policy_created(&receipt, self.contract_address).ok_or_else(|| {
+                BlockchainError::InvalidTransaction(
+                    "Transaction did not emit PolicyCreated".to_string(),
+                )
+            })?;
+
+        Ok((receipt, block_number, policy_created))
+    }
+
+    // Read a policy as it is stored in the contract
+    pub async fn get_blockchain_policy(
+        &self,
+        policy_id: U256,
+    ) -> Result<BlockchainPolicy, BlockchainError> {
+        let contract = WeatherInsurance::new(self.contract_address, self.provider.clone());
+        let (user, payout, start_time, end_time, paid, threshold, event_type, h3_hex_id) =
+            contract.policies(policy_id).call().await.map_err(|e| {
+                BlockchainError::ContractError(format!(
+                    "Failed to read policy {}: {}",
+                    policy_id, e
+                ))
+            })?;
+
+        Ok(BlockchainPolicy {
+            policy_id,
+            user,
+            payout,
+            start_time,
+            end_time,
+            paid,
+            threshold,
+            event_type,
+            h3_hex_id,
+        })
+    }
+
+    // Whether the service holds the owner key needed to send refunds
+    pub fn can_send_refunds(&self) -> bool {
+        self.config.owner_private_key.is_some()
+    }
+
+    // Sign a call cancelling a policy on chain, refunding `refund` wei of its
+    // premium to the buyer. Returns the raw transaction without sending it, so
+    // `pay_refund` can record its hash first; it also holds the owner nonce
+    // lock from filling in the nonce until the transaction is broadcast.
+    pub async fn sign_cancel_policy(
+        &self,
+        policy_id: U256,
+        refund: U256,
+    ) -> Result<Bytes, BlockchainError> {
+        let private_key = self.config.owner_private_key.as_deref().ok_or_else(|| {
+            BlockchainError::ContractError("No contract owner key configured".to_string())
+        })?;
+        let chain_id =
+            self.provider.get_chainid().await.map_err(|e| {
+                BlockchainError::NetworkError(format!("Failed to get chain id: {}", e))
+            })?;
+        let wallet = private_key
+            .parse::<LocalWallet>()
+            .map_err(|e| BlockchainError::ParseError(format!("Invalid owner key: {}", e)))?
+            .with_chain_id(chain_id.as_u64());
+        let client = Arc::new(SignerMiddleware::new(
+            self.provider.as_ref().clone(),
+            wallet,
+        ));
+        let contract = WeatherInsurance::new(self.contract_address, client.clone());
+
+        let mut tx = contract.cancel(policy_id, refund).tx;
+        client.fill_transaction(&mut tx, None).await.map_err(|e| {
+            BlockchainError::ContractError(format!("Failed to cancel policy {}: {}", policy_id, e))
+        })?;
+        let signature = client.signer().sign_transaction(&tx).await.map_err(|e| {
+            BlockchainError::ContractError(format!("Failed to sign cancellation: {}", e))
+        })?;
+
+        Ok(tx.rlp_signed(&signature))
+    }
+
+    // Broadcast a signed transaction without waiting for it to be mined
+    pub async fn send_raw_transaction(&self, raw: Bytes) -> Result<H256, BlockchainError> {
+        let pending = self.provider.send_raw_transaction(raw).await.map_err(|e| {
+            BlockchainError::NetworkError(format!("Failed to send transaction: {}", e))
+        })?;
+        Ok(pending.tx_hash())
     }
 
     // Get transaction details
@@ -213,3 +327,459 @@ impl BlockchainService {
         }
     }
 }
+
+// Maximum difference between the purchased duration and the requested policy
+// period. Clients compute the end date in local time, so a daylight saving
+// change inside the period shifts it by up to an hour.
+const DURATION_TOLERANCE_SECONDS: i64 = 3600;
+
+// The canonical form of a transaction hash: lowercase hex with a 0x prefix.
+// Hashes are stored in this form so that the same transaction is never
+// stored twice under different spellings.
+pub fn normalize_transaction_hash(tx_hash: &str) -> Result<String, BlockchainError> {
+    H256::from_str(tx_hash)
+        .map(|hash| format!("{:?}", hash))
+        .map_err(|e| BlockchainError::ParseError(format!("Invalid transaction hash: {}", e)))
+}
+
+// Find the PolicyCreated event emitted by the contract in a receipt
+pub fn parse_policy_created(
+    receipt: &TransactionReceipt,
+    contract_address: Address,
+) -> Option<PolicyCreatedFilter> {
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == contract_address)
+        .find_map(|log| parse_log::<PolicyCreatedFilter>(log.clone()).ok())
+}
+
+// Decode the buyPolicy arguments from a transaction's input data
+pub fn decode_buy_policy_transaction(
+    transaction: &Transaction,
+) -> Result<BuyPolicyTransaction, BlockchainError> {
+    let call = BuyPolicyCall::decode(&transaction.input).map_err(|e| {
+        BlockchainError::InvalidTransaction(format!("Not a buyPolicy transaction: {}", e))
+    })?;
+
+    Ok(BuyPolicyTransaction {
+        duration: call.duration,
+        payout: call.payout,
+        threshold: call.threshold,
+        event_type: call.event_type,
+        h3_hex_id: call.h_3_hex_id,
+        premium_paid: transaction.value,
+        buyer: transaction.from,
+    })
+}
+
+// Compare a decoded buyPolicy call with the policy the user asked to create
+// and the conditions it will be created with
+pub fn verify_buy_policy_parameters(
+    buy_policy: &BuyPolicyTransaction,
+    policy_request: &CreateInsurancePolicyRequest,
+    conditions: &[CreatePolicyCondition],
+) -> Result<(), BlockchainError> {
+    let requested_duration = (policy_request.end_date - policy_request.start_date).whole_seconds();
+    let purchased_duration = i64::try_from(buy_policy.duration).map_err(|_| {
+        BlockchainError::ParameterMismatch(format!(
+            "Duration {} is out of range",
+            buy_policy.duration
+        ))
+    })?;
+    if (purchased_duration - requested_duration).abs() > DURATION_TOLERANCE_SECONDS {
+        return Err(BlockchainError::ParameterMismatch(format!(
+            "Duration {}s does not match policy period of {}s",
+            purchased_duration, requested_duration
+        )));
+    }
+
+    let payout = wei_to_eth(buy_policy.payout)?;
+    if payout != policy_request.coverage_amount {
+        return Err(BlockchainError::ParameterMismatch(format!(
+            "Payout {} ETH does not match coverage amount {}",
+            payout, policy_request.coverage_amount
+        )));
+    }
+
+    let event_type = event_type_for_policy_type(policy_request.policy_type.as_str());
+    if event_type.is_none_or(|event_type| event_type.as_str() != buy_policy.event_type) {
+        return Err(BlockchainError::ParameterMismatch(format!(
+            "Event type {} does not match policy type {}",
+            buy_policy.event_type, policy_request.policy_type
+        )));
+    }
+
+    // The contract records a single, whole-number threshold: that of the
+    // policy's first condition
+    let threshold = conditions.first().map(|c| c.threshold_value);
+    let threshold_matches = threshold.is_some_and(|threshold| {
+        threshold.fract().is_zero()
+            && threshold.to_i64().map(I256::from) == Some(buy_policy.threshold)
+    });
+    if !threshold_matches {
+        return Err(BlockchainError::ParameterMismatch(format!(
+            "Threshold {} does not match policy threshold {}",
+            buy_policy.threshold,
+            threshold.map_or("(none)".to_string(), |t| t.to_string())
+        )));
+    }
+
+    let h3_matches = policy_request
+        .location_h3_index
+        .as_deref()
+        .is_some_and(|h3| h3.eq_ignore_ascii_case(&buy_policy.h3_hex_id));
+    if !h3_matches {
+        return Err(BlockchainError::ParameterMismatch(format!(
+            "H3 index {} does not match policy location {}",
+            buy_policy.h3_hex_id,
+            policy_request
+                .location_h3_index
+                .as_deref()
+                .unwrap_or("(none)")
+        )));
+    }
+
+    // Premiums are stored with two decimal places
+    let premium_paid = wei_to_eth(buy_policy.premium_paid)?;
+    if premium_paid.round_dp(2) != policy_request.premium_amount.round_dp(2) {
+        return Err(BlockchainError::ParameterMismatch(format!(
+            "Premium paid {} ETH does not match premium amount {}",
+            premium_paid, policy_request.premium_amount
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn wei_to_eth(wei: U256) -> Result<Decimal, BlockchainError> {
+    Decimal::from_str(&format_ether(wei))
+        .map_err(|e| BlockchainError::ParseError(format!("Invalid ETH amount {}: {}", wei, e)))
+}
+
+pub fn eth_to_wei(eth: Decimal) -> Result<U256, BlockchainError> {
+    parse_ether(eth.normalize().to_string())
+        .map_err(|e| BlockchainError::ParseError(format!("Invalid ETH amount {}: {}", eth, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::contract_abi::TriggerCall;
+    use crate::db::models::{
+        ComparisonOperator, ConditionType, MeasurementPeriod, MeasurementUnit, PolicyType,
+    };
+    use ethers::abi::AbiEncode;
+    use time::{Date, Month, PrimitiveDateTime, Time};
+
+    fn datetime(day: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2026, Month::March, day).unwrap(),
+            Time::MIDNIGHT,
+        )
+    }
+
+    fn policy_request() -> CreateInsurancePolicyRequest {
+        CreateInsurancePolicyRequest {
+            policy_template_id: Some(2),
+            policy_name: "Rain Event Insurance".to_string(),
+            policy_type: PolicyType::Rain,
+            location_latitude: Decimal::from_str("40.7128").unwrap(),
+            location_longitude: Decimal::from_str("-74.0060").unwrap(),
+            location_h3_index: Some("872a1072bffffff".to_string()),
+            location_name: None,
+            coverage_amount: Decimal::from_str("5.00").unwrap(),
+            // Clients compute the premium in floating point
+            premium_amount: Decimal::from_str("0.5000000000000001").unwrap(),
+            currency: Some("ETH".to_string()),
+            start_date: datetime(1),
+            end_date: datetime(31),
+            weather_station_id: None,
+            smart_contract_address: None,
+            purchase_transaction_hash: None,
+            quote_token: None,
+            condition_overrides: Vec::new(),
+        }
+    }
+
+    // The conditions of a policy from the rain template
+    fn rain_conditions() -> Vec<CreatePolicyCondition> {
+        vec![CreatePolicyCondition {
+            policy_id: 0,
+            condition_type: ConditionType::Rainfall,
+            operator: ComparisonOperator::GreaterThan,
+            threshold_value: Decimal::from(10),
+            measurement_unit: MeasurementUnit::Millimeters,
+            measurement_period: MeasurementPeriod::Daily,
+            consecutive_days: Some(1),
+        }]
+    }
+
+    fn buy_policy_transaction(call: BuyPolicyCall, value: U256) -> Transaction {
+        Transaction {
+            from: Address::repeat_byte(0x11),
+            to: Some(Address::repeat_byte(0x22)),
+            value,
+            input: call.encode().into(),
+            ..Default::default()
+        }
+    }
+
+    fn buy_policy_call() -> BuyPolicyCall {
+        BuyPolicyCall {
+            duration: U256::from(30 * 24 * 60 * 60),
+            payout: parse_ether("5").unwrap(),
+            threshold: I256::from(10),
+            event_type: "RAIN_ABOVE".to_string(),
+            h_3_hex_id: "872a1072bffffff".to_string(),
+        }
+    }
+
+    fn decoded(call: BuyPolicyCall) -> BuyPolicyTransaction {
+        let transaction = buy_policy_transaction(call, parse_ether("0.5").unwrap());
+        decode_buy_policy_transaction(&transaction).unwrap()
+    }
+
+    fn assert_mismatch(result: Result<(), BlockchainError>, expected: &str) {
+        match result {
+            Err(BlockchainError::ParameterMismatch(message)) => {
+                assert!(
+                    message.contains(expected),
+                    "unexpected message: {}",
+                    message
+                )
+            }
+            other => panic!("expected parameter mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_buy_policy_transaction() {
+        let transaction = buy_policy_transaction(buy_policy_call(), parse_ether("0.5").unwrap());
+        let buy_policy = decode_buy_policy_transaction(&transaction).unwrap();
+
+        assert_eq!(buy_policy.duration, U256::from(2_592_000));
+        assert_eq!(buy_policy.payout, parse_ether("5").unwrap());
+        assert_eq!(buy_policy.threshold, I256::from(10));
+        assert_eq!(buy_policy.event_type, "RAIN_ABOVE");
+        assert_eq!(buy_policy.h3_hex_id, "872a1072bffffff");
+        assert_eq!(buy_policy.premium_paid, parse_ether("0.5").unwrap());
+        assert_eq!(buy_policy.buyer, Address::repeat_byte(0x11));
+    }
+
+    #[test]
+    fn test_decode_rejects_other_calls() {
+        let mut transaction = buy_policy_transaction(buy_policy_call(), U256::one());
+        transaction.input = TriggerCall {
+            policy_id: U256::zero(),
+            observed: I256::from(-5),
+        }
+        .encode()
+        .into();
+
+        let result = decode_buy_policy_transaction(&transaction);
+        assert!(matches!(
+            result,
+            Err(BlockchainError::InvalidTransaction(_))
+        ));
+    }
+
+    fn policy_created_log(contract_address: Address, policy_id: u64) -> Log {
+        let user = Address::repeat_byte(0x11);
+        Log {
+            address: contract_address,
+            topics: vec![
+                PolicyCreatedFilter::signature(),
+                H256::from_low_u64_be(policy_id),
+                H256::from(user),
+            ],
+            data: ethers::abi::encode(&[
+                ethers::abi::Token::Uint(parse_ether("5").unwrap()),
+                ethers::abi::Token::Uint(U256::from(1_700_000_000)),
+                ethers::abi::Token::Uint(U256::from(1_702_592_000)),
+            ])
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_policy_created() {
+        let contract_address = Address::repeat_byte(0x22);
+        let receipt = TransactionReceipt {
+            logs: vec![
+                // The same event from another contract is ignored
+                policy_created_log(Address::repeat_byte(0x33), 99),
+                policy_created_log(contract_address, 3),
+            ],
+            ..Default::default()
+        };
+
+        let event = parse_policy_created(&receipt, contract_address).unwrap();
+        assert_eq!(event.policy_id, U256::from(3));
+        assert_eq!(event.user, Address::repeat_byte(0x11));
+        assert_eq!(event.payout, parse_ether("5").unwrap());
+        assert_eq!(event.end_time - event.start_time, U256::from(2_592_000));
+    }
+
+    #[test]
+    fn test_parse_policy_created_missing() {
+        let receipt = TransactionReceipt::default();
+        assert!(parse_policy_created(&receipt, Address::repeat_byte(0x22)).is_none());
+    }
+
+    #[test]
+    fn test_normalize_transaction_hash() {
+        let expected = format!("0x{}", "ab".repeat(32));
+        for spelling in [
+            expected.clone(),
+            format!("0x{}", "AB".repeat(32)),
+            "ab".repeat(32),
+        ] {
+            assert_eq!(normalize_transaction_hash(&spelling).unwrap(), expected);
+        }
+        assert!(normalize_transaction_hash("0x1234").is_err());
+    }
+
+    #[test]
+    fn test_verify_matching_parameters() {
+        let buy_policy = decoded(buy_policy_call());
+        assert!(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &rain_conditions())
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_verify_allows_daylight_saving_shift() {
+        let mut call = buy_policy_call();
+        call.duration = U256::from(30 * 24 * 60 * 60 - 3600);
+        let buy_policy = decoded(call);
+        assert!(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &rain_conditions())
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_verify_duration_mismatch() {
+        let mut call = buy_policy_call();
+        call.duration = U256::from(7 * 24 * 60 * 60);
+        let buy_policy = decoded(call);
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &rain_conditions()),
+            "Duration",
+        );
+    }
+
+    #[test]
+    fn test_verify_payout_mismatch() {
+        let mut call = buy_policy_call();
+        call.payout = parse_ether("50").unwrap();
+        let buy_policy = decoded(call);
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &rain_conditions()),
+            "Payout",
+        );
+    }
+
+    #[test]
+    fn test_verify_event_type_mismatch() {
+        let mut call = buy_policy_call();
+        call.event_type = "RAIN_BELOW".to_string();
+        let buy_policy = decoded(call);
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &rain_conditions()),
+            "Event type",
+        );
+
+        // Condition types are not contract event types
+        let mut call = buy_policy_call();
+        call.event_type = "rainfall".to_string();
+        let buy_policy = decoded(call);
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &rain_conditions()),
+            "Event type",
+        );
+    }
+
+    #[test]
+    fn test_verify_event_type_for_drought_policy() {
+        let mut call = buy_policy_call();
+        call.event_type = "RAIN_BELOW".to_string();
+        let mut request = policy_request();
+        request.policy_type = PolicyType::Drought;
+        assert!(verify_buy_policy_parameters(&decoded(call), &request, &rain_conditions()).is_ok());
+    }
+
+    #[test]
+    fn test_verify_threshold_mismatch() {
+        let mut call = buy_policy_call();
+        call.threshold = I256::from(1);
+        let buy_policy = decoded(call);
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &rain_conditions()),
+            "Threshold",
+        );
+
+        // Overridden thresholds have to be bought too
+        let mut conditions = rain_conditions();
+        conditions[0].threshold_value = Decimal::from(20);
+        let buy_policy = decoded(buy_policy_call());
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &conditions),
+            "Threshold",
+        );
+
+        // Fractional thresholds can't be recorded on chain
+        conditions[0].threshold_value = Decimal::from_str("10.5").unwrap();
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &conditions),
+            "Threshold",
+        );
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &[]),
+            "Threshold",
+        );
+    }
+
+    #[test]
+    fn test_verify_h3_mismatch() {
+        let mut call = buy_policy_call();
+        call.h_3_hex_id = "872a10729ffffff".to_string();
+        let buy_policy = decoded(call);
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &rain_conditions()),
+            "H3 index",
+        );
+
+        let mut request = policy_request();
+        request.location_h3_index = None;
+        let buy_policy = decoded(buy_policy_call());
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &request, &rain_conditions()),
+            "H3 index",
+        );
+    }
+
+    #[test]
+    fn test_verify_premium_mismatch() {
+        let transaction = buy_policy_transaction(buy_policy_call(), parse_ether("0.6").unwrap());
+        let buy_policy = decode_buy_policy_transaction(&transaction).unwrap();
+        assert_mismatch(
+            verify_buy_policy_parameters(&buy_policy, &policy_request(), &rain_conditions()),
+            "Premium",
+        );
+    }
+
+    #[test]
+    fn test_eth_to_wei() {
+        let wei = eth_to_wei(Decimal::from_str("0.185000000000000000").unwrap()).unwrap();
+        assert_eq!(wei, parse_ether("0.185").unwrap());
+        assert_eq!(
+            wei_to_eth(wei).unwrap(),
+            Decimal::from_str("0.185").unwrap()
+        );
+        assert_eq!(eth_to_wei(Decimal::ZERO).unwrap(), U256::zero());
+    }
+}
@@ -6,17 +6,22 @@ abigen!(
     r#"[
         function buyPolicy(uint256 duration, uint256 payout, int256 threshold, string memory eventType, string memory h3HexId) external payable
         function trigger(uint256 policyId, int256 observed) external
+        function cancel(uint256 policyId, uint256 refund) external
         function policies(uint256) external view returns (address user, uint256 payout, uint256 startTime, uint256 endTime, bool paid, int256 threshold, string memory eventType, string memory h3HexId)
+        function premiums(uint256) external view returns (uint256)
         function policyCount() external view returns (uint256)
         function owner() external view returns (address)
+        function isSupportedEventType(string memory eventType) external pure returns (bool)
         event PolicyCreated(uint256 indexed policyId, address indexed user, uint256 payout, uint256 startTime, uint256 endTime)
         event PolicyTriggered(uint256 indexed policyId, uint256 payout, bool triggered)
+        event PolicyCancelled(uint256 indexed policyId, address indexed user, uint256 refund)
     ]"#,
 );
 
 // Struct to represent a policy as it exists on the blockchain
 #[derive(Debug, Clone)]
 pub struct BlockchainPolicy {
+    pub policy_id: U256,
     pub user: Address,
     pub payout: U256,
     pub start_time: U256,
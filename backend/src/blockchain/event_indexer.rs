@@ -0,0 +1,1120 @@
+use crate::blockchain::contract_abi::{
+    PolicyCancelledFilter, PolicyCreatedFilter, PolicyTriggeredFilter, WeatherInsuranceEvents,
+};
+use crate::blockchain::{BlockchainConfig, BlockchainError, wei_to_eth};
+use crate::db::blockchain_queries;
+use crate::db::models::{
+    BlockchainEvent, CreateBlockchainEvent, CreateReconciliationIssue, PolicyStatus,
+};
+use crate::db::policy_queries;
+use crate::policies::{Actor, LifecycleError, transition_policy};
+use ethers::prelude::*;
+use sqlx::{Pool, Postgres};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+
+// Reconciliation issue types
+pub const ISSUE_ORPHAN_POLICY: &str = "orphan_policy";
+pub const ISSUE_POLICY_ID_MISMATCH: &str = "policy_id_mismatch";
+pub const ISSUE_PAYOUT_MISMATCH: &str = "payout_mismatch";
+pub const ISSUE_OWNER_MISMATCH: &str = "owner_mismatch";
+pub const ISSUE_ORPHAN_TRIGGER: &str = "orphan_trigger";
+pub const ISSUE_UNRECORDED_PAYOUT: &str = "unrecorded_payout";
+pub const ISSUE_UNRECORDED_SETTLEMENT: &str = "unrecorded_settlement";
+pub const ISSUE_PAYOUT_NOT_TRIGGERED: &str = "payout_not_triggered";
+pub const ISSUE_ORPHAN_CANCELLATION: &str = "orphan_cancellation";
+pub const ISSUE_UNRECORDED_CANCELLATION: &str = "unrecorded_cancellation";
+pub const ISSUE_REFUND_MISMATCH: &str = "refund_mismatch";
+
+const INDEXER_ACTOR: Actor = Actor::System("event_indexer");
+
+// Error types for event indexing
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Blockchain error: {0}")]
+    Blockchain(#[from] BlockchainError),
+    #[error("Lifecycle error: {0}")]
+    Lifecycle(#[from] LifecycleError),
+}
+
+// Configuration for the event indexer
+#[derive(Debug, Clone)]
+pub struct EventIndexerConfig {
+    pub enabled: bool,
+    pub start_block: u64,
+    pub confirmations: u64,
+    pub batch_size: u64,
+    pub poll_interval_seconds: u64,
+}
+
+impl Default for EventIndexerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start_block: 0,
+            confirmations: 12,
+            batch_size: 2000,
+            poll_interval_seconds: 30,
+        }
+    }
+}
+
+impl EventIndexerConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("BLOCKCHAIN_INDEXER_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            start_block: std::env::var("BLOCKCHAIN_INDEXER_START_BLOCK")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.start_block),
+            confirmations: std::env::var("BLOCKCHAIN_INDEXER_CONFIRMATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.confirmations),
+            batch_size: std::env::var("BLOCKCHAIN_INDEXER_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(defaults.batch_size),
+            poll_interval_seconds: std::env::var("BLOCKCHAIN_INDEXER_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.poll_interval_seconds),
+        }
+    }
+}
+
+// Summary of a single indexing pass
+#[derive(Debug, Default)]
+pub struct IndexingReport {
+    pub reorg_detected: bool,
+    pub last_indexed_block: Option<u64>,
+    pub events_indexed: usize,
+    pub issues_raised: usize,
+}
+
+// Abstraction over the chain so the indexer can run against an in-process
+// fake in tests
+pub trait ChainEventSource: Send + Sync {
+    fn latest_block(&self) -> impl Future<Output = Result<u64, BlockchainError>> + Send;
+
+    fn block_hash(
+        &self,
+        block_number: u64,
+    ) -> impl Future<Output = Result<Option<H256>, BlockchainError>> + Send;
+
+    // PolicyCreated, PolicyTriggered and PolicyCancelled logs in an inclusive
+    // block range
+    fn contract_logs(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> impl Future<Output = Result<Vec<Log>, BlockchainError>> + Send;
+}
+
+pub struct ProviderEventSource {
+    provider: Arc<Provider<Http>>,
+    contract_address: Address,
+}
+
+impl ProviderEventSource {
+    pub fn new(config: &BlockchainConfig) -> Result<Self, BlockchainError> {
+        let provider = Provider::<Http>::try_from(&config.rpc_url).map_err(|e| {
+            BlockchainError::NetworkError(format!("Failed to connect to RPC: {}", e))
+        })?;
+        let contract_address = Address::from_str(&config.contract_address)
+            .map_err(|e| BlockchainError::ParseError(format!("Invalid contract address: {}", e)))?;
+
+        Ok(Self {
+            provider: Arc::new(provider),
+            contract_address,
+        })
+    }
+
+    pub fn contract_address(&self) -> Address {
+        self.contract_address
+    }
+}
+
+impl ChainEventSource for ProviderEventSource {
+    async fn latest_block(&self) -> Result<u64, BlockchainError> {
+        self.provider
+            .get_block_number()
+            .await
+            .map(|n| n.as_u64())
+            .map_err(|e| {
+                BlockchainError::NetworkError(format!("Failed to get block number: {}", e))
+            })
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<Option<H256>, BlockchainError> {
+        let block = self.provider.get_block(block_number).await.map_err(|e| {
+            BlockchainError::NetworkError(format!("Failed to get block {}: {}", block_number, e))
+        })?;
+        Ok(block.and_then(|b| b.hash))
+    }
+
+    async fn contract_logs(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Log>, BlockchainError> {
+        let filter = Filter::new()
+            .address(self.contract_address)
+            .from_block(from_block)
+            .to_block(to_block)
+            .topic0(vec![
+                PolicyCreatedFilter::signature(),
+                PolicyTriggeredFilter::signature(),
+                PolicyCancelledFilter::signature(),
+            ]);
+
+        self.provider.get_logs(&filter).await.map_err(|e| {
+            BlockchainError::NetworkError(format!(
+                "Failed to get logs for blocks {}-{}: {}",
+                from_block, to_block, e
+            ))
+        })
+    }
+}
+
+pub struct EventIndexer<S: ChainEventSource> {
+    pool: Pool<Postgres>,
+    source: S,
+    contract_address: String,
+    config: EventIndexerConfig,
+}
+
+impl<S: ChainEventSource> EventIndexer<S> {
+    pub fn new(
+        pool: Pool<Postgres>,
+        source: S,
+        contract_address: Address,
+        config: EventIndexerConfig,
+    ) -> Self {
+        Self {
+            pool,
+            source,
+            contract_address: format!("{:?}", contract_address),
+            config,
+        }
+    }
+
+    // Index on the configured interval until the task is dropped
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            self.config.poll_interval_seconds,
+        ));
+
+        loop {
+            interval.tick().await;
+            match self.run_once().await {
+                Ok(report) => debug!(
+                    "Indexing pass complete: {} events, {} issues, last block {:?}",
+                    report.events_indexed, report.issues_raised, report.last_indexed_block
+                ),
+                Err(e) => error!("Indexing pass failed: {}", e),
+            }
+        }
+    }
+
+    // Index every block that has reached the confirmation depth, in batches,
+    // saving the checkpoint after each batch
+    pub async fn run_once(&self) -> Result<IndexingReport, IndexerError> {
+        let mut report = IndexingReport::default();
+        let mut next_block = self.resume_block(&mut report).await?;
+
+        let latest = self.source.latest_block().await?;
+        let safe_head = latest.saturating_sub(self.config.confirmations);
+
+        while next_block <= safe_head {
+            let to_block = (next_block + self.config.batch_size - 1).min(safe_head);
+
+            let mut logs = self.source.contract_logs(next_block, to_block).await?;
+            logs.sort_by_key(|log| (log.block_number, log.log_index));
+            for log in logs {
+                if let Some(issues) = self.index_log(log).await? {
+                    report.events_indexed += 1;
+                    report.issues_raised += issues;
+                }
+            }
+
+            let block_hash = self.source.block_hash(to_block).await?.ok_or_else(|| {
+                BlockchainError::NetworkError(format!("Block {} not found", to_block))
+            })?;
+            blockchain_queries::save_indexer_checkpoint(
+                &self.pool,
+                &self.contract_address,
+                to_block as i64,
+                &format!("{:?}", block_hash),
+            )
+            .await?;
+
+            report.last_indexed_block = Some(to_block);
+            next_block = to_block + 1;
+        }
+
+        if report.events_indexed > 0 {
+            info!(
+                "Indexed {} contract events up to block {:?} ({} reconciliation issues)",
+                report.events_indexed, report.last_indexed_block, report.issues_raised
+            );
+        }
+
+        Ok(report)
+    }
+
+    // The block to index next. If the checkpointed block is no longer on the
+    // canonical chain, drop everything indexed within the confirmation depth
+    // below it and index those blocks again.
+    async fn resume_block(&self, report: &mut IndexingReport) -> Result<u64, IndexerError> {
+        let Some(checkpoint) =
+            blockchain_queries::get_indexer_checkpoint(&self.pool, &self.contract_address).await?
+        else {
+            return Ok(self.config.start_block);
+        };
+
+        let last_block = checkpoint.last_indexed_block as u64;
+        let current_hash = self.source.block_hash(last_block).await?;
+        if current_hash.map(|h| format!("{:?}", h)).as_deref()
+            == Some(checkpoint.last_block_hash.as_str())
+        {
+            return Ok(last_block + 1);
+        }
+
+        report.reorg_detected = true;
+        let rewind_to = last_block.saturating_sub(self.config.confirmations);
+        warn!(
+            "Reorg detected at block {}, rewinding indexer for {} to block {}",
+            last_block, self.contract_address, rewind_to
+        );
+
+        if rewind_to < self.config.start_block {
+            blockchain_queries::delete_blockchain_events_after_block(
+                &self.pool,
+                &self.contract_address,
+                self.config.start_block as i64 - 1,
+            )
+            .await?;
+            blockchain_queries::delete_indexer_checkpoint(&self.pool, &self.contract_address)
+                .await?;
+            return Ok(self.config.start_block);
+        }
+
+        blockchain_queries::delete_blockchain_events_after_block(
+            &self.pool,
+            &self.contract_address,
+            rewind_to as i64,
+        )
+        .await?;
+        let rewind_hash = self.source.block_hash(rewind_to).await?.ok_or_else(|| {
+            BlockchainError::NetworkError(format!("Block {} not found", rewind_to))
+        })?;
+        blockchain_queries::save_indexer_checkpoint(
+            &self.pool,
+            &self.contract_address,
+            rewind_to as i64,
+            &format!("{:?}", rewind_hash),
+        )
+        .await?;
+
+        Ok(rewind_to + 1)
+    }
+
+    // Store a log and reconcile it. Returns the number of new issues, or None
+    // for logs that are not contract events.
+    async fn index_log(&self, log: Log) -> Result<Option<usize>, IndexerError> {
+        if log.removed == Some(true) {
+            return Ok(None);
+        }
+        let (Some(block_number), Some(block_hash), Some(tx_hash), Some(log_index)) = (
+            log.block_number,
+            log.block_hash,
+            log.transaction_hash,
+            log.log_index,
+        ) else {
+            warn!("Skipping log without block metadata");
+            return Ok(None);
+        };
+        let Ok(decoded) = parse_log::<WeatherInsuranceEvents>(log) else {
+            debug!("Skipping unrecognised log {:?}/{}", tx_hash, log_index);
+            return Ok(None);
+        };
+
+        let (event_name, policy_id, event_data) = match &decoded {
+            WeatherInsuranceEvents::PolicyCreatedFilter(event) => (
+                "PolicyCreated",
+                event.policy_id,
+                serde_json::json!({
+                    "user": format!("{:?}", event.user),
+                    "payout": event.payout.to_string(),
+                    "start_time": event.start_time.to_string(),
+                    "end_time": event.end_time.to_string(),
+                }),
+            ),
+            WeatherInsuranceEvents::PolicyTriggeredFilter(event) => (
+                "PolicyTriggered",
+                event.policy_id,
+                serde_json::json!({
+                    "payout": event.payout.to_string(),
+                    "triggered": event.triggered,
+                }),
+            ),
+            WeatherInsuranceEvents::PolicyCancelledFilter(event) => (
+                "PolicyCancelled",
+                event.policy_id,
+                serde_json::json!({
+                    "user": format!("{:?}", event.user),
+                    "refund": event.refund.to_string(),
+                }),
+            ),
+        };
+        let blockchain_policy_id = i64::try_from(policy_id).map_err(|_| {
+            BlockchainError::ParseError(format!("Policy id {} out of range", policy_id))
+        })?;
+
+        let event = blockchain_queries::upsert_blockchain_event(
+            &self.pool,
+            &CreateBlockchainEvent {
+                contract_address: self.contract_address.clone(),
+                event_name: event_name.to_string(),
+                blockchain_policy_id,
+                transaction_hash: format!("{:?}", tx_hash),
+                log_index: log_index.as_u32() as i32,
+                block_number: block_number.as_u64() as i64,
+                block_hash: format!("{:?}", block_hash),
+                event_data,
+            },
+        )
+        .await?;
+
+        let issues = match decoded {
+            WeatherInsuranceEvents::PolicyCreatedFilter(created) => {
+                self.reconcile_policy_created(&event, &created).await?
+            }
+            WeatherInsuranceEvents::PolicyTriggeredFilter(triggered) => {
+                self.reconcile_policy_triggered(&event, &triggered).await?
+            }
+            WeatherInsuranceEvents::PolicyCancelledFilter(cancelled) => {
+                self.reconcile_policy_cancelled(&event, &cancelled).await?
+            }
+        };
+
+        let mut raised = 0;
+        for issue in issues {
+            if blockchain_queries::create_reconciliation_issue(&self.pool, &issue)
+                .await?
+                .is_some()
+            {
+                raised += 1;
+            }
+        }
+        Ok(Some(raised))
+    }
+
+    // Match a new on-chain policy with its database row, linking the on-chain
+    // id when the row only knew the purchase transaction
+    async fn reconcile_policy_created(
+        &self,
+        event: &BlockchainEvent,
+        created: &PolicyCreatedFilter,
+    ) -> Result<Vec<CreateReconciliationIssue>, IndexerError> {
+        let issue = |issue_type: &str, policy_id: Option<i32>, details: serde_json::Value| {
+            CreateReconciliationIssue {
+                event_id: event.id,
+                issue_type: issue_type.to_string(),
+                policy_id,
+                claim_id: None,
+                details: Some(details),
+            }
+        };
+
+        let Some(policy) = blockchain_queries::find_policy_for_blockchain_policy(
+            &self.pool,
+            &self.contract_address,
+            event.blockchain_policy_id,
+            Some(&event.transaction_hash),
+        )
+        .await?
+        else {
+            return Ok(vec![issue(
+                ISSUE_ORPHAN_POLICY,
+                None,
+                event.event_data.clone(),
+            )]);
+        };
+
+        let mut issues = Vec::new();
+        match policy.blockchain_policy_id {
+            None => {
+                blockchain_queries::set_blockchain_policy_id(
+                    &self.pool,
+                    policy.id,
+                    event.blockchain_policy_id,
+                )
+                .await?;
+            }
+            Some(recorded) if recorded != event.blockchain_policy_id => {
+                issues.push(issue(
+                    ISSUE_POLICY_ID_MISMATCH,
+                    Some(policy.id),
+                    serde_json::json!({
+                        "recorded_policy_id": recorded,
+                        "event_policy_id": event.blockchain_policy_id,
+                    }),
+                ));
+            }
+            Some(_) => {}
+        }
+
+        let payout = wei_to_eth(created.payout)?;
+        if payout != policy.coverage_amount {
+            issues.push(issue(
+                ISSUE_PAYOUT_MISMATCH,
+                Some(policy.id),
+                serde_json::json!({
+                    "on_chain_payout": payout,
+                    "coverage_amount": policy.coverage_amount,
+                }),
+            ));
+        }
+
+        let owner = format!("{:?}", created.user);
+        let wallet =
+            blockchain_queries::get_user_wallet_address(&self.pool, policy.user_id).await?;
+        if let Some(wallet) = wallet.filter(|w| !w.eq_ignore_ascii_case(&owner)) {
+            issues.push(issue(
+                ISSUE_OWNER_MISMATCH,
+                Some(policy.id),
+                serde_json::json!({
+                    "on_chain_user": owner,
+                    "wallet_address": wallet,
+                }),
+            ));
+        }
+
+        // A confirmed purchase that matches the policy verifies it
+        if issues.is_empty() && policy.status == Some(PolicyStatus::PendingVerification) {
+            let transition = transition_policy(
+                &self.pool,
+                policy.id,
+                PolicyStatus::Active,
+                INDEXER_ACTOR,
+                Some("Purchase confirmed on chain"),
+            )
+            .await;
+            match transition {
+                // Cancelled since it was read
+                Err(LifecycleError::IllegalTransition { .. }) => {}
+                result => {
+                    result?;
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+
+    // Match a settlement with the claim whose payout sent it
+    async fn reconcile_policy_triggered(
+        &self,
+        event: &BlockchainEvent,
+        triggered: &PolicyTriggeredFilter,
+    ) -> Result<Vec<CreateReconciliationIssue>, IndexerError> {
+        let issue = |issue_type: &str,
+                     policy_id: Option<i32>,
+                     claim_id: Option<i32>,
+                     details: serde_json::Value| CreateReconciliationIssue {
+            event_id: event.id,
+            issue_type: issue_type.to_string(),
+            policy_id,
+            claim_id,
+            details: Some(details),
+        };
+
+        let Some(policy) = blockchain_queries::find_policy_for_blockchain_policy(
+            &self.pool,
+            &self.contract_address,
+            event.blockchain_policy_id,
+            None,
+        )
+        .await?
+        else {
+            return Ok(vec![issue(
+                ISSUE_ORPHAN_TRIGGER,
+                None,
+                None,
+                event.event_data.clone(),
+            )]);
+        };
+
+        let claims = policy_queries::get_claims_by_policy_id(&self.pool, policy.id).await?;
+        let claim = claims.iter().find(|claim| {
+            claim
+                .payout_transaction_hash
+                .as_deref()
+                .is_some_and(|hash| hash.eq_ignore_ascii_case(&event.transaction_hash))
+        });
+        let payout = wei_to_eth(triggered.payout)?;
+
+        let issues = match (claim, triggered.triggered) {
+            (None, true) => vec![issue(
+                ISSUE_UNRECORDED_PAYOUT,
+                Some(policy.id),
+                None,
+                serde_json::json!({ "payout": payout }),
+            )],
+            (None, false) => vec![issue(
+                ISSUE_UNRECORDED_SETTLEMENT,
+                Some(policy.id),
+                None,
+                event.event_data.clone(),
+            )],
+            (Some(claim), false) => vec![issue(
+                ISSUE_PAYOUT_NOT_TRIGGERED,
+                Some(policy.id),
+                Some(claim.id),
+                serde_json::json!({ "claim_amount": claim.claim_amount }),
+            )],
+            (Some(claim), true) if payout != claim.claim_amount => vec![issue(
+                ISSUE_PAYOUT_MISMATCH,
+                Some(policy.id),
+                Some(claim.id),
+                serde_json::json!({
+                    "on_chain_payout": payout,
+                    "claim_amount": claim.claim_amount,
+                }),
+            )],
+            (Some(_), true) => Vec::new(),
+        };
+
+        Ok(issues)
+    }
+
+    // Match an on-chain cancellation with the cancelled policy, recording the
+    // transaction that paid its refund
+    async fn reconcile_policy_cancelled(
+        &self,
+        event: &BlockchainEvent,
+        cancelled: &PolicyCancelledFilter,
+    ) -> Result<Vec<CreateReconciliationIssue>, IndexerError> {
+        let issue = |issue_type: &str, policy_id: Option<i32>, details: serde_json::Value| {
+            CreateReconciliationIssue {
+                event_id: event.id,
+                issue_type: issue_type.to_string(),
+                policy_id,
+                claim_id: None,
+                details: Some(details),
+            }
+        };
+
+        let Some(policy) = blockchain_queries::find_policy_for_blockchain_policy(
+            &self.pool,
+            &self.contract_address,
+            event.blockchain_policy_id,
+            None,
+        )
+        .await?
+        else {
+            return Ok(vec![issue(
+                ISSUE_ORPHAN_CANCELLATION,
+                None,
+                event.event_data.clone(),
+            )]);
+        };
+
+        let refund = wei_to_eth(cancelled.refund)?;
+        if policy.status != Some(PolicyStatus::Cancelled) {
+            return Ok(vec![issue(
+                ISSUE_UNRECORDED_CANCELLATION,
+                Some(policy.id),
+                serde_json::json!({ "status": policy.status, "refund": refund }),
+            )]);
+        }
+
+        // The event is the refund that went through, whatever was recorded
+        // when it was sent
+        let recorded = policy.refund_transaction_hash.as_deref();
+        if !recorded.is_some_and(|hash| hash.eq_ignore_ascii_case(&event.transaction_hash)) {
+            policy_queries::set_refund_transaction_hash(
+                &self.pool,
+                policy.id,
+                &event.transaction_hash,
+            )
+            .await?;
+        }
+
+        let mut issues = Vec::new();
+        if policy.refund_amount != Some(refund) {
+            issues.push(issue(
+                ISSUE_REFUND_MISMATCH,
+                Some(policy.id),
+                serde_json::json!({
+                    "on_chain_refund": refund,
+                    "refund_amount": policy.refund_amount,
+                }),
+            ));
+        }
+
+        Ok(issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{
+        CreateInsurancePolicy, CreatePolicyClaim, InsurancePolicy, PolicyType,
+    };
+    use crate::policies::cancellation::{Refund, cancel_policy};
+    use crate::policies::lifecycle::Actor;
+    use crate::test_utils::{create_test_db, create_test_user, test_policy_data};
+    use ethers::abi::Token;
+    use ethers::utils::parse_ether;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    const CONTRACT: &str = "0x5fbdb2315678afecb367f032d93f642f64180aa3";
+
+    // In-memory chain whose block hashes depend on the current fork, so a
+    // reorg can be simulated by bumping the fork from some height onwards
+    #[derive(Default)]
+    struct FakeChain {
+        latest: Mutex<u64>,
+        forks: Mutex<HashMap<u64, u8>>,
+        logs: Mutex<Vec<Log>>,
+    }
+
+    impl FakeChain {
+        fn hash(&self, block_number: u64) -> H256 {
+            let fork = *self.forks.lock().unwrap().get(&block_number).unwrap_or(&0);
+            let mut hash = H256::from_low_u64_be(block_number);
+            hash.0[0] = fork;
+            hash
+        }
+
+        fn set_latest(&self, block_number: u64) {
+            *self.latest.lock().unwrap() = block_number;
+        }
+
+        fn reorg_from(&self, block_number: u64) {
+            let latest = *self.latest.lock().unwrap();
+            let mut forks = self.forks.lock().unwrap();
+            for n in block_number..=latest {
+                *forks.entry(n).or_insert(0) += 1;
+            }
+            self.logs
+                .lock()
+                .unwrap()
+                .retain(|log| log.block_number.unwrap().as_u64() < block_number);
+        }
+
+        fn push_log(&self, block_number: u64, topics: Vec<H256>, data: Vec<Token>) -> H256 {
+            let mut logs = self.logs.lock().unwrap();
+            let tx_hash = H256::from_low_u64_be(1000 + logs.len() as u64 + block_number * 100);
+            logs.push(Log {
+                address: Address::from_str(CONTRACT).unwrap(),
+                topics,
+                data: ethers::abi::encode(&data).into(),
+                block_number: Some(U64::from(block_number)),
+                block_hash: Some(self.hash(block_number)),
+                transaction_hash: Some(tx_hash),
+                log_index: Some(U256::zero()),
+                removed: Some(false),
+                ..Default::default()
+            });
+            tx_hash
+        }
+
+        fn policy_created(
+            &self,
+            block_number: u64,
+            policy_id: u64,
+            user: Address,
+            payout: &str,
+        ) -> H256 {
+            self.push_log(
+                block_number,
+                vec![
+                    PolicyCreatedFilter::signature(),
+                    H256::from_low_u64_be(policy_id),
+                    H256::from(user),
+                ],
+                vec![
+                    Token::Uint(parse_ether(payout).unwrap()),
+                    Token::Uint(U256::from(1_700_000_000)),
+                    Token::Uint(U256::from(1_702_592_000)),
+                ],
+            )
+        }
+
+        fn policy_triggered(
+            &self,
+            block_number: u64,
+            policy_id: u64,
+            payout: &str,
+            triggered: bool,
+        ) -> H256 {
+            self.push_log(
+                block_number,
+                vec![
+                    PolicyTriggeredFilter::signature(),
+                    H256::from_low_u64_be(policy_id),
+                ],
+                vec![
+                    Token::Uint(parse_ether(payout).unwrap()),
+                    Token::Bool(triggered),
+                ],
+            )
+        }
+
+        fn policy_cancelled(
+            &self,
+            block_number: u64,
+            policy_id: u64,
+            user: Address,
+            refund: &str,
+        ) -> H256 {
+            self.push_log(
+                block_number,
+                vec![
+                    PolicyCancelledFilter::signature(),
+                    H256::from_low_u64_be(policy_id),
+                    H256::from(user),
+                ],
+                vec![Token::Uint(parse_ether(refund).unwrap())],
+            )
+        }
+    }
+
+    impl ChainEventSource for &FakeChain {
+        async fn latest_block(&self) -> Result<u64, BlockchainError> {
+            Ok(*self.latest.lock().unwrap())
+        }
+
+        async fn block_hash(&self, block_number: u64) -> Result<Option<H256>, BlockchainError> {
+            let latest = *self.latest.lock().unwrap();
+            Ok((block_number <= latest).then(|| self.hash(block_number)))
+        }
+
+        async fn contract_logs(
+            &self,
+            from_block: u64,
+            to_block: u64,
+        ) -> Result<Vec<Log>, BlockchainError> {
+            Ok(self
+                .logs
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|log| {
+                    let n = log.block_number.unwrap().as_u64();
+                    n >= from_block && n <= to_block
+                })
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn indexer<'a>(pool: &Pool<Postgres>, chain: &'a FakeChain) -> EventIndexer<&'a FakeChain> {
+        EventIndexer::new(
+            pool.clone(),
+            chain,
+            Address::from_str(CONTRACT).unwrap(),
+            EventIndexerConfig {
+                enabled: true,
+                start_block: 0,
+                confirmations: 5,
+                batch_size: 4,
+                poll_interval_seconds: 1,
+            },
+        )
+    }
+
+    async fn create_user_with_wallet(pool: &Pool<Postgres>, wallet: Address) -> i32 {
+        let user = create_test_user(pool, "Chain User", "chain@example.com", "password123")
+            .await
+            .unwrap();
+        crate::db::user_queries::update_user_wallet_address(
+            pool,
+            user.id,
+            &format!("{:?}", wallet),
+        )
+        .await
+        .unwrap();
+        user.id
+    }
+
+    async fn create_policy(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        coverage: &str,
+        purchase_transaction_hash: Option<H256>,
+    ) -> InsurancePolicy {
+        policy_queries::create_insurance_policy(
+            pool,
+            &CreateInsurancePolicy {
+                policy_name: "Freeze Cover".to_string(),
+                policy_type: PolicyType::Temperature,
+                location_h3_index: Some("872a1072bffffff".to_string()),
+                coverage_amount: Decimal::from_str(coverage).unwrap(),
+                premium_amount: Decimal::from_str("0.50").unwrap(),
+                smart_contract_address: Some(CONTRACT.to_string()),
+                purchase_transaction_hash: purchase_transaction_hash.map(|h| format!("{:?}", h)),
+                ..test_policy_data(user_id)
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn issue_types(pool: &Pool<Postgres>) -> Vec<String> {
+        let mut types: Vec<String> = blockchain_queries::get_unresolved_reconciliation_issues(pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|issue| issue.issue_type)
+            .collect();
+        types.sort();
+        types
+    }
+
+    #[tokio::test]
+    async fn test_indexes_confirmed_events_and_links_policies() {
+        let test_db = create_test_db().await;
+        let pool = &test_db.pool;
+        let chain = FakeChain::default();
+        chain.set_latest(20);
+
+        let buyer = Address::repeat_byte(0x11);
+        let user_id = create_user_with_wallet(pool, buyer).await;
+        let purchase = chain.policy_created(10, 0, buyer, "5");
+        let policy = create_policy(pool, user_id, "5.00", Some(purchase)).await;
+        policy_queries::set_policy_status(pool, policy.id, PolicyStatus::PendingVerification)
+            .await
+            .unwrap();
+        // Not yet at the confirmation depth
+        chain.policy_created(18, 1, buyer, "5");
+
+        let report = indexer(pool, &chain).run_once().await.unwrap();
+        assert_eq!(report.events_indexed, 1);
+        assert_eq!(report.issues_raised, 0);
+        assert_eq!(report.last_indexed_block, Some(15));
+
+        let linked = policy_queries::get_policy_by_id(pool, policy.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(linked.blockchain_policy_id, Some(0));
+        // The confirmed purchase verifies the policy
+        assert_eq!(linked.status, Some(PolicyStatus::Active));
+
+        let checkpoint = blockchain_queries::get_indexer_checkpoint(pool, CONTRACT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(checkpoint.last_indexed_block, 15);
+        assert_eq!(checkpoint.last_block_hash, format!("{:?}", chain.hash(15)));
+
+        // The second purchase has no policy row in the database
+        chain.set_latest(30);
+        let report = indexer(pool, &chain).run_once().await.unwrap();
+        assert_eq!(report.events_indexed, 1);
+        assert_eq!(report.last_indexed_block, Some(25));
+        assert_eq!(issue_types(pool).await, vec![ISSUE_ORPHAN_POLICY]);
+    }
+
+    #[tokio::test]
+    async fn test_flags_policy_mismatches_once() {
+        let test_db = create_test_db().await;
+        let pool = &test_db.pool;
+        let chain = FakeChain::default();
+        chain.set_latest(20);
+
+        let user_id = create_user_with_wallet(pool, Address::repeat_byte(0x11)).await;
+        let purchase = chain.policy_created(3, 0, Address::repeat_byte(0x99), "6");
+        let policy = create_policy(pool, user_id, "5.00", Some(purchase)).await;
+        policy_queries::set_policy_status(pool, policy.id, PolicyStatus::PendingVerification)
+            .await
+            .unwrap();
+
+        let report = indexer(pool, &chain).run_once().await.unwrap();
+        assert_eq!(report.issues_raised, 2);
+        assert_eq!(
+            issue_types(pool).await,
+            vec![ISSUE_OWNER_MISMATCH, ISSUE_PAYOUT_MISMATCH]
+        );
+        // A purchase that doesn't match leaves the policy unverified
+        let policy = policy_queries::get_policy_by_id(pool, policy.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.status, Some(PolicyStatus::PendingVerification));
+
+        // Indexing the same blocks again does not duplicate issues
+        blockchain_queries::delete_indexer_checkpoint(pool, CONTRACT)
+            .await
+            .unwrap();
+        let report = indexer(pool, &chain).run_once().await.unwrap();
+        assert_eq!(report.events_indexed, 1);
+        assert_eq!(report.issues_raised, 0);
+        assert_eq!(issue_types(pool).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reconciles_policy_triggered() {
+        let test_db = create_test_db().await;
+        let pool = &test_db.pool;
+        let chain = FakeChain::default();
+        chain.set_latest(20);
+
+        let user_id = create_user_with_wallet(pool, Address::repeat_byte(0x11)).await;
+        let paid_policy = create_policy(pool, user_id, "2.50", None).await;
+        blockchain_queries::set_blockchain_policy_id(pool, paid_policy.id, 4)
+            .await
+            .unwrap();
+        let unpaid_policy = create_policy(pool, user_id, "2.50", None).await;
+        blockchain_queries::set_blockchain_policy_id(pool, unpaid_policy.id, 5)
+            .await
+            .unwrap();
+
+        // A payout sent by the oracle for a claim
+        let payout_tx = chain.policy_triggered(2, 4, "2.5", true);
+        let claim = policy_queries::create_policy_claim(
+            pool,
+            &CreatePolicyClaim {
+                policy_id: paid_policy.id,
+                claim_amount: Decimal::from_str("2.50").unwrap(),
+                trigger_date: paid_policy.start_date,
+                trigger_period_start: None,
+                trigger_period_end: None,
+                verification_data: None,
+            },
+        )
+        .await
+        .unwrap();
+        sqlx::query!(
+            "UPDATE policy_claims SET payout_transaction_hash = $1 WHERE id = $2",
+            format!("{:?}", payout_tx),
+            claim.id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        // A payout made outside the backend, and one for an unknown policy
+        chain.policy_triggered(3, 5, "2.5", true);
+        chain.policy_triggered(4, 99, "1", true);
+
+        let report = indexer(pool, &chain).run_once().await.unwrap();
+        assert_eq!(report.events_indexed, 3);
+
+        let issues = blockchain_queries::get_unresolved_reconciliation_issues(pool)
+            .await
+            .unwrap();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].issue_type, ISSUE_UNRECORDED_PAYOUT);
+        assert_eq!(issues[0].policy_id, Some(unpaid_policy.id));
+        assert_eq!(issues[1].issue_type, ISSUE_ORPHAN_TRIGGER);
+        assert_eq!(issues[1].policy_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_reconciles_policy_cancelled() {
+        let test_db = create_test_db().await;
+        let pool = &test_db.pool;
+        let chain = FakeChain::default();
+        chain.set_latest(20);
+
+        let wallet = Address::repeat_byte(0x11);
+        let user_id = create_user_with_wallet(pool, wallet).await;
+        let cancelled = create_policy(pool, user_id, "2.50", None).await;
+        blockchain_queries::set_blockchain_policy_id(pool, cancelled.id, 4)
+            .await
+            .unwrap();
+        let refund = Refund {
+            unused_premium: Decimal::from_str("0.25").unwrap(),
+            fee: Decimal::ZERO,
+            amount: Decimal::from_str("0.25").unwrap(),
+        };
+        cancel_policy(
+            pool,
+            cancelled.id,
+            Actor::User(user_id),
+            &refund,
+            cancelled.start_date,
+        )
+        .await
+        .unwrap();
+        let active = create_policy(pool, user_id, "2.50", None).await;
+        blockchain_queries::set_blockchain_policy_id(pool, active.id, 5)
+            .await
+            .unwrap();
+
+        // The refund for the cancelled policy, one for a policy the backend
+        // never cancelled, and one for an unknown policy
+        let refund_tx = chain.policy_cancelled(2, 4, wallet, "0.25");
+        chain.policy_cancelled(3, 5, wallet, "0.25");
+        chain.policy_cancelled(4, 99, wallet, "0.25");
+
+        let report = indexer(pool, &chain).run_once().await.unwrap();
+        assert_eq!(report.events_indexed, 3);
+
+        let policy = policy_queries::get_policy_by_id(pool, cancelled.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            policy.refund_transaction_hash,
+            Some(format!("{:?}", refund_tx))
+        );
+
+        let issues = blockchain_queries::get_unresolved_reconciliation_issues(pool)
+            .await
+            .unwrap();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].issue_type, ISSUE_UNRECORDED_CANCELLATION);
+        assert_eq!(issues[0].policy_id, Some(active.id));
+        assert_eq!(issues[1].issue_type, ISSUE_ORPHAN_CANCELLATION);
+        assert_eq!(issues[1].policy_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_rewinds_after_reorg() {
+        let test_db = create_test_db().await;
+        let pool = &test_db.pool;
+        let chain = FakeChain::default();
+        chain.set_latest(20);
+
+        let buyer = Address::repeat_byte(0x11);
+        let dropped = chain.policy_created(14, 0, buyer, "1");
+        let report = indexer(pool, &chain).run_once().await.unwrap();
+        assert_eq!(report.events_indexed, 1);
+        assert!(!report.reorg_detected);
+
+        // Blocks from 12 are replaced and the purchase lands in block 13
+        chain.reorg_from(12);
+        let replacement = chain.policy_created(13, 0, buyer, "1");
+        chain.set_latest(21);
+
+        let report = indexer(pool, &chain).run_once().await.unwrap();
+        assert!(report.reorg_detected);
+        assert_eq!(report.last_indexed_block, Some(16));
+
+        let events = blockchain_queries::get_blockchain_events(pool, CONTRACT)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].transaction_hash, format!("{:?}", replacement));
+        assert_ne!(events[0].transaction_hash, format!("{:?}", dropped));
+        assert_eq!(events[0].block_number, 13);
+        assert_eq!(events[0].block_hash, format!("{:?}", chain.hash(13)));
+    }
+}
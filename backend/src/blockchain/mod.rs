@@ -1,5 +1,9 @@
 pub mod blockchain_service;
 pub mod contract_abi;
+pub mod event_indexer;
+pub mod refund_processor;
 
 pub use blockchain_service::*;
 pub use contract_abi::*;
+pub use event_indexer::*;
+pub use refund_processor::*;
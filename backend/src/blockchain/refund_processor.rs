@@ -0,0 +1,347 @@
+use crate::blockchain::{BlockchainError, BlockchainService, eth_to_wei};
+use crate::db::models::InsurancePolicy;
+use crate::db::policy_queries;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{Pool, Postgres};
+use time::{Duration, OffsetDateTime};
+use tracing::{error, info, warn};
+
+// Error types for refund payment
+#[derive(Debug, thiserror::Error)]
+pub enum RefundError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Blockchain error: {0}")]
+    Blockchain(#[from] BlockchainError),
+}
+
+// Configuration for the refund processor, which pays the refunds of
+// cancelled policies that could not be sent when they were cancelled
+#[derive(Debug, Clone)]
+pub struct RefundProcessorConfig {
+    pub enabled: bool,
+    pub interval_seconds: u64,
+    // How long after a cancellation its refund is retried, so a refund the
+    // cancel request is still sending is not sent twice
+    pub retry_delay_seconds: u64,
+}
+
+impl Default for RefundProcessorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_seconds: 300,
+            retry_delay_seconds: 120,
+        }
+    }
+}
+
+impl RefundProcessorConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("REFUND_PROCESSOR_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            interval_seconds: std::env::var("REFUND_PROCESSOR_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.interval_seconds),
+            retry_delay_seconds: std::env::var("REFUND_PROCESSOR_RETRY_DELAY_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.retry_delay_seconds),
+        }
+    }
+}
+
+// Abstraction over the contract so refunds can be paid against an in-process
+// fake in tests
+pub trait RefundSender: Send + Sync {
+    // Whether the contract has already paid out or refunded the policy
+    fn is_settled(
+        &self,
+        policy_id: U256,
+    ) -> impl Future<Output = Result<bool, BlockchainError>> + Send;
+
+    // Sign the call refunding a policy, returning the raw transaction
+    fn sign_refund(
+        &self,
+        policy_id: U256,
+        refund: U256,
+    ) -> impl Future<Output = Result<Bytes, BlockchainError>> + Send;
+
+    fn broadcast(&self, raw: Bytes) -> impl Future<Output = Result<H256, BlockchainError>> + Send;
+}
+
+impl RefundSender for BlockchainService {
+    async fn is_settled(&self, policy_id: U256) -> Result<bool, BlockchainError> {
+        Ok(self.get_blockchain_policy(policy_id).await?.paid)
+    }
+
+    async fn sign_refund(&self, policy_id: U256, refund: U256) -> Result<Bytes, BlockchainError> {
+        self.sign_cancel_policy(policy_id, refund).await
+    }
+
+    async fn broadcast(&self, raw: Bytes) -> Result<H256, BlockchainError> {
+        self.send_raw_transaction(raw).await
+    }
+}
+
+// Send the refund of a cancelled on-chain policy and record its transaction.
+// Returns None if the contract has already settled the policy; the event
+// indexer records the transaction that did. The refund is signed and sent
+// under the owner nonce lock, since the oracle sends payouts from the same
+// key. Its hash is written in that transaction before it is broadcast, so
+// the hash is kept only if the refund was sent.
+pub async fn pay_refund<S: RefundSender>(
+    pool: &Pool<Postgres>,
+    sender: &S,
+    policy: &InsurancePolicy,
+) -> Result<Option<String>, RefundError> {
+    let (Some(blockchain_policy_id), Some(refund_amount)) =
+        (policy.blockchain_policy_id, policy.refund_amount)
+    else {
+        return Ok(None);
+    };
+    let blockchain_policy_id = U256::from(blockchain_policy_id as u64);
+
+    if sender.is_settled(blockchain_policy_id).await? {
+        warn!(
+            "Policy {} is already settled on chain, not sending its refund",
+            policy.id
+        );
+        return Ok(None);
+    }
+
+    let refund = eth_to_wei(refund_amount)?;
+    let mut nonce_lock = pool.begin().await?;
+    contract_events::lock_owner_nonce(&mut *nonce_lock).await?;
+    let raw = sender.sign_refund(blockchain_policy_id, refund).await?;
+    let tx_hash = format!("{:?}", H256::from(keccak256(&raw)));
+    policy_queries::set_refund_transaction_hash(&mut *nonce_lock, policy.id, &tx_hash).await?;
+    sender.broadcast(raw).await?;
+    nonce_lock.commit().await?;
+
+    Ok(Some(tx_hash))
+}
+
+// Summary of a single refund pass
+#[derive(Debug, Default)]
+pub struct RefundReport {
+    pub refunds_sent: usize,
+    pub already_settled: usize,
+    pub failed_policies: Vec<i32>,
+}
+
+pub struct RefundProcessor<S: RefundSender> {
+    pool: Pool<Postgres>,
+    sender: S,
+    config: RefundProcessorConfig,
+}
+
+impl<S: RefundSender> RefundProcessor<S> {
+    pub fn new(pool: Pool<Postgres>, sender: S, config: RefundProcessorConfig) -> Self {
+        Self {
+            pool,
+            sender,
+            config,
+        }
+    }
+
+    // Pay refunds on the configured interval until the task is dropped
+    pub async fn run(self) {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(self.config.interval_seconds));
+
+        loop {
+            interval.tick().await;
+            let now = OffsetDateTime::now_utc();
+            let as_of = PrimitiveDateTime::new(now.date(), now.time());
+            match self.run_once(as_of).await {
+                Ok(report) => info!(
+                    "Refund pass complete: {} sent, {} already settled, {} failures",
+                    report.refunds_sent,
+                    report.already_settled,
+                    report.failed_policies.len()
+                ),
+                Err(e) => error!("Refund pass failed: {}", e),
+            }
+        }
+    }
+
+    // Pay every unpaid refund of a policy cancelled at least the retry delay
+    // before `as_of`. A failure on one policy is recorded in the report and
+    // does not stop the others.
+    pub async fn run_once(&self, as_of: PrimitiveDateTime) -> Result<RefundReport, RefundError> {
+        let cancelled_before = as_of - Duration::seconds(self.config.retry_delay_seconds as i64);
+        let policies = policy_queries::get_unpaid_refunds(&self.pool, cancelled_before).await?;
+        let mut report = RefundReport::default();
+
+        for policy in policies {
+            match pay_refund(&self.pool, &self.sender, &policy).await {
+                Ok(Some(tx_hash)) => {
+                    info!("Sent refund {} for policy {}", tx_hash, policy.id);
+                    report.refunds_sent += 1;
+                }
+                Ok(None) => report.already_settled += 1,
+                Err(e) => {
+                    error!("Failed to refund policy {}: {}", policy.id, e);
+                    report.failed_policies.push(policy.id);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::blockchain_queries;
+    use crate::policies::cancellation::{Refund, cancel_policy};
+    use crate::policies::lifecycle::Actor;
+    use crate::test_utils::{create_test_db, create_test_user_for_policies, test_policy_data};
+    use rust_decimal::Decimal;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+    use std::sync::Mutex;
+
+    // Contract whose policies are settled once refunded, and which fails to
+    // send refunds for the policies in `failing`
+    #[derive(Default)]
+    struct FakeContract {
+        settled: Mutex<HashSet<U256>>,
+        failing: HashSet<U256>,
+        sent: Mutex<Vec<(U256, U256)>>,
+    }
+
+    impl RefundSender for FakeContract {
+        async fn is_settled(&self, policy_id: U256) -> Result<bool, BlockchainError> {
+            Ok(self.settled.lock().unwrap().contains(&policy_id))
+        }
+
+        // The raw "transaction" is the policy id and refund, big-endian
+        async fn sign_refund(
+            &self,
+            policy_id: U256,
+            refund: U256,
+        ) -> Result<Bytes, BlockchainError> {
+            let mut raw = [0u8; 64];
+            policy_id.to_big_endian(&mut raw[..32]);
+            refund.to_big_endian(&mut raw[32..]);
+            Ok(Bytes::from(raw.to_vec()))
+        }
+
+        async fn broadcast(&self, raw: Bytes) -> Result<H256, BlockchainError> {
+            let policy_id = U256::from_big_endian(&raw[..32]);
+            let refund = U256::from_big_endian(&raw[32..]);
+            if self.failing.contains(&policy_id) {
+                return Err(BlockchainError::NetworkError(
+                    "connection refused".to_string(),
+                ));
+            }
+            self.settled.lock().unwrap().insert(policy_id);
+            self.sent.lock().unwrap().push((policy_id, refund));
+            Ok(H256::from(keccak256(&raw)))
+        }
+    }
+
+    // An on-chain policy cancelled at `cancelled_at` with a refund of 0.05
+    async fn cancelled_policy(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        blockchain_policy_id: i64,
+        cancelled_at: PrimitiveDateTime,
+    ) -> i32 {
+        let policy = policy_queries::create_insurance_policy(pool, &test_policy_data(user_id))
+            .await
+            .unwrap();
+        blockchain_queries::set_blockchain_policy_id(pool, policy.id, blockchain_policy_id)
+            .await
+            .unwrap();
+        let refund = Refund {
+            unused_premium: Decimal::from_str("0.05").unwrap(),
+            fee: Decimal::ZERO,
+            amount: Decimal::from_str("0.05").unwrap(),
+        };
+        cancel_policy(pool, policy.id, Actor::User(user_id), &refund, cancelled_at)
+            .await
+            .unwrap();
+        policy.id
+    }
+
+    #[tokio::test]
+    async fn test_run_once_pays_unpaid_refunds() {
+        let test_db = create_test_db().await;
+        let pool = &test_db.pool;
+        let user_id = create_test_user_for_policies(pool).await;
+        let as_of = test_policy_data(user_id).start_date + Duration::days(10);
+        let earlier = as_of - Duration::hours(1);
+
+        let unpaid = cancelled_policy(pool, user_id, 1, earlier).await;
+        let settled = cancelled_policy(pool, user_id, 2, earlier).await;
+        let failing = cancelled_policy(pool, user_id, 3, earlier).await;
+        let paid = cancelled_policy(pool, user_id, 4, earlier).await;
+        policy_queries::set_refund_transaction_hash(pool, paid, "0xpaid")
+            .await
+            .unwrap();
+        // Still within the retry delay, so the cancel request may be sending it
+        cancelled_policy(pool, user_id, 5, as_of - Duration::seconds(30)).await;
+
+        let contract = FakeContract {
+            failing: HashSet::from([U256::from(3)]),
+            ..Default::default()
+        };
+        contract.settled.lock().unwrap().insert(U256::from(2));
+        let processor =
+            RefundProcessor::new(pool.clone(), contract, RefundProcessorConfig::default());
+
+        let report = processor.run_once(as_of).await.unwrap();
+        assert_eq!(report.refunds_sent, 1);
+        assert_eq!(report.already_settled, 1);
+        assert_eq!(report.failed_policies, vec![failing]);
+        assert_eq!(
+            *processor.sender.sent.lock().unwrap(),
+            vec![(
+                U256::from(1),
+                eth_to_wei(Decimal::from_str("0.05").unwrap()).unwrap()
+            )]
+        );
+
+        let refund = eth_to_wei(Decimal::from_str("0.05").unwrap()).unwrap();
+        let raw = processor
+            .sender
+            .sign_refund(U256::from(1), refund)
+            .await
+            .unwrap();
+        let policy = policy_queries::get_policy_by_id(pool, unpaid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            policy.refund_transaction_hash,
+            Some(format!("{:?}", H256::from(keccak256(&raw))))
+        );
+        // A refund that failed to broadcast leaves no hash behind
+        let policy = policy_queries::get_policy_by_id(pool, failing)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.refund_transaction_hash, None);
+        let policy = policy_queries::get_policy_by_id(pool, settled)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.refund_transaction_hash, None);
+
+        // The sent refund is not sent again and the failed one is retried
+        let report = processor.run_once(as_of).await.unwrap();
+        assert_eq!(report.refunds_sent, 0);
+        assert_eq!(report.already_settled, 1);
+        assert_eq!(report.failed_policies, vec![failing]);
+    }
+}
@@ -0,0 +1,583 @@
+use crate::claims::{EvaluationError, TriggerEvaluation, evaluate_policy};
+use crate::db::models::{
+    CreatePolicyClaim, InsurancePolicy, PolicyClaim, PolicyStatus, PolicyWithConditions,
+};
+use crate::db::policy_queries;
+use crate::policies::{Actor, LifecycleError, transition_policy, transition_policy_in};
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{PgConnection, Pool, Postgres};
+use time::OffsetDateTime;
+use tracing::{debug, error, info, warn};
+
+// Error types for claims processing
+#[derive(Debug, thiserror::Error)]
+pub enum ClaimsError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Evaluation error: {0}")]
+    Evaluation(#[from] EvaluationError),
+    #[error("Lifecycle error: {0}")]
+    Lifecycle(#[from] LifecycleError),
+}
+
+const PROCESSOR_ACTOR: Actor = Actor::System("claims_processor");
+
+// Configuration for the claims processor
+#[derive(Debug, Clone)]
+pub struct ClaimsProcessorConfig {
+    pub enabled: bool,
+    pub interval_seconds: u64,
+}
+
+impl Default for ClaimsProcessorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: 3600,
+        }
+    }
+}
+
+impl ClaimsProcessorConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("CLAIMS_PROCESSOR_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            interval_seconds: std::env::var("CLAIMS_PROCESSOR_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.interval_seconds),
+        }
+    }
+}
+
+// What happened to a single policy during a processing pass
+#[derive(Debug, PartialEq)]
+pub enum PolicyOutcome {
+    ClaimCreated(i32),
+    AlreadyClaimed,
+    Expired,
+    NotTriggered,
+}
+
+// Summary of a single processing pass
+#[derive(Debug, Default)]
+pub struct ClaimsProcessingReport {
+    pub policies_evaluated: usize,
+    pub claims_created: usize,
+    pub policies_expired: usize,
+    pub failed_policies: Vec<i32>,
+}
+
+pub struct ClaimsProcessor {
+    pool: Pool<Postgres>,
+    config: ClaimsProcessorConfig,
+}
+
+impl ClaimsProcessor {
+    pub fn new(pool: Pool<Postgres>, config: ClaimsProcessorConfig) -> Self {
+        Self { pool, config }
+    }
+
+    // Process on the configured interval until the task is dropped
+    pub async fn run(self) {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(self.config.interval_seconds));
+
+        loop {
+            interval.tick().await;
+            let now = OffsetDateTime::now_utc();
+            let as_of = PrimitiveDateTime::new(now.date(), now.time());
+            match self.run_once(as_of).await {
+                Ok(report) => info!(
+                    "Claims processing pass complete: {} evaluated, {} claims created, {} expired, {} failures",
+                    report.policies_evaluated,
+                    report.claims_created,
+                    report.policies_expired,
+                    report.failed_policies.len()
+                ),
+                Err(e) => error!("Claims processing pass failed: {}", e),
+            }
+        }
+    }
+
+    // Evaluate every active policy as of `as_of`. A failure on one policy is
+    // recorded in the report and does not stop the others.
+    pub async fn run_once(
+        &self,
+        as_of: PrimitiveDateTime,
+    ) -> Result<ClaimsProcessingReport, ClaimsError> {
+        let policies = policy_queries::get_active_policies(&self.pool).await?;
+        let mut report = ClaimsProcessingReport::default();
+
+        for policy in policies {
+            if policy.start_date > as_of {
+                debug!("Policy {} has not started yet", policy.id);
+                continue;
+            }
+
+            report.policies_evaluated += 1;
+            match self.process_policy(policy.clone(), as_of).await {
+                Ok(PolicyOutcome::ClaimCreated(_)) => report.claims_created += 1,
+                Ok(PolicyOutcome::Expired) => report.policies_expired += 1,
+                Ok(PolicyOutcome::AlreadyClaimed | PolicyOutcome::NotTriggered) => {}
+                Err(e) => {
+                    warn!("Failed to process claims for policy {}: {}", policy.id, e);
+                    report.failed_policies.push(policy.id);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    pub async fn process_policy(
+        &self,
+        policy: InsurancePolicy,
+        as_of: PrimitiveDateTime,
+    ) -> Result<PolicyOutcome, ClaimsError> {
+        let ended = policy.end_date <= as_of;
+
+        let Some(station_id) = policy.weather_station_id.clone() else {
+            debug!("Policy {} has no weather station", policy.id);
+            return self.expire_if_ended(policy.id, ended).await;
+        };
+
+        let conditions = policy_queries::get_conditions_by_policy_id(&self.pool, policy.id).await?;
+        let readings = policy_queries::get_weather_data_by_station_and_date_range(
+            &self.pool,
+            &station_id,
+            &policy.start_date,
+            &policy.end_date.min(as_of),
+        )
+        .await?;
+
+        let policy = PolicyWithConditions { policy, conditions };
+        let evaluation = evaluate_policy(&policy, &readings, as_of)?;
+
+        if !evaluation.triggered {
+            return self.expire_if_ended(policy.policy.id, ended).await;
+        }
+
+        // The claim and the move to claimed are written together, so a
+        // failed transition doesn't leave a claim on an active policy
+        let mut tx = self.pool.begin().await?;
+        let created = self
+            .file_claim(&mut tx, &policy.policy, &evaluation)
+            .await?;
+        let (outcome, reason) = match &created {
+            Some(claim) => (
+                PolicyOutcome::ClaimCreated(claim.id),
+                format!("Claim {} filed", claim.id),
+            ),
+            None => (
+                PolicyOutcome::AlreadyClaimed,
+                "Claim already filed".to_string(),
+            ),
+        };
+        let transition = transition_policy_in(
+            &mut tx,
+            policy.policy.id,
+            PolicyStatus::Claimed,
+            PROCESSOR_ACTOR,
+            Some(&reason),
+        )
+        .await;
+        match transition {
+            // A policy whose claim was already filed is normally claimed already
+            Err(LifecycleError::IllegalTransition {
+                from: PolicyStatus::Claimed,
+                ..
+            }) if outcome == PolicyOutcome::AlreadyClaimed => {}
+            result => {
+                result?;
+            }
+        }
+        tx.commit().await?;
+
+        if let (Some(claim), Some(window)) = (&created, evaluation.window) {
+            info!(
+                "Filed claim {} for policy {} (window {} - {})",
+                claim.id, policy.policy.id, window.start, window.end
+            );
+        }
+        Ok(outcome)
+    }
+
+    async fn file_claim(
+        &self,
+        conn: &mut PgConnection,
+        policy: &InsurancePolicy,
+        evaluation: &TriggerEvaluation,
+    ) -> Result<Option<PolicyClaim>, ClaimsError> {
+        let window = evaluation
+            .window
+            .expect("triggered evaluations always have a window");
+        let readings = evaluation.triggering_readings();
+
+        // The trigger date is the last reading that completed the trigger
+        let trigger_date = readings.last().map(|r| r.recorded_at).unwrap_or(window.end);
+
+        let claim = CreatePolicyClaim {
+            policy_id: policy.id,
+            claim_amount: policy.coverage_amount,
+            trigger_date,
+            trigger_period_start: Some(window.start),
+            trigger_period_end: Some(window.end),
+            verification_data: Some(verification_data(evaluation)),
+        };
+
+        Ok(policy_queries::create_policy_claim_if_absent(conn, &claim).await?)
+    }
+
+    async fn expire_if_ended(
+        &self,
+        policy_id: i32,
+        ended: bool,
+    ) -> Result<PolicyOutcome, ClaimsError> {
+        if !ended {
+            return Ok(PolicyOutcome::NotTriggered);
+        }
+
+        transition_policy(
+            &self.pool,
+            policy_id,
+            PolicyStatus::Expired,
+            PROCESSOR_ACTOR,
+            Some("Coverage ended without a trigger"),
+        )
+        .await?;
+        info!("Policy {} expired without triggering", policy_id);
+        Ok(PolicyOutcome::Expired)
+    }
+}
+
+// The evidence stored with a claim: the evaluated conditions and every weather
+// reading inside the triggering windows.
+fn verification_data(evaluation: &TriggerEvaluation) -> serde_json::Value {
+    let conditions: Vec<serde_json::Value> = evaluation
+        .conditions
+        .iter()
+        .map(|c| {
+            serde_json::json!({
+                "condition_id": c.condition_id,
+                "normalized_threshold": c.normalized_threshold,
+                "observed_value": c.observed_value,
+                "window": c.window,
+                "periods": c.periods,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "evaluated_at": evaluation.evaluated_at,
+        "window": evaluation.window,
+        "conditions": conditions,
+        "readings": evaluation.triggering_readings(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{
+        ClaimStatus, ComparisonOperator, ConditionType, CreateInsurancePolicy,
+        CreatePolicyCondition, CreateWeatherData, MeasurementPeriod, MeasurementUnit,
+    };
+    use crate::test_utils::{create_test_db, create_test_user_for_policies, test_policy_data};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+    use time::{Date, Month, Time};
+
+    fn datetime(month: Month, day: u8, hour: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2024, month, day).unwrap(),
+            Time::from_hms(hour, 0, 0).unwrap(),
+        )
+    }
+
+    // A rain policy for June 2024 that triggers on a day above 10mm
+    async fn create_rain_policy(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        station_id: Option<&str>,
+    ) -> InsurancePolicy {
+        let policy = policy_queries::create_insurance_policy(
+            pool,
+            &CreateInsurancePolicy {
+                policy_name: "Rain Cover".to_string(),
+                coverage_amount: Decimal::from_str("2.50").unwrap(),
+                premium_amount: Decimal::from_str("0.25").unwrap(),
+                weather_station_id: station_id.map(|s| s.to_string()),
+                ..test_policy_data(user_id)
+            },
+        )
+        .await
+        .unwrap();
+
+        policy_queries::create_policy_condition(
+            pool,
+            &CreatePolicyCondition {
+                policy_id: policy.id,
+                condition_type: ConditionType::Rainfall,
+                operator: ComparisonOperator::GreaterThan,
+                threshold_value: Decimal::from_str("10").unwrap(),
+                measurement_unit: MeasurementUnit::Millimeters,
+                measurement_period: MeasurementPeriod::Daily,
+                consecutive_days: Some(1),
+            },
+        )
+        .await
+        .unwrap();
+
+        policy
+    }
+
+    async fn insert_rain(pool: &Pool<Postgres>, station_id: &str, at: PrimitiveDateTime, mm: &str) {
+        policy_queries::insert_weather_data(
+            pool,
+            &CreateWeatherData {
+                station_id: station_id.to_string(),
+                recorded_at: at,
+                temperature: None,
+                humidity: None,
+                precipitation: Some(Decimal::from_str(mm).unwrap()),
+                wind_speed: None,
+                wind_direction: None,
+                atmospheric_pressure: None,
+                data_source: None,
+                raw_data: None,
+                quality_score: None,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    fn processor(pool: &Pool<Postgres>) -> ClaimsProcessor {
+        ClaimsProcessor::new(pool.clone(), ClaimsProcessorConfig::default())
+    }
+
+    #[tokio::test]
+    async fn test_triggered_policy_files_claim() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+        let policy = create_rain_policy(&test_db.pool, user_id, Some("rain_station")).await;
+
+        insert_rain(
+            &test_db.pool,
+            "rain_station",
+            datetime(Month::June, 9, 12),
+            "3",
+        )
+        .await;
+        insert_rain(
+            &test_db.pool,
+            "rain_station",
+            datetime(Month::June, 10, 8),
+            "6",
+        )
+        .await;
+        insert_rain(
+            &test_db.pool,
+            "rain_station",
+            datetime(Month::June, 10, 16),
+            "7",
+        )
+        .await;
+
+        let report = processor(&test_db.pool)
+            .run_once(datetime(Month::June, 15, 0))
+            .await
+            .unwrap();
+        assert_eq!(report.policies_evaluated, 1);
+        assert_eq!(report.claims_created, 1);
+
+        let claims = policy_queries::get_claims_by_policy_id(&test_db.pool, policy.id)
+            .await
+            .unwrap();
+        assert_eq!(claims.len(), 1);
+        let claim = &claims[0];
+        assert_eq!(claim.claim_amount, policy.coverage_amount);
+        assert_eq!(claim.claim_status, Some(ClaimStatus::Pending));
+        assert_eq!(claim.trigger_date, datetime(Month::June, 10, 16));
+        assert_eq!(
+            claim.trigger_period_start,
+            Some(datetime(Month::June, 10, 0))
+        );
+        assert_eq!(claim.trigger_period_end, Some(datetime(Month::June, 11, 0)));
+
+        let verification = claim.verification_data.as_ref().unwrap();
+        assert_eq!(verification["readings"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            verification["conditions"][0]["observed_value"],
+            serde_json::json!("13.00")
+        );
+
+        let policy = policy_queries::get_policy_by_id(&test_db.pool, policy.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.status, Some(PolicyStatus::Claimed));
+    }
+
+    #[tokio::test]
+    async fn test_same_window_is_not_claimed_twice() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+        let policy = create_rain_policy(&test_db.pool, user_id, Some("dup_station")).await;
+
+        insert_rain(
+            &test_db.pool,
+            "dup_station",
+            datetime(Month::June, 10, 8),
+            "12",
+        )
+        .await;
+
+        let processor = processor(&test_db.pool);
+        let first = processor
+            .process_policy(policy.clone(), datetime(Month::June, 15, 0))
+            .await
+            .unwrap();
+        assert!(matches!(first, PolicyOutcome::ClaimCreated(_)));
+
+        let second = processor
+            .process_policy(policy.clone(), datetime(Month::June, 20, 0))
+            .await
+            .unwrap();
+        assert_eq!(second, PolicyOutcome::AlreadyClaimed);
+
+        let claims = policy_queries::get_claims_by_policy_id(&test_db.pool, policy.id)
+            .await
+            .unwrap();
+        assert_eq!(claims.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_claim_is_not_filed_if_policy_cannot_be_claimed() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+        let policy = create_rain_policy(&test_db.pool, user_id, Some("late_station")).await;
+
+        insert_rain(
+            &test_db.pool,
+            "late_station",
+            datetime(Month::June, 10, 8),
+            "12",
+        )
+        .await;
+        // Cancelled after the processor read it as active
+        transition_policy(
+            &test_db.pool,
+            policy.id,
+            PolicyStatus::Cancelled,
+            Actor::User(user_id),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let result = processor(&test_db.pool)
+            .process_policy(policy.clone(), datetime(Month::June, 15, 0))
+            .await;
+        assert!(matches!(
+            result,
+            Err(ClaimsError::Lifecycle(LifecycleError::IllegalTransition {
+                from: PolicyStatus::Cancelled,
+                ..
+            }))
+        ));
+
+        let claims = policy_queries::get_claims_by_policy_id(&test_db.pool, policy.id)
+            .await
+            .unwrap();
+        assert!(claims.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_untriggered_policy_expires_after_end_date() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+        let policy = create_rain_policy(&test_db.pool, user_id, Some("dry_station")).await;
+
+        insert_rain(
+            &test_db.pool,
+            "dry_station",
+            datetime(Month::June, 10, 8),
+            "2",
+        )
+        .await;
+
+        let processor = processor(&test_db.pool);
+
+        // Still running: nothing happens
+        let report = processor
+            .run_once(datetime(Month::June, 20, 0))
+            .await
+            .unwrap();
+        assert_eq!(report.claims_created, 0);
+        assert_eq!(report.policies_expired, 0);
+
+        // Past the end date: expired, no claim
+        let report = processor
+            .run_once(datetime(Month::July, 2, 0))
+            .await
+            .unwrap();
+        assert_eq!(report.claims_created, 0);
+        assert_eq!(report.policies_expired, 1);
+
+        let policy = policy_queries::get_policy_by_id(&test_db.pool, policy.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.status, Some(PolicyStatus::Expired));
+        let claims = policy_queries::get_claims_by_policy_id(&test_db.pool, policy.id)
+            .await
+            .unwrap();
+        assert!(claims.is_empty());
+
+        // Expired policies are no longer processed
+        let report = processor
+            .run_once(datetime(Month::July, 3, 0))
+            .await
+            .unwrap();
+        assert_eq!(report.policies_evaluated, 0);
+    }
+
+    #[tokio::test]
+    async fn test_policy_without_station_expires() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+        let policy = create_rain_policy(&test_db.pool, user_id, None).await;
+
+        let outcome = processor(&test_db.pool)
+            .process_policy(policy, datetime(Month::July, 2, 0))
+            .await
+            .unwrap();
+        assert_eq!(outcome, PolicyOutcome::Expired);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_condition_is_reported_as_failure() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+        let policy = create_rain_policy(&test_db.pool, user_id, Some("bad_station")).await;
+
+        sqlx::query!(
+            "UPDATE policy_conditions SET measurement_unit = 'celsius' WHERE policy_id = $1",
+            policy.id
+        )
+        .execute(&test_db.pool)
+        .await
+        .unwrap();
+
+        let report = processor(&test_db.pool)
+            .run_once(datetime(Month::June, 15, 0))
+            .await
+            .unwrap();
+        assert_eq!(report.failed_policies, vec![policy.id]);
+    }
+}
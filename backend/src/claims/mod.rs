@@ -0,0 +1,5 @@
+pub mod claims_processor;
+pub mod trigger_evaluation;
+
+pub use claims_processor::*;
+pub use trigger_evaluation::*;
@@ -0,0 +1,904 @@
+use crate::db::models::{
+    ComparisonOperator, ConditionType, CreatePolicyCondition, MeasurementPeriod, MeasurementUnit,
+    PolicyCondition, PolicyWithConditions, WeatherData,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::types::time::PrimitiveDateTime;
+use time::{Date, Duration, Month, Time};
+
+// Error types for trigger evaluation. These indicate a policy_conditions row
+// that cannot be interpreted, not a condition that simply was not met.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum EvaluationError {
+    #[error("Unsupported measurement unit '{unit}' for condition type '{condition_type}'")]
+    UnsupportedUnit {
+        condition_type: ConditionType,
+        unit: MeasurementUnit,
+    },
+    #[error("Invalid consecutive days: {0}")]
+    InvalidConsecutiveDays(i32),
+}
+
+// Half-open time range [start, end) that satisfied a condition
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TriggerWindow {
+    pub start: PrimitiveDateTime,
+    pub end: PrimitiveDateTime,
+}
+
+// Aggregated value of a single period (day, week, month or whole coverage)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PeriodValue {
+    pub start: PrimitiveDateTime,
+    pub end: PrimitiveDateTime,
+    pub value: Decimal,
+    pub reading_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConditionEvaluation {
+    pub condition_id: i32,
+    pub met: bool,
+    // Threshold converted into the unit weather_data is stored in
+    pub normalized_threshold: Decimal,
+    pub window: Option<TriggerWindow>,
+    // The run of periods that met the condition, oldest first
+    pub periods: Vec<PeriodValue>,
+    // The period value furthest past the threshold within the window
+    pub observed_value: Option<Decimal>,
+    pub readings: Vec<WeatherData>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TriggerEvaluation {
+    pub policy_id: i32,
+    pub triggered: bool,
+    pub evaluated_at: PrimitiveDateTime,
+    pub window: Option<TriggerWindow>,
+    pub conditions: Vec<ConditionEvaluation>,
+}
+
+impl TriggerEvaluation {
+    // Readings from every triggering window, deduplicated and in time order
+    pub fn triggering_readings(&self) -> Vec<&WeatherData> {
+        let mut readings: Vec<&WeatherData> = self
+            .conditions
+            .iter()
+            .flat_map(|c| c.readings.iter())
+            .collect();
+        readings.sort_by_key(|r| (r.recorded_at, r.id));
+        readings.dedup_by_key(|r| r.id);
+        readings
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aggregation {
+    Sum,
+    Min,
+    Max,
+    Mean,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    Rainfall,
+    TemperatureMin,
+    TemperatureMax,
+    TemperatureAvg,
+    WindSpeed,
+    Humidity,
+    Pressure,
+}
+
+impl From<ConditionType> for Metric {
+    fn from(condition_type: ConditionType) -> Self {
+        match condition_type {
+            ConditionType::Rainfall => Metric::Rainfall,
+            ConditionType::TemperatureMin => Metric::TemperatureMin,
+            ConditionType::TemperatureMax => Metric::TemperatureMax,
+            ConditionType::TemperatureAvg => Metric::TemperatureAvg,
+            ConditionType::WindSpeed => Metric::WindSpeed,
+            ConditionType::Humidity => Metric::Humidity,
+            ConditionType::AtmosphericPressure => Metric::Pressure,
+        }
+    }
+}
+
+impl Metric {
+    fn value(self, reading: &WeatherData) -> Option<Decimal> {
+        match self {
+            Metric::Rainfall => reading.precipitation,
+            Metric::TemperatureMin | Metric::TemperatureMax | Metric::TemperatureAvg => {
+                reading.temperature
+            }
+            Metric::WindSpeed => reading.wind_speed,
+            Metric::Humidity => reading.humidity,
+            Metric::Pressure => reading.atmospheric_pressure,
+        }
+    }
+
+    fn aggregation(self) -> Aggregation {
+        match self {
+            Metric::Rainfall => Aggregation::Sum,
+            Metric::TemperatureMin => Aggregation::Min,
+            Metric::TemperatureMax | Metric::WindSpeed => Aggregation::Max,
+            Metric::TemperatureAvg | Metric::Humidity | Metric::Pressure => Aggregation::Mean,
+        }
+    }
+
+    // Convert a threshold into the unit weather_data stores for this metric:
+    // mm, celsius, km/h, percent and hPa respectively.
+    fn normalize_threshold(self, threshold: Decimal, unit: MeasurementUnit) -> Option<Decimal> {
+        use MeasurementUnit::*;
+        match (self, unit) {
+            (Metric::Rainfall, Millimeters) => Some(threshold),
+            (Metric::Rainfall, Centimeters) => Some(threshold * Decimal::from(10)),
+            (Metric::Rainfall, Inches) => Some(threshold * Decimal::new(254, 1)),
+            (Metric::TemperatureMin | Metric::TemperatureMax | Metric::TemperatureAvg, Celsius) => {
+                Some(threshold)
+            }
+            (
+                Metric::TemperatureMin | Metric::TemperatureMax | Metric::TemperatureAvg,
+                Fahrenheit,
+            ) => Some((threshold - Decimal::from(32)) * Decimal::from(5) / Decimal::from(9)),
+            (Metric::WindSpeed, KilometersPerHour) => Some(threshold),
+            (Metric::WindSpeed, MilesPerHour) => Some(threshold * Decimal::new(1_609_344, 6)),
+            (Metric::WindSpeed, MetersPerSecond) => Some(threshold * Decimal::new(36, 1)),
+            (Metric::Humidity, Percent) => Some(threshold),
+            (Metric::Pressure, Hectopascals) => Some(threshold),
+            _ => None,
+        }
+    }
+}
+
+// Evaluate every condition of a policy against its station's readings, as of
+// `as_of`. A policy is triggered once all of its conditions are met. A policy
+// without conditions never triggers.
+//
+// Each condition splits the coverage period into buckets according to its
+// measurement_period (calendar days, 7-day blocks from start_date, calendar
+// months, or the whole coverage period) and aggregates the readings in each
+// bucket: rainfall is summed, temperature_min/temperature_max/wind_speed take
+// the extreme, everything else is averaged. The condition is met when
+// `consecutive_days` consecutive buckets satisfy the operator; for weekly and
+// monthly periods this counts consecutive weeks or months.
+//
+// Buckets that have not finished by `as_of` only count when the partial value
+// can no longer change the outcome, e.g. rainfall already above a `>`
+// threshold. Such a bucket is still reported with its full extent, so the
+// trigger window stays the same as more readings arrive. Buckets with no
+// readings never satisfy a condition.
+pub fn evaluate_policy(
+    policy: &PolicyWithConditions,
+    readings: &[WeatherData],
+    as_of: PrimitiveDateTime,
+) -> Result<TriggerEvaluation, EvaluationError> {
+    let conditions = evaluate_conditions(
+        &policy.conditions,
+        policy.policy.start_date,
+        policy.policy.end_date,
+        readings,
+        as_of,
+    )?;
+
+    let triggered = !conditions.is_empty() && conditions.iter().all(|c| c.met);
+    let window = if triggered {
+        let windows = conditions.iter().filter_map(|c| c.window);
+        windows.reduce(|a, b| TriggerWindow {
+            start: a.start.min(b.start),
+            end: a.end.max(b.end),
+        })
+    } else {
+        None
+    };
+
+    Ok(TriggerEvaluation {
+        policy_id: policy.policy.id,
+        triggered,
+        evaluated_at: as_of,
+        window,
+        conditions,
+    })
+}
+
+// Evaluate conditions over an arbitrary coverage period, as evaluate_policy
+// does for a stored policy
+pub fn evaluate_conditions(
+    conditions: &[PolicyCondition],
+    coverage_start: PrimitiveDateTime,
+    coverage_end: PrimitiveDateTime,
+    readings: &[WeatherData],
+    as_of: PrimitiveDateTime,
+) -> Result<Vec<ConditionEvaluation>, EvaluationError> {
+    let mut readings: Vec<&WeatherData> = readings.iter().collect();
+    readings.sort_by_key(|r| r.recorded_at);
+
+    conditions
+        .iter()
+        .map(|condition| {
+            evaluate_condition(condition, coverage_start, coverage_end, &readings, as_of)
+        })
+        .collect()
+}
+
+// Check that a condition can be evaluated, before it is stored
+pub fn validate_condition(condition: &CreatePolicyCondition) -> Result<(), EvaluationError> {
+    normalized_threshold(
+        condition.condition_type,
+        condition.threshold_value,
+        condition.measurement_unit,
+    )?;
+    required_periods(condition.consecutive_days)?;
+    Ok(())
+}
+
+fn normalized_threshold(
+    condition_type: ConditionType,
+    threshold: Decimal,
+    unit: MeasurementUnit,
+) -> Result<Decimal, EvaluationError> {
+    Metric::from(condition_type)
+        .normalize_threshold(threshold, unit)
+        .ok_or(EvaluationError::UnsupportedUnit {
+            condition_type,
+            unit,
+        })
+}
+
+fn required_periods(consecutive_days: Option<i32>) -> Result<usize, EvaluationError> {
+    match consecutive_days.unwrap_or(1) {
+        n if n >= 1 => Ok(n as usize),
+        n => Err(EvaluationError::InvalidConsecutiveDays(n)),
+    }
+}
+
+fn evaluate_condition(
+    condition: &PolicyCondition,
+    coverage_start: PrimitiveDateTime,
+    coverage_end: PrimitiveDateTime,
+    readings: &[&WeatherData],
+    as_of: PrimitiveDateTime,
+) -> Result<ConditionEvaluation, EvaluationError> {
+    let metric = Metric::from(condition.condition_type);
+    let operator = condition.operator;
+    let period = condition.measurement_period;
+    let threshold = normalized_threshold(
+        condition.condition_type,
+        condition.threshold_value,
+        condition.measurement_unit,
+    )?;
+    let required = required_periods(condition.consecutive_days)?;
+
+    let aggregation = metric.aggregation();
+
+    let mut run: Vec<(PeriodValue, Vec<&WeatherData>)> = Vec::new();
+    for (start, end) in buckets(period, coverage_start, coverage_end) {
+        if start >= as_of {
+            break;
+        }
+        let complete = as_of >= end;
+
+        let bucket_readings: Vec<&WeatherData> = readings
+            .iter()
+            .copied()
+            .filter(|r| r.recorded_at >= start && r.recorded_at < end.min(as_of))
+            .filter(|r| metric.value(r).is_some())
+            .collect();
+
+        let value = aggregate(
+            aggregation,
+            bucket_readings.iter().filter_map(|r| metric.value(r)),
+        )
+        .filter(|_| complete || settled_early(aggregation, operator))
+        .filter(|value| operator.compare(*value, threshold));
+
+        let Some(value) = value else {
+            run.clear();
+            continue;
+        };
+
+        run.push((
+            PeriodValue {
+                start,
+                end,
+                value,
+                reading_count: bucket_readings.len(),
+            },
+            bucket_readings,
+        ));
+
+        if run.len() >= required {
+            let (periods, period_readings): (Vec<PeriodValue>, Vec<Vec<&WeatherData>>) =
+                run.into_iter().unzip();
+            let observed_value = extreme(operator, periods.iter().map(|p| p.value));
+            return Ok(ConditionEvaluation {
+                condition_id: condition.id,
+                met: true,
+                normalized_threshold: threshold,
+                window: Some(TriggerWindow {
+                    start: periods[0].start,
+                    end: periods[periods.len() - 1].end,
+                }),
+                periods,
+                observed_value,
+                readings: period_readings.into_iter().flatten().cloned().collect(),
+            });
+        }
+    }
+
+    Ok(ConditionEvaluation {
+        condition_id: condition.id,
+        met: false,
+        normalized_threshold: threshold,
+        window: None,
+        periods: Vec::new(),
+        observed_value: None,
+        readings: Vec::new(),
+    })
+}
+
+// Whether an unfinished bucket's partial value is already final for this
+// comparison: a running sum or max only grows, a running min only shrinks.
+fn settled_early(aggregation: Aggregation, operator: ComparisonOperator) -> bool {
+    matches!(
+        (aggregation, operator),
+        (
+            Aggregation::Sum | Aggregation::Max,
+            ComparisonOperator::GreaterThan | ComparisonOperator::GreaterOrEqual
+        ) | (
+            Aggregation::Min,
+            ComparisonOperator::LessThan | ComparisonOperator::LessOrEqual
+        )
+    )
+}
+
+fn aggregate(aggregation: Aggregation, values: impl Iterator<Item = Decimal>) -> Option<Decimal> {
+    let values: Vec<Decimal> = values.collect();
+    if values.is_empty() {
+        return None;
+    }
+    match aggregation {
+        Aggregation::Sum => Some(values.iter().sum()),
+        Aggregation::Min => values.iter().min().copied(),
+        Aggregation::Max => values.iter().max().copied(),
+        Aggregation::Mean => {
+            Some(values.iter().sum::<Decimal>() / Decimal::from(values.len() as u64))
+        }
+    }
+}
+
+fn extreme(operator: ComparisonOperator, values: impl Iterator<Item = Decimal>) -> Option<Decimal> {
+    match operator {
+        ComparisonOperator::LessThan | ComparisonOperator::LessOrEqual => values.min(),
+        ComparisonOperator::GreaterThan | ComparisonOperator::GreaterOrEqual => values.max(),
+        ComparisonOperator::Equal | ComparisonOperator::NotEqual => values.last(),
+    }
+}
+
+// Split [start, end) into half-open buckets for the given period
+fn buckets(
+    period: MeasurementPeriod,
+    start: PrimitiveDateTime,
+    end: PrimitiveDateTime,
+) -> Vec<(PrimitiveDateTime, PrimitiveDateTime)> {
+    let mut result = Vec::new();
+    if start >= end {
+        return result;
+    }
+
+    if period == MeasurementPeriod::Cumulative {
+        result.push((start, end));
+        return result;
+    }
+
+    let mut bucket_start = start;
+    while bucket_start < end {
+        let next = match period {
+            MeasurementPeriod::Daily => midnight(bucket_start.date()) + Duration::days(1),
+            MeasurementPeriod::Weekly => bucket_start + Duration::weeks(1),
+            MeasurementPeriod::Monthly => midnight(first_of_next_month(bucket_start.date())),
+            MeasurementPeriod::Cumulative => end,
+        };
+        let bucket_end = next.min(end);
+        result.push((bucket_start, bucket_end));
+        bucket_start = bucket_end;
+    }
+    result
+}
+
+fn midnight(date: Date) -> PrimitiveDateTime {
+    PrimitiveDateTime::new(date, Time::MIDNIGHT)
+}
+
+fn first_of_next_month(date: Date) -> Date {
+    let (year, month) = match date.month() {
+        Month::December => (date.year() + 1, Month::January),
+        month => (date.year(), month.next()),
+    };
+    Date::from_calendar_date(year, month, 1).expect("first of month is always valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{InsurancePolicy, PolicyStatus, PolicyType};
+    use std::str::FromStr;
+
+    fn datetime(month: Month, day: u8, hour: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2024, month, day).unwrap(),
+            Time::from_hms(hour, 0, 0).unwrap(),
+        )
+    }
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn policy(conditions: Vec<PolicyCondition>) -> PolicyWithConditions {
+        PolicyWithConditions {
+            policy: InsurancePolicy {
+                id: 1,
+                user_id: 1,
+                policy_template_id: None,
+                policy_name: "Evaluation Test".to_string(),
+                policy_type: PolicyType::Drought,
+                location_latitude: dec("40.7128"),
+                location_longitude: dec("-74.0060"),
+                location_h3_index: None,
+                location_name: None,
+                coverage_amount: dec("100.00"),
+                premium_amount: dec("5.00"),
+                currency: Some("ETH".to_string()),
+                start_date: datetime(Month::June, 1, 0),
+                end_date: datetime(Month::August, 1, 0),
+                status: Some(PolicyStatus::Active),
+                weather_station_id: Some("station".to_string()),
+                smart_contract_address: None,
+                purchase_transaction_hash: None,
+                blockchain_verified: Some(true),
+                verification_timestamp: None,
+                blockchain_block_number: None,
+                verification_error_message: None,
+                blockchain_policy_id: None,
+                cancelled_at: None,
+                refund_amount: None,
+                refund_transaction_hash: None,
+                created_at: None,
+                updated_at: None,
+            },
+            conditions,
+        }
+    }
+
+    fn condition(
+        id: i32,
+        condition_type: &str,
+        operator: &str,
+        threshold: &str,
+        unit: &str,
+        period: &str,
+        consecutive_days: Option<i32>,
+    ) -> PolicyCondition {
+        PolicyCondition {
+            id,
+            policy_id: 1,
+            condition_type: condition_type.parse().unwrap(),
+            operator: operator.parse().unwrap(),
+            threshold_value: dec(threshold),
+            measurement_unit: unit.parse().unwrap(),
+            measurement_period: period.parse().unwrap(),
+            consecutive_days,
+            created_at: None,
+        }
+    }
+
+    fn reading(id: i32, recorded_at: PrimitiveDateTime) -> WeatherData {
+        WeatherData {
+            id,
+            station_id: "station".to_string(),
+            recorded_at,
+            temperature: None,
+            humidity: None,
+            precipitation: None,
+            wind_speed: None,
+            wind_direction: None,
+            atmospheric_pressure: None,
+            data_source: Some("weatherxm".to_string()),
+            raw_data: None,
+            quality_score: None,
+            created_at: None,
+        }
+    }
+
+    fn rain(id: i32, recorded_at: PrimitiveDateTime, mm: &str) -> WeatherData {
+        WeatherData {
+            precipitation: Some(dec(mm)),
+            ..reading(id, recorded_at)
+        }
+    }
+
+    fn temperature(id: i32, recorded_at: PrimitiveDateTime, celsius: &str) -> WeatherData {
+        WeatherData {
+            temperature: Some(dec(celsius)),
+            ..reading(id, recorded_at)
+        }
+    }
+
+    // Two rain readings per day in June, `mm` each
+    fn daily_rain(days: std::ops::RangeInclusive<u8>, mm: &str) -> Vec<WeatherData> {
+        days.flat_map(|day| {
+            vec![
+                rain(day as i32 * 10, datetime(Month::June, day, 6), mm),
+                rain(day as i32 * 10 + 1, datetime(Month::June, day, 18), mm),
+            ]
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_daily_sum_triggers_single_day() {
+        let policy = policy(vec![condition(
+            1,
+            "rainfall",
+            ">",
+            "10",
+            "mm",
+            "daily",
+            Some(1),
+        )]);
+        let mut readings = daily_rain(1..=5, "2");
+        readings.push(rain(99, datetime(Month::June, 3, 12), "7"));
+
+        let result = evaluate_policy(&policy, &readings, datetime(Month::July, 1, 0)).unwrap();
+
+        assert!(result.triggered);
+        let window = result.window.unwrap();
+        assert_eq!(window.start, datetime(Month::June, 3, 0));
+        assert_eq!(window.end, datetime(Month::June, 4, 0));
+
+        let condition = &result.conditions[0];
+        assert_eq!(condition.observed_value, Some(dec("11")));
+        assert_eq!(condition.readings.len(), 3);
+        assert!(condition.readings.iter().any(|r| r.id == 99));
+    }
+
+    #[test]
+    fn test_consecutive_days_requires_unbroken_run() {
+        let policy = policy(vec![condition(
+            1,
+            "rainfall",
+            "<",
+            "5",
+            "mm",
+            "daily",
+            Some(3),
+        )]);
+        // Dry on June 1-2, wet June 3, dry June 4-6
+        let mut readings = daily_rain(1..=2, "1");
+        readings.extend(daily_rain(3..=3, "4"));
+        readings.extend(daily_rain(4..=6, "0.5"));
+
+        let result = evaluate_policy(&policy, &readings, datetime(Month::July, 1, 0)).unwrap();
+
+        assert!(result.triggered);
+        let condition = &result.conditions[0];
+        assert_eq!(condition.periods.len(), 3);
+        assert_eq!(condition.window.unwrap().start, datetime(Month::June, 4, 0));
+        assert_eq!(condition.window.unwrap().end, datetime(Month::June, 7, 0));
+        assert_eq!(condition.observed_value, Some(dec("1.0")));
+        assert_eq!(condition.readings.len(), 6);
+    }
+
+    #[test]
+    fn test_missing_day_breaks_consecutive_run() {
+        let policy = policy(vec![condition(
+            1,
+            "rainfall",
+            "<",
+            "5",
+            "mm",
+            "daily",
+            Some(3),
+        )]);
+        let mut readings = daily_rain(1..=2, "0");
+        readings.extend(daily_rain(4..=5, "0"));
+
+        let result = evaluate_policy(&policy, &readings, datetime(Month::July, 1, 0)).unwrap();
+
+        assert!(!result.triggered);
+        assert!(result.window.is_none());
+        assert!(result.conditions[0].readings.is_empty());
+    }
+
+    #[test]
+    fn test_daily_min_and_max_temperature() {
+        let readings = vec![
+            temperature(1, datetime(Month::June, 10, 3), "-3.5"),
+            temperature(2, datetime(Month::June, 10, 14), "12"),
+            temperature(3, datetime(Month::June, 11, 14), "36"),
+        ];
+
+        let freeze = policy(vec![condition(
+            1,
+            "temperature_min",
+            "<",
+            "-2",
+            "celsius",
+            "daily",
+            None,
+        )]);
+        let result = evaluate_policy(&freeze, &readings, datetime(Month::July, 1, 0)).unwrap();
+        assert!(result.triggered);
+        assert_eq!(result.conditions[0].observed_value, Some(dec("-3.5")));
+        assert_eq!(result.window.unwrap().start, datetime(Month::June, 10, 0));
+
+        let heat = policy(vec![condition(
+            1,
+            "temperature_max",
+            ">=",
+            "36",
+            "celsius",
+            "daily",
+            None,
+        )]);
+        let result = evaluate_policy(&heat, &readings, datetime(Month::July, 1, 0)).unwrap();
+        assert!(result.triggered);
+        assert_eq!(result.window.unwrap().start, datetime(Month::June, 11, 0));
+    }
+
+    #[test]
+    fn test_all_operators() {
+        let readings = vec![temperature(1, datetime(Month::June, 10, 12), "20")];
+        let cases = [
+            (">", "19", true),
+            (">", "20", false),
+            ("<", "21", true),
+            ("<", "20", false),
+            (">=", "20", true),
+            (">=", "21", false),
+            ("<=", "20", true),
+            ("<=", "19", false),
+            ("==", "20", true),
+            ("==", "20.5", false),
+            ("!=", "20.5", true),
+            ("!=", "20", false),
+        ];
+
+        for (operator, threshold, expected) in cases {
+            let policy = policy(vec![condition(
+                1,
+                "temperature",
+                operator,
+                threshold,
+                "celsius",
+                "daily",
+                None,
+            )]);
+            let result = evaluate_policy(&policy, &readings, datetime(Month::July, 1, 0)).unwrap();
+            assert_eq!(
+                result.triggered, expected,
+                "20 {} {} should be {}",
+                operator, threshold, expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_weekly_sum_uses_blocks_from_start_date() {
+        let policy = policy(vec![condition(
+            1, "rainfall", ">", "20", "mm", "weekly", None,
+        )]);
+        // 3mm/day across June 5-11 = 21mm, but split over the first two weeks
+        let readings: Vec<WeatherData> = (5..=11)
+            .map(|day| rain(day, datetime(Month::June, day as u8, 12), "3"))
+            .collect();
+
+        let result = evaluate_policy(&policy, &readings, datetime(Month::July, 1, 0)).unwrap();
+        assert!(!result.triggered);
+
+        // Another 9mm on June 12-14 pushes the second week (June 8-14) to 21mm
+        let mut readings = readings;
+        readings.extend((12..=14).map(|day| rain(day, datetime(Month::June, day as u8, 12), "3")));
+        let result = evaluate_policy(&policy, &readings, datetime(Month::July, 1, 0)).unwrap();
+
+        assert!(result.triggered);
+        let window = result.window.unwrap();
+        assert_eq!(window.start, datetime(Month::June, 8, 0));
+        assert_eq!(window.end, datetime(Month::June, 15, 0));
+        assert_eq!(result.conditions[0].observed_value, Some(dec("21")));
+    }
+
+    #[test]
+    fn test_monthly_uses_calendar_months() {
+        let policy = policy(vec![condition(
+            1, "rainfall", "<", "10", "mm", "monthly", None,
+        )]);
+        let readings = vec![
+            rain(1, datetime(Month::June, 15, 12), "30"),
+            rain(2, datetime(Month::July, 15, 12), "4"),
+        ];
+
+        let result = evaluate_policy(&policy, &readings, datetime(Month::August, 2, 0)).unwrap();
+
+        assert!(result.triggered);
+        let window = result.window.unwrap();
+        assert_eq!(window.start, datetime(Month::July, 1, 0));
+        assert_eq!(window.end, datetime(Month::August, 1, 0));
+    }
+
+    #[test]
+    fn test_cumulative_below_waits_for_period_end() {
+        let policy = policy(vec![condition(
+            1,
+            "rainfall",
+            "<",
+            "50",
+            "mm",
+            "cumulative",
+            None,
+        )]);
+        let readings = daily_rain(1..=10, "1");
+
+        let mid_period = evaluate_policy(&policy, &readings, datetime(Month::June, 20, 0)).unwrap();
+        assert!(!mid_period.triggered);
+
+        let after_end = evaluate_policy(&policy, &readings, datetime(Month::August, 2, 0)).unwrap();
+        assert!(after_end.triggered);
+        let window = after_end.window.unwrap();
+        assert_eq!(window.start, datetime(Month::June, 1, 0));
+        assert_eq!(window.end, datetime(Month::August, 1, 0));
+        assert_eq!(after_end.conditions[0].observed_value, Some(dec("20")));
+        assert_eq!(after_end.conditions[0].readings.len(), 20);
+    }
+
+    #[test]
+    fn test_cumulative_above_triggers_early() {
+        let policy = policy(vec![condition(
+            1,
+            "rainfall",
+            ">",
+            "15",
+            "mm",
+            "cumulative",
+            None,
+        )]);
+        let readings = daily_rain(1..=10, "1");
+
+        let result = evaluate_policy(&policy, &readings, datetime(Month::June, 20, 0)).unwrap();
+
+        assert!(result.triggered);
+        assert_eq!(result.window.unwrap().end, datetime(Month::August, 1, 0));
+    }
+
+    #[test]
+    fn test_incomplete_day_is_not_evaluated_for_below_threshold() {
+        let policy = policy(vec![condition(
+            1, "rainfall", "<", "5", "mm", "daily", None,
+        )]);
+        let readings = vec![rain(1, datetime(Month::June, 10, 6), "0")];
+
+        let result = evaluate_policy(&policy, &readings, datetime(Month::June, 10, 12)).unwrap();
+        assert!(!result.triggered);
+
+        let result = evaluate_policy(&policy, &readings, datetime(Month::June, 11, 0)).unwrap();
+        assert!(result.triggered);
+    }
+
+    #[test]
+    fn test_unit_conversion() {
+        let readings = vec![
+            temperature(1, datetime(Month::June, 10, 12), "-1"),
+            WeatherData {
+                wind_speed: Some(dec("100")),
+                ..reading(2, datetime(Month::June, 11, 12))
+            },
+        ];
+
+        // 32F is 0C, so -1C is below it
+        let freeze = policy(vec![condition(
+            1,
+            "temperature_min",
+            "<",
+            "32",
+            "fahrenheit",
+            "daily",
+            None,
+        )]);
+        let result = evaluate_policy(&freeze, &readings, datetime(Month::July, 1, 0)).unwrap();
+        assert!(result.triggered);
+        assert_eq!(result.conditions[0].normalized_threshold, dec("0"));
+
+        // 60 mph is ~96.56 km/h
+        let storm = policy(vec![condition(
+            1,
+            "wind_speed",
+            ">",
+            "60",
+            "mph",
+            "daily",
+            None,
+        )]);
+        let result = evaluate_policy(&storm, &readings, datetime(Month::July, 1, 0)).unwrap();
+        assert!(result.triggered);
+    }
+
+    #[test]
+    fn test_all_conditions_must_be_met() {
+        let conditions = vec![
+            condition(1, "rainfall", "<", "2", "mm", "daily", Some(2)),
+            condition(2, "temperature_max", ">", "35", "celsius", "daily", None),
+        ];
+        let mut readings = daily_rain(1..=2, "0");
+
+        let result = evaluate_policy(
+            &policy(conditions.clone()),
+            &readings,
+            datetime(Month::July, 1, 0),
+        )
+        .unwrap();
+        assert!(!result.triggered);
+        assert!(result.conditions[0].met);
+        assert!(!result.conditions[1].met);
+
+        readings.push(temperature(100, datetime(Month::June, 5, 15), "38"));
+        let result =
+            evaluate_policy(&policy(conditions), &readings, datetime(Month::July, 1, 0)).unwrap();
+        assert!(result.triggered);
+        let window = result.window.unwrap();
+        assert_eq!(window.start, datetime(Month::June, 1, 0));
+        assert_eq!(window.end, datetime(Month::June, 6, 0));
+        assert_eq!(result.triggering_readings().len(), 5);
+    }
+
+    #[test]
+    fn test_policy_without_conditions_never_triggers() {
+        let readings = daily_rain(1..=5, "100");
+        let result =
+            evaluate_policy(&policy(vec![]), &readings, datetime(Month::July, 1, 0)).unwrap();
+        assert!(!result.triggered);
+    }
+
+    #[test]
+    fn test_readings_outside_coverage_are_ignored() {
+        let policy = policy(vec![condition(
+            1, "rainfall", ">", "10", "mm", "daily", None,
+        )]);
+        let readings = vec![rain(1, datetime(Month::May, 31, 12), "50")];
+
+        let result = evaluate_policy(&policy, &readings, datetime(Month::July, 1, 0)).unwrap();
+        assert!(!result.triggered);
+    }
+
+    #[test]
+    fn test_invalid_conditions_are_errors() {
+        let as_of = datetime(Month::July, 1, 0);
+        let cases = [
+            (
+                condition(1, "rainfall", ">", "1", "celsius", "daily", None),
+                EvaluationError::UnsupportedUnit {
+                    condition_type: ConditionType::Rainfall,
+                    unit: MeasurementUnit::Celsius,
+                },
+            ),
+            (
+                condition(1, "wind_speed", ">", "1", "mm", "daily", None),
+                EvaluationError::UnsupportedUnit {
+                    condition_type: ConditionType::WindSpeed,
+                    unit: MeasurementUnit::Millimeters,
+                },
+            ),
+            (
+                condition(1, "rainfall", ">", "1", "mm", "daily", Some(0)),
+                EvaluationError::InvalidConsecutiveDays(0),
+            ),
+        ];
+
+        for (condition, expected) in cases {
+            let result = evaluate_policy(&policy(vec![condition]), &[], as_of);
+            assert_eq!(result.unwrap_err(), expected);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::db::models::*;
+use sqlx::{PgExecutor, Pool, Postgres};
+use tracing::{debug, info};
+
+// ============================================================================
+// ADMIN AUDIT LOG QUERIES
+// ============================================================================
+
+pub async fn create_audit_log_entry(
+    executor: impl PgExecutor<'_>,
+    entry: &CreateAdminAuditEntry,
+) -> Result<AdminAuditEntry, sqlx::Error> {
+    info!(
+        "Admin {} performed {} on {} {}",
+        entry.admin_user_id, entry.action, entry.target_type, entry.target_id
+    );
+
+    sqlx::query_as!(
+        AdminAuditEntry,
+        "INSERT INTO admin_audit_log (admin_user_id, action, target_type, target_id, details)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id, admin_user_id, action, target_type, target_id, details, created_at",
+        entry.admin_user_id,
+        entry.action,
+        entry.target_type,
+        entry.target_id,
+        entry.details
+    )
+    .fetch_one(executor)
+    .await
+}
+
+// The most recent entries first
+pub async fn get_audit_log(
+    pool: &Pool<Postgres>,
+    limit: i64,
+) -> Result<Vec<AdminAuditEntry>, sqlx::Error> {
+    debug!("Fetching the latest {} admin audit log entries", limit);
+
+    sqlx::query_as!(
+        AdminAuditEntry,
+        "SELECT id, admin_user_id, action, target_type, target_id, details, created_at
+         FROM admin_audit_log
+         ORDER BY created_at DESC, id DESC
+         LIMIT $1",
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,284 @@
+use crate::db::models::*;
+use sqlx::{Pool, Postgres};
+use tracing::{debug, info};
+
+// ============================================================================
+// INDEXER CHECKPOINT QUERIES
+// ============================================================================
+
+pub async fn get_indexer_checkpoint(
+    pool: &Pool<Postgres>,
+    contract_address: &str,
+) -> Result<Option<IndexerCheckpoint>, sqlx::Error> {
+    debug!(
+        "Fetching indexer checkpoint for contract {}",
+        contract_address
+    );
+
+    let checkpoint = sqlx::query_as!(
+        IndexerCheckpoint,
+        "SELECT contract_address, last_indexed_block, last_block_hash, updated_at
+         FROM blockchain_indexer_checkpoints
+         WHERE contract_address = LOWER($1)",
+        contract_address
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(checkpoint)
+}
+
+pub async fn save_indexer_checkpoint(
+    pool: &Pool<Postgres>,
+    contract_address: &str,
+    last_indexed_block: i64,
+    last_block_hash: &str,
+) -> Result<(), sqlx::Error> {
+    debug!(
+        "Saving indexer checkpoint for contract {} at block {}",
+        contract_address, last_indexed_block
+    );
+
+    sqlx::query!(
+        "INSERT INTO blockchain_indexer_checkpoints (contract_address, last_indexed_block, last_block_hash)
+         VALUES (LOWER($1), $2, $3)
+         ON CONFLICT (contract_address) DO UPDATE
+         SET last_indexed_block = EXCLUDED.last_indexed_block,
+             last_block_hash = EXCLUDED.last_block_hash,
+             updated_at = CURRENT_TIMESTAMP",
+        contract_address,
+        last_indexed_block,
+        last_block_hash
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_indexer_checkpoint(
+    pool: &Pool<Postgres>,
+    contract_address: &str,
+) -> Result<(), sqlx::Error> {
+    info!(
+        "Deleting indexer checkpoint for contract {}",
+        contract_address
+    );
+
+    sqlx::query!(
+        "DELETE FROM blockchain_indexer_checkpoints WHERE contract_address = LOWER($1)",
+        contract_address
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// BLOCKCHAIN EVENT QUERIES
+// ============================================================================
+
+// Insert an event, or return the stored row if the log was already indexed
+pub async fn upsert_blockchain_event(
+    pool: &Pool<Postgres>,
+    event_data: &CreateBlockchainEvent,
+) -> Result<BlockchainEvent, sqlx::Error> {
+    debug!(
+        "Storing {} event from {} (log {})",
+        event_data.event_name, event_data.transaction_hash, event_data.log_index
+    );
+
+    let event = sqlx::query_as!(
+        BlockchainEvent,
+        "INSERT INTO blockchain_events
+         (contract_address, event_name, blockchain_policy_id, transaction_hash, log_index,
+          block_number, block_hash, event_data)
+         VALUES (LOWER($1), $2, $3, LOWER($4), $5, $6, $7, $8)
+         ON CONFLICT (transaction_hash, log_index) DO UPDATE
+         SET block_number = EXCLUDED.block_number, block_hash = EXCLUDED.block_hash
+         RETURNING id, contract_address, event_name, blockchain_policy_id, transaction_hash,
+         log_index, block_number, block_hash, event_data, created_at",
+        event_data.contract_address,
+        event_data.event_name,
+        event_data.blockchain_policy_id,
+        event_data.transaction_hash,
+        event_data.log_index,
+        event_data.block_number,
+        event_data.block_hash,
+        event_data.event_data
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(event)
+}
+
+pub async fn get_blockchain_events(
+    pool: &Pool<Postgres>,
+    contract_address: &str,
+) -> Result<Vec<BlockchainEvent>, sqlx::Error> {
+    debug!("Fetching indexed events for contract {}", contract_address);
+
+    let events = sqlx::query_as!(
+        BlockchainEvent,
+        "SELECT id, contract_address, event_name, blockchain_policy_id, transaction_hash,
+         log_index, block_number, block_hash, event_data, created_at
+         FROM blockchain_events
+         WHERE contract_address = LOWER($1)
+         ORDER BY block_number, log_index",
+        contract_address
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+// Remove events above a block after a reorg. Their reconciliation issues
+// are removed with them.
+pub async fn delete_blockchain_events_after_block(
+    pool: &Pool<Postgres>,
+    contract_address: &str,
+    block_number: i64,
+) -> Result<u64, sqlx::Error> {
+    info!(
+        "Deleting indexed events for contract {} after block {}",
+        contract_address, block_number
+    );
+
+    let result = sqlx::query!(
+        "DELETE FROM blockchain_events WHERE contract_address = LOWER($1) AND block_number > $2",
+        contract_address,
+        block_number
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ============================================================================
+// RECONCILIATION QUERIES
+// ============================================================================
+
+// Find the policy row for an on-chain policy, either by its recorded id or
+// by the transaction that created it
+pub async fn find_policy_for_blockchain_policy(
+    pool: &Pool<Postgres>,
+    contract_address: &str,
+    blockchain_policy_id: i64,
+    purchase_transaction_hash: Option<&str>,
+) -> Result<Option<InsurancePolicy>, sqlx::Error> {
+    debug!(
+        "Looking up policy for on-chain policy {} on {}",
+        blockchain_policy_id, contract_address
+    );
+
+    let policy = sqlx::query_as!(
+        InsurancePolicy,
+        "SELECT id, user_id, policy_template_id, policy_name, policy_type as \"policy_type: _\",
+         location_latitude, location_longitude, location_h3_index, location_name,
+         coverage_amount, premium_amount, currency, start_date, end_date, status as \"status: _\",
+         weather_station_id, smart_contract_address, purchase_transaction_hash,
+         blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message,
+         blockchain_policy_id, cancelled_at, refund_amount, refund_transaction_hash,
+         created_at, updated_at
+         FROM insurance_policies
+         WHERE (blockchain_policy_id = $2
+                AND (smart_contract_address IS NULL OR LOWER(smart_contract_address) = LOWER($1)))
+            OR LOWER(purchase_transaction_hash) = LOWER($3)
+         ORDER BY (blockchain_policy_id = $2) DESC NULLS LAST, id
+         LIMIT 1",
+        contract_address,
+        blockchain_policy_id,
+        purchase_transaction_hash
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(policy)
+}
+
+pub async fn set_blockchain_policy_id(
+    pool: &Pool<Postgres>,
+    policy_id: i32,
+    blockchain_policy_id: i64,
+) -> Result<bool, sqlx::Error> {
+    info!(
+        "Linking policy {} to on-chain policy {}",
+        policy_id, blockchain_policy_id
+    );
+
+    let result = sqlx::query!(
+        "UPDATE insurance_policies SET blockchain_policy_id = $2, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND blockchain_policy_id IS NULL",
+        policy_id,
+        blockchain_policy_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_user_wallet_address(
+    pool: &Pool<Postgres>,
+    user_id: i32,
+) -> Result<Option<String>, sqlx::Error> {
+    let wallet_address =
+        sqlx::query_scalar!("SELECT wallet_address FROM users WHERE id = $1", user_id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(wallet_address.flatten())
+}
+
+// Record an issue unless the same issue was already raised for the event.
+// Returns None for duplicates.
+pub async fn create_reconciliation_issue(
+    pool: &Pool<Postgres>,
+    issue_data: &CreateReconciliationIssue,
+) -> Result<Option<ReconciliationIssue>, sqlx::Error> {
+    let issue = sqlx::query_as!(
+        ReconciliationIssue,
+        "INSERT INTO blockchain_reconciliation_issues (event_id, issue_type, policy_id, claim_id, details)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (event_id, issue_type) DO NOTHING
+         RETURNING id, event_id, issue_type, policy_id, claim_id, details, resolved, resolved_at, created_at",
+        issue_data.event_id,
+        issue_data.issue_type,
+        issue_data.policy_id,
+        issue_data.claim_id,
+        issue_data.details
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(issue) = &issue {
+        info!(
+            "Recorded {} reconciliation issue {} for event {}",
+            issue.issue_type, issue.id, issue.event_id
+        );
+    }
+
+    Ok(issue)
+}
+
+pub async fn get_unresolved_reconciliation_issues(
+    pool: &Pool<Postgres>,
+) -> Result<Vec<ReconciliationIssue>, sqlx::Error> {
+    debug!("Fetching unresolved reconciliation issues");
+
+    let issues = sqlx::query_as!(
+        ReconciliationIssue,
+        "SELECT id, event_id, issue_type, policy_id, claim_id, details, resolved, resolved_at, created_at
+         FROM blockchain_reconciliation_issues
+         WHERE resolved = false
+         ORDER BY id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(issues)
+}
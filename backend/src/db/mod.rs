@@ -1,4 +1,10 @@
+pub mod admin_queries;
+pub mod blockchain_queries;
 pub mod models;
 pub mod policy_queries;
 pub mod pool;
+pub mod rate_limit_queries;
+pub mod session_queries;
+pub mod token_queries;
 pub mod user_queries;
+pub mod wallet_queries;
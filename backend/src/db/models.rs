@@ -1,5 +1,5 @@
 use rust_decimal::Decimal;
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sqlx::types::time::PrimitiveDateTime;
 use time::OffsetDateTime;
 
@@ -14,6 +14,253 @@ where
     Ok(PrimitiveDateTime::new(offset_dt.date(), offset_dt.time()))
 }
 
+fn deserialize_optional_primitive_datetime<'de, D>(
+    deserializer: D,
+) -> Result<Option<PrimitiveDateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Wrapper(#[serde(deserialize_with = "deserialize_primitive_datetime")] PrimitiveDateTime);
+
+    Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(value)| value))
+}
+
+// Timestamps are stored without an offset and are UTC
+fn serialize_primitive_datetime<S>(
+    value: &PrimitiveDateTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let formatted = value
+        .assume_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .map_err(serde::ser::Error::custom)?;
+    serializer.serialize_str(&formatted)
+}
+
+// ============================================================================
+// ENUMS
+// ============================================================================
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("Unknown {kind}: {value}")]
+pub struct InvalidEnumValue {
+    pub kind: &'static str,
+    pub value: String,
+}
+
+// Enums stored as VARCHAR, with CHECK constraints limiting the columns to the
+// canonical values. Parsing ignores case and surrounding whitespace and also
+// accepts the listed aliases.
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident($kind:literal) {
+            $($(#[$variant_meta:meta])* $variant:ident => $value:literal $(| $alias:literal)*,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)+
+        }
+
+        impl $name {
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $($name::$variant => $value,)+
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = InvalidEnumValue;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s.trim().to_lowercase().as_str() {
+                    $($value $(| $alias)* => Ok($name::$variant),)+
+                    _ => Err(InvalidEnumValue {
+                        kind: $kind,
+                        value: s.to_string(),
+                    }),
+                }
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl sqlx::Type<sqlx::Postgres> for $name {
+            fn type_info() -> sqlx::postgres::PgTypeInfo {
+                <String as sqlx::Type<sqlx::Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+                <String as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+            }
+        }
+
+        impl sqlx::Encode<'_, sqlx::Postgres> for $name {
+            fn encode_by_ref(
+                &self,
+                buf: &mut sqlx::postgres::PgArgumentBuffer,
+            ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+                <&str as sqlx::Encode<sqlx::Postgres>>::encode(self.as_str(), buf)
+            }
+        }
+
+        impl<'r> sqlx::Decode<'r, sqlx::Postgres> for $name {
+            fn decode(
+                value: sqlx::postgres::PgValueRef<'r>,
+            ) -> Result<Self, sqlx::error::BoxDynError> {
+                let s = <&str as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+                Ok(s.parse()?)
+            }
+        }
+    };
+}
+
+string_enum! {
+    pub enum PolicyType("policy type") {
+        Drought => "drought",
+        Rain => "rain",
+        Temperature => "temperature",
+        Freeze => "freeze",
+        Heat => "heat",
+        Wind => "wind",
+        Storm => "storm",
+    }
+}
+
+string_enum! {
+    pub enum PolicyStatus("policy status") {
+        PendingVerification => "pending_verification",
+        Active => "active",
+        Expired => "expired",
+        Claimed => "claimed",
+        Cancelled => "cancelled",
+    }
+}
+
+string_enum! {
+    pub enum ClaimStatus("claim status") {
+        Pending => "pending",
+        Approved => "approved",
+        Rejected => "rejected",
+        Paid => "paid",
+    }
+}
+
+string_enum! {
+    pub enum ConditionType("condition type") {
+        Rainfall => "rainfall" | "precipitation",
+        TemperatureMin => "temperature_min",
+        TemperatureMax => "temperature_max",
+        TemperatureAvg => "temperature_avg" | "temperature",
+        WindSpeed => "wind_speed",
+        Humidity => "humidity",
+        AtmosphericPressure => "atmospheric_pressure" | "pressure",
+    }
+}
+
+string_enum! {
+    pub enum ComparisonOperator("operator") {
+        GreaterThan => ">",
+        LessThan => "<",
+        GreaterOrEqual => ">=",
+        LessOrEqual => "<=",
+        Equal => "==" | "=",
+        NotEqual => "!=" | "<>",
+    }
+}
+
+string_enum! {
+    pub enum MeasurementUnit("measurement unit") {
+        Millimeters => "mm",
+        Centimeters => "cm",
+        Inches => "in" | "inch" | "inches",
+        Celsius => "celsius" | "c",
+        Fahrenheit => "fahrenheit" | "f",
+        KilometersPerHour => "km/h" | "kmh" | "kph",
+        MilesPerHour => "mph",
+        MetersPerSecond => "m/s",
+        Percent => "%" | "percent",
+        Hectopascals => "hpa" | "mbar",
+    }
+}
+
+string_enum! {
+    pub enum MeasurementPeriod("measurement period") {
+        Daily => "daily",
+        Weekly => "weekly",
+        Monthly => "monthly",
+        Cumulative => "cumulative",
+    }
+}
+
+string_enum! {
+    #[derive(Default)]
+    pub enum UserRole("user role") {
+        #[default]
+        User => "user",
+        Admin => "admin",
+        Oracle => "oracle",
+    }
+}
+
+string_enum! {
+    pub enum UserTokenPurpose("user token purpose") {
+        VerifyEmail => "verify_email",
+        ResetPassword => "reset_password",
+    }
+}
+
+impl ComparisonOperator {
+    pub fn compare(self, value: Decimal, threshold: Decimal) -> bool {
+        match self {
+            ComparisonOperator::GreaterThan => value > threshold,
+            ComparisonOperator::LessThan => value < threshold,
+            ComparisonOperator::GreaterOrEqual => value >= threshold,
+            ComparisonOperator::LessOrEqual => value <= threshold,
+            ComparisonOperator::Equal => value == threshold,
+            ComparisonOperator::NotEqual => value != threshold,
+        }
+    }
+
+    // Whether the operator is met by values above or below the threshold.
+    // None for == and !=, which have no direction.
+    pub fn direction(self) -> Option<std::cmp::Ordering> {
+        match self {
+            ComparisonOperator::GreaterThan | ComparisonOperator::GreaterOrEqual => {
+                Some(std::cmp::Ordering::Greater)
+            }
+            ComparisonOperator::LessThan | ComparisonOperator::LessOrEqual => {
+                Some(std::cmp::Ordering::Less)
+            }
+            ComparisonOperator::Equal | ComparisonOperator::NotEqual => None,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct CreateUser {
     pub name: String,
@@ -25,11 +272,28 @@ pub struct CreateUser {
 pub struct User {
     pub id: i32,
     pub name: String,
-    pub email: String,
-    pub password_hash: String,
+    // Accounts created through Sign-In With Ethereum have neither an email
+    // nor a password
+    pub email: Option<String>,
+    pub password_hash: Option<String>,
     pub wallet_address: Option<String>,
+    // When the user confirmed they receive mail at their email address
+    pub email_verified_at: Option<PrimitiveDateTime>,
     pub created_at: Option<PrimitiveDateTime>,
     pub updated_at: Option<PrimitiveDateTime>,
+    #[serde(default)]
+    pub role: UserRole,
+}
+
+impl User {
+    // How the user is named in logs: their email, or their wallet address if
+    // they have no email
+    pub fn identifier(&self) -> &str {
+        self.email
+            .as_deref()
+            .or(self.wallet_address.as_deref())
+            .unwrap_or("(unknown)")
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -45,6 +309,140 @@ pub struct CurrentUser {
     pub password_hash: String,
 }
 
+// A signed-in device. Access tokens name their session and stop working once
+// it is revoked or expires.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub user_id: i32,
+    #[serde(skip_serializing)]
+    pub refresh_token_hash: String,
+    pub expires_at: PrimitiveDateTime,
+    pub revoked_at: Option<PrimitiveDateTime>,
+    pub last_refreshed_at: Option<PrimitiveDateTime>,
+    pub created_at: Option<PrimitiveDateTime>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+// A nonce for a Sign-In With Ethereum message
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuthNonce {
+    pub nonce: String,
+    #[serde(
+        serialize_with = "serialize_primitive_datetime",
+        deserialize_with = "deserialize_primitive_datetime"
+    )]
+    pub expires_at: PrimitiveDateTime,
+}
+
+// An EIP-4361 message and the wallet's signature of it. Used both to sign in
+// and to prove ownership of a wallet being linked.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignedSiweMessage {
+    pub message: String,
+    pub signature: String,
+}
+
+// A wallet the user has proven they own
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UserWallet {
+    pub id: i32,
+    pub user_id: i32,
+    pub wallet_address: String,
+    pub verified_at: PrimitiveDateTime,
+    pub created_at: Option<PrimitiveDateTime>,
+}
+
+// A verification or password reset token, found by the hash of the token
+// that was mailed to the user
+#[derive(Clone, Debug)]
+pub struct UserToken {
+    pub user_id: i32,
+    // The address a verification token was sent to
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+}
+
+// The signed-in user's own account, without its password hash
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserProfile {
+    pub id: i32,
+    pub name: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub wallet_address: Option<String>,
+    // Wallet-only accounts have no password to change
+    pub has_password: bool,
+    pub role: UserRole,
+    pub created_at: Option<PrimitiveDateTime>,
+}
+
+impl From<User> for UserProfile {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            email_verified: user.email_verified_at.is_some(),
+            wallet_address: user.wallet_address,
+            has_password: user.password_hash.is_some(),
+            role: user.role,
+            created_at: user.created_at,
+        }
+    }
+}
+
+// Fields left out are not changed. Changing the email of an account with a
+// password needs that password; a wallet-only account signs a fresh SIWE
+// message with one of its wallets instead.
+#[derive(Deserialize, Debug)]
+pub struct UpdateProfileRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub current_password: Option<String>,
+    pub signed_message: Option<SignedSiweMessage>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+// Accounts with a password have to give it to be deleted, and wallet-only
+// accounts a fresh SIWE message signed with one of their wallets
+#[derive(Deserialize, Debug, Default)]
+pub struct DeleteAccountRequest {
+    pub current_password: Option<String>,
+    pub signed_message: Option<SignedSiweMessage>,
+}
+
+// A token bucket of a rate limited route
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimitBucket {
+    pub tokens: f64,
+    pub updated_at: PrimitiveDateTime,
+}
+
 // ============================================================================
 // INSURANCE POLICY MODELS
 // ============================================================================
@@ -54,7 +452,7 @@ pub struct PolicyTemplate {
     pub id: i32,
     pub template_name: String,
     pub description: Option<String>,
-    pub policy_type: String,
+    pub policy_type: PolicyType,
     pub default_conditions: Option<serde_json::Value>,
     pub min_coverage_amount: Decimal,
     pub max_coverage_amount: Decimal,
@@ -68,20 +466,32 @@ pub struct PolicyTemplate {
 pub struct CreatePolicyTemplate {
     pub template_name: String,
     pub description: Option<String>,
-    pub policy_type: String,
+    pub policy_type: PolicyType,
     pub default_conditions: Option<serde_json::Value>,
     pub min_coverage_amount: Decimal,
     pub max_coverage_amount: Decimal,
     pub base_premium_rate: Decimal,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+// Changes to a policy template. Fields left out keep their current value.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct UpdatePolicyTemplate {
+    pub template_name: Option<String>,
+    pub description: Option<String>,
+    pub policy_type: Option<PolicyType>,
+    pub default_conditions: Option<serde_json::Value>,
+    pub min_coverage_amount: Option<Decimal>,
+    pub max_coverage_amount: Option<Decimal>,
+    pub base_premium_rate: Option<Decimal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, sqlx::FromRow)]
 pub struct InsurancePolicy {
     pub id: i32,
     pub user_id: i32,
     pub policy_template_id: Option<i32>,
     pub policy_name: String,
-    pub policy_type: String,
+    pub policy_type: PolicyType,
     pub location_latitude: Decimal,
     pub location_longitude: Decimal,
     pub location_h3_index: Option<String>,
@@ -91,7 +501,7 @@ pub struct InsurancePolicy {
     pub currency: Option<String>,
     pub start_date: PrimitiveDateTime,
     pub end_date: PrimitiveDateTime,
-    pub status: Option<String>,
+    pub status: Option<PolicyStatus>,
     pub weather_station_id: Option<String>,
     pub smart_contract_address: Option<String>,
     pub purchase_transaction_hash: Option<String>,
@@ -99,6 +509,10 @@ pub struct InsurancePolicy {
     pub verification_timestamp: Option<PrimitiveDateTime>,
     pub blockchain_block_number: Option<i64>,
     pub verification_error_message: Option<String>,
+    pub blockchain_policy_id: Option<i64>,
+    pub cancelled_at: Option<PrimitiveDateTime>,
+    pub refund_amount: Option<Decimal>,
+    pub refund_transaction_hash: Option<String>,
     pub created_at: Option<PrimitiveDateTime>,
     pub updated_at: Option<PrimitiveDateTime>,
 }
@@ -108,7 +522,7 @@ pub struct CreateInsurancePolicy {
     pub user_id: i32,
     pub policy_template_id: Option<i32>,
     pub policy_name: String,
-    pub policy_type: String,
+    pub policy_type: PolicyType,
     pub location_latitude: Decimal,
     pub location_longitude: Decimal,
     pub location_h3_index: Option<String>,
@@ -129,7 +543,7 @@ pub struct CreateInsurancePolicy {
 pub struct CreateInsurancePolicyRequest {
     pub policy_template_id: Option<i32>,
     pub policy_name: String,
-    pub policy_type: String,
+    pub policy_type: PolicyType,
     pub location_latitude: Decimal,
     pub location_longitude: Decimal,
     pub location_h3_index: Option<String>,
@@ -144,29 +558,85 @@ pub struct CreateInsurancePolicyRequest {
     pub weather_station_id: Option<String>,
     pub smart_contract_address: Option<String>,
     pub purchase_transaction_hash: Option<String>,
+    // Signed quote the premium and terms were priced with
+    #[serde(default)]
+    pub quote_token: Option<String>,
+    // Changes to the template's default conditions for this policy
+    #[serde(default)]
+    pub condition_overrides: Vec<PolicyConditionOverride>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PolicyCondition {
     pub id: i32,
     pub policy_id: i32,
-    pub condition_type: String,
-    pub operator: String,
+    pub condition_type: ConditionType,
+    pub operator: ComparisonOperator,
     pub threshold_value: Decimal,
-    pub measurement_unit: String,
-    pub measurement_period: String,
+    pub measurement_unit: MeasurementUnit,
+    pub measurement_period: MeasurementPeriod,
     pub consecutive_days: Option<i32>,
     pub created_at: Option<PrimitiveDateTime>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct CreatePolicyCondition {
     pub policy_id: i32,
-    pub condition_type: String,
-    pub operator: String,
+    pub condition_type: ConditionType,
+    pub operator: ComparisonOperator,
     pub threshold_value: Decimal,
-    pub measurement_unit: String,
-    pub measurement_period: String,
+    pub measurement_unit: MeasurementUnit,
+    pub measurement_period: MeasurementPeriod,
+    pub consecutive_days: Option<i32>,
+}
+
+// A condition in a template's default_conditions JSON,
+// {"conditions": [{"type", "operator", "threshold", "unit", "period", "consecutive_days",
+// "min_threshold", "max_threshold"}]}
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TemplateCondition {
+    #[serde(rename = "type")]
+    pub condition_type: ConditionType,
+    pub operator: ComparisonOperator,
+    pub threshold: Decimal,
+    pub unit: MeasurementUnit,
+    pub period: MeasurementPeriod,
+    pub consecutive_days: Option<i32>,
+    // The range policies may move the threshold within. Without one the
+    // threshold can't be overridden.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_threshold: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_threshold: Option<Decimal>,
+}
+
+impl TemplateCondition {
+    // The allowed threshold range, if the template gives both ends of one
+    pub fn threshold_range(&self) -> Option<(Decimal, Decimal)> {
+        self.min_threshold.zip(self.max_threshold)
+    }
+
+    // A range needs both ends, with the default threshold inside it
+    pub fn has_valid_threshold_range(&self) -> bool {
+        match (self.min_threshold, self.max_threshold) {
+            (None, None) => true,
+            (Some(min), Some(max)) => min <= self.threshold && self.threshold <= max,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TemplateConditions {
+    pub conditions: Vec<TemplateCondition>,
+}
+
+// Replaces fields of the template condition with the same condition_type
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PolicyConditionOverride {
+    pub condition_type: ConditionType,
+    pub operator: Option<ComparisonOperator>,
+    pub threshold: Option<Decimal>,
     pub consecutive_days: Option<i32>,
 }
 
@@ -202,12 +672,51 @@ pub struct CreateWeatherData {
     pub quality_score: Option<i32>,
 }
 
+// A weather station and the H3 cell it is in
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WeatherStation {
+    pub id: String,
+    pub latitude: Decimal,
+    pub longitude: Decimal,
+    pub h3_index: String,
+    pub updated_at: PrimitiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateWeatherStation {
+    pub id: String,
+    pub latitude: Decimal,
+    pub longitude: Decimal,
+    pub h3_index: String,
+}
+
+// One transition in a policy's status history
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PolicyStatusChange {
+    pub id: i32,
+    pub policy_id: i32,
+    pub from_status: PolicyStatus,
+    pub to_status: PolicyStatus,
+    pub actor: String,
+    pub reason: Option<String>,
+    pub created_at: Option<PrimitiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreatePolicyStatusChange {
+    pub policy_id: i32,
+    pub from_status: PolicyStatus,
+    pub to_status: PolicyStatus,
+    pub actor: String,
+    pub reason: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PolicyClaim {
     pub id: i32,
     pub policy_id: i32,
     pub claim_amount: Decimal,
-    pub claim_status: Option<String>,
+    pub claim_status: Option<ClaimStatus>,
     pub trigger_date: PrimitiveDateTime,
     pub trigger_period_start: Option<PrimitiveDateTime>,
     pub trigger_period_end: Option<PrimitiveDateTime>,
@@ -232,6 +741,220 @@ pub struct CreatePolicyClaim {
     pub verification_data: Option<serde_json::Value>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IndexerCheckpoint {
+    pub contract_address: String,
+    pub last_indexed_block: i64,
+    pub last_block_hash: String,
+    pub updated_at: Option<PrimitiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BlockchainEvent {
+    pub id: i32,
+    pub contract_address: String,
+    pub event_name: String,
+    pub blockchain_policy_id: i64,
+    pub transaction_hash: String,
+    pub log_index: i32,
+    pub block_number: i64,
+    pub block_hash: String,
+    pub event_data: serde_json::Value,
+    pub created_at: Option<PrimitiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateBlockchainEvent {
+    pub contract_address: String,
+    pub event_name: String,
+    pub blockchain_policy_id: i64,
+    pub transaction_hash: String,
+    pub log_index: i32,
+    pub block_number: i64,
+    pub block_hash: String,
+    pub event_data: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReconciliationIssue {
+    pub id: i32,
+    pub event_id: i32,
+    pub issue_type: String,
+    pub policy_id: Option<i32>,
+    pub claim_id: Option<i32>,
+    pub details: Option<serde_json::Value>,
+    pub resolved: Option<bool>,
+    pub resolved_at: Option<PrimitiveDateTime>,
+    pub created_at: Option<PrimitiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateReconciliationIssue {
+    pub event_id: i32,
+    pub issue_type: String,
+    pub policy_id: Option<i32>,
+    pub claim_id: Option<i32>,
+    pub details: Option<serde_json::Value>,
+}
+
+// ============================================================================
+// QUOTE MODELS
+// ============================================================================
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateQuoteRequest {
+    pub policy_template_id: i32,
+    pub location_h3_index: String,
+    pub coverage_amount: Decimal,
+    #[serde(deserialize_with = "deserialize_primitive_datetime")]
+    pub start_date: PrimitiveDateTime,
+    #[serde(deserialize_with = "deserialize_primitive_datetime")]
+    pub end_date: PrimitiveDateTime,
+    pub weather_station_id: Option<String>,
+    #[serde(default)]
+    pub condition_overrides: Vec<PolicyConditionOverride>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyQuote {
+    pub quote_token: String,
+    pub policy_template_id: i32,
+    pub policy_type: PolicyType,
+    pub location_h3_index: String,
+    pub coverage_amount: Decimal,
+    pub premium_amount: Decimal,
+    pub currency: String,
+    #[serde(
+        serialize_with = "serialize_primitive_datetime",
+        deserialize_with = "deserialize_primitive_datetime"
+    )]
+    pub start_date: PrimitiveDateTime,
+    #[serde(
+        serialize_with = "serialize_primitive_datetime",
+        deserialize_with = "deserialize_primitive_datetime"
+    )]
+    pub end_date: PrimitiveDateTime,
+    // Share of historical windows in which the template would have paid out,
+    // or None when there was too little weather data to estimate it
+    pub trigger_probability: Option<Decimal>,
+    pub historical_samples: usize,
+    // The station the policy will be evaluated against. Policies created
+    // from the quote must name it.
+    pub weather_station_id: String,
+    // Every station whose history the premium was priced from, nearest first
+    pub weather_station_ids: Vec<String>,
+    #[serde(
+        serialize_with = "serialize_primitive_datetime",
+        deserialize_with = "deserialize_primitive_datetime"
+    )]
+    pub expires_at: PrimitiveDateTime,
+}
+
+// ============================================================================
+// POLICY LISTING MODELS
+// ============================================================================
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicySort {
+    #[default]
+    CreatedAt,
+    EndDate,
+    CoverageAmount,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Query parameters of GET /policies and GET /admin/policies. `from` and `to`
+// select policies whose coverage overlaps the range.
+#[derive(Deserialize, Debug, Default)]
+pub struct PolicyListQuery {
+    pub status: Option<PolicyStatus>,
+    pub policy_type: Option<PolicyType>,
+    #[serde(default, deserialize_with = "deserialize_optional_primitive_datetime")]
+    pub from: Option<PrimitiveDateTime>,
+    #[serde(default, deserialize_with = "deserialize_optional_primitive_datetime")]
+    pub to: Option<PrimitiveDateTime>,
+    pub h3_prefix: Option<String>,
+    pub blockchain_verified: Option<bool>,
+    #[serde(default)]
+    pub sort: PolicySort,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+// Filters on policies. Without a user_id, every user's policies match.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyFilter {
+    pub user_id: Option<i32>,
+    pub status: Option<PolicyStatus>,
+    pub policy_type: Option<PolicyType>,
+    pub from: Option<PrimitiveDateTime>,
+    pub to: Option<PrimitiveDateTime>,
+    pub h3_prefix: Option<String>,
+    pub blockchain_verified: Option<bool>,
+}
+
+// The value a policy is sorted by. Policies without a created_at sort as if
+// created at the Unix epoch.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum PolicySortKey {
+    Timestamp(PrimitiveDateTime),
+    Amount(Decimal),
+}
+
+#[derive(Serialize, Debug)]
+pub struct PolicyPage {
+    pub policies: Vec<InsurancePolicy>,
+    pub total_count: i64,
+    pub next_cursor: Option<String>,
+}
+
+// ============================================================================
+// ADMIN MODELS
+// ============================================================================
+
+// A change made through the admin API
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AdminAuditEntry {
+    pub id: i32,
+    pub admin_user_id: Option<i32>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: i32,
+    pub details: Option<serde_json::Value>,
+    pub created_at: Option<PrimitiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateAdminAuditEntry {
+    pub admin_user_id: i32,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: i32,
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ClaimRejection {
+    pub rejection_reason: String,
+}
+
+// Sets a policy's status regardless of the usual lifecycle rules
+#[derive(Deserialize, Debug)]
+pub struct ForcePolicyStatus {
+    pub status: PolicyStatus,
+    pub reason: String,
+}
+
 // Helper structs for API responses
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PolicyWithConditions {
@@ -247,6 +970,40 @@ pub struct PolicyWithClaims {
     pub claims: Vec<PolicyClaim>,
 }
 
+// Where a policy stands with its on-chain purchase
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Verified,
+    Pending,
+    Failed,
+}
+
+impl VerificationStatus {
+    pub fn of(policy: &InsurancePolicy) -> Self {
+        if policy.blockchain_verified == Some(true) {
+            VerificationStatus::Verified
+        } else if policy.verification_error_message.is_some() {
+            VerificationStatus::Failed
+        } else {
+            VerificationStatus::Pending
+        }
+    }
+}
+
+// A policy with its conditions, claims, status history and the latest
+// readings from its weather station during the coverage period
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PolicyDetails {
+    #[serde(flatten)]
+    pub policy: InsurancePolicy,
+    pub verification_status: VerificationStatus,
+    pub conditions: Vec<PolicyCondition>,
+    pub claims: Vec<PolicyClaim>,
+    pub status_history: Vec<PolicyStatusChange>,
+    pub weather_data: Vec<WeatherData>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -345,11 +1102,13 @@ mod tests {
         let user = User {
             id: 42,
             name: "Alice Johnson".to_string(),
-            email: "alice@example.com".to_string(),
-            password_hash: "$2b$12$hashed_password_string".to_string(),
+            email: Some("alice@example.com".to_string()),
+            password_hash: Some("$2b$12$hashed_password_string".to_string()),
             wallet_address: None,
+            email_verified_at: None,
             created_at: None,
             updated_at: None,
+            role: UserRole::User,
         };
 
         let json = serde_json::to_string(&user).unwrap();
@@ -374,8 +1133,11 @@ mod tests {
         let user: User = serde_json::from_str(json).unwrap();
         assert_eq!(user.id, 123);
         assert_eq!(user.name, "Bob Wilson");
-        assert_eq!(user.email, "bob@example.com");
-        assert_eq!(user.password_hash, "$2b$12$another_hashed_password");
+        assert_eq!(user.email.as_deref(), Some("bob@example.com"));
+        assert_eq!(
+            user.password_hash.as_deref(),
+            Some("$2b$12$another_hashed_password")
+        );
         assert!(user.created_at.is_none());
         assert!(user.updated_at.is_none());
     }
@@ -385,11 +1147,13 @@ mod tests {
         let original_user = User {
             id: 99,
             name: "Clone Test".to_string(),
-            email: "clone@test.com".to_string(),
-            password_hash: "hashed_clone_password".to_string(),
+            email: Some("clone@test.com".to_string()),
+            password_hash: Some("hashed_clone_password".to_string()),
             wallet_address: None,
+            email_verified_at: None,
             created_at: None,
             updated_at: None,
+            role: UserRole::User,
         };
 
         let cloned_user = original_user.clone();
@@ -404,11 +1168,13 @@ mod tests {
         let user = User {
             id: -1,
             name: "Negative ID User".to_string(),
-            email: "negative@example.com".to_string(),
-            password_hash: "some_hash".to_string(),
+            email: Some("negative@example.com".to_string()),
+            password_hash: Some("some_hash".to_string()),
             wallet_address: None,
+            email_verified_at: None,
             created_at: None,
             updated_at: None,
+            role: UserRole::User,
         };
 
         let json = serde_json::to_string(&user).unwrap();
@@ -421,11 +1187,13 @@ mod tests {
         let user = User {
             id: i32::MAX,
             name: "Max ID User".to_string(),
-            email: "max@example.com".to_string(),
-            password_hash: "max_hash".to_string(),
+            email: Some("max@example.com".to_string()),
+            password_hash: Some("max_hash".to_string()),
             wallet_address: None,
+            email_verified_at: None,
             created_at: None,
             updated_at: None,
+            role: UserRole::User,
         };
 
         let json = serde_json::to_string(&user).unwrap();
@@ -435,9 +1203,10 @@ mod tests {
 
     #[test]
     fn test_user_missing_required_fields_error() {
+        // Email and password are optional, as wallet-only accounts have neither
         let incomplete_json = r#"{
             "id": 123,
-            "name": "Incomplete User"
+            "email": "incomplete@example.com"
         }"#;
 
         let result: Result<User, _> = serde_json::from_str(incomplete_json);
@@ -619,4 +1388,67 @@ mod tests {
         assert_eq!(current_user.password_hash, "hash_with_extras");
         // Extra fields should be ignored during deserialization
     }
+
+    #[test]
+    fn test_enum_values_round_trip() {
+        for status in [PolicyStatus::Active, PolicyStatus::Cancelled] {
+            assert_eq!(status.as_str().parse::<PolicyStatus>(), Ok(status));
+        }
+        for unit in [MeasurementUnit::KilometersPerHour, MeasurementUnit::Percent] {
+            assert_eq!(unit.to_string().parse::<MeasurementUnit>(), Ok(unit));
+        }
+        for policy_type in [PolicyType::Drought, PolicyType::Storm] {
+            let json = serde_json::to_string(&policy_type).unwrap();
+            assert_eq!(
+                serde_json::from_str::<PolicyType>(&json).unwrap(),
+                policy_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_enum_aliases() {
+        assert_eq!(" Precipitation ".parse(), Ok(ConditionType::Rainfall));
+        assert_eq!("temperature".parse(), Ok(ConditionType::TemperatureAvg));
+        assert_eq!("<>".parse(), Ok(ComparisonOperator::NotEqual));
+        assert_eq!("KPH".parse(), Ok(MeasurementUnit::KilometersPerHour));
+        assert_eq!(ComparisonOperator::Equal.as_str(), "==");
+    }
+
+    #[test]
+    fn test_invalid_enum_values_are_rejected() {
+        assert_eq!(
+            "snow_depth".parse::<ConditionType>(),
+            Err(InvalidEnumValue {
+                kind: "condition type",
+                value: "snow_depth".to_string(),
+            })
+        );
+        assert!("~".parse::<ComparisonOperator>().is_err());
+        assert!("hourly".parse::<MeasurementPeriod>().is_err());
+        assert!("aproved".parse::<ClaimStatus>().is_err());
+
+        let err = serde_json::from_str::<PolicyType>(r#""earthquake""#).unwrap_err();
+        assert!(err.to_string().contains("Unknown policy type: earthquake"));
+    }
+
+    #[test]
+    fn test_comparison_operators() {
+        let value = Decimal::from(20);
+        let cases = [
+            (">", "19", true),
+            (">", "20", false),
+            ("<", "21", true),
+            (">=", "20", true),
+            ("<=", "19", false),
+            ("==", "20", true),
+            ("!=", "20", false),
+        ];
+
+        for (operator, threshold, expected) in cases {
+            let operator: ComparisonOperator = operator.parse().unwrap();
+            let threshold: Decimal = threshold.parse().unwrap();
+            assert_eq!(operator.compare(value, threshold), expected);
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use crate::db::models::*;
-use sqlx::{Pool, Postgres};
+use rust_decimal::Decimal;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{PgExecutor, Pool, Postgres, QueryBuilder};
 use tracing::{debug, info};
 
 // ============================================================================
@@ -13,7 +15,7 @@ pub async fn get_all_policy_templates(
 
     let templates = sqlx::query_as!(
         PolicyTemplate,
-        "SELECT id, template_name, description, policy_type, default_conditions, 
+        "SELECT id, template_name, description, policy_type as \"policy_type: _\", default_conditions, 
          min_coverage_amount, max_coverage_amount, base_premium_rate, is_active, 
          created_at, updated_at 
          FROM policy_templates 
@@ -35,7 +37,7 @@ pub async fn get_policy_template_by_id(
 
     let template = sqlx::query_as!(
         PolicyTemplate,
-        "SELECT id, template_name, description, policy_type, default_conditions, 
+        "SELECT id, template_name, description, policy_type as \"policy_type: _\", default_conditions, 
          min_coverage_amount, max_coverage_amount, base_premium_rate, is_active, 
          created_at, updated_at 
          FROM policy_templates 
@@ -55,7 +57,7 @@ pub async fn get_policy_template_by_id(
 }
 
 pub async fn create_policy_template(
-    pool: &Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     template_data: &CreatePolicyTemplate,
 ) -> Result<PolicyTemplate, sqlx::Error> {
     info!(
@@ -68,50 +70,188 @@ pub async fn create_policy_template(
         "INSERT INTO policy_templates 
          (template_name, description, policy_type, default_conditions, min_coverage_amount, max_coverage_amount, base_premium_rate)
          VALUES ($1, $2, $3, $4, $5, $6, $7)
-         RETURNING id, template_name, description, policy_type, default_conditions, 
+         RETURNING id, template_name, description, policy_type as \"policy_type: _\", default_conditions, 
          min_coverage_amount, max_coverage_amount, base_premium_rate, is_active, created_at, updated_at",
         template_data.template_name,
         template_data.description,
-        template_data.policy_type,
+        template_data.policy_type.as_str(),
         template_data.default_conditions,
         template_data.min_coverage_amount,
         template_data.max_coverage_amount,
         template_data.base_premium_rate
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     info!("Created policy template with id: {}", template.id);
     Ok(template)
 }
 
+pub async fn update_policy_template(
+    executor: impl PgExecutor<'_>,
+    template_id: i32,
+    template_data: &CreatePolicyTemplate,
+) -> Result<Option<PolicyTemplate>, sqlx::Error> {
+    info!("Updating policy template with id: {}", template_id);
+
+    let template = sqlx::query_as!(
+        PolicyTemplate,
+        "UPDATE policy_templates
+         SET template_name = $1, description = $2, policy_type = $3, default_conditions = $4,
+         min_coverage_amount = $5, max_coverage_amount = $6, base_premium_rate = $7,
+         updated_at = CURRENT_TIMESTAMP
+         WHERE id = $8
+         RETURNING id, template_name, description, policy_type as \"policy_type: _\", default_conditions, 
+         min_coverage_amount, max_coverage_amount, base_premium_rate, is_active, created_at, updated_at",
+        template_data.template_name,
+        template_data.description,
+        template_data.policy_type.as_str(),
+        template_data.default_conditions,
+        template_data.min_coverage_amount,
+        template_data.max_coverage_amount,
+        template_data.base_premium_rate,
+        template_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(template)
+}
+
+// Inactive templates are no longer offered, but their policies keep them
+pub async fn set_policy_template_active(
+    executor: impl PgExecutor<'_>,
+    template_id: i32,
+    is_active: bool,
+) -> Result<Option<PolicyTemplate>, sqlx::Error> {
+    info!(
+        "Setting policy template {} active: {}",
+        template_id, is_active
+    );
+
+    let template = sqlx::query_as!(
+        PolicyTemplate,
+        "UPDATE policy_templates SET is_active = $1, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $2
+         RETURNING id, template_name, description, policy_type as \"policy_type: _\", default_conditions, 
+         min_coverage_amount, max_coverage_amount, base_premium_rate, is_active, created_at, updated_at",
+        is_active,
+        template_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(template)
+}
+
 // ============================================================================
 // INSURANCE POLICY QUERIES
 // ============================================================================
 
-pub async fn get_policies_by_user_id(
+// The expression policies are sorted by. Matches the listing indexes.
+fn policy_sort_expression(sort: PolicySort) -> &'static str {
+    match sort {
+        PolicySort::CreatedAt => "COALESCE(created_at, 'epoch'::timestamp)",
+        PolicySort::EndDate => "end_date",
+        PolicySort::CoverageAmount => "coverage_amount",
+    }
+}
+
+fn push_policy_filter(builder: &mut QueryBuilder<'_, Postgres>, filter: &PolicyFilter) {
+    builder.push(" WHERE TRUE");
+    if let Some(user_id) = filter.user_id {
+        builder.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(status) = filter.status {
+        builder.push(" AND status = ").push_bind(status.as_str());
+    }
+    if let Some(policy_type) = filter.policy_type {
+        builder
+            .push(" AND policy_type = ")
+            .push_bind(policy_type.as_str());
+    }
+    if let Some(from) = filter.from {
+        builder.push(" AND end_date >= ").push_bind(from);
+    }
+    if let Some(to) = filter.to {
+        builder.push(" AND start_date <= ").push_bind(to);
+    }
+    if let Some(prefix) = &filter.h3_prefix {
+        // Callers only pass hexadecimal prefixes, so there is nothing to escape
+        builder
+            .push(" AND location_h3_index LIKE ")
+            .push_bind(format!("{}%", prefix.to_lowercase()));
+    }
+    if let Some(verified) = filter.blockchain_verified {
+        builder
+            .push(" AND COALESCE(blockchain_verified, false) = ")
+            .push_bind(verified);
+    }
+}
+
+pub async fn count_policies(
+    pool: &Pool<Postgres>,
+    filter: &PolicyFilter,
+) -> Result<i64, sqlx::Error> {
+    let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM insurance_policies");
+    push_policy_filter(&mut builder, filter);
+
+    builder.build_query_scalar().fetch_one(pool).await
+}
+
+// A page of the policies matching `filter`, starting after the policy with
+// sort key `after`. Ties are broken by id.
+pub async fn list_policies(
     pool: &Pool<Postgres>,
-    user_id: i32,
+    filter: &PolicyFilter,
+    sort: PolicySort,
+    order: SortOrder,
+    after: Option<(&PolicySortKey, i32)>,
+    limit: i64,
 ) -> Result<Vec<InsurancePolicy>, sqlx::Error> {
-    debug!("Fetching policies for user id: {}", user_id);
+    debug!(
+        "Listing policies matching {:?} by {:?} {:?}",
+        filter, sort, order
+    );
 
-    let policies = sqlx::query_as!(
-        InsurancePolicy,
+    let sort_expression = policy_sort_expression(sort);
+    let (comparison, direction) = match order {
+        SortOrder::Asc => (">", "ASC"),
+        SortOrder::Desc => ("<", "DESC"),
+    };
+
+    let mut builder = QueryBuilder::new(
         "SELECT id, user_id, policy_template_id, policy_name, policy_type,
          location_latitude, location_longitude, location_h3_index, location_name,
          coverage_amount, premium_amount, currency, start_date, end_date, status,
          weather_station_id, smart_contract_address, purchase_transaction_hash,
          blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message,
+         blockchain_policy_id, cancelled_at, refund_amount, refund_transaction_hash,
          created_at, updated_at
-         FROM insurance_policies 
-         WHERE user_id = $1
-         ORDER BY created_at DESC",
-        user_id
-    )
-    .fetch_all(pool)
-    .await?;
-
-    info!("Retrieved {} policies for user {}", policies.len(), user_id);
+         FROM insurance_policies",
+    );
+    push_policy_filter(&mut builder, filter);
+    if let Some((key, id)) = after {
+        builder.push(format!(" AND ({}, id) {} (", sort_expression, comparison));
+        match key {
+            PolicySortKey::Timestamp(value) => builder.push_bind(*value),
+            PolicySortKey::Amount(value) => builder.push_bind(*value),
+        };
+        builder.push(", ").push_bind(id).push(")");
+    }
+    builder
+        .push(format!(
+            " ORDER BY {} {}, id {} LIMIT ",
+            sort_expression, direction, direction
+        ))
+        .push_bind(limit);
+
+    let policies = builder
+        .build_query_as::<InsurancePolicy>()
+        .fetch_all(pool)
+        .await?;
+
+    debug!("Retrieved {} policies", policies.len());
     Ok(policies)
 }
 
@@ -123,11 +263,12 @@ pub async fn get_policy_by_id(
 
     let policy = sqlx::query_as!(
         InsurancePolicy,
-        "SELECT id, user_id, policy_template_id, policy_name, policy_type,
+        "SELECT id, user_id, policy_template_id, policy_name, policy_type as \"policy_type: _\",
          location_latitude, location_longitude, location_h3_index, location_name,
-         coverage_amount, premium_amount, currency, start_date, end_date, status,
+         coverage_amount, premium_amount, currency, start_date, end_date, status as \"status: _\",
          weather_station_id, smart_contract_address, purchase_transaction_hash,
          blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message,
+         blockchain_policy_id, cancelled_at, refund_amount, refund_transaction_hash,
          created_at, updated_at
          FROM insurance_policies 
          WHERE id = $1",
@@ -163,16 +304,17 @@ pub async fn create_insurance_policy(
           location_h3_index, location_name, coverage_amount, premium_amount, currency, start_date, end_date,
           weather_station_id, smart_contract_address, purchase_transaction_hash)
          VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
-         RETURNING id, user_id, policy_template_id, policy_name, policy_type,
+         RETURNING id, user_id, policy_template_id, policy_name, policy_type as \"policy_type: _\",
          location_latitude, location_longitude, location_h3_index, location_name,
-         coverage_amount, premium_amount, currency, start_date, end_date, status,
+         coverage_amount, premium_amount, currency, start_date, end_date, status as \"status: _\",
          weather_station_id, smart_contract_address, purchase_transaction_hash,
          blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message,
+         blockchain_policy_id, cancelled_at, refund_amount, refund_transaction_hash,
          created_at, updated_at",
         policy_data.user_id,
         policy_data.policy_template_id,
         policy_data.policy_name,
-        policy_data.policy_type,
+        policy_data.policy_type.as_str(),
         policy_data.location_latitude,
         policy_data.location_longitude,
         policy_data.location_h3_index,
@@ -193,8 +335,9 @@ pub async fn create_insurance_policy(
     Ok(policy)
 }
 
+// Verified policies start active. Unverified ones wait in pending_verification.
 pub async fn create_insurance_policy_with_verification(
-    pool: &Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     policy_data: &CreateInsurancePolicy,
     verification_result: &crate::blockchain::VerificationResult,
 ) -> Result<InsurancePolicy, sqlx::Error> {
@@ -211,18 +354,21 @@ pub async fn create_insurance_policy_with_verification(
          (user_id, policy_template_id, policy_name, policy_type, location_latitude, location_longitude,
           location_h3_index, location_name, coverage_amount, premium_amount, currency, start_date, end_date,
           weather_station_id, smart_contract_address, purchase_transaction_hash,
-          blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, CURRENT_TIMESTAMP, $18, $19)
-         RETURNING id, user_id, policy_template_id, policy_name, policy_type,
+          blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message,
+          blockchain_policy_id, status)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, CURRENT_TIMESTAMP, $18, $19, $20,
+                 CASE WHEN $17 THEN 'active' ELSE 'pending_verification' END)
+         RETURNING id, user_id, policy_template_id, policy_name, policy_type as \"policy_type: _\",
          location_latitude, location_longitude, location_h3_index, location_name,
-         coverage_amount, premium_amount, currency, start_date, end_date, status,
+         coverage_amount, premium_amount, currency, start_date, end_date, status as \"status: _\",
          weather_station_id, smart_contract_address, purchase_transaction_hash,
          blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message,
+         blockchain_policy_id, cancelled_at, refund_amount, refund_transaction_hash,
          created_at, updated_at",
         policy_data.user_id,
         policy_data.policy_template_id,
         policy_data.policy_name,
-        policy_data.policy_type,
+        policy_data.policy_type.as_str(),
         policy_data.location_latitude,
         policy_data.location_longitude,
         policy_data.location_h3_index,
@@ -237,28 +383,94 @@ pub async fn create_insurance_policy_with_verification(
         policy_data.purchase_transaction_hash,
         verification_result.verified,
         verification_result.block_number.map(|n| n as i64),
-        verification_result.error_message.as_deref()
+        verification_result.error_message.as_deref(),
+        verification_result
+            .blockchain_policy
+            .as_ref()
+            .and_then(|p| i64::try_from(p.policy_id).ok())
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     info!("Created verified insurance policy with id: {}", policy.id);
     Ok(policy)
 }
 
-pub async fn update_policy_status(
+// Create a verified policy together with its conditions. Either everything is
+// written or nothing is.
+pub async fn create_insurance_policy_with_conditions(
+    pool: &Pool<Postgres>,
+    policy_data: &CreateInsurancePolicy,
+    verification_result: &crate::blockchain::VerificationResult,
+    conditions: &[CreatePolicyCondition],
+) -> Result<PolicyWithConditions, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let policy =
+        create_insurance_policy_with_verification(&mut *tx, policy_data, verification_result)
+            .await?;
+    let mut created_conditions = Vec::with_capacity(conditions.len());
+    for condition in conditions {
+        let condition_data = CreatePolicyCondition {
+            policy_id: policy.id,
+            ..condition.clone()
+        };
+        created_conditions.push(create_policy_condition(&mut *tx, &condition_data).await?);
+    }
+
+    tx.commit().await?;
+
+    info!(
+        "Created policy {} with {} conditions",
+        policy.id,
+        created_conditions.len()
+    );
+    Ok(PolicyWithConditions {
+        policy,
+        conditions: created_conditions,
+    })
+}
+
+pub async fn get_active_policies(
     pool: &Pool<Postgres>,
+) -> Result<Vec<InsurancePolicy>, sqlx::Error> {
+    debug!("Fetching all active policies");
+
+    let policies = sqlx::query_as!(
+        InsurancePolicy,
+        "SELECT id, user_id, policy_template_id, policy_name, policy_type as \"policy_type: _\",
+         location_latitude, location_longitude, location_h3_index, location_name,
+         coverage_amount, premium_amount, currency, start_date, end_date, status as \"status: _\",
+         weather_station_id, smart_contract_address, purchase_transaction_hash,
+         blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message,
+         blockchain_policy_id, cancelled_at, refund_amount, refund_transaction_hash,
+         created_at, updated_at
+         FROM insurance_policies 
+         WHERE status = 'active'
+         ORDER BY id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    info!("Retrieved {} active policies", policies.len());
+    Ok(policies)
+}
+
+// Write a policy's status without checking the transition. Status changes
+// should go through policies::lifecycle, which checks and records them.
+pub async fn set_policy_status(
+    executor: impl PgExecutor<'_>,
     policy_id: i32,
-    new_status: &str,
+    new_status: PolicyStatus,
 ) -> Result<bool, sqlx::Error> {
     info!("Updating policy {} status to: {}", policy_id, new_status);
 
     let result = sqlx::query!(
         "UPDATE insurance_policies SET status = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2",
-        new_status,
+        new_status.as_str(),
         policy_id
     )
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     let updated = result.rows_affected() > 0;
@@ -271,6 +483,152 @@ pub async fn update_policy_status(
     Ok(updated)
 }
 
+// Record when a policy was cancelled and the refund owed for it
+pub async fn record_policy_cancellation(
+    executor: impl PgExecutor<'_>,
+    policy_id: i32,
+    cancelled_at: PrimitiveDateTime,
+    refund_amount: Decimal,
+) -> Result<InsurancePolicy, sqlx::Error> {
+    info!(
+        "Recording cancellation of policy {} with refund {}",
+        policy_id, refund_amount
+    );
+
+    let policy = sqlx::query_as!(
+        InsurancePolicy,
+        "UPDATE insurance_policies
+         SET cancelled_at = $2, refund_amount = $3, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1
+         RETURNING id, user_id, policy_template_id, policy_name, policy_type as \"policy_type: _\",
+         location_latitude, location_longitude, location_h3_index, location_name,
+         coverage_amount, premium_amount, currency, start_date, end_date, status as \"status: _\",
+         weather_station_id, smart_contract_address, purchase_transaction_hash,
+         blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message,
+         blockchain_policy_id, cancelled_at, refund_amount, refund_transaction_hash,
+         created_at, updated_at",
+        policy_id,
+        cancelled_at,
+        refund_amount
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(policy)
+}
+
+pub async fn set_refund_transaction_hash(
+    executor: impl PgExecutor<'_>,
+    policy_id: i32,
+    transaction_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    info!(
+        "Recording refund transaction {} for policy {}",
+        transaction_hash, policy_id
+    );
+
+    let result = sqlx::query!(
+        "UPDATE insurance_policies
+         SET refund_transaction_hash = $2, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1",
+        policy_id,
+        transaction_hash
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Cancelled on-chain policies whose refund has not been sent, cancelled
+// before `cancelled_before`
+pub async fn get_unpaid_refunds(
+    pool: &Pool<Postgres>,
+    cancelled_before: PrimitiveDateTime,
+) -> Result<Vec<InsurancePolicy>, sqlx::Error> {
+    debug!("Fetching cancelled policies with unpaid refunds");
+
+    sqlx::query_as!(
+        InsurancePolicy,
+        "SELECT id, user_id, policy_template_id, policy_name, policy_type as \"policy_type: _\",
+         location_latitude, location_longitude, location_h3_index, location_name,
+         coverage_amount, premium_amount, currency, start_date, end_date, status as \"status: _\",
+         weather_station_id, smart_contract_address, purchase_transaction_hash,
+         blockchain_verified, verification_timestamp, blockchain_block_number, verification_error_message,
+         blockchain_policy_id, cancelled_at, refund_amount, refund_transaction_hash,
+         created_at, updated_at
+         FROM insurance_policies
+         WHERE status = 'cancelled' AND blockchain_policy_id IS NOT NULL
+         AND refund_amount IS NOT NULL AND refund_transaction_hash IS NULL
+         AND cancelled_at < $1
+         ORDER BY cancelled_at",
+        cancelled_before
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Lock a policy's row until the end of the transaction and return its status.
+// Policies without a status are active.
+pub async fn lock_policy_status(
+    executor: impl PgExecutor<'_>,
+    policy_id: i32,
+) -> Result<Option<PolicyStatus>, sqlx::Error> {
+    debug!("Locking policy {} for a status change", policy_id);
+
+    let status = sqlx::query_scalar!(
+        "SELECT COALESCE(status, 'active') as \"status!: PolicyStatus\"
+         FROM insurance_policies WHERE id = $1 FOR UPDATE",
+        policy_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(status)
+}
+
+pub async fn create_policy_status_change(
+    executor: impl PgExecutor<'_>,
+    change: &CreatePolicyStatusChange,
+) -> Result<PolicyStatusChange, sqlx::Error> {
+    let created = sqlx::query_as!(
+        PolicyStatusChange,
+        "INSERT INTO policy_status_history (policy_id, from_status, to_status, actor, reason)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id, policy_id, from_status as \"from_status: _\", to_status as \"to_status: _\",
+         actor, reason, created_at",
+        change.policy_id,
+        change.from_status.as_str(),
+        change.to_status.as_str(),
+        change.actor,
+        change.reason
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(created)
+}
+
+pub async fn get_policy_status_history(
+    pool: &Pool<Postgres>,
+    policy_id: i32,
+) -> Result<Vec<PolicyStatusChange>, sqlx::Error> {
+    debug!("Fetching status history for policy {}", policy_id);
+
+    let history = sqlx::query_as!(
+        PolicyStatusChange,
+        "SELECT id, policy_id, from_status as \"from_status: _\", to_status as \"to_status: _\",
+         actor, reason, created_at
+         FROM policy_status_history WHERE policy_id = $1
+         ORDER BY created_at, id",
+        policy_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(history)
+}
+
 // ============================================================================
 // POLICY CONDITION QUERIES
 // ============================================================================
@@ -283,8 +641,8 @@ pub async fn get_conditions_by_policy_id(
 
     let conditions = sqlx::query_as!(
         PolicyCondition,
-        "SELECT id, policy_id, condition_type, operator, threshold_value,
-         measurement_unit, measurement_period, consecutive_days, created_at
+        "SELECT id, policy_id, condition_type as \"condition_type: _\", operator as \"operator: _\", threshold_value,
+         measurement_unit as \"measurement_unit: _\", measurement_period as \"measurement_period: _\", consecutive_days, created_at
          FROM policy_conditions 
          WHERE policy_id = $1
          ORDER BY id",
@@ -302,7 +660,7 @@ pub async fn get_conditions_by_policy_id(
 }
 
 pub async fn create_policy_condition(
-    pool: &Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     condition_data: &CreatePolicyCondition,
 ) -> Result<PolicyCondition, sqlx::Error> {
     debug!(
@@ -317,17 +675,17 @@ pub async fn create_policy_condition(
         "INSERT INTO policy_conditions 
          (policy_id, condition_type, operator, threshold_value, measurement_unit, measurement_period, consecutive_days)
          VALUES ($1, $2, $3, $4, $5, $6, $7)
-         RETURNING id, policy_id, condition_type, operator, threshold_value,
-         measurement_unit, measurement_period, consecutive_days, created_at",
+         RETURNING id, policy_id, condition_type as \"condition_type: _\", operator as \"operator: _\", threshold_value,
+         measurement_unit as \"measurement_unit: _\", measurement_period as \"measurement_period: _\", consecutive_days, created_at",
         condition_data.policy_id,
-        condition_data.condition_type,
-        condition_data.operator,
+        condition_data.condition_type.as_str(),
+        condition_data.operator.as_str(),
         condition_data.threshold_value,
-        condition_data.measurement_unit,
-        condition_data.measurement_period,
+        condition_data.measurement_unit.as_str(),
+        condition_data.measurement_period.as_str(),
         consecutive_days
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     debug!("Created policy condition with id: {}", condition.id);
@@ -419,6 +777,127 @@ pub async fn get_weather_data_by_station_and_date_range(
     Ok(data)
 }
 
+// Up to `limit` of the latest readings in the range, oldest first
+pub async fn get_latest_weather_data_in_range(
+    pool: &Pool<Postgres>,
+    station_id: &str,
+    start_date: &PrimitiveDateTime,
+    end_date: &PrimitiveDateTime,
+    limit: i64,
+) -> Result<Vec<WeatherData>, sqlx::Error> {
+    debug!(
+        "Fetching the latest {} weather readings for station {} from {} to {}",
+        limit, station_id, start_date, end_date
+    );
+
+    let mut data = sqlx::query_as!(
+        WeatherData,
+        "SELECT id, station_id, recorded_at, temperature, humidity, precipitation,
+         wind_speed, wind_direction, atmospheric_pressure, data_source, raw_data, quality_score, created_at
+         FROM weather_data
+         WHERE station_id = $1 AND recorded_at >= $2 AND recorded_at <= $3
+         ORDER BY recorded_at DESC
+         LIMIT $4",
+        station_id,
+        start_date,
+        end_date,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    data.reverse();
+
+    Ok(data)
+}
+
+pub async fn get_active_weather_station_ids(
+    pool: &Pool<Postgres>,
+) -> Result<Vec<String>, sqlx::Error> {
+    debug!("Fetching weather stations referenced by active policies");
+
+    let rows = sqlx::query!(
+        "SELECT DISTINCT weather_station_id AS \"weather_station_id!\"
+         FROM insurance_policies
+         WHERE status = 'active' AND weather_station_id IS NOT NULL
+         ORDER BY weather_station_id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let station_ids: Vec<String> = rows.into_iter().map(|r| r.weather_station_id).collect();
+
+    debug!(
+        "Found {} weather stations for active policies",
+        station_ids.len()
+    );
+    Ok(station_ids)
+}
+
+// Record where a station is, or move it if it was already known
+pub async fn upsert_weather_station(
+    pool: &Pool<Postgres>,
+    station: &CreateWeatherStation,
+) -> Result<WeatherStation, sqlx::Error> {
+    sqlx::query_as!(
+        WeatherStation,
+        "INSERT INTO weather_stations (id, latitude, longitude, h3_index)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (id) DO UPDATE SET
+           latitude = EXCLUDED.latitude,
+           longitude = EXCLUDED.longitude,
+           h3_index = EXCLUDED.h3_index,
+           updated_at = CURRENT_TIMESTAMP
+         RETURNING id, latitude, longitude, h3_index, updated_at",
+        station.id,
+        station.latitude,
+        station.longitude,
+        station.h3_index
+    )
+    .fetch_one(pool)
+    .await
+}
+
+// Stations located in any of the given H3 cells
+pub async fn get_weather_stations_by_h3_indexes(
+    pool: &Pool<Postgres>,
+    h3_indexes: &[String],
+) -> Result<Vec<WeatherStation>, sqlx::Error> {
+    debug!(
+        "Fetching weather stations for {} H3 cells",
+        h3_indexes.len()
+    );
+
+    sqlx::query_as!(
+        WeatherStation,
+        "SELECT id, latitude, longitude, h3_index, updated_at
+         FROM weather_stations
+         WHERE h3_index = ANY($1)
+         ORDER BY id",
+        h3_indexes
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_latest_weather_recorded_at(
+    pool: &Pool<Postgres>,
+    station_id: &str,
+) -> Result<Option<sqlx::types::time::PrimitiveDateTime>, sqlx::Error> {
+    debug!(
+        "Fetching latest weather reading time for station {}",
+        station_id
+    );
+
+    let latest = sqlx::query_scalar!(
+        "SELECT MAX(recorded_at) FROM weather_data WHERE station_id = $1",
+        station_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(latest)
+}
+
 // ============================================================================
 // POLICY CLAIM QUERIES
 // ============================================================================
@@ -431,7 +910,7 @@ pub async fn get_claims_by_policy_id(
 
     let claims = sqlx::query_as!(
         PolicyClaim,
-        "SELECT id, policy_id, claim_amount, claim_status, trigger_date,
+        "SELECT id, policy_id, claim_amount, claim_status as \"claim_status: _\", trigger_date,
          trigger_period_start, trigger_period_end, verification_data,
          evaluated_at, approved_at, rejected_at, rejection_reason,
          payout_transaction_hash, payout_block_number, created_at, updated_at
@@ -461,7 +940,7 @@ pub async fn create_policy_claim(
         "INSERT INTO policy_claims 
          (policy_id, claim_amount, trigger_date, trigger_period_start, trigger_period_end, verification_data)
          VALUES ($1, $2, $3, $4, $5, $6)
-         RETURNING id, policy_id, claim_amount, claim_status, trigger_date,
+         RETURNING id, policy_id, claim_amount, claim_status as \"claim_status: _\", trigger_date,
          trigger_period_start, trigger_period_end, verification_data,
          evaluated_at, approved_at, rejected_at, rejection_reason,
          payout_transaction_hash, payout_block_number, created_at, updated_at",
@@ -479,41 +958,103 @@ pub async fn create_policy_claim(
     Ok(claim)
 }
 
+// Insert a claim unless one already exists for the same policy and trigger
+// window. Returns None when the claim was a duplicate.
+pub async fn create_policy_claim_if_absent(
+    executor: impl PgExecutor<'_>,
+    claim_data: &CreatePolicyClaim,
+) -> Result<Option<PolicyClaim>, sqlx::Error> {
+    info!(
+        "Creating policy claim for policy id: {} if not already filed",
+        claim_data.policy_id
+    );
+
+    let claim = sqlx::query_as!(
+        PolicyClaim,
+        "INSERT INTO policy_claims 
+         (policy_id, claim_amount, trigger_date, trigger_period_start, trigger_period_end, verification_data)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (policy_id, trigger_period_start, trigger_period_end) DO NOTHING
+         RETURNING id, policy_id, claim_amount, claim_status as \"claim_status: _\", trigger_date,
+         trigger_period_start, trigger_period_end, verification_data,
+         evaluated_at, approved_at, rejected_at, rejection_reason,
+         payout_transaction_hash, payout_block_number, created_at, updated_at",
+        claim_data.policy_id,
+        claim_data.claim_amount,
+        claim_data.trigger_date,
+        claim_data.trigger_period_start,
+        claim_data.trigger_period_end,
+        claim_data.verification_data
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    match &claim {
+        Some(claim) => info!("Created policy claim with id: {}", claim.id),
+        None => debug!(
+            "Claim for policy {} and this trigger window already exists",
+            claim_data.policy_id
+        ),
+    }
+
+    Ok(claim)
+}
+
+// Lock a claim for the rest of the transaction while it is reviewed
+pub async fn lock_claim(
+    executor: impl PgExecutor<'_>,
+    claim_id: i32,
+) -> Result<Option<PolicyClaim>, sqlx::Error> {
+    sqlx::query_as!(
+        PolicyClaim,
+        "SELECT id, policy_id, claim_amount, claim_status as \"claim_status: _\", trigger_date,
+         trigger_period_start, trigger_period_end, verification_data,
+         evaluated_at, approved_at, rejected_at, rejection_reason,
+         payout_transaction_hash, payout_block_number, created_at, updated_at
+         FROM policy_claims
+         WHERE id = $1
+         FOR UPDATE",
+        claim_id
+    )
+    .fetch_optional(executor)
+    .await
+}
+
 pub async fn update_claim_status(
-    pool: &Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     claim_id: i32,
-    new_status: &str,
+    new_status: ClaimStatus,
     rejection_reason: Option<&str>,
 ) -> Result<bool, sqlx::Error> {
     info!("Updating claim {} status to: {}", claim_id, new_status);
 
     let result = match new_status {
-        "approved" => {
+        ClaimStatus::Approved => {
             sqlx::query!(
                 "UPDATE policy_claims SET claim_status = $1, approved_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP WHERE id = $2",
-                new_status,
+                new_status.as_str(),
                 claim_id
             )
-            .execute(pool)
+            .execute(executor)
             .await?
         },
-        "rejected" => {
+        ClaimStatus::Rejected => {
             sqlx::query!(
                 "UPDATE policy_claims SET claim_status = $1, rejected_at = CURRENT_TIMESTAMP, rejection_reason = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3",
-                new_status,
+                new_status.as_str(),
                 rejection_reason,
                 claim_id
             )
-            .execute(pool)
+            .execute(executor)
             .await?
         },
-        _ => {
+        ClaimStatus::Pending | ClaimStatus::Paid => {
             sqlx::query!(
                 "UPDATE policy_claims SET claim_status = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2",
-                new_status,
+                new_status.as_str(),
                 claim_id
             )
-            .execute(pool)
+            .execute(executor)
             .await?
         }
     };
@@ -563,27 +1104,71 @@ pub async fn get_policy_with_claims(
     }
 }
 
+// The policy's details, with up to `reading_limit` of the latest readings from
+// its station between the start of coverage and the earlier of its end and
+// `as_of`, oldest first
+pub async fn get_policy_details(
+    pool: &Pool<Postgres>,
+    policy_id: i32,
+    as_of: PrimitiveDateTime,
+    reading_limit: i64,
+) -> Result<Option<PolicyDetails>, sqlx::Error> {
+    let Some(PolicyWithConditions { policy, conditions }) =
+        get_policy_with_conditions(pool, policy_id).await?
+    else {
+        return Ok(None);
+    };
+
+    let claims = get_claims_by_policy_id(pool, policy_id).await?;
+    let status_history = get_policy_status_history(pool, policy_id).await?;
+    let weather_data = match &policy.weather_station_id {
+        Some(station_id) => {
+            get_latest_weather_data_in_range(
+                pool,
+                station_id,
+                &policy.start_date,
+                &policy.end_date.min(as_of),
+                reading_limit,
+            )
+            .await?
+        }
+        None => Vec::new(),
+    };
+
+    Ok(Some(PolicyDetails {
+        verification_status: VerificationStatus::of(&policy),
+        policy,
+        conditions,
+        claims,
+        status_history,
+        weather_data,
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::create_test_db;
+    use crate::test_utils::{create_test_db, create_test_user_for_policies};
     use rust_decimal::Decimal;
     use sqlx::types::time::PrimitiveDateTime;
     use std::str::FromStr;
 
-    // Helper function to create a test user for policy tests
-    async fn create_test_user_for_policies(pool: &Pool<Postgres>) -> i32 {
-        let user = sqlx::query!(
-            "INSERT INTO users (name, email, password_hash) VALUES ($1, $2, $3) RETURNING id",
-            "Test User",
-            "test@policies.com",
-            "$2b$12$test_hash"
+    // All of a user's policies, newest first
+    async fn list_user_policies(pool: &Pool<Postgres>, user_id: i32) -> Vec<InsurancePolicy> {
+        let filter = PolicyFilter {
+            user_id: Some(user_id),
+            ..Default::default()
+        };
+        list_policies(
+            pool,
+            &filter,
+            PolicySort::CreatedAt,
+            SortOrder::Desc,
+            None,
+            100,
         )
-        .fetch_one(pool)
         .await
-        .expect("Failed to create test user");
-
-        user.id
+        .unwrap()
     }
 
     // ============================================================================
@@ -642,7 +1227,7 @@ mod tests {
         let template_data = CreatePolicyTemplate {
             template_name: "Test Template".to_string(),
             description: Some("A test template".to_string()),
-            policy_type: "test".to_string(),
+            policy_type: PolicyType::Rain,
             default_conditions: Some(serde_json::json!({"test": true})),
             min_coverage_amount: Decimal::from_str("100.00").unwrap(),
             max_coverage_amount: Decimal::from_str("5000.00").unwrap(),
@@ -654,7 +1239,7 @@ mod tests {
             .unwrap();
 
         assert_eq!(created_template.template_name, "Test Template");
-        assert_eq!(created_template.policy_type, "test");
+        assert_eq!(created_template.policy_type, PolicyType::Rain);
         assert_eq!(
             created_template.min_coverage_amount,
             Decimal::from_str("100.00").unwrap()
@@ -676,7 +1261,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "Test Policy".to_string(),
-            policy_type: "drought".to_string(),
+            policy_type: PolicyType::Drought,
             location_latitude: Decimal::from_str("40.7128").unwrap(),
             location_longitude: Decimal::from_str("-74.0060").unwrap(),
             location_h3_index: Some("8a2a1072b59ffff".to_string()),
@@ -703,24 +1288,174 @@ mod tests {
 
         assert_eq!(created_policy.user_id, user_id);
         assert_eq!(created_policy.policy_name, "Test Policy");
-        assert_eq!(created_policy.policy_type, "drought");
+        assert_eq!(created_policy.policy_type, PolicyType::Drought);
         assert_eq!(
             created_policy.coverage_amount,
             Decimal::from_str("1000.00").unwrap()
         );
-        assert_eq!(created_policy.status, Some("active".to_string()));
+        assert_eq!(created_policy.status, Some(PolicyStatus::Active));
         assert!(created_policy.id > 0);
     }
 
     #[tokio::test]
-    async fn test_get_policies_by_user_id() {
+    async fn test_create_insurance_policy_with_verification() {
+        use crate::blockchain::{BlockchainPolicy, VerificationResult};
+        use ethers::types::{Address, I256, U256};
+
         let test_db = create_test_db().await;
         let user_id = create_test_user_for_policies(&test_db.pool).await;
 
-        // Initially no policies
-        let policies = get_policies_by_user_id(&test_db.pool, user_id)
+        let policy_data = CreateInsurancePolicy {
+            user_id,
+            policy_template_id: None,
+            policy_name: "Verified Policy".to_string(),
+            policy_type: PolicyType::Rain,
+            location_latitude: Decimal::from_str("40.7128").unwrap(),
+            location_longitude: Decimal::from_str("-74.0060").unwrap(),
+            location_h3_index: Some("872a1072bffffff".to_string()),
+            location_name: None,
+            coverage_amount: Decimal::from_str("5.00").unwrap(),
+            premium_amount: Decimal::from_str("0.50").unwrap(),
+            currency: Some("ETH".to_string()),
+            start_date: PrimitiveDateTime::new(
+                time::Date::from_calendar_date(2024, time::Month::June, 1).unwrap(),
+                time::Time::from_hms(0, 0, 0).unwrap(),
+            ),
+            end_date: PrimitiveDateTime::new(
+                time::Date::from_calendar_date(2024, time::Month::July, 1).unwrap(),
+                time::Time::from_hms(0, 0, 0).unwrap(),
+            ),
+            weather_station_id: None,
+            smart_contract_address: Some("0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string()),
+            purchase_transaction_hash: Some(format!("0x{}", "ab".repeat(32))),
+        };
+        let verification_result = VerificationResult {
+            verified: true,
+            block_number: Some(42),
+            error_message: None,
+            blockchain_policy: Some(BlockchainPolicy {
+                policy_id: U256::from(7),
+                user: Address::zero(),
+                payout: U256::exp10(18) * 5,
+                start_time: U256::zero(),
+                end_time: U256::from(2_592_000),
+                paid: false,
+                threshold: I256::from(10),
+                event_type: "RAIN_ABOVE".to_string(),
+                h3_hex_id: "872a1072bffffff".to_string(),
+            }),
+        };
+
+        let created_policy = create_insurance_policy_with_verification(
+            &test_db.pool,
+            &policy_data,
+            &verification_result,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(created_policy.blockchain_verified, Some(true));
+        assert_eq!(created_policy.blockchain_block_number, Some(42));
+        assert_eq!(created_policy.blockchain_policy_id, Some(7));
+
+        let fetched = get_policy_by_id(&test_db.pool, created_policy.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched.blockchain_policy_id, Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_create_insurance_policy_with_conditions() {
+        use crate::blockchain::VerificationResult;
+
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+
+        let policy_data = CreateInsurancePolicy {
+            user_id,
+            policy_template_id: None,
+            policy_name: "Drought Policy".to_string(),
+            policy_type: PolicyType::Drought,
+            location_latitude: Decimal::from_str("40.7128").unwrap(),
+            location_longitude: Decimal::from_str("-74.0060").unwrap(),
+            location_h3_index: Some("872a1072bffffff".to_string()),
+            location_name: None,
+            coverage_amount: Decimal::from_str("10.00").unwrap(),
+            premium_amount: Decimal::from_str("1.00").unwrap(),
+            currency: Some("ETH".to_string()),
+            start_date: PrimitiveDateTime::new(
+                time::Date::from_calendar_date(2024, time::Month::June, 1).unwrap(),
+                time::Time::from_hms(0, 0, 0).unwrap(),
+            ),
+            end_date: PrimitiveDateTime::new(
+                time::Date::from_calendar_date(2024, time::Month::July, 1).unwrap(),
+                time::Time::from_hms(0, 0, 0).unwrap(),
+            ),
+            weather_station_id: None,
+            smart_contract_address: None,
+            purchase_transaction_hash: Some(format!("0x{}", "cd".repeat(32))),
+        };
+        let verification_result = VerificationResult {
+            verified: true,
+            block_number: Some(42),
+            error_message: None,
+            blockchain_policy: None,
+        };
+        let condition = CreatePolicyCondition {
+            policy_id: 0,
+            condition_type: ConditionType::Rainfall,
+            operator: ComparisonOperator::LessThan,
+            threshold_value: Decimal::from_str("5").unwrap(),
+            measurement_unit: MeasurementUnit::Millimeters,
+            measurement_period: MeasurementPeriod::Daily,
+            consecutive_days: Some(10),
+        };
+
+        let created = create_insurance_policy_with_conditions(
+            &test_db.pool,
+            &policy_data,
+            &verification_result,
+            std::slice::from_ref(&condition),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(created.conditions.len(), 1);
+        assert_eq!(created.conditions[0].policy_id, created.policy.id);
+        assert_eq!(created.conditions[0].consecutive_days, Some(10));
+        let fetched = get_policy_with_conditions(&test_db.pool, created.policy.id)
             .await
+            .unwrap()
             .unwrap();
+        assert_eq!(fetched.conditions.len(), 1);
+        assert_eq!(fetched.conditions[0].operator, ComparisonOperator::LessThan);
+
+        // A condition that cannot be stored rolls back the policy as well
+        let invalid = CreatePolicyCondition {
+            threshold_value: Decimal::from(10_000_000),
+            ..condition
+        };
+        let result = create_insurance_policy_with_conditions(
+            &test_db.pool,
+            &policy_data,
+            &verification_result,
+            &[invalid],
+        )
+        .await;
+
+        assert!(result.is_err());
+        let policies = list_user_policies(&test_db.pool, user_id).await;
+        assert_eq!(policies.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_policies() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+
+        // Initially no policies
+        let policies = list_user_policies(&test_db.pool, user_id).await;
         assert_eq!(policies.len(), 0);
 
         // Create a policy
@@ -728,7 +1463,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "User Policy Test".to_string(),
-            policy_type: "rain".to_string(),
+            policy_type: PolicyType::Rain,
             location_latitude: Decimal::from_str("40.7128").unwrap(),
             location_longitude: Decimal::from_str("-74.0060").unwrap(),
             location_h3_index: None,
@@ -754,9 +1489,7 @@ mod tests {
             .unwrap();
 
         // Now should have one policy
-        let policies = get_policies_by_user_id(&test_db.pool, user_id)
-            .await
-            .unwrap();
+        let policies = list_user_policies(&test_db.pool, user_id).await;
         assert_eq!(policies.len(), 1);
         assert_eq!(policies[0].policy_name, "User Policy Test");
         assert_eq!(policies[0].user_id, user_id);
@@ -771,7 +1504,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "ID Test Policy".to_string(),
-            policy_type: "temperature".to_string(),
+            policy_type: PolicyType::Temperature,
             location_latitude: Decimal::from_str("34.0522").unwrap(),
             location_longitude: Decimal::from_str("-118.2437").unwrap(),
             location_h3_index: None,
@@ -808,7 +1541,79 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_update_policy_status() {
+    async fn test_get_active_policies() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+
+        let active = create_insurance_policy(
+            &test_db.pool,
+            &CreateInsurancePolicy {
+                user_id,
+                policy_template_id: None,
+                policy_name: "Active Policy".to_string(),
+                policy_type: PolicyType::Rain,
+                location_latitude: Decimal::from_str("40.7128").unwrap(),
+                location_longitude: Decimal::from_str("-74.0060").unwrap(),
+                location_h3_index: None,
+                location_name: None,
+                coverage_amount: Decimal::from_str("100.00").unwrap(),
+                premium_amount: Decimal::from_str("5.00").unwrap(),
+                currency: None,
+                start_date: PrimitiveDateTime::new(
+                    time::Date::from_calendar_date(2024, time::Month::January, 1).unwrap(),
+                    time::Time::from_hms(0, 0, 0).unwrap(),
+                ),
+                end_date: PrimitiveDateTime::new(
+                    time::Date::from_calendar_date(2024, time::Month::December, 31).unwrap(),
+                    time::Time::from_hms(23, 59, 59).unwrap(),
+                ),
+                weather_station_id: None,
+                smart_contract_address: None,
+                purchase_transaction_hash: None,
+            },
+        )
+        .await
+        .unwrap();
+        let cancelled = create_insurance_policy(
+            &test_db.pool,
+            &CreateInsurancePolicy {
+                user_id,
+                policy_template_id: None,
+                policy_name: "Cancelled Policy".to_string(),
+                policy_type: PolicyType::Rain,
+                location_latitude: Decimal::from_str("40.7128").unwrap(),
+                location_longitude: Decimal::from_str("-74.0060").unwrap(),
+                location_h3_index: None,
+                location_name: None,
+                coverage_amount: Decimal::from_str("100.00").unwrap(),
+                premium_amount: Decimal::from_str("5.00").unwrap(),
+                currency: None,
+                start_date: PrimitiveDateTime::new(
+                    time::Date::from_calendar_date(2024, time::Month::January, 1).unwrap(),
+                    time::Time::from_hms(0, 0, 0).unwrap(),
+                ),
+                end_date: PrimitiveDateTime::new(
+                    time::Date::from_calendar_date(2024, time::Month::December, 31).unwrap(),
+                    time::Time::from_hms(23, 59, 59).unwrap(),
+                ),
+                weather_station_id: None,
+                smart_contract_address: None,
+                purchase_transaction_hash: None,
+            },
+        )
+        .await
+        .unwrap();
+        set_policy_status(&test_db.pool, cancelled.id, PolicyStatus::Cancelled)
+            .await
+            .unwrap();
+
+        let policies = get_active_policies(&test_db.pool).await.unwrap();
+        assert_eq!(policies.len(), 1);
+        assert_eq!(policies[0].id, active.id);
+    }
+
+    #[tokio::test]
+    async fn test_set_policy_status() {
         let test_db = create_test_db().await;
         let user_id = create_test_user_for_policies(&test_db.pool).await;
 
@@ -816,7 +1621,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "Status Test Policy".to_string(),
-            policy_type: "wind".to_string(),
+            policy_type: PolicyType::Wind,
             location_latitude: Decimal::from_str("41.8781").unwrap(),
             location_longitude: Decimal::from_str("-87.6298").unwrap(),
             location_h3_index: None,
@@ -840,10 +1645,10 @@ mod tests {
         let created_policy = create_insurance_policy(&test_db.pool, &policy_data)
             .await
             .unwrap();
-        assert_eq!(created_policy.status, Some("active".to_string()));
+        assert_eq!(created_policy.status, Some(PolicyStatus::Active));
 
         // Update status to expired
-        let updated = update_policy_status(&test_db.pool, created_policy.id, "expired")
+        let updated = set_policy_status(&test_db.pool, created_policy.id, PolicyStatus::Expired)
             .await
             .unwrap();
         assert!(updated);
@@ -853,10 +1658,10 @@ mod tests {
             .await
             .unwrap()
             .unwrap();
-        assert_eq!(retrieved_policy.status, Some("expired".to_string()));
+        assert_eq!(retrieved_policy.status, Some(PolicyStatus::Expired));
 
         // Test updating non-existent policy
-        let not_updated = update_policy_status(&test_db.pool, 99999, "cancelled")
+        let not_updated = set_policy_status(&test_db.pool, 99999, PolicyStatus::Cancelled)
             .await
             .unwrap();
         assert!(!not_updated);
@@ -876,7 +1681,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "Condition Test Policy".to_string(),
-            policy_type: "drought".to_string(),
+            policy_type: PolicyType::Drought,
             location_latitude: Decimal::from_str("32.7767").unwrap(),
             location_longitude: Decimal::from_str("-96.7970").unwrap(),
             location_h3_index: None,
@@ -910,11 +1715,11 @@ mod tests {
         // Create a condition
         let condition_data = CreatePolicyCondition {
             policy_id: policy.id,
-            condition_type: "rainfall".to_string(),
-            operator: "<".to_string(),
+            condition_type: ConditionType::Rainfall,
+            operator: ComparisonOperator::LessThan,
             threshold_value: Decimal::from_str("5.0").unwrap(),
-            measurement_unit: "mm".to_string(),
-            measurement_period: "daily".to_string(),
+            measurement_unit: MeasurementUnit::Millimeters,
+            measurement_period: MeasurementPeriod::Daily,
             consecutive_days: Some(7),
         };
 
@@ -923,8 +1728,8 @@ mod tests {
             .unwrap();
 
         assert_eq!(created_condition.policy_id, policy.id);
-        assert_eq!(created_condition.condition_type, "rainfall");
-        assert_eq!(created_condition.operator, "<");
+        assert_eq!(created_condition.condition_type, ConditionType::Rainfall);
+        assert_eq!(created_condition.operator, ComparisonOperator::LessThan);
         assert_eq!(
             created_condition.threshold_value,
             Decimal::from_str("5.0").unwrap()
@@ -949,7 +1754,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "Default Condition Test".to_string(),
-            policy_type: "temperature".to_string(),
+            policy_type: PolicyType::Temperature,
             location_latitude: Decimal::from_str("25.7617").unwrap(),
             location_longitude: Decimal::from_str("-80.1918").unwrap(),
             location_h3_index: None,
@@ -977,11 +1782,11 @@ mod tests {
         // Create condition without consecutive_days (should default to 1)
         let condition_data = CreatePolicyCondition {
             policy_id: policy.id,
-            condition_type: "temperature_min".to_string(),
-            operator: "<".to_string(),
+            condition_type: ConditionType::TemperatureMin,
+            operator: ComparisonOperator::LessThan,
             threshold_value: Decimal::from_str("0.0").unwrap(),
-            measurement_unit: "celsius".to_string(),
-            measurement_period: "daily".to_string(),
+            measurement_unit: MeasurementUnit::Celsius,
+            measurement_period: MeasurementPeriod::Daily,
             consecutive_days: None, // Should default to 1
         };
 
@@ -1211,6 +2016,140 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_get_active_weather_station_ids() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+
+        let stations = [
+            ("Active A", Some("station_a")),
+            ("Active A Again", Some("station_a")),
+            ("Expired B", Some("station_b")),
+            ("No Station", None),
+        ];
+
+        for (name, station) in stations {
+            let policy = create_insurance_policy(
+                &test_db.pool,
+                &CreateInsurancePolicy {
+                    user_id,
+                    policy_template_id: None,
+                    policy_name: name.to_string(),
+                    policy_type: PolicyType::Rain,
+                    location_latitude: Decimal::from_str("40.7128").unwrap(),
+                    location_longitude: Decimal::from_str("-74.0060").unwrap(),
+                    location_h3_index: None,
+                    location_name: None,
+                    coverage_amount: Decimal::from_str("100.00").unwrap(),
+                    premium_amount: Decimal::from_str("5.00").unwrap(),
+                    currency: None,
+                    start_date: PrimitiveDateTime::new(
+                        time::Date::from_calendar_date(2024, time::Month::January, 1).unwrap(),
+                        time::Time::from_hms(0, 0, 0).unwrap(),
+                    ),
+                    end_date: PrimitiveDateTime::new(
+                        time::Date::from_calendar_date(2024, time::Month::December, 31).unwrap(),
+                        time::Time::from_hms(23, 59, 59).unwrap(),
+                    ),
+                    weather_station_id: station.map(|s| s.to_string()),
+                    smart_contract_address: None,
+                    purchase_transaction_hash: None,
+                },
+            )
+            .await
+            .unwrap();
+
+            if name.starts_with("Expired") {
+                set_policy_status(&test_db.pool, policy.id, PolicyStatus::Expired)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let station_ids = get_active_weather_station_ids(&test_db.pool).await.unwrap();
+        assert_eq!(station_ids, vec!["station_a".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_weather_recorded_at() {
+        let test_db = create_test_db().await;
+
+        let latest = get_latest_weather_recorded_at(&test_db.pool, "latest_station")
+            .await
+            .unwrap();
+        assert!(latest.is_none());
+
+        for hour in [6, 18, 12] {
+            let weather_data = CreateWeatherData {
+                station_id: "latest_station".to_string(),
+                recorded_at: PrimitiveDateTime::new(
+                    time::Date::from_calendar_date(2024, time::Month::March, 3).unwrap(),
+                    time::Time::from_hms(hour, 0, 0).unwrap(),
+                ),
+                temperature: None,
+                humidity: None,
+                precipitation: None,
+                wind_speed: None,
+                wind_direction: None,
+                atmospheric_pressure: None,
+                data_source: None,
+                raw_data: None,
+                quality_score: None,
+            };
+            insert_weather_data(&test_db.pool, &weather_data)
+                .await
+                .unwrap();
+        }
+
+        let latest = get_latest_weather_recorded_at(&test_db.pool, "latest_station")
+            .await
+            .unwrap();
+        assert_eq!(
+            latest,
+            Some(PrimitiveDateTime::new(
+                time::Date::from_calendar_date(2024, time::Month::March, 3).unwrap(),
+                time::Time::from_hms(18, 0, 0).unwrap(),
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_weather_data_in_range() {
+        let test_db = create_test_db().await;
+        let at = |day| {
+            PrimitiveDateTime::new(
+                time::Date::from_calendar_date(2024, time::Month::March, day).unwrap(),
+                time::Time::MIDNIGHT,
+            )
+        };
+
+        for day in [1, 2, 3, 4, 5] {
+            let weather_data = CreateWeatherData {
+                station_id: "range_station".to_string(),
+                recorded_at: at(day),
+                temperature: None,
+                humidity: None,
+                precipitation: None,
+                wind_speed: None,
+                wind_direction: None,
+                atmospheric_pressure: None,
+                data_source: None,
+                raw_data: None,
+                quality_score: None,
+            };
+            insert_weather_data(&test_db.pool, &weather_data)
+                .await
+                .unwrap();
+        }
+
+        let readings =
+            get_latest_weather_data_in_range(&test_db.pool, "range_station", &at(1), &at(4), 2)
+                .await
+                .unwrap();
+        let days: Vec<_> = readings.iter().map(|r| r.recorded_at).collect();
+        assert_eq!(days, vec![at(3), at(4)]);
+    }
+
     // ============================================================================
     // POLICY CLAIM TESTS
     // ============================================================================
@@ -1225,7 +2164,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "Claim Test Policy".to_string(),
-            policy_type: "storm".to_string(),
+            policy_type: PolicyType::Storm,
             location_latitude: Decimal::from_str("29.7604").unwrap(),
             location_longitude: Decimal::from_str("-95.3698").unwrap(),
             location_h3_index: None,
@@ -1288,7 +2227,7 @@ mod tests {
             created_claim.claim_amount,
             Decimal::from_str("5000.00").unwrap()
         );
-        assert_eq!(created_claim.claim_status, Some("pending".to_string())); // Default status
+        assert_eq!(created_claim.claim_status, Some(ClaimStatus::Pending)); // Default status
         assert!(created_claim.verification_data.is_some());
 
         // Now should have one claim
@@ -1299,6 +2238,77 @@ mod tests {
         assert_eq!(claims[0].id, created_claim.id);
     }
 
+    #[tokio::test]
+    async fn test_create_policy_claim_if_absent() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+
+        let policy = create_insurance_policy(
+            &test_db.pool,
+            &CreateInsurancePolicy {
+                user_id,
+                policy_template_id: None,
+                policy_name: "Dedup Claim Policy".to_string(),
+                policy_type: PolicyType::Rain,
+                location_latitude: Decimal::from_str("40.7128").unwrap(),
+                location_longitude: Decimal::from_str("-74.0060").unwrap(),
+                location_h3_index: None,
+                location_name: None,
+                coverage_amount: Decimal::from_str("100.00").unwrap(),
+                premium_amount: Decimal::from_str("5.00").unwrap(),
+                currency: None,
+                start_date: PrimitiveDateTime::new(
+                    time::Date::from_calendar_date(2024, time::Month::January, 1).unwrap(),
+                    time::Time::from_hms(0, 0, 0).unwrap(),
+                ),
+                end_date: PrimitiveDateTime::new(
+                    time::Date::from_calendar_date(2024, time::Month::December, 31).unwrap(),
+                    time::Time::from_hms(23, 59, 59).unwrap(),
+                ),
+                weather_station_id: None,
+                smart_contract_address: None,
+                purchase_transaction_hash: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let window_start = PrimitiveDateTime::new(
+            time::Date::from_calendar_date(2024, time::Month::May, 3).unwrap(),
+            time::Time::from_hms(0, 0, 0).unwrap(),
+        );
+        let window_end = PrimitiveDateTime::new(
+            time::Date::from_calendar_date(2024, time::Month::May, 4).unwrap(),
+            time::Time::from_hms(0, 0, 0).unwrap(),
+        );
+        let claim_data = CreatePolicyClaim {
+            policy_id: policy.id,
+            claim_amount: Decimal::from_str("100.00").unwrap(),
+            trigger_date: window_start,
+            trigger_period_start: Some(window_start),
+            trigger_period_end: Some(window_end),
+            verification_data: Some(serde_json::json!({"rainfall": 25.0})),
+        };
+
+        let first = create_policy_claim_if_absent(&test_db.pool, &claim_data)
+            .await
+            .unwrap();
+        assert!(first.is_some());
+
+        // Same trigger window is ignored
+        let second = create_policy_claim_if_absent(&test_db.pool, &claim_data)
+            .await
+            .unwrap();
+        assert!(second.is_none());
+
+        let claims = get_claims_by_policy_id(&test_db.pool, policy.id)
+            .await
+            .unwrap();
+        assert_eq!(claims.len(), 1);
+        assert_eq!(claims[0].trigger_period_start, Some(window_start));
+        assert_eq!(claims[0].trigger_period_end, Some(window_end));
+    }
+
     #[tokio::test]
     async fn test_update_claim_status_approved() {
         let test_db = create_test_db().await;
@@ -1309,7 +2319,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "Approval Test Policy".to_string(),
-            policy_type: "drought".to_string(),
+            policy_type: PolicyType::Drought,
             location_latitude: Decimal::from_str("33.4484").unwrap(),
             location_longitude: Decimal::from_str("-112.0740").unwrap(),
             location_h3_index: None,
@@ -1349,10 +2359,10 @@ mod tests {
         let claim = create_policy_claim(&test_db.pool, &claim_data)
             .await
             .unwrap();
-        assert_eq!(claim.claim_status, Some("pending".to_string()));
+        assert_eq!(claim.claim_status, Some(ClaimStatus::Pending));
 
         // Approve the claim
-        let updated = update_claim_status(&test_db.pool, claim.id, "approved", None)
+        let updated = update_claim_status(&test_db.pool, claim.id, ClaimStatus::Approved, None)
             .await
             .unwrap();
         assert!(updated);
@@ -1362,7 +2372,7 @@ mod tests {
             .await
             .unwrap();
         assert_eq!(claims.len(), 1);
-        assert_eq!(claims[0].claim_status, Some("approved".to_string()));
+        assert_eq!(claims[0].claim_status, Some(ClaimStatus::Approved));
         assert!(claims[0].approved_at.is_some());
         assert!(claims[0].rejected_at.is_none());
     }
@@ -1377,7 +2387,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "Rejection Test Policy".to_string(),
-            policy_type: "rain".to_string(),
+            policy_type: PolicyType::Rain,
             location_latitude: Decimal::from_str("47.6062").unwrap(),
             location_longitude: Decimal::from_str("-122.3321").unwrap(),
             location_h3_index: None,
@@ -1421,10 +2431,14 @@ mod tests {
         // Reject the claim
         let rejection_reason =
             "Rainfall threshold not met according to official weather station data";
-        let updated =
-            update_claim_status(&test_db.pool, claim.id, "rejected", Some(rejection_reason))
-                .await
-                .unwrap();
+        let updated = update_claim_status(
+            &test_db.pool,
+            claim.id,
+            ClaimStatus::Rejected,
+            Some(rejection_reason),
+        )
+        .await
+        .unwrap();
         assert!(updated);
 
         // Verify the status and rejection details
@@ -1432,7 +2446,7 @@ mod tests {
             .await
             .unwrap();
         assert_eq!(claims.len(), 1);
-        assert_eq!(claims[0].claim_status, Some("rejected".to_string()));
+        assert_eq!(claims[0].claim_status, Some(ClaimStatus::Rejected));
         assert!(claims[0].rejected_at.is_some());
         assert_eq!(
             claims[0].rejection_reason,
@@ -1455,7 +2469,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "Combined Test Policy".to_string(),
-            policy_type: "multi".to_string(),
+            policy_type: PolicyType::Temperature,
             location_latitude: Decimal::from_str("39.7392").unwrap(),
             location_longitude: Decimal::from_str("-104.9903").unwrap(),
             location_h3_index: None,
@@ -1484,20 +2498,20 @@ mod tests {
         let conditions = vec![
             CreatePolicyCondition {
                 policy_id: policy.id,
-                condition_type: "rainfall".to_string(),
-                operator: "<".to_string(),
+                condition_type: ConditionType::Rainfall,
+                operator: ComparisonOperator::LessThan,
                 threshold_value: Decimal::from_str("2.0").unwrap(),
-                measurement_unit: "mm".to_string(),
-                measurement_period: "daily".to_string(),
+                measurement_unit: MeasurementUnit::Millimeters,
+                measurement_period: MeasurementPeriod::Daily,
                 consecutive_days: Some(10),
             },
             CreatePolicyCondition {
                 policy_id: policy.id,
-                condition_type: "temperature_max".to_string(),
-                operator: ">".to_string(),
+                condition_type: ConditionType::TemperatureMax,
+                operator: ComparisonOperator::GreaterThan,
                 threshold_value: Decimal::from_str("35.0").unwrap(),
-                measurement_unit: "celsius".to_string(),
-                measurement_period: "daily".to_string(),
+                measurement_unit: MeasurementUnit::Celsius,
+                measurement_period: MeasurementPeriod::Daily,
                 consecutive_days: Some(3),
             },
         ];
@@ -1522,17 +2536,17 @@ mod tests {
         let rainfall_condition = policy_with_conditions
             .conditions
             .iter()
-            .find(|c| c.condition_type == "rainfall")
+            .find(|c| c.condition_type == ConditionType::Rainfall)
             .unwrap();
-        assert_eq!(rainfall_condition.operator, "<");
+        assert_eq!(rainfall_condition.operator, ComparisonOperator::LessThan);
         assert_eq!(rainfall_condition.consecutive_days, Some(10));
 
         let temp_condition = policy_with_conditions
             .conditions
             .iter()
-            .find(|c| c.condition_type == "temperature_max")
+            .find(|c| c.condition_type == ConditionType::TemperatureMax)
             .unwrap();
-        assert_eq!(temp_condition.operator, ">");
+        assert_eq!(temp_condition.operator, ComparisonOperator::GreaterThan);
         assert_eq!(temp_condition.consecutive_days, Some(3));
     }
 
@@ -1546,7 +2560,7 @@ mod tests {
             user_id,
             policy_template_id: None,
             policy_name: "Claims Test Policy".to_string(),
-            policy_type: "comprehensive".to_string(),
+            policy_type: PolicyType::Storm,
             location_latitude: Decimal::from_str("37.7749").unwrap(),
             location_longitude: Decimal::from_str("-122.4194").unwrap(),
             location_h3_index: None,
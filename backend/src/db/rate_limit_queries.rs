@@ -0,0 +1,129 @@
+use crate::db::models::*;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{PgExecutor, Pool, Postgres};
+use tracing::{info, warn};
+
+// ============================================================================
+// RATE LIMIT BUCKET QUERIES
+// ============================================================================
+
+// The bucket with this key, created full if it does not exist yet. The row
+// stays locked for the rest of the transaction.
+pub async fn lock_rate_limit_bucket(
+    executor: impl PgExecutor<'_>,
+    key: &str,
+    capacity: f64,
+    now: PrimitiveDateTime,
+) -> Result<RateLimitBucket, sqlx::Error> {
+    sqlx::query_as!(
+        RateLimitBucket,
+        "INSERT INTO rate_limit_buckets (key, tokens, updated_at) VALUES ($1, $2, $3)
+         ON CONFLICT (key) DO UPDATE SET key = EXCLUDED.key
+         RETURNING tokens, updated_at",
+        key,
+        capacity,
+        now
+    )
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn save_rate_limit_bucket(
+    executor: impl PgExecutor<'_>,
+    key: &str,
+    bucket: &RateLimitBucket,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE rate_limit_buckets SET tokens = $2, updated_at = $3 WHERE key = $1",
+        key,
+        bucket.tokens,
+        bucket.updated_at
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+// Remove buckets last used before `idle_before`. Returns how many were
+// removed.
+pub async fn delete_idle_rate_limit_buckets(
+    pool: &Pool<Postgres>,
+    idle_before: PrimitiveDateTime,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        "DELETE FROM rate_limit_buckets WHERE updated_at < $1",
+        idle_before
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ============================================================================
+// LOGIN LOCKOUT QUERIES
+// ============================================================================
+
+// When the user's lockout ends, if they are locked out now
+pub async fn get_login_locked_until(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+) -> Result<Option<PrimitiveDateTime>, sqlx::Error> {
+    let locked_until = sqlx::query_scalar!(
+        "SELECT locked_until FROM login_lockouts
+         WHERE user_id = $1 AND locked_until > (NOW() AT TIME ZONE 'UTC')",
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(locked_until.flatten())
+}
+
+// Count a failed sign-in and return how many the user has had since their
+// last successful one
+pub async fn record_failed_login(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar!(
+        "INSERT INTO login_lockouts (user_id, failed_attempts, last_failed_at)
+         VALUES ($1, 1, NOW() AT TIME ZONE 'UTC')
+         ON CONFLICT (user_id) DO UPDATE
+         SET failed_attempts = login_lockouts.failed_attempts + 1,
+             last_failed_at = EXCLUDED.last_failed_at
+         RETURNING failed_attempts",
+        user_id
+    )
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn lock_login(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+    locked_until: PrimitiveDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE login_lockouts SET locked_until = $2 WHERE user_id = $1",
+        user_id,
+        locked_until
+    )
+    .execute(executor)
+    .await?;
+    warn!(
+        "Locked sign-in of user id {} until {}",
+        user_id, locked_until
+    );
+    Ok(())
+}
+
+// Forget the user's failed sign-ins after a successful one
+pub async fn clear_failed_logins(pool: &Pool<Postgres>, user_id: i32) -> Result<(), sqlx::Error> {
+    let result = sqlx::query!("DELETE FROM login_lockouts WHERE user_id = $1", user_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() > 0 {
+        info!("Cleared failed sign-ins of user id: {}", user_id);
+    }
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use crate::db::models::*;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{PgExecutor, Pool, Postgres};
+use tracing::{debug, info};
+
+// ============================================================================
+// SESSION QUERIES
+// ============================================================================
+
+pub async fn create_session(
+    pool: &Pool<Postgres>,
+    user_id: i32,
+    refresh_token_hash: &str,
+    expires_at: PrimitiveDateTime,
+) -> Result<Session, sqlx::Error> {
+    let session = sqlx::query_as!(
+        Session,
+        "INSERT INTO sessions (user_id, refresh_token_hash, expires_at)
+         VALUES ($1, $2, $3)
+         RETURNING id, user_id, refresh_token_hash,
+         expires_at, revoked_at, last_refreshed_at, created_at",
+        user_id,
+        refresh_token_hash,
+        expires_at
+    )
+    .fetch_one(pool)
+    .await?;
+
+    info!("Started session {} for user id: {}", session.id, user_id);
+    Ok(session)
+}
+
+// The session if it belongs to the user and is neither revoked nor expired
+pub async fn get_active_session(
+    pool: &Pool<Postgres>,
+    session_id: i32,
+    user_id: i32,
+) -> Result<Option<Session>, sqlx::Error> {
+    sqlx::query_as!(
+        Session,
+        "SELECT id, user_id, refresh_token_hash,
+         expires_at, revoked_at, last_refreshed_at, created_at
+         FROM sessions
+         WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+         AND expires_at > (NOW() AT TIME ZONE 'UTC')",
+        session_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+// The session whose current or previous refresh token has this hash, locked
+// for the rest of the transaction
+pub async fn lock_session_by_refresh_token_hash(
+    executor: impl PgExecutor<'_>,
+    refresh_token_hash: &str,
+) -> Result<Option<Session>, sqlx::Error> {
+    sqlx::query_as!(
+        Session,
+        "SELECT id, user_id, refresh_token_hash,
+         expires_at, revoked_at, last_refreshed_at, created_at
+         FROM sessions
+         WHERE refresh_token_hash = $1 OR previous_refresh_token_hash = $1
+         FOR UPDATE",
+        refresh_token_hash
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+// Replace the session's refresh token, keeping the old hash to detect reuse
+pub async fn rotate_refresh_token(
+    executor: impl PgExecutor<'_>,
+    session_id: i32,
+    refresh_token_hash: &str,
+) -> Result<Session, sqlx::Error> {
+    debug!("Rotating refresh token of session {}", session_id);
+
+    sqlx::query_as!(
+        Session,
+        "UPDATE sessions
+         SET previous_refresh_token_hash = refresh_token_hash, refresh_token_hash = $1,
+         last_refreshed_at = (NOW() AT TIME ZONE 'UTC')
+         WHERE id = $2
+         RETURNING id, user_id, refresh_token_hash,
+         expires_at, revoked_at, last_refreshed_at, created_at",
+        refresh_token_hash,
+        session_id
+    )
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn revoke_session(
+    executor: impl PgExecutor<'_>,
+    session_id: i32,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE sessions SET revoked_at = (NOW() AT TIME ZONE 'UTC')
+         WHERE id = $1 AND revoked_at IS NULL",
+        session_id
+    )
+    .execute(executor)
+    .await?;
+
+    let revoked = result.rows_affected() > 0;
+    if revoked {
+        info!("Revoked session {}", session_id);
+    }
+    Ok(revoked)
+}
+
+// Revoke every session of the user, signing them out everywhere
+pub async fn revoke_user_sessions(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE sessions SET revoked_at = (NOW() AT TIME ZONE 'UTC')
+         WHERE user_id = $1 AND revoked_at IS NULL",
+        user_id
+    )
+    .execute(executor)
+    .await?;
+
+    info!(
+        "Revoked {} sessions of user id: {}",
+        result.rows_affected(),
+        user_id
+    );
+    Ok(result.rows_affected())
+}
+
+// Revoke every session of the user but the one given, signing them out
+// everywhere else
+pub async fn revoke_other_user_sessions(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+    session_id: i32,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE sessions SET revoked_at = (NOW() AT TIME ZONE 'UTC')
+         WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL",
+        user_id,
+        session_id
+    )
+    .execute(executor)
+    .await?;
+
+    info!(
+        "Revoked {} other sessions of user id: {}",
+        result.rows_affected(),
+        user_id
+    );
+    Ok(result.rows_affected())
+}
+
+// ============================================================================
+// SIGN-IN NONCE QUERIES
+// ============================================================================
+
+pub async fn create_auth_nonce(
+    pool: &Pool<Postgres>,
+    nonce: &str,
+    expires_at: PrimitiveDateTime,
+) -> Result<AuthNonce, sqlx::Error> {
+    debug!("Issuing sign-in nonce expiring at {}", expires_at);
+
+    sqlx::query_as!(
+        AuthNonce,
+        "INSERT INTO auth_nonces (nonce, expires_at) VALUES ($1, $2)
+         RETURNING nonce, expires_at",
+        nonce,
+        expires_at
+    )
+    .fetch_one(pool)
+    .await
+}
+
+// Mark the nonce used. False if it was never issued, has expired or was
+// already used, so each nonce signs in at most once.
+pub async fn consume_auth_nonce(
+    executor: impl PgExecutor<'_>,
+    nonce: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE auth_nonces SET used_at = (NOW() AT TIME ZONE 'UTC')
+         WHERE nonce = $1 AND used_at IS NULL
+         AND expires_at > (NOW() AT TIME ZONE 'UTC')",
+        nonce
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Remove nonces that expired before `now`, used or not. Returns how many were
+// removed.
+pub async fn delete_expired_auth_nonces(
+    pool: &Pool<Postgres>,
+    now: PrimitiveDateTime,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!("DELETE FROM auth_nonces WHERE expires_at <= $1", now)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
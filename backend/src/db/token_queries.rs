@@ -0,0 +1,76 @@
+use crate::db::models::*;
+use sqlx::PgExecutor;
+use sqlx::types::time::PrimitiveDateTime;
+use tracing::info;
+
+// ============================================================================
+// USER TOKEN QUERIES
+// ============================================================================
+
+// Store the hash of a token mailed to the user. Tokens they were sent earlier
+// for the same purpose stop working.
+pub async fn create_user_token(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+    purpose: UserTokenPurpose,
+    token_hash: &str,
+    email: Option<&str>,
+    expires_at: PrimitiveDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "WITH superseded AS (
+             UPDATE user_tokens SET used_at = (NOW() AT TIME ZONE 'UTC')
+             WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL
+         )
+         INSERT INTO user_tokens (user_id, purpose, token_hash, email, expires_at)
+         VALUES ($1, $2, $3, $4, $5)",
+        user_id,
+        purpose.as_str(),
+        token_hash,
+        email,
+        expires_at
+    )
+    .execute(executor)
+    .await?;
+
+    info!("Issued {} token for user id: {}", purpose, user_id);
+    Ok(())
+}
+
+// Mark the token used and return it. None if it was never issued, has
+// expired, was already used or is for another purpose.
+pub async fn consume_user_token(
+    executor: impl PgExecutor<'_>,
+    token_hash: &str,
+    purpose: UserTokenPurpose,
+) -> Result<Option<UserToken>, sqlx::Error> {
+    sqlx::query_as!(
+        UserToken,
+        "UPDATE user_tokens SET used_at = (NOW() AT TIME ZONE 'UTC')
+         WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL
+         AND expires_at > (NOW() AT TIME ZONE 'UTC')
+         RETURNING user_id, email",
+        token_hash,
+        purpose.as_str()
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+// Delete every token mailed to the user, used or not. Links they were sent
+// stop working, and the addresses they were sent to are forgotten.
+pub async fn delete_user_tokens(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+) -> Result<(), sqlx::Error> {
+    let result = sqlx::query!("DELETE FROM user_tokens WHERE user_id = $1", user_id)
+        .execute(executor)
+        .await?;
+
+    info!(
+        "Deleted {} tokens of user id: {}",
+        result.rows_affected(),
+        user_id
+    );
+    Ok(())
+}
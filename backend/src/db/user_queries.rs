@@ -1,17 +1,21 @@
 use crate::db::models::{CreateUser, User};
+use crate::mail::AppMailer;
+use crate::web::account::send_verification_email;
+use crate::web::password_hashing::{PasswordHashError, PasswordHashing};
+use crate::web::validation::{PasswordPolicy, validate_password};
 use axum::http::StatusCode;
 use axum::{Json, extract::Extension};
-use bcrypt::{DEFAULT_COST, hash};
-use sqlx::{Error as SqlxError, Pool, Postgres};
+use sqlx::{Error as SqlxError, PgExecutor, Pool, Postgres};
 use tracing;
 
-// Helper function to hash passwords using bcrypt
-fn hash_password(password: &str) -> Result<String, bcrypt::BcryptError> {
-    hash(password, DEFAULT_COST)
+// Helper function to hash passwords with the configured hasher
+pub fn hash_password(password: &str) -> Result<String, PasswordHashError> {
+    PasswordHashing::from_env()?.hash(password)
 }
 
 pub async fn create_user(
     Extension(pool): Extension<Pool<Postgres>>,
+    Extension(mailer): Extension<AppMailer>,
     Json(new_user): Json<CreateUser>,
 ) -> Result<Json<User>, (StatusCode, Json<serde_json::Value>)> {
     // Validate input
@@ -33,13 +37,12 @@ pub async fn create_user(
         ));
     }
 
-    if new_user.password.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": "Password cannot be empty"
-            })),
-        ));
+    if let Err(errors) = validate_password(
+        &new_user.password,
+        Some(&new_user.email),
+        &PasswordPolicy::from_env(),
+    ) {
+        return Err((StatusCode::BAD_REQUEST, Json(serde_json::json!(errors))));
     }
 
     // Hash the password
@@ -58,7 +61,7 @@ pub async fn create_user(
     // Attempt to create user
     let user = sqlx::query_as!(
         User,
-        "INSERT INTO users (name, email, password_hash) VALUES ($1, $2, $3) RETURNING id, name, email, password_hash, wallet_address, created_at, updated_at",
+        "INSERT INTO users (name, email, password_hash) VALUES ($1, $2, $3) RETURNING id, name, email, password_hash, wallet_address, email_verified_at, created_at, updated_at, role as \"role: _\"",
         new_user.name.trim(),
         new_user.email.trim().to_lowercase(),
         password_hash
@@ -101,7 +104,22 @@ pub async fn create_user(
         }
     })?;
 
-    tracing::info!("Successfully created user with email: {}", user.email);
+    tracing::info!(
+        "Successfully created user with email: {}",
+        user.identifier()
+    );
+
+    // The account can't sign in with its password until the email is
+    // verified. If the email can't be sent, a password reset verifies it too.
+    if let Some(email) = &user.email
+        && let Err(e) = send_verification_email(&pool, &mailer, user.id, email).await
+    {
+        tracing::error!(
+            "Failed to send verification email to user id {}: {}",
+            user.id,
+            e
+        );
+    }
     Ok(Json(user))
 }
 
@@ -113,7 +131,7 @@ pub async fn retrieve_user_by_email(
 
     let user = sqlx::query_as!(
         User,
-        "SELECT id, name, email, password_hash, wallet_address, created_at, updated_at FROM users WHERE email = $1",
+        "SELECT id, name, email, password_hash, wallet_address, email_verified_at, created_at, updated_at, role as \"role: _\" FROM users WHERE email = $1",
         email.trim().to_lowercase()
     )
     .fetch_optional(pool)
@@ -131,8 +149,66 @@ pub async fn retrieve_user_by_email(
     Ok(user)
 }
 
+pub async fn retrieve_user_by_id(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+) -> Result<Option<User>, SqlxError> {
+    tracing::debug!("Looking up user by id: {}", user_id);
+
+    sqlx::query_as!(
+        User,
+        "SELECT id, name, email, password_hash, wallet_address, email_verified_at, created_at, updated_at, role as \"role: _\" FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+// The account that has proven it owns this wallet
+pub async fn retrieve_user_by_wallet_address(
+    executor: impl PgExecutor<'_>,
+    wallet_address: &str,
+) -> Result<Option<User>, SqlxError> {
+    tracing::debug!("Looking up user by wallet address: {}", wallet_address);
+
+    sqlx::query_as!(
+        User,
+        "SELECT u.id, u.name, u.email, u.password_hash, u.wallet_address, u.email_verified_at, u.created_at, u.updated_at, u.role as \"role: _\"
+         FROM users u
+         JOIN user_wallets w ON w.user_id = u.id
+         WHERE LOWER(w.wallet_address) = LOWER($1)",
+        wallet_address
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+// An account that signs in with its wallet only, without email or password
+pub async fn create_wallet_user(
+    executor: impl PgExecutor<'_>,
+    name: &str,
+    wallet_address: &str,
+) -> Result<User, SqlxError> {
+    let user = sqlx::query_as!(
+        User,
+        "INSERT INTO users (name, wallet_address) VALUES ($1, $2) RETURNING id, name, email, password_hash, wallet_address, email_verified_at, created_at, updated_at, role as \"role: _\"",
+        name,
+        wallet_address
+    )
+    .fetch_one(executor)
+    .await?;
+
+    tracing::info!(
+        "Created user id {} for wallet address {}",
+        user.id,
+        wallet_address
+    );
+    Ok(user)
+}
+
+// Set the user's primary wallet, which must be one of their verified wallets
 pub async fn update_user_wallet_address(
-    pool: &Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     user_id: i32,
     wallet_address: &str,
 ) -> Result<User, SqlxError> {
@@ -142,11 +218,11 @@ pub async fn update_user_wallet_address(
         User,
         "UPDATE users SET wallet_address = $1, updated_at = CURRENT_TIMESTAMP 
          WHERE id = $2 
-         RETURNING id, name, email, password_hash, wallet_address, created_at, updated_at",
+         RETURNING id, name, email, password_hash, wallet_address, email_verified_at, created_at, updated_at, role as \"role: _\"",
         wallet_address,
         user_id
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     tracing::info!(
@@ -156,6 +232,109 @@ pub async fn update_user_wallet_address(
     Ok(user)
 }
 
+// Mark the user's email verified, as long as it is still the address the
+// verification was sent to. False if the user has changed it since.
+pub async fn mark_email_verified(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+    email: &str,
+) -> Result<bool, SqlxError> {
+    let result = sqlx::query!(
+        "UPDATE users SET email_verified_at = COALESCE(email_verified_at, CURRENT_TIMESTAMP),
+         updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND email = $2",
+        user_id,
+        email.trim().to_lowercase()
+    )
+    .execute(executor)
+    .await?;
+
+    let verified = result.rows_affected() > 0;
+    if verified {
+        tracing::info!("Verified email of user id: {}", user_id);
+    }
+    Ok(verified)
+}
+
+pub async fn update_password_hash(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+    password_hash: &str,
+) -> Result<(), SqlxError> {
+    sqlx::query!(
+        "UPDATE users SET password_hash = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2",
+        password_hash,
+        user_id
+    )
+    .execute(executor)
+    .await?;
+
+    tracing::info!("Updated password of user id: {}", user_id);
+    Ok(())
+}
+
+// Replace the user's password hash only if it is still `old_hash`, so an
+// upgraded hash can't overwrite a password changed in the meantime. Returns
+// whether the hash was replaced.
+pub async fn replace_password_hash(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+    old_hash: &str,
+    new_hash: &str,
+) -> Result<bool, SqlxError> {
+    let result = sqlx::query!(
+        "UPDATE users SET password_hash = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2 AND password_hash = $3",
+        new_hash,
+        user_id,
+        old_hash
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+// Change the user's name and email. A changed email is no longer verified.
+pub async fn update_user_profile(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+    name: &str,
+    email: Option<&str>,
+) -> Result<User, SqlxError> {
+    tracing::info!("Updating profile of user id: {}", user_id);
+
+    sqlx::query_as!(
+        User,
+        "UPDATE users SET name = $1, email = $2::VARCHAR,
+         email_verified_at = CASE WHEN email IS NOT DISTINCT FROM $2::VARCHAR THEN email_verified_at END,
+         updated_at = CURRENT_TIMESTAMP
+         WHERE id = $3
+         RETURNING id, name, email, password_hash, wallet_address, email_verified_at, created_at, updated_at, role as \"role: _\"",
+        name.trim(),
+        email.map(|email| email.trim().to_lowercase()),
+        user_id
+    )
+    .fetch_one(executor)
+    .await
+}
+
+// Remove everything that identifies the user. The row stays, so the policies
+// and claims they bought keep their owner for the audit trail.
+pub async fn anonymize_user(executor: impl PgExecutor<'_>, user_id: i32) -> Result<(), SqlxError> {
+    sqlx::query!(
+        "UPDATE users SET name = 'Deleted user', email = NULL, password_hash = NULL,
+         wallet_address = NULL, email_verified_at = NULL,
+         deleted_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1",
+        user_id
+    )
+    .execute(executor)
+    .await?;
+
+    tracing::info!("Anonymised user id: {}", user_id);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,8 +349,8 @@ mod tests {
         assert!(!hash.is_empty());
         assert_ne!(hash, password); // Hash should be different from original password
 
-        // Verify the hash starts with bcrypt identifier
-        assert!(hash.starts_with("$2b$") || hash.starts_with("$2a$") || hash.starts_with("$2y$"));
+        // New hashes use Argon2id
+        assert!(hash.starts_with("$argon2id$"));
     }
 
     #[test]
@@ -226,4 +405,40 @@ mod tests {
         let hash = result.unwrap();
         assert!(!hash.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_replace_password_hash_only_replaces_current_hash() {
+        let test_db = crate::test_utils::create_test_db().await;
+        let pool = &test_db.pool;
+        let user = crate::test_utils::create_test_user(
+            pool,
+            "Rehash User",
+            "rehash@example.com",
+            "old_password",
+        )
+        .await
+        .unwrap();
+        let checked_hash = user.password_hash.clone().unwrap();
+
+        // The password is changed after the old hash was checked at sign-in
+        update_password_hash(pool, user.id, "changed")
+            .await
+            .unwrap();
+        assert!(
+            !replace_password_hash(pool, user.id, &checked_hash, "rehashed")
+                .await
+                .unwrap()
+        );
+        assert!(
+            replace_password_hash(pool, user.id, "changed", "rehashed")
+                .await
+                .unwrap()
+        );
+
+        let hash = sqlx::query_scalar!("SELECT password_hash FROM users WHERE id = $1", user.id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(hash.as_deref(), Some("rehashed"));
+    }
 }
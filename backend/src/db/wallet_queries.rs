@@ -0,0 +1,66 @@
+use crate::db::models::*;
+use sqlx::PgExecutor;
+use tracing::info;
+
+// ============================================================================
+// USER WALLET QUERIES
+// ============================================================================
+
+// Record that the user proved they own the wallet. Linking a wallet again
+// refreshes verified_at. None if the wallet belongs to another user.
+pub async fn add_user_wallet(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+    wallet_address: &str,
+) -> Result<Option<UserWallet>, sqlx::Error> {
+    let wallet = sqlx::query_as!(
+        UserWallet,
+        "INSERT INTO user_wallets (user_id, wallet_address) VALUES ($1, $2)
+         ON CONFLICT ((LOWER(wallet_address))) DO UPDATE
+         SET verified_at = CURRENT_TIMESTAMP
+         WHERE user_wallets.user_id = EXCLUDED.user_id
+         RETURNING id, user_id, wallet_address, verified_at, created_at",
+        user_id,
+        wallet_address
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    if wallet.is_some() {
+        info!("Linked wallet {} to user id: {}", wallet_address, user_id);
+    }
+    Ok(wallet)
+}
+
+pub async fn get_user_wallets(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+) -> Result<Vec<UserWallet>, sqlx::Error> {
+    sqlx::query_as!(
+        UserWallet,
+        "SELECT id, user_id, wallet_address, verified_at, created_at
+         FROM user_wallets
+         WHERE user_id = $1
+         ORDER BY id",
+        user_id
+    )
+    .fetch_all(executor)
+    .await
+}
+
+// Unlink every wallet of the user
+pub async fn delete_user_wallets(
+    executor: impl PgExecutor<'_>,
+    user_id: i32,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!("DELETE FROM user_wallets WHERE user_id = $1", user_id)
+        .execute(executor)
+        .await?;
+
+    info!(
+        "Unlinked {} wallets of user id: {}",
+        result.rows_affected(),
+        user_id
+    );
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,285 @@
+use rand::RngCore;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc2822;
+
+use crate::mail::smtp::{SmtpConfig, SmtpMailer};
+
+// Error types for sending mail
+#[derive(Debug, thiserror::Error)]
+pub enum MailerError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("Refusing to send SMTP credentials without TLS")]
+    InsecureSmtpAuth,
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+}
+
+// A plain text email
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+// Where mail goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MailerBackend {
+    Smtp,
+    // Written to files in `file_dir`, for local development
+    File,
+    // Kept in memory, for tests
+    InMemory,
+}
+
+// Configuration for sending mail
+#[derive(Debug, Clone)]
+pub struct MailerConfig {
+    pub backend: MailerBackend,
+    pub from: String,
+    pub file_dir: PathBuf,
+    pub smtp: SmtpConfig,
+}
+
+impl Default for MailerConfig {
+    fn default() -> Self {
+        Self {
+            backend: MailerBackend::File,
+            from: "Weather Boyz <no-reply@weather-boyz.local>".to_string(),
+            file_dir: PathBuf::from("mail"),
+            smtp: SmtpConfig::default(),
+        }
+    }
+}
+
+impl MailerConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            backend: match std::env::var("MAILER").as_deref() {
+                Ok("smtp") => MailerBackend::Smtp,
+                Ok("file") => MailerBackend::File,
+                Ok("memory") => MailerBackend::InMemory,
+                _ => defaults.backend,
+            },
+            from: std::env::var("MAIL_FROM").unwrap_or(defaults.from),
+            file_dir: std::env::var("MAIL_FILE_DIR")
+                .map(PathBuf::from)
+                .unwrap_or(defaults.file_dir),
+            smtp: SmtpConfig::from_env(),
+        }
+    }
+}
+
+// Abstraction over how mail is delivered, so flows that send mail can be
+// tested without a mail server.
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: &Email) -> impl Future<Output = Result<(), MailerError>> + Send;
+}
+
+// Reject addresses that could inject headers or SMTP commands
+pub(crate) fn check_address(address: &str) -> Result<(), MailerError> {
+    if address.is_empty()
+        || address
+            .chars()
+            .any(|c| c.is_control() || c == '<' || c == '>')
+    {
+        return Err(MailerError::InvalidAddress(address.to_string()));
+    }
+    Ok(())
+}
+
+// The email as an RFC 5322 message with CRLF line endings
+pub(crate) fn format_message(from: &str, email: &Email, date: OffsetDateTime) -> String {
+    let mut message_id = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut message_id);
+    let domain = from
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.trim_end_matches('>'))
+        .unwrap_or("localhost");
+    let subject: String = email.subject.chars().filter(|c| !c.is_control()).collect();
+
+    let mut message = format!(
+        "From: {}\r\nTo: <{}>\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\n\
+         MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\
+         Content-Transfer-Encoding: 8bit\r\n\r\n",
+        from,
+        email.to,
+        subject,
+        date.format(&Rfc2822).unwrap_or_default(),
+        hex::encode(message_id),
+        domain
+    );
+    for line in email.body.lines() {
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message
+}
+
+// Writes each email to its own .eml file, for reading mail in development
+#[derive(Debug, Clone)]
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(from: String, dir: PathBuf) -> Self {
+        Self { from, dir }
+    }
+}
+
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailerError> {
+        check_address(&email.to)?;
+        let now = OffsetDateTime::now_utc();
+        let mut suffix = [0u8; 4];
+        rand::thread_rng().fill_bytes(&mut suffix);
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            now.unix_timestamp(),
+            hex::encode(suffix)
+        ));
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(&path, format_message(&self.from, email, now)).await?;
+        tracing::info!("Wrote email to {} to {}", email.to, path.display());
+        Ok(())
+    }
+}
+
+// Keeps sent mail in memory so tests can read it
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryMailer {
+    sent: Arc<Mutex<Vec<Email>>>,
+}
+
+impl InMemoryMailer {
+    // Everything sent so far, oldest first
+    #[cfg(test)]
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl Mailer for InMemoryMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailerError> {
+        check_address(&email.to)?;
+        self.sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(email.clone());
+        Ok(())
+    }
+}
+
+// The mailer chosen by `MailerConfig`. Handlers get it as an extension.
+#[derive(Debug, Clone)]
+pub enum AppMailer {
+    Smtp(Arc<SmtpMailer>),
+    File(FileMailer),
+    InMemory(InMemoryMailer),
+}
+
+impl AppMailer {
+    pub fn new(config: MailerConfig) -> Result<Self, MailerError> {
+        Ok(match config.backend {
+            MailerBackend::Smtp => {
+                AppMailer::Smtp(Arc::new(SmtpMailer::new(config.from, config.smtp)?))
+            }
+            MailerBackend::File => AppMailer::File(FileMailer::new(config.from, config.file_dir)),
+            MailerBackend::InMemory => AppMailer::InMemory(InMemoryMailer::default()),
+        })
+    }
+}
+
+impl Mailer for AppMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailerError> {
+        match self {
+            AppMailer::Smtp(mailer) => mailer.send(email).await,
+            AppMailer::File(mailer) => mailer.send(email).await,
+            AppMailer::InMemory(mailer) => mailer.send(email).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email() -> Email {
+        Email {
+            to: "alice@example.com".to_string(),
+            subject: "Verify your email\r\nBcc: eve@example.com".to_string(),
+            body: "Hello\nClick the link".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_format_message() {
+        let message = format_message(
+            "Weather Boyz <no-reply@weather-boyz.local>",
+            &email(),
+            OffsetDateTime::UNIX_EPOCH,
+        );
+
+        assert!(message.starts_with("From: Weather Boyz <no-reply@weather-boyz.local>\r\n"));
+        assert!(message.contains("To: <alice@example.com>\r\n"));
+        // Line breaks in the subject can't add headers
+        assert!(message.contains("Subject: Verify your emailBcc: eve@example.com\r\n"));
+        assert!(message.contains("Date: Thu, 01 Jan 1970 00:00:00 +0000\r\n"));
+        assert!(message.contains("@weather-boyz.local>\r\n"));
+        assert!(message.ends_with("\r\n\r\nHello\r\nClick the link\r\n"));
+    }
+
+    #[test]
+    fn test_check_address() {
+        assert!(check_address("alice@example.com").is_ok());
+        assert!(check_address("").is_err());
+        assert!(check_address("alice@example.com>\r\nRCPT TO:<eve@example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_mailer_writes_eml_files() {
+        let dir = std::env::temp_dir().join(format!("mailer-test-{}", std::process::id()));
+        let mailer = FileMailer::new("no-reply@example.com".to_string(), dir.clone());
+
+        mailer.send(&email()).await.unwrap();
+
+        let mut entries = std::fs::read_dir(&dir).unwrap();
+        let path = entries.next().unwrap().unwrap().path();
+        assert_eq!(path.extension().unwrap(), "eml");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("To: <alice@example.com>"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_mailer_keeps_sent_mail() {
+        let mailer = AppMailer::new(MailerConfig {
+            backend: MailerBackend::InMemory,
+            ..MailerConfig::default()
+        })
+        .unwrap();
+
+        mailer.send(&email()).await.unwrap();
+        let AppMailer::InMemory(in_memory) = &mailer else {
+            panic!("expected the in-memory mailer");
+        };
+        assert_eq!(in_memory.sent(), vec![email()]);
+
+        let invalid = Email {
+            to: "".to_string(),
+            ..email()
+        };
+        assert!(mailer.send(&invalid).await.is_err());
+        assert_eq!(in_memory.sent().len(), 1);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod mailer;
+pub mod smtp;
+
+pub use mailer::*;
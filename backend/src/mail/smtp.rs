@@ -0,0 +1,291 @@
+use lettre::address::Envelope;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::transport::smtp::extension::ClientId;
+use lettre::{Address, AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+use crate::mail::mailer::{Email, Mailer, MailerError, check_address, format_message};
+
+// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    // Plain text throughout, only for local mail catchers. Credentials are
+    // never sent this way.
+    None,
+    // Upgraded with STARTTLS after connecting, usually on port 587
+    StartTls,
+    // TLS from the start, usually on port 465
+    Tls,
+}
+
+// Configuration for delivering mail through an SMTP server
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub tls: SmtpTls,
+    // Name we greet the server with
+    pub helo_name: String,
+    pub timeout_seconds: u64,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 587,
+            username: None,
+            password: None,
+            tls: SmtpTls::StartTls,
+            helo_name: "localhost".to_string(),
+            timeout_seconds: 30,
+        }
+    }
+}
+
+impl SmtpConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            host: std::env::var("SMTP_HOST").unwrap_or(defaults.host),
+            port: std::env::var("SMTP_PORT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.port),
+            username: std::env::var("SMTP_USERNAME")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            password: std::env::var("SMTP_PASSWORD")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            tls: match std::env::var("SMTP_TLS").as_deref() {
+                Ok("none") => SmtpTls::None,
+                Ok("starttls") => SmtpTls::StartTls,
+                Ok("tls") => SmtpTls::Tls,
+                _ => defaults.tls,
+            },
+            helo_name: std::env::var("SMTP_HELO_NAME").unwrap_or(defaults.helo_name),
+            timeout_seconds: std::env::var("SMTP_TIMEOUT_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.timeout_seconds),
+        }
+    }
+}
+
+// Sends each email over its own SMTP connection
+#[derive(Debug)]
+pub struct SmtpMailer {
+    from: String,
+    sender: Address,
+    host: String,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    // Fails if `from` is not a valid mailbox, or if credentials would be
+    // sent over a connection without TLS
+    pub fn new(from: String, config: SmtpConfig) -> Result<Self, MailerError> {
+        let sender = from
+            .parse::<Mailbox>()
+            .map_err(|_| MailerError::InvalidAddress(from.clone()))?
+            .email;
+
+        let tls = match config.tls {
+            SmtpTls::None => Tls::None,
+            SmtpTls::StartTls => Tls::Required(TlsParameters::new(config.host.clone())?),
+            SmtpTls::Tls => Tls::Wrapper(TlsParameters::new(config.host.clone())?),
+        };
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+            .port(config.port)
+            .tls(tls)
+            .hello_name(ClientId::Domain(config.helo_name))
+            .timeout(Some(Duration::from_secs(config.timeout_seconds)));
+        if let Some(username) = config.username {
+            if config.tls == SmtpTls::None {
+                return Err(MailerError::InsecureSmtpAuth);
+            }
+            builder = builder.credentials((username, config.password.unwrap_or_default()).into());
+        }
+
+        Ok(Self {
+            from,
+            sender,
+            host: config.host,
+            transport: builder.build(),
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailerError> {
+        check_address(&email.to)?;
+        let recipient = email
+            .to
+            .parse::<Address>()
+            .map_err(|_| MailerError::InvalidAddress(email.to.clone()))?;
+        let envelope = Envelope::new(Some(self.sender.clone()), vec![recipient])
+            .map_err(|_| MailerError::InvalidAddress(email.to.clone()))?;
+        let message = format_message(&self.from, email, OffsetDateTime::now_utc());
+
+        // The transport ends the data with CRLF before the final dot itself
+        let message = message.strip_suffix("\r\n").unwrap_or(&message);
+        self.transport
+            .send_raw(&envelope, message.as_bytes())
+            .await?;
+        tracing::info!("Sent email to {} through {}", email.to, self.host);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    // A mail server that replies to every command with the next scripted
+    // reply and returns what it was sent
+    async fn fake_smtp_server(
+        replies: Vec<&'static str>,
+    ) -> (u16, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (read, mut write) = socket.into_split();
+            let mut read = BufReader::new(read);
+            let mut transcript = String::new();
+            let mut in_data = false;
+
+            write
+                .write_all(b"220 fake.example ESMTP\r\n")
+                .await
+                .unwrap();
+            let mut replies = replies.into_iter();
+            loop {
+                let mut line = String::new();
+                if read.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                transcript.push_str(&line);
+                if in_data && line != ".\r\n" {
+                    continue;
+                }
+                in_data = false;
+                let Some(reply) = replies.next() else {
+                    break;
+                };
+                if reply.starts_with("354") {
+                    in_data = true;
+                }
+                write.write_all(reply.as_bytes()).await.unwrap();
+            }
+            transcript
+        });
+        (port, server)
+    }
+
+    fn config(port: u16) -> SmtpConfig {
+        SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            tls: SmtpTls::None,
+            timeout_seconds: 5,
+            ..SmtpConfig::default()
+        }
+    }
+
+    fn mailer(port: u16) -> SmtpMailer {
+        SmtpMailer::new(
+            "Weather Boyz <no-reply@weather-boyz.local>".to_string(),
+            config(port),
+        )
+        .unwrap()
+    }
+
+    fn email() -> Email {
+        Email {
+            to: "alice@example.com".to_string(),
+            subject: "Hello".to_string(),
+            body: "First line\n.hidden line".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_smtp_mailer_delivers_message() {
+        let (port, server) = fake_smtp_server(vec![
+            "250-fake.example\r\n250 8BITMIME\r\n",
+            "250 OK\r\n",
+            "250 OK\r\n",
+            "354 Go ahead\r\n",
+            "250 Queued\r\n",
+            "221 Bye\r\n",
+        ])
+        .await;
+
+        mailer(port).send(&email()).await.unwrap();
+        let transcript = server.await.unwrap();
+
+        assert!(transcript.starts_with("EHLO localhost\r\n"));
+        assert!(transcript.contains("MAIL FROM:<no-reply@weather-boyz.local>"));
+        assert!(transcript.contains("RCPT TO:<alice@example.com>\r\n"));
+        assert!(transcript.contains("Subject: Hello\r\n"));
+        // Lines starting with a dot are escaped so they don't end the message
+        assert!(transcript.contains("\r\nFirst line\r\n..hidden line\r\n.\r\nQUIT\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_smtp_mailer_reports_rejected_recipient() {
+        let (port, server) = fake_smtp_server(vec![
+            "250 fake.example\r\n",
+            "250 OK\r\n",
+            "550 No such user\r\n",
+        ])
+        .await;
+
+        let error = mailer(port).send(&email()).await.unwrap_err();
+        match error {
+            MailerError::Smtp(error) => assert!(error.is_permanent()),
+            other => panic!("unexpected error: {}", other),
+        }
+        drop(server);
+    }
+
+    #[test]
+    fn test_smtp_mailer_refuses_credentials_without_tls() {
+        let from = "no-reply@weather-boyz.local".to_string();
+        let with_credentials = SmtpConfig {
+            username: Some("mailer".to_string()),
+            password: Some("secret".to_string()),
+            ..config(25)
+        };
+
+        assert!(matches!(
+            SmtpMailer::new(from.clone(), with_credentials.clone()),
+            Err(MailerError::InsecureSmtpAuth)
+        ));
+        assert!(
+            SmtpMailer::new(
+                from,
+                SmtpConfig {
+                    tls: SmtpTls::StartTls,
+                    ..with_credentials
+                }
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_smtp_mailer_rejects_invalid_sender() {
+        assert!(matches!(
+            SmtpMailer::new("not a mailbox".to_string(), config(25)),
+            Err(MailerError::InvalidAddress(_))
+        ));
+    }
+}
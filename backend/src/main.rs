@@ -1,10 +1,16 @@
-use axum::{Router, routing::post};
+use axum::{Router, middleware, routing::post};
+use std::net::SocketAddr;
 use tower_http::cors::CorsLayer;
 
 mod db;
+mod mail;
 use db::user_queries::create_user;
 
 mod blockchain;
+mod claims;
+mod policies;
+mod pricing;
+mod weather;
 mod web;
 
 #[cfg(test)]
@@ -16,11 +22,10 @@ async fn main() {
     dotenvy::from_path("../.env").ok();
     let backend_url: String =
         std::env::var("BACKEND_URL").unwrap_or_else(|_| "http://0.0.0.0:6969".to_string());
-    
+
     let backend_address: String =
         std::env::var("BACKEND_ADDRESS").unwrap_or_else(|_| "0.0.0.0:6969".to_string());
 
-
     tracing_subscriber::fmt::init();
     tracing::info!("Starting weather-boyz backend server...");
 
@@ -37,21 +42,140 @@ async fn main() {
         }
     };
 
+    // Start the WeatherXM ingestion worker
+    let ingestion_config = weather::IngestionConfig::from_env();
+    if ingestion_config.enabled {
+        match weather::HttpWeatherXmClient::new(weather::WeatherXmConfig::from_env()) {
+            Ok(client) => {
+                tracing::info!(
+                    "Starting weather ingestion worker (every {}s)",
+                    ingestion_config.poll_interval_seconds
+                );
+                let service =
+                    weather::WeatherIngestionService::new(pool.clone(), client, ingestion_config);
+                tokio::spawn(service.run());
+            }
+            Err(e) => {
+                tracing::error!("Failed to initialize WeatherXM client: {}", e);
+            }
+        }
+    } else {
+        tracing::info!("Weather ingestion worker disabled");
+    }
+
+    // Start the claims processor
+    let claims_config = claims::ClaimsProcessorConfig::from_env();
+    if claims_config.enabled {
+        tracing::info!(
+            "Starting claims processor (every {}s)",
+            claims_config.interval_seconds
+        );
+        tokio::spawn(claims::ClaimsProcessor::new(pool.clone(), claims_config).run());
+    } else {
+        tracing::info!("Claims processor disabled");
+    }
+
+    // Start the blockchain event indexer
+    let indexer_config = blockchain::EventIndexerConfig::from_env();
+    if indexer_config.enabled {
+        match blockchain::ProviderEventSource::new(&web::services::get_blockchain_config()) {
+            Ok(source) => {
+                tracing::info!(
+                    "Starting blockchain event indexer from block {} ({} confirmations)",
+                    indexer_config.start_block,
+                    indexer_config.confirmations
+                );
+                let contract_address = source.contract_address();
+                let indexer = blockchain::EventIndexer::new(
+                    pool.clone(),
+                    source,
+                    contract_address,
+                    indexer_config,
+                );
+                tokio::spawn(indexer.run());
+            }
+            Err(e) => {
+                tracing::error!("Failed to initialize blockchain event indexer: {}", e);
+            }
+        }
+    } else {
+        tracing::info!("Blockchain event indexer disabled");
+    }
+
+    // Start the refund processor, which retries refunds that failed to send
+    let refund_config = blockchain::RefundProcessorConfig::from_env();
+    if refund_config.enabled {
+        match blockchain::BlockchainService::new(web::services::get_blockchain_config()) {
+            Ok(service) if service.can_send_refunds() => {
+                tracing::info!(
+                    "Starting refund processor (every {}s)",
+                    refund_config.interval_seconds
+                );
+                tokio::spawn(
+                    blockchain::RefundProcessor::new(pool.clone(), service, refund_config).run(),
+                );
+            }
+            Ok(_) => {
+                tracing::info!("Refund processor disabled: no contract owner key");
+            }
+            Err(e) => {
+                tracing::error!("Failed to initialize refund processor: {}", e);
+            }
+        }
+    } else {
+        tracing::info!("Refund processor disabled");
+    }
+
+    // Start the cleanup worker, which removes expired sign-in nonces and idle
+    // rate limit buckets
+    let cleanup_config = web::cleanup::CleanupConfig::from_env();
+    if cleanup_config.enabled {
+        tracing::info!(
+            "Starting cleanup worker (every {}s)",
+            cleanup_config.interval_seconds
+        );
+        tokio::spawn(web::cleanup::Cleanup::new(pool.clone(), cleanup_config).run());
+    } else {
+        tracing::info!("Cleanup worker disabled");
+    }
+
     let cors = CorsLayer::permissive();
     tracing::info!("CORS layer configured");
 
     // Create your main router
     tracing::info!("Setting up main router...");
-    let main_router = Router::new().route("/createUser", post(create_user));
+    let main_router = Router::new().route(
+        "/createUser",
+        post(create_user).layer(middleware::from_fn_with_state(
+            "create_user",
+            web::rate_limit::rate_limit_middleware,
+        )),
+    );
 
     // Get the web routes router
     tracing::info!("Setting up web routes...");
     let web_router = web::routes::app().await;
 
+    // Limit password guessing on /signin and /createUser
+    let rate_limiter = web::rate_limit::RateLimiter::new(
+        pool.clone(),
+        web::rate_limit::RateLimitConfig::from_env(),
+    );
+
+    let mailer = match mail::AppMailer::new(mail::MailerConfig::from_env()) {
+        Ok(mailer) => mailer,
+        Err(e) => {
+            tracing::error!("Failed to configure the mailer: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Merge the routers
     let app = main_router
         .merge(web_router)
         .layer(cors)
+        .layer(axum::extract::Extension(rate_limiter))
+        .layer(axum::extract::Extension(mailer))
         .layer(axum::extract::Extension(pool));
 
     let listener = match tokio::net::TcpListener::bind(&backend_address).await {
@@ -71,7 +195,12 @@ async fn main() {
     tracing::info!("  POST /createUser - Create new user");
     tracing::info!("  GET  /tokenvalid/ - Protected route (requires token auth)");
 
-    if let Err(e) = axum::serve(listener, app).await {
+    if let Err(e) = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    {
         tracing::error!("Server error: {:?}", e);
         std::process::exit(1);
     }
@@ -0,0 +1,244 @@
+use crate::db::models::{InsurancePolicy, PolicyStatus};
+use crate::db::policy_queries;
+use crate::policies::lifecycle::{Actor, LifecycleError, transition_policy_in};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Serialize;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{Pool, Postgres};
+use std::str::FromStr;
+
+// Refunds are paid in wei, so keep the full 18 decimal places of ETH
+const REFUND_SCALE: u32 = 18;
+
+// Configuration for policy cancellation
+#[derive(Debug, Clone)]
+pub struct CancellationConfig {
+    // Share of the premium kept when a policy is cancelled
+    pub fee_rate: Decimal,
+}
+
+impl Default for CancellationConfig {
+    fn default() -> Self {
+        Self {
+            fee_rate: Decimal::new(5, 2),
+        }
+    }
+}
+
+impl CancellationConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            fee_rate: std::env::var("CANCELLATION_FEE_RATE")
+                .ok()
+                .and_then(|v| Decimal::from_str(&v).ok())
+                .unwrap_or(defaults.fee_rate),
+        }
+    }
+}
+
+// What a policyholder gets back on cancelling
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Refund {
+    pub unused_premium: Decimal,
+    pub fee: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PolicyCancellation {
+    pub policy: InsurancePolicy,
+    pub refund: Refund,
+}
+
+// The premium for the part of the coverage period that is left as of `now`,
+// less the cancellation fee. A policy that has not started yet refunds its
+// whole premium less the fee.
+pub fn pro_rata_refund(
+    policy: &InsurancePolicy,
+    now: PrimitiveDateTime,
+    config: &CancellationConfig,
+) -> Refund {
+    let total_seconds = (policy.end_date - policy.start_date).whole_seconds();
+    let remaining_seconds = (policy.end_date - now.max(policy.start_date))
+        .whole_seconds()
+        .clamp(0, total_seconds.max(0));
+
+    let unused_premium = if total_seconds > 0 {
+        (policy.premium_amount * Decimal::from(remaining_seconds) / Decimal::from(total_seconds))
+            .round_dp_with_strategy(REFUND_SCALE, RoundingStrategy::ToZero)
+    } else {
+        Decimal::ZERO
+    };
+    let fee = (policy.premium_amount * config.fee_rate)
+        .round_dp_with_strategy(REFUND_SCALE, RoundingStrategy::AwayFromZero);
+
+    Refund {
+        unused_premium: unused_premium.normalize(),
+        fee: fee.normalize(),
+        amount: (unused_premium - fee).max(Decimal::ZERO).normalize(),
+    }
+}
+
+// Cancel a policy and record the refund owed for it in one transaction. The
+// refund itself is paid separately.
+pub async fn cancel_policy(
+    pool: &Pool<Postgres>,
+    policy_id: i32,
+    actor: Actor,
+    refund: &Refund,
+    now: PrimitiveDateTime,
+) -> Result<InsurancePolicy, LifecycleError> {
+    let mut tx = pool.begin().await?;
+
+    transition_policy_in(
+        &mut tx,
+        policy_id,
+        PolicyStatus::Cancelled,
+        actor,
+        Some(&format!("Cancelled with a refund of {}", refund.amount)),
+    )
+    .await?;
+    let policy =
+        policy_queries::record_policy_cancellation(&mut *tx, policy_id, now, refund.amount).await?;
+
+    tx.commit().await?;
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{CreateInsurancePolicy, PolicyType};
+    use crate::test_utils::{create_test_db, create_test_user_for_policies, test_policy_data};
+    use time::{Date, Month, Time};
+
+    fn datetime(month: Month, day: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2024, month, day).unwrap(),
+            Time::MIDNIGHT,
+        )
+    }
+
+    // A 30 day policy for June 2024 with a premium of 0.30
+    fn active_policy() -> InsurancePolicy {
+        InsurancePolicy {
+            id: 1,
+            user_id: 1,
+            policy_template_id: None,
+            policy_name: "Cancellable Policy".to_string(),
+            policy_type: PolicyType::Rain,
+            location_latitude: Decimal::from_str("40.7128").unwrap(),
+            location_longitude: Decimal::from_str("-74.0060").unwrap(),
+            location_h3_index: None,
+            location_name: None,
+            coverage_amount: Decimal::from_str("3.00").unwrap(),
+            premium_amount: Decimal::from_str("0.30").unwrap(),
+            currency: None,
+            start_date: datetime(Month::June, 1),
+            end_date: datetime(Month::July, 1),
+            status: Some(PolicyStatus::Active),
+            weather_station_id: None,
+            smart_contract_address: None,
+            purchase_transaction_hash: None,
+            blockchain_verified: Some(true),
+            verification_timestamp: None,
+            blockchain_block_number: None,
+            verification_error_message: None,
+            blockchain_policy_id: None,
+            cancelled_at: None,
+            refund_amount: None,
+            refund_transaction_hash: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    async fn create_policy(pool: &Pool<Postgres>) -> InsurancePolicy {
+        let user_id = create_test_user_for_policies(pool).await;
+        policy_queries::create_insurance_policy(
+            pool,
+            &CreateInsurancePolicy {
+                premium_amount: Decimal::from_str("0.30").unwrap(),
+                ..test_policy_data(user_id)
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    fn refund_at(policy: &InsurancePolicy, now: PrimitiveDateTime, fee_rate: &str) -> Refund {
+        let config = CancellationConfig {
+            fee_rate: Decimal::from_str(fee_rate).unwrap(),
+        };
+        pro_rata_refund(policy, now, &config)
+    }
+
+    #[test]
+    fn test_pro_rata_refund() {
+        let policy = active_policy();
+
+        // Two thirds of the period left
+        let refund = refund_at(&policy, datetime(Month::June, 11), "0.05");
+        assert_eq!(refund.unused_premium, Decimal::from_str("0.20").unwrap());
+        assert_eq!(refund.fee, Decimal::from_str("0.015").unwrap());
+        assert_eq!(refund.amount, Decimal::from_str("0.185").unwrap());
+
+        // Not started yet
+        let refund = refund_at(&policy, datetime(Month::May, 20), "0.05");
+        assert_eq!(refund.unused_premium, Decimal::from_str("0.30").unwrap());
+        assert_eq!(refund.amount, Decimal::from_str("0.285").unwrap());
+
+        // Nearly over, so the fee is more than what is left
+        let refund = refund_at(&policy, datetime(Month::June, 30), "0.05");
+        assert_eq!(refund.amount, Decimal::ZERO);
+
+        // Already ended
+        let refund = refund_at(&policy, datetime(Month::August, 1), "0");
+        assert_eq!(refund.unused_premium, Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_policy_records_refund_and_history() {
+        let test_db = create_test_db().await;
+        let policy = create_policy(&test_db.pool).await;
+        let now = datetime(Month::June, 11);
+        let refund = refund_at(&policy, now, "0.05");
+
+        let cancelled = cancel_policy(
+            &test_db.pool,
+            policy.id,
+            Actor::User(policy.user_id),
+            &refund,
+            now,
+        )
+        .await
+        .unwrap();
+        assert_eq!(cancelled.status, Some(PolicyStatus::Cancelled));
+        assert_eq!(cancelled.cancelled_at, Some(now));
+        assert_eq!(cancelled.refund_amount, Some(refund.amount));
+
+        let history = policy_queries::get_policy_status_history(&test_db.pool, policy.id)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].actor, format!("user:{}", policy.user_id));
+
+        // A cancelled policy cannot be cancelled again
+        let again = cancel_policy(
+            &test_db.pool,
+            policy.id,
+            Actor::User(policy.user_id),
+            &refund,
+            now,
+        )
+        .await;
+        assert!(matches!(
+            again,
+            Err(LifecycleError::IllegalTransition {
+                from: PolicyStatus::Cancelled,
+                ..
+            })
+        ));
+    }
+}
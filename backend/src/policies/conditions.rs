@@ -0,0 +1,401 @@
+use crate::claims::{EvaluationError, validate_condition};
+use crate::db::models::{
+    ComparisonOperator, ConditionType, CreatePolicyCondition, PolicyConditionOverride,
+    PolicyTemplate, TemplateCondition, TemplateConditions,
+};
+use rust_decimal::Decimal;
+
+// Error types for building a policy's conditions from its template
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ConditionError {
+    #[error("Policy template {0} has no conditions")]
+    MissingConditions(i32),
+    #[error("Policy template {template_id} has invalid conditions: {reason}")]
+    InvalidTemplate { template_id: i32, reason: String },
+    #[error("Policy template has no {0} condition to override")]
+    UnknownCondition(ConditionType),
+    #[error("Condition {0} is overridden more than once")]
+    DuplicateOverride(ConditionType),
+    #[error("Condition {condition_type} can't use operator {operator}, only {allowed}")]
+    OperatorNotAllowed {
+        condition_type: ConditionType,
+        operator: ComparisonOperator,
+        allowed: ComparisonOperator,
+    },
+    #[error("The threshold of condition {0} can't be changed")]
+    ThresholdFixed(ConditionType),
+    #[error("Threshold {threshold} of condition {condition_type} must be between {min} and {max}")]
+    ThresholdOutOfRange {
+        condition_type: ConditionType,
+        threshold: Decimal,
+        min: Decimal,
+        max: Decimal,
+    },
+    #[error("Invalid override for condition {condition_type}: {source}")]
+    InvalidOverride {
+        condition_type: ConditionType,
+        source: EvaluationError,
+    },
+}
+
+impl ConditionError {
+    // Whether the overrides in the request are at fault, rather than the template
+    pub fn is_override_error(&self) -> bool {
+        matches!(
+            self,
+            ConditionError::UnknownCondition(_)
+                | ConditionError::DuplicateOverride(_)
+                | ConditionError::OperatorNotAllowed { .. }
+                | ConditionError::ThresholdFixed(_)
+                | ConditionError::ThresholdOutOfRange { .. }
+                | ConditionError::InvalidOverride { .. }
+        )
+    }
+}
+
+impl From<TemplateCondition> for CreatePolicyCondition {
+    fn from(c: TemplateCondition) -> Self {
+        CreatePolicyCondition {
+            policy_id: 0,
+            condition_type: c.condition_type,
+            operator: c.operator,
+            threshold_value: c.threshold,
+            measurement_unit: c.unit,
+            measurement_period: c.period,
+            consecutive_days: c.consecutive_days,
+        }
+    }
+}
+
+// The template's default_conditions as conditions for a new policy, with the
+// overrides applied. The returned conditions have a policy_id of 0 until the
+// policy is created.
+//
+// Overrides can only make a condition stricter or looser in the direction the
+// template pays out in: an operator must point the same way as the
+// template's, and a threshold must stay within the template's range.
+pub fn policy_conditions(
+    template: &PolicyTemplate,
+    overrides: &[PolicyConditionOverride],
+) -> Result<Vec<CreatePolicyCondition>, ConditionError> {
+    let default_conditions = template
+        .default_conditions
+        .clone()
+        .ok_or(ConditionError::MissingConditions(template.id))?;
+    let parsed: TemplateConditions = serde_json::from_value(default_conditions).map_err(|e| {
+        ConditionError::InvalidTemplate {
+            template_id: template.id,
+            reason: e.to_string(),
+        }
+    })?;
+    if parsed.conditions.is_empty() {
+        return Err(ConditionError::MissingConditions(template.id));
+    }
+    if let Some(condition) = parsed
+        .conditions
+        .iter()
+        .find(|c| !c.has_valid_threshold_range())
+    {
+        return Err(ConditionError::InvalidTemplate {
+            template_id: template.id,
+            reason: format!(
+                "Threshold range of condition {} must have both ends and include its threshold",
+                condition.condition_type
+            ),
+        });
+    }
+
+    let mut conditions: Vec<CreatePolicyCondition> =
+        parsed.conditions.iter().cloned().map(Into::into).collect();
+    for condition in &conditions {
+        validate_condition(condition).map_err(|e| ConditionError::InvalidTemplate {
+            template_id: template.id,
+            reason: e.to_string(),
+        })?;
+    }
+
+    for (i, policy_override) in overrides.iter().enumerate() {
+        let condition_type = policy_override.condition_type;
+        if overrides[..i]
+            .iter()
+            .any(|o| o.condition_type == condition_type)
+        {
+            return Err(ConditionError::DuplicateOverride(condition_type));
+        }
+        let (condition, template_condition) = conditions
+            .iter_mut()
+            .zip(&parsed.conditions)
+            .find(|(c, _)| c.condition_type == condition_type)
+            .ok_or(ConditionError::UnknownCondition(condition_type))?;
+
+        if let Some(operator) = policy_override.operator {
+            check_operator(template_condition, operator)?;
+            condition.operator = operator;
+        }
+        if let Some(threshold) = policy_override.threshold {
+            check_threshold(template_condition, threshold)?;
+            condition.threshold_value = threshold;
+        }
+        if let Some(consecutive_days) = policy_override.consecutive_days {
+            condition.consecutive_days = Some(consecutive_days);
+        }
+        validate_condition(condition).map_err(|source| ConditionError::InvalidOverride {
+            condition_type,
+            source,
+        })?;
+    }
+
+    Ok(conditions)
+}
+
+fn check_operator(
+    template_condition: &TemplateCondition,
+    operator: ComparisonOperator,
+) -> Result<(), ConditionError> {
+    let allowed = template_condition.operator;
+    let same_direction = allowed
+        .direction()
+        .is_some_and(|direction| operator.direction() == Some(direction));
+    if operator != allowed && !same_direction {
+        return Err(ConditionError::OperatorNotAllowed {
+            condition_type: template_condition.condition_type,
+            operator,
+            allowed,
+        });
+    }
+    Ok(())
+}
+
+fn check_threshold(
+    template_condition: &TemplateCondition,
+    threshold: Decimal,
+) -> Result<(), ConditionError> {
+    let condition_type = template_condition.condition_type;
+    if threshold == template_condition.threshold {
+        return Ok(());
+    }
+    let Some((min, max)) = template_condition.threshold_range() else {
+        return Err(ConditionError::ThresholdFixed(condition_type));
+    };
+    if threshold < min || threshold > max {
+        return Err(ConditionError::ThresholdOutOfRange {
+            condition_type,
+            threshold,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{ComparisonOperator, MeasurementPeriod, MeasurementUnit, PolicyType};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn template(default_conditions: Option<serde_json::Value>) -> PolicyTemplate {
+        PolicyTemplate {
+            id: 1,
+            template_name: "Drought Protection".to_string(),
+            description: None,
+            policy_type: PolicyType::Drought,
+            default_conditions,
+            min_coverage_amount: dec("1.00"),
+            max_coverage_amount: dec("100.00"),
+            base_premium_rate: dec("0.05"),
+            is_active: Some(true),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn drought_template() -> PolicyTemplate {
+        template(Some(serde_json::json!({"conditions": [{
+            "type": "rainfall",
+            "operator": "<",
+            "threshold": 5,
+            "unit": "mm",
+            "period": "daily",
+            "consecutive_days": 10,
+            "min_threshold": 1,
+            "max_threshold": 10
+        }]})))
+    }
+
+    fn threshold_override(threshold: &str) -> PolicyConditionOverride {
+        PolicyConditionOverride {
+            condition_type: ConditionType::Rainfall,
+            operator: None,
+            threshold: Some(dec(threshold)),
+            consecutive_days: None,
+        }
+    }
+
+    #[test]
+    fn test_conditions_from_template() {
+        let conditions = policy_conditions(&drought_template(), &[]).unwrap();
+
+        assert_eq!(
+            conditions,
+            vec![CreatePolicyCondition {
+                policy_id: 0,
+                condition_type: ConditionType::Rainfall,
+                operator: ComparisonOperator::LessThan,
+                threshold_value: dec("5"),
+                measurement_unit: MeasurementUnit::Millimeters,
+                measurement_period: MeasurementPeriod::Daily,
+                consecutive_days: Some(10),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_conditions_with_overrides() {
+        let mut policy_override = threshold_override("2.5");
+        policy_override.consecutive_days = Some(14);
+
+        let conditions = policy_conditions(&drought_template(), &[policy_override]).unwrap();
+
+        assert_eq!(conditions[0].operator, ComparisonOperator::LessThan);
+        assert_eq!(conditions[0].threshold_value, dec("2.5"));
+        assert_eq!(conditions[0].consecutive_days, Some(14));
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        let unknown = PolicyConditionOverride {
+            condition_type: ConditionType::WindSpeed,
+            ..threshold_override("50")
+        };
+        let err = policy_conditions(&drought_template(), &[unknown]).unwrap_err();
+        assert_eq!(
+            err,
+            ConditionError::UnknownCondition(ConditionType::WindSpeed)
+        );
+
+        let overrides = [threshold_override("2"), threshold_override("3")];
+        let err = policy_conditions(&drought_template(), &overrides).unwrap_err();
+        assert_eq!(
+            err,
+            ConditionError::DuplicateOverride(ConditionType::Rainfall)
+        );
+
+        let invalid = PolicyConditionOverride {
+            consecutive_days: Some(0),
+            ..threshold_override("2")
+        };
+        let err = policy_conditions(&drought_template(), &[invalid]).unwrap_err();
+        assert!(matches!(err, ConditionError::InvalidOverride { .. }));
+        assert!(err.is_override_error());
+    }
+
+    #[test]
+    fn test_overrides_keep_the_template_direction() {
+        let operator_override = |operator| PolicyConditionOverride {
+            operator: Some(operator),
+            threshold: None,
+            ..threshold_override("5")
+        };
+
+        let conditions = policy_conditions(
+            &drought_template(),
+            &[operator_override(ComparisonOperator::LessOrEqual)],
+        )
+        .unwrap();
+        assert_eq!(conditions[0].operator, ComparisonOperator::LessOrEqual);
+
+        for operator in [
+            ComparisonOperator::GreaterOrEqual,
+            ComparisonOperator::GreaterThan,
+            ComparisonOperator::NotEqual,
+        ] {
+            let err =
+                policy_conditions(&drought_template(), &[operator_override(operator)]).unwrap_err();
+            assert_eq!(
+                err,
+                ConditionError::OperatorNotAllowed {
+                    condition_type: ConditionType::Rainfall,
+                    operator,
+                    allowed: ComparisonOperator::LessThan,
+                }
+            );
+            assert!(err.is_override_error());
+        }
+    }
+
+    #[test]
+    fn test_overrides_keep_the_threshold_in_range() {
+        for threshold in ["0.5", "10.01", "-5"] {
+            let err = policy_conditions(&drought_template(), &[threshold_override(threshold)])
+                .unwrap_err();
+            assert_eq!(
+                err,
+                ConditionError::ThresholdOutOfRange {
+                    condition_type: ConditionType::Rainfall,
+                    threshold: dec(threshold),
+                    min: dec("1"),
+                    max: dec("10"),
+                }
+            );
+            assert!(err.is_override_error());
+        }
+        assert!(policy_conditions(&drought_template(), &[threshold_override("10")]).is_ok());
+
+        // Without a range only the template's own threshold is allowed
+        let fixed = template(Some(serde_json::json!({"conditions": [{
+            "type": "rainfall",
+            "operator": "<",
+            "threshold": 5,
+            "unit": "mm",
+            "period": "daily",
+            "consecutive_days": 10
+        }]})));
+        assert!(policy_conditions(&fixed, &[threshold_override("5")]).is_ok());
+        assert_eq!(
+            policy_conditions(&fixed, &[threshold_override("4")]).unwrap_err(),
+            ConditionError::ThresholdFixed(ConditionType::Rainfall)
+        );
+    }
+
+    #[test]
+    fn test_invalid_template_conditions() {
+        let err = policy_conditions(&template(None), &[]).unwrap_err();
+        assert_eq!(err, ConditionError::MissingConditions(1));
+
+        let err = policy_conditions(&template(Some(serde_json::json!({"conditions": []}))), &[])
+            .unwrap_err();
+        assert_eq!(err, ConditionError::MissingConditions(1));
+
+        let err = policy_conditions(
+            &template(Some(
+                serde_json::json!({"conditions": [{"type": "rainfall"}]}),
+            )),
+            &[],
+        )
+        .unwrap_err();
+        assert!(matches!(err, ConditionError::InvalidTemplate { .. }));
+        assert!(!err.is_override_error());
+
+        // The default threshold must be inside the range
+        let err = policy_conditions(
+            &template(Some(serde_json::json!({"conditions": [{
+                "type": "rainfall",
+                "operator": "<",
+                "threshold": 5,
+                "unit": "mm",
+                "period": "daily",
+                "min_threshold": 6,
+                "max_threshold": 10
+            }]}))),
+            &[],
+        )
+        .unwrap_err();
+        assert!(matches!(err, ConditionError::InvalidTemplate { .. }));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::db::models::{CreatePolicyStatusChange, PolicyStatus, PolicyStatusChange};
+use crate::db::policy_queries;
+use sqlx::{PgConnection, Pool, Postgres};
+use std::fmt;
+use tracing::info;
+
+// Error types for policy status changes
+#[derive(Debug, thiserror::Error)]
+pub enum LifecycleError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Policy {0} not found")]
+    PolicyNotFound(i32),
+    #[error("Policy {policy_id} cannot move from {from} to {to}")]
+    IllegalTransition {
+        policy_id: i32,
+        from: PolicyStatus,
+        to: PolicyStatus,
+    },
+}
+
+// Who changed a policy's status. Recorded in the history as "system:<name>",
+// "user:<id>" or "admin:<id>".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Actor {
+    System(&'static str),
+    User(i32),
+    Admin(i32),
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Actor::System(name) => write!(f, "system:{}", name),
+            Actor::User(id) => write!(f, "user:{}", id),
+            Actor::Admin(id) => write!(f, "admin:{}", id),
+        }
+    }
+}
+
+// The statuses a policy may move to from `from`. Expired, claimed and
+// cancelled are final.
+pub fn allowed_transitions(from: PolicyStatus) -> &'static [PolicyStatus] {
+    match from {
+        PolicyStatus::PendingVerification => &[PolicyStatus::Active, PolicyStatus::Cancelled],
+        PolicyStatus::Active => &[
+            PolicyStatus::Expired,
+            PolicyStatus::Claimed,
+            PolicyStatus::Cancelled,
+        ],
+        PolicyStatus::Expired | PolicyStatus::Claimed | PolicyStatus::Cancelled => &[],
+    }
+}
+
+pub fn can_transition(from: PolicyStatus, to: PolicyStatus) -> bool {
+    allowed_transitions(from).contains(&to)
+}
+
+// Move a policy to `to` and record the change
+pub async fn transition_policy(
+    pool: &Pool<Postgres>,
+    policy_id: i32,
+    to: PolicyStatus,
+    actor: Actor,
+    reason: Option<&str>,
+) -> Result<PolicyStatusChange, LifecycleError> {
+    let mut tx = pool.begin().await?;
+    let change = transition_policy_in(&mut tx, policy_id, to, actor, reason).await?;
+    tx.commit().await?;
+    Ok(change)
+}
+
+// As transition_policy, inside the caller's transaction. The policy row is
+// locked while the transition is checked, so concurrent changes are checked
+// against the status they actually replace.
+pub async fn transition_policy_in(
+    conn: &mut PgConnection,
+    policy_id: i32,
+    to: PolicyStatus,
+    actor: Actor,
+    reason: Option<&str>,
+) -> Result<PolicyStatusChange, LifecycleError> {
+    apply_transition(conn, policy_id, to, actor, reason, false).await
+}
+
+// As transition_policy_in, but allows any change of status, including out of
+// a final one. For admins correcting a policy by hand; it does not pay or
+// refund anything.
+pub async fn force_transition_policy_in(
+    conn: &mut PgConnection,
+    policy_id: i32,
+    to: PolicyStatus,
+    actor: Actor,
+    reason: &str,
+) -> Result<PolicyStatusChange, LifecycleError> {
+    apply_transition(conn, policy_id, to, actor, Some(reason), true).await
+}
+
+async fn apply_transition(
+    conn: &mut PgConnection,
+    policy_id: i32,
+    to: PolicyStatus,
+    actor: Actor,
+    reason: Option<&str>,
+    force: bool,
+) -> Result<PolicyStatusChange, LifecycleError> {
+    let from = policy_queries::lock_policy_status(&mut *conn, policy_id)
+        .await?
+        .ok_or(LifecycleError::PolicyNotFound(policy_id))?;
+    let allowed = if force {
+        from != to
+    } else {
+        can_transition(from, to)
+    };
+    if !allowed {
+        return Err(LifecycleError::IllegalTransition {
+            policy_id,
+            from,
+            to,
+        });
+    }
+
+    policy_queries::set_policy_status(&mut *conn, policy_id, to).await?;
+    let change = policy_queries::create_policy_status_change(
+        &mut *conn,
+        &CreatePolicyStatusChange {
+            policy_id,
+            from_status: from,
+            to_status: to,
+            actor: actor.to_string(),
+            reason: reason.map(|r| r.to_string()),
+        },
+    )
+    .await?;
+
+    info!(
+        "Policy {} moved from {} to {} by {}",
+        policy_id, from, to, actor
+    );
+    Ok(change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_db, create_test_user_for_policies, test_policy_data};
+
+    async fn create_policy(pool: &Pool<Postgres>) -> i32 {
+        let user_id = create_test_user_for_policies(pool).await;
+        policy_queries::create_insurance_policy(pool, &test_policy_data(user_id))
+            .await
+            .unwrap()
+            .id
+    }
+
+    #[test]
+    fn test_allowed_transitions() {
+        assert!(can_transition(
+            PolicyStatus::PendingVerification,
+            PolicyStatus::Active
+        ));
+        assert!(can_transition(PolicyStatus::Active, PolicyStatus::Claimed));
+        assert!(can_transition(
+            PolicyStatus::Active,
+            PolicyStatus::Cancelled
+        ));
+        assert!(!can_transition(
+            PolicyStatus::PendingVerification,
+            PolicyStatus::Claimed
+        ));
+        assert!(!can_transition(PolicyStatus::Active, PolicyStatus::Active));
+        assert!(!can_transition(PolicyStatus::Claimed, PolicyStatus::Active));
+        assert!(!can_transition(
+            PolicyStatus::Expired,
+            PolicyStatus::Cancelled
+        ));
+    }
+
+    #[test]
+    fn test_actor_display() {
+        assert_eq!(
+            Actor::System("claims_processor").to_string(),
+            "system:claims_processor"
+        );
+        assert_eq!(Actor::User(7).to_string(), "user:7");
+        assert_eq!(Actor::Admin(3).to_string(), "admin:3");
+    }
+
+    #[tokio::test]
+    async fn test_transition_records_history() {
+        let test_db = create_test_db().await;
+        let policy_id = create_policy(&test_db.pool).await;
+
+        let change = transition_policy(
+            &test_db.pool,
+            policy_id,
+            PolicyStatus::Claimed,
+            Actor::System("claims_processor"),
+            Some("Claim filed"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(change.from_status, PolicyStatus::Active);
+        assert_eq!(change.to_status, PolicyStatus::Claimed);
+
+        let policy = policy_queries::get_policy_by_id(&test_db.pool, policy_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.status, Some(PolicyStatus::Claimed));
+
+        let history = policy_queries::get_policy_status_history(&test_db.pool, policy_id)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].actor, "system:claims_processor");
+        assert_eq!(history[0].reason.as_deref(), Some("Claim filed"));
+    }
+
+    #[tokio::test]
+    async fn test_illegal_transition_changes_nothing() {
+        let test_db = create_test_db().await;
+        let policy_id = create_policy(&test_db.pool).await;
+
+        transition_policy(
+            &test_db.pool,
+            policy_id,
+            PolicyStatus::Claimed,
+            Actor::System("test"),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let result = transition_policy(
+            &test_db.pool,
+            policy_id,
+            PolicyStatus::Active,
+            Actor::System("test"),
+            Some("Reopen"),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(LifecycleError::IllegalTransition {
+                from: PolicyStatus::Claimed,
+                to: PolicyStatus::Active,
+                ..
+            })
+        ));
+
+        let policy = policy_queries::get_policy_by_id(&test_db.pool, policy_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.status, Some(PolicyStatus::Claimed));
+        let history = policy_queries::get_policy_status_history(&test_db.pool, policy_id)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 1);
+
+        let missing = transition_policy(
+            &test_db.pool,
+            99999,
+            PolicyStatus::Cancelled,
+            Actor::System("test"),
+            None,
+        )
+        .await;
+        assert!(matches!(
+            missing,
+            Err(LifecycleError::PolicyNotFound(99999))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_forced_transition_leaves_final_status() {
+        let test_db = create_test_db().await;
+        let policy_id = create_policy(&test_db.pool).await;
+        transition_policy(
+            &test_db.pool,
+            policy_id,
+            PolicyStatus::Claimed,
+            Actor::System("test"),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut conn = test_db.pool.acquire().await.unwrap();
+        let change = force_transition_policy_in(
+            &mut conn,
+            policy_id,
+            PolicyStatus::Active,
+            Actor::Admin(1),
+            "Claim filed in error",
+        )
+        .await
+        .unwrap();
+        assert_eq!(change.from_status, PolicyStatus::Claimed);
+        assert_eq!(change.actor, "admin:1");
+
+        // Forcing a policy into the status it already has is still refused
+        let same = force_transition_policy_in(
+            &mut conn,
+            policy_id,
+            PolicyStatus::Active,
+            Actor::Admin(1),
+            "Again",
+        )
+        .await;
+        assert!(matches!(
+            same,
+            Err(LifecycleError::IllegalTransition { .. })
+        ));
+    }
+}
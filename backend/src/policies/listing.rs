@@ -0,0 +1,382 @@
+use crate::db::models::{
+    InsurancePolicy, PolicyFilter, PolicyListQuery, PolicyPage, PolicySort, PolicySortKey,
+    SortOrder,
+};
+use crate::db::policy_queries;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{Pool, Postgres};
+use time::OffsetDateTime;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+// H3 cell indexes are 15 hexadecimal digits
+const MAX_H3_PREFIX_LENGTH: usize = 15;
+
+// Error types for listing policies
+#[derive(Debug, thiserror::Error)]
+pub enum ListingError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("limit must be between 1 and {MAX_PAGE_SIZE}")]
+    InvalidLimit,
+    #[error("from must not be after to")]
+    InvalidDateRange,
+    #[error("h3_prefix must be up to {MAX_H3_PREFIX_LENGTH} hexadecimal digits")]
+    InvalidH3Prefix,
+    #[error("Invalid cursor")]
+    InvalidCursor,
+    #[error("The cursor belongs to a listing with a different sort or order")]
+    CursorMismatch,
+}
+
+// Where the previous page ended. Encoded as URL-safe base64 JSON so clients
+// treat it as opaque.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Cursor {
+    sort: PolicySort,
+    order: SortOrder,
+    key: PolicySortKey,
+    id: i32,
+}
+
+impl Cursor {
+    fn after(policy: &InsurancePolicy, sort: PolicySort, order: SortOrder) -> Self {
+        Self {
+            sort,
+            order,
+            key: sort_key(policy, sort),
+            id: policy.id,
+        }
+    }
+
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("cursors always serialize"))
+    }
+
+    fn decode(cursor: &str) -> Result<Self, ListingError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|_| ListingError::InvalidCursor)?;
+        serde_json::from_slice(&bytes).map_err(|_| ListingError::InvalidCursor)
+    }
+}
+
+fn sort_key(policy: &InsurancePolicy, sort: PolicySort) -> PolicySortKey {
+    match sort {
+        PolicySort::CreatedAt => {
+            PolicySortKey::Timestamp(policy.created_at.unwrap_or(PrimitiveDateTime::new(
+                OffsetDateTime::UNIX_EPOCH.date(),
+                OffsetDateTime::UNIX_EPOCH.time(),
+            )))
+        }
+        PolicySort::EndDate => PolicySortKey::Timestamp(policy.end_date),
+        PolicySort::CoverageAmount => PolicySortKey::Amount(policy.coverage_amount),
+    }
+}
+
+fn policy_filter(
+    user_id: Option<i32>,
+    query: &PolicyListQuery,
+) -> Result<PolicyFilter, ListingError> {
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(ListingError::InvalidDateRange);
+    }
+    if let Some(prefix) = &query.h3_prefix
+        && (prefix.is_empty()
+            || prefix.len() > MAX_H3_PREFIX_LENGTH
+            || !prefix.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        return Err(ListingError::InvalidH3Prefix);
+    }
+
+    Ok(PolicyFilter {
+        user_id,
+        status: query.status,
+        policy_type: query.policy_type,
+        from: query.from,
+        to: query.to,
+        h3_prefix: query.h3_prefix.clone(),
+        blockchain_verified: query.blockchain_verified,
+    })
+}
+
+// One page of a user's policies, with the total number matching the filters
+// and a cursor for the next page when there is one
+pub async fn list_user_policies(
+    pool: &Pool<Postgres>,
+    user_id: i32,
+    query: &PolicyListQuery,
+) -> Result<PolicyPage, ListingError> {
+    list_policies_page(pool, Some(user_id), query).await
+}
+
+// As list_user_policies, across every user
+pub async fn list_all_policies(
+    pool: &Pool<Postgres>,
+    query: &PolicyListQuery,
+) -> Result<PolicyPage, ListingError> {
+    list_policies_page(pool, None, query).await
+}
+
+async fn list_policies_page(
+    pool: &Pool<Postgres>,
+    user_id: Option<i32>,
+    query: &PolicyListQuery,
+) -> Result<PolicyPage, ListingError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ListingError::InvalidLimit);
+    }
+    let filter = policy_filter(user_id, query)?;
+    let after = match &query.cursor {
+        Some(cursor) => {
+            let cursor = Cursor::decode(cursor)?;
+            if cursor.sort != query.sort || cursor.order != query.order {
+                return Err(ListingError::CursorMismatch);
+            }
+            Some(cursor)
+        }
+        None => None,
+    };
+
+    let total_count = policy_queries::count_policies(pool, &filter).await?;
+    // Fetch one extra policy to tell whether there is a next page
+    let mut policies = policy_queries::list_policies(
+        pool,
+        &filter,
+        query.sort,
+        query.order,
+        after.as_ref().map(|cursor| (&cursor.key, cursor.id)),
+        limit + 1,
+    )
+    .await?;
+
+    let next_cursor = if policies.len() as i64 > limit {
+        policies.truncate(limit as usize);
+        policies
+            .last()
+            .map(|policy| Cursor::after(policy, query.sort, query.order).encode())
+    } else {
+        None
+    };
+
+    Ok(PolicyPage {
+        policies,
+        total_count,
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{CreateInsurancePolicy, PolicyStatus, PolicyType};
+    use crate::test_utils::{create_test_db, create_test_user_for_policies, test_policy_data};
+    use rust_decimal::Decimal;
+    use time::{Date, Month, Time};
+
+    fn date(month: Month, day: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2024, month, day).unwrap(),
+            Time::MIDNIGHT,
+        )
+    }
+
+    async fn create_policy(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        policy_type: PolicyType,
+        coverage: i64,
+        h3_index: &str,
+        end: PrimitiveDateTime,
+    ) -> InsurancePolicy {
+        policy_queries::create_insurance_policy(
+            pool,
+            &CreateInsurancePolicy {
+                policy_name: format!("{} cover", policy_type),
+                policy_type,
+                location_h3_index: Some(h3_index.to_string()),
+                coverage_amount: Decimal::from(coverage),
+                premium_amount: Decimal::ONE,
+                end_date: end,
+                ..test_policy_data(user_id)
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    fn listing_query(sort: PolicySort, order: SortOrder, limit: i64) -> PolicyListQuery {
+        PolicyListQuery {
+            sort,
+            order,
+            limit: Some(limit),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor {
+            sort: PolicySort::CoverageAmount,
+            order: SortOrder::Asc,
+            key: PolicySortKey::Amount(Decimal::new(1050, 2)),
+            id: 42,
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(matches!(
+            Cursor::decode("not a cursor"),
+            Err(ListingError::InvalidCursor)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pages_through_policies_in_order() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+        for coverage in [30, 10, 50, 20, 40] {
+            create_policy(
+                &test_db.pool,
+                user_id,
+                PolicyType::Rain,
+                coverage,
+                "872a1072bffffff",
+                date(Month::July, 1),
+            )
+            .await;
+        }
+
+        let mut query = listing_query(PolicySort::CoverageAmount, SortOrder::Desc, 2);
+        let mut coverages = Vec::new();
+        loop {
+            let page = list_user_policies(&test_db.pool, user_id, &query)
+                .await
+                .unwrap();
+            assert_eq!(page.total_count, 5);
+            coverages.extend(page.policies.iter().map(|p| p.coverage_amount));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(coverages, [50, 40, 30, 20, 10].map(Decimal::from).to_vec());
+
+        // A cursor only continues the listing it came from
+        let page = list_user_policies(
+            &test_db.pool,
+            user_id,
+            &listing_query(PolicySort::CoverageAmount, SortOrder::Desc, 2),
+        )
+        .await
+        .unwrap();
+        let mut other = listing_query(PolicySort::EndDate, SortOrder::Desc, 2);
+        other.cursor = page.next_cursor;
+        assert!(matches!(
+            list_user_policies(&test_db.pool, user_id, &other).await,
+            Err(ListingError::CursorMismatch)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_filters_policies() {
+        let test_db = create_test_db().await;
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+        let rain = create_policy(
+            &test_db.pool,
+            user_id,
+            PolicyType::Rain,
+            10,
+            "872a1072bffffff",
+            date(Month::July, 1),
+        )
+        .await;
+        create_policy(
+            &test_db.pool,
+            user_id,
+            PolicyType::Wind,
+            20,
+            "8828308281fffff",
+            date(Month::June, 10),
+        )
+        .await;
+        policy_queries::set_policy_status(&test_db.pool, rain.id, PolicyStatus::Claimed)
+            .await
+            .unwrap();
+
+        let count = |query: PolicyListQuery| {
+            let pool = test_db.pool.clone();
+            async move {
+                list_user_policies(&pool, user_id, &query)
+                    .await
+                    .unwrap()
+                    .total_count
+            }
+        };
+
+        assert_eq!(count(PolicyListQuery::default()).await, 2);
+        assert_eq!(
+            count(PolicyListQuery {
+                status: Some(PolicyStatus::Claimed),
+                ..Default::default()
+            })
+            .await,
+            1
+        );
+        assert_eq!(
+            count(PolicyListQuery {
+                policy_type: Some(PolicyType::Wind),
+                ..Default::default()
+            })
+            .await,
+            1
+        );
+        assert_eq!(
+            count(PolicyListQuery {
+                h3_prefix: Some("872A".to_string()),
+                ..Default::default()
+            })
+            .await,
+            1
+        );
+        // Only the rain policy is still covered after June 10
+        assert_eq!(
+            count(PolicyListQuery {
+                from: Some(date(Month::June, 15)),
+                ..Default::default()
+            })
+            .await,
+            1
+        );
+        assert_eq!(
+            count(PolicyListQuery {
+                blockchain_verified: Some(true),
+                ..Default::default()
+            })
+            .await,
+            0
+        );
+
+        let invalid = PolicyListQuery {
+            h3_prefix: Some("87%".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            list_user_policies(&test_db.pool, user_id, &invalid).await,
+            Err(ListingError::InvalidH3Prefix)
+        ));
+        let invalid = PolicyListQuery {
+            limit: Some(0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            list_user_policies(&test_db.pool, user_id, &invalid).await,
+            Err(ListingError::InvalidLimit)
+        ));
+    }
+}
@@ -0,0 +1,11 @@
+pub mod cancellation;
+pub mod conditions;
+pub mod lifecycle;
+pub mod listing;
+pub mod templates;
+
+pub use cancellation::*;
+pub use conditions::*;
+pub use lifecycle::*;
+pub use listing::*;
+pub use templates::*;
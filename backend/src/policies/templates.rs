@@ -0,0 +1,201 @@
+use crate::claims::validate_condition;
+use crate::db::models::{
+    CreatePolicyCondition, CreatePolicyTemplate, PolicyTemplate, TemplateConditions,
+    UpdatePolicyTemplate,
+};
+use rust_decimal::Decimal;
+
+// Error types for templates created or edited by admins
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum TemplateError {
+    #[error("Template name cannot be empty")]
+    EmptyName,
+    #[error("Coverage amounts must be positive, with the minimum no more than the maximum")]
+    InvalidCoverageRange,
+    #[error("Base premium rate must be positive")]
+    InvalidPremiumRate,
+    #[error("Template has no conditions")]
+    MissingConditions,
+    #[error("Template has invalid conditions: {0}")]
+    InvalidConditions(String),
+}
+
+// Check a template before it is saved, so that policies can always be created
+// from it
+pub fn validate_template(template: &CreatePolicyTemplate) -> Result<(), TemplateError> {
+    if template.template_name.trim().is_empty() {
+        return Err(TemplateError::EmptyName);
+    }
+    if template.min_coverage_amount <= Decimal::ZERO
+        || template.min_coverage_amount > template.max_coverage_amount
+    {
+        return Err(TemplateError::InvalidCoverageRange);
+    }
+    if template.base_premium_rate <= Decimal::ZERO {
+        return Err(TemplateError::InvalidPremiumRate);
+    }
+
+    let default_conditions = template
+        .default_conditions
+        .clone()
+        .ok_or(TemplateError::MissingConditions)?;
+    let parsed: TemplateConditions = serde_json::from_value(default_conditions)
+        .map_err(|e| TemplateError::InvalidConditions(e.to_string()))?;
+    if parsed.conditions.is_empty() {
+        return Err(TemplateError::MissingConditions);
+    }
+    for condition in parsed.conditions {
+        if !condition.has_valid_threshold_range() {
+            return Err(TemplateError::InvalidConditions(format!(
+                "Threshold range of condition {} must have both ends and include its threshold",
+                condition.condition_type
+            )));
+        }
+        let condition: CreatePolicyCondition = condition.into();
+        validate_condition(&condition)
+            .map_err(|e| TemplateError::InvalidConditions(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+// The template as it will be once `update` is applied
+pub fn apply_template_update(
+    template: &PolicyTemplate,
+    update: UpdatePolicyTemplate,
+) -> CreatePolicyTemplate {
+    CreatePolicyTemplate {
+        template_name: update
+            .template_name
+            .unwrap_or_else(|| template.template_name.clone()),
+        description: update.description.or_else(|| template.description.clone()),
+        policy_type: update.policy_type.unwrap_or(template.policy_type),
+        default_conditions: update
+            .default_conditions
+            .or_else(|| template.default_conditions.clone()),
+        min_coverage_amount: update
+            .min_coverage_amount
+            .unwrap_or(template.min_coverage_amount),
+        max_coverage_amount: update
+            .max_coverage_amount
+            .unwrap_or(template.max_coverage_amount),
+        base_premium_rate: update
+            .base_premium_rate
+            .unwrap_or(template.base_premium_rate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::PolicyType;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn rain_template() -> CreatePolicyTemplate {
+        CreatePolicyTemplate {
+            template_name: "Rain Event Insurance".to_string(),
+            description: None,
+            policy_type: PolicyType::Rain,
+            default_conditions: Some(serde_json::json!({"conditions": [{
+                "type": "rainfall",
+                "operator": ">",
+                "threshold": 50,
+                "unit": "mm",
+                "period": "daily",
+                "consecutive_days": 1
+            }]})),
+            min_coverage_amount: dec("1.00"),
+            max_coverage_amount: dec("10.00"),
+            base_premium_rate: dec("0.05"),
+        }
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert_eq!(validate_template(&rain_template()), Ok(()));
+
+        let mut template = rain_template();
+        template.min_coverage_amount = dec("20.00");
+        assert_eq!(
+            validate_template(&template),
+            Err(TemplateError::InvalidCoverageRange)
+        );
+
+        let mut template = rain_template();
+        template.base_premium_rate = Decimal::ZERO;
+        assert_eq!(
+            validate_template(&template),
+            Err(TemplateError::InvalidPremiumRate)
+        );
+
+        let mut template = rain_template();
+        template.default_conditions = Some(serde_json::json!({"conditions": []}));
+        assert_eq!(
+            validate_template(&template),
+            Err(TemplateError::MissingConditions)
+        );
+
+        // Wind speed cannot be measured in millimetres
+        let mut template = rain_template();
+        template.default_conditions = Some(serde_json::json!({"conditions": [{
+            "type": "wind_speed",
+            "operator": ">",
+            "threshold": 50,
+            "unit": "mm",
+            "period": "daily"
+        }]}));
+        assert!(matches!(
+            validate_template(&template),
+            Err(TemplateError::InvalidConditions(_))
+        ));
+
+        // Overrides may only move the threshold within a complete range
+        let mut template = rain_template();
+        template.default_conditions = Some(serde_json::json!({"conditions": [{
+            "type": "rainfall",
+            "operator": ">",
+            "threshold": 50,
+            "unit": "mm",
+            "period": "daily",
+            "min_threshold": 20
+        }]}));
+        assert!(matches!(
+            validate_template(&template),
+            Err(TemplateError::InvalidConditions(_))
+        ));
+    }
+
+    #[test]
+    fn test_apply_template_update() {
+        let created = rain_template();
+        let template = PolicyTemplate {
+            id: 1,
+            template_name: created.template_name,
+            description: Some("Pays out on heavy rain".to_string()),
+            policy_type: created.policy_type,
+            default_conditions: created.default_conditions,
+            min_coverage_amount: created.min_coverage_amount,
+            max_coverage_amount: created.max_coverage_amount,
+            base_premium_rate: created.base_premium_rate,
+            is_active: Some(true),
+            created_at: None,
+            updated_at: None,
+        };
+
+        let updated = apply_template_update(
+            &template,
+            UpdatePolicyTemplate {
+                max_coverage_amount: Some(dec("25.00")),
+                ..Default::default()
+            },
+        );
+        assert_eq!(updated.max_coverage_amount, dec("25.00"));
+        assert_eq!(updated.min_coverage_amount, dec("1.00"));
+        assert_eq!(updated.description, template.description);
+        assert_eq!(updated.default_conditions, template.default_conditions);
+    }
+}
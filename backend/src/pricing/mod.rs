@@ -0,0 +1,5 @@
+pub mod quote_engine;
+pub mod quote_signing;
+
+pub use quote_engine::*;
+pub use quote_signing::*;
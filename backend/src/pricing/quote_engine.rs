@@ -0,0 +1,658 @@
+use crate::claims::{EvaluationError, evaluate_conditions};
+use crate::db::models::{
+    CreateQuoteRequest, PolicyCondition, PolicyConditionOverride, PolicyQuote, PolicyTemplate,
+    WeatherData,
+};
+use crate::db::policy_queries;
+use crate::policies::policy_conditions;
+use crate::pricing::quote_signing::{QuoteClaims, QuoteError, sign_quote};
+use crate::weather::STATION_CELL_RESOLUTION;
+use h3o::CellIndex;
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{Pool, Postgres};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use time::{Duration, Time};
+use tracing::{debug, info};
+
+// The contract rejects purchases that pay less than a tenth of the payout
+pub const MIN_PREMIUM_RATE: Decimal = Decimal::from_parts(1, 0, 0, false, 1);
+
+// How far in the past a quoted policy may start. Clients compute the start
+// date when the quote is requested, so it is slightly stale by the time the
+// request arrives.
+const MAX_BACKDATE_SECONDS: i64 = 3600;
+
+// Error types for quoting
+#[derive(Debug, thiserror::Error)]
+pub enum PricingError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Policy template {0} not found")]
+    TemplateNotFound(i32),
+    #[error("Policy template {0} is not active")]
+    TemplateInactive(i32),
+    #[error("Invalid quote request: {0}")]
+    InvalidRequest(String),
+    #[error("Invalid template conditions: {0}")]
+    InvalidConditions(String),
+    #[error("Condition evaluation error: {0}")]
+    Evaluation(#[from] EvaluationError),
+    #[error("Trigger estimation failed: {0}")]
+    Estimation(#[from] tokio::task::JoinError),
+    #[error("Quote signing error: {0}")]
+    Signing(#[from] QuoteError),
+}
+
+// Configuration for premium quoting
+#[derive(Debug, Clone)]
+pub struct PricingConfig {
+    pub quote_ttl_seconds: i64,
+    // Quotes are still accepted this long after expiry, so a purchase sent
+    // just before expiry can be recorded once it is mined
+    pub acceptance_grace_seconds: u64,
+    pub lookback_days: i64,
+    // H3 rings around the requested cell searched for weather stations
+    pub search_rings: u32,
+    pub min_historical_samples: usize,
+    pub risk_loading: Decimal,
+    // Trigger probability assumed when there is too little weather history
+    pub fallback_trigger_probability: Decimal,
+    pub signing_secret: Option<String>,
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            quote_ttl_seconds: 900,
+            acceptance_grace_seconds: 600,
+            lookback_days: 365,
+            search_rings: 1,
+            min_historical_samples: 30,
+            risk_loading: Decimal::new(2, 1),
+            fallback_trigger_probability: Decimal::new(1, 1),
+            signing_secret: None,
+        }
+    }
+}
+
+impl PricingConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            quote_ttl_seconds: std::env::var("QUOTE_TTL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.quote_ttl_seconds),
+            acceptance_grace_seconds: std::env::var("QUOTE_ACCEPTANCE_GRACE_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.acceptance_grace_seconds),
+            lookback_days: std::env::var("QUOTE_LOOKBACK_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.lookback_days),
+            search_rings: std::env::var("QUOTE_SEARCH_RINGS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.search_rings),
+            min_historical_samples: std::env::var("QUOTE_MIN_HISTORICAL_SAMPLES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.min_historical_samples),
+            risk_loading: std::env::var("QUOTE_RISK_LOADING")
+                .ok()
+                .and_then(|v| Decimal::from_str(&v).ok())
+                .unwrap_or(defaults.risk_loading),
+            fallback_trigger_probability: std::env::var("QUOTE_FALLBACK_TRIGGER_PROBABILITY")
+                .ok()
+                .and_then(|v| Decimal::from_str(&v).ok())
+                .unwrap_or(defaults.fallback_trigger_probability),
+            signing_secret: std::env::var("QUOTE_SIGNING_SECRET").ok(),
+        }
+    }
+}
+
+// How often a template's conditions were met over historical windows
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriggerEstimate {
+    pub probability: Option<Decimal>,
+    pub samples: usize,
+    pub triggered: usize,
+}
+
+// The conditions a quoted policy would be created with, as unsaved policy
+// conditions. Faults in the overrides are the client's; faults in the
+// template are ours.
+pub fn template_conditions(
+    template: &PolicyTemplate,
+    overrides: &[PolicyConditionOverride],
+) -> Result<Vec<PolicyCondition>, PricingError> {
+    let conditions = policy_conditions(template, overrides).map_err(|e| {
+        if e.is_override_error() {
+            PricingError::InvalidRequest(e.to_string())
+        } else {
+            PricingError::InvalidConditions(e.to_string())
+        }
+    })?;
+
+    Ok(conditions
+        .into_iter()
+        .map(|c| PolicyCondition {
+            id: 0,
+            policy_id: 0,
+            condition_type: c.condition_type,
+            operator: c.operator,
+            threshold_value: c.threshold_value,
+            measurement_unit: c.measurement_unit,
+            measurement_period: c.measurement_period,
+            consecutive_days: c.consecutive_days,
+            created_at: None,
+        })
+        .collect())
+}
+
+// Replay the conditions over every complete window of the given duration in
+// each station's history, one window per day. Windows where fewer than 80% of
+// days have readings are skipped, since missing days never meet a condition
+// and would understate the risk.
+pub fn estimate_trigger_probability(
+    conditions: &[PolicyCondition],
+    duration: Duration,
+    readings_by_station: Vec<Vec<WeatherData>>,
+    min_samples: usize,
+) -> Result<TriggerEstimate, EvaluationError> {
+    let mut samples = 0;
+    let mut triggered = 0;
+
+    for mut readings in readings_by_station {
+        readings.sort_by_key(|r| r.recorded_at);
+        let (Some(first), Some(last)) = (readings.first(), readings.last()) else {
+            continue;
+        };
+        let last_recorded_at = last.recorded_at;
+
+        let mut start = PrimitiveDateTime::new(first.recorded_at.date(), Time::MIDNIGHT);
+        while start + duration <= last_recorded_at {
+            let end = start + duration;
+            let from = readings.partition_point(|r| r.recorded_at < start);
+            let to = readings.partition_point(|r| r.recorded_at < end);
+            let window = &readings[from..to];
+
+            if has_coverage(window, duration) {
+                samples += 1;
+                let evaluations = evaluate_conditions(conditions, start, end, window, end)?;
+                if !evaluations.is_empty() && evaluations.iter().all(|c| c.met) {
+                    triggered += 1;
+                }
+            }
+            start += Duration::days(1);
+        }
+    }
+
+    let probability = (samples > 0 && samples >= min_samples)
+        .then(|| (Decimal::from(triggered) / Decimal::from(samples)).round_dp(4));
+
+    Ok(TriggerEstimate {
+        probability,
+        samples,
+        triggered,
+    })
+}
+
+fn has_coverage(readings: &[WeatherData], duration: Duration) -> bool {
+    let days = duration.whole_days().max(1) as usize;
+    let days_with_readings = readings
+        .iter()
+        .map(|r| r.recorded_at.date())
+        .collect::<BTreeSet<_>>()
+        .len();
+    days_with_readings * 5 >= days * 4
+}
+
+// Premium for a coverage amount: the template's base rate plus the expected
+// loss with a risk loading. Never less than the contract's minimum premium or
+// more than the coverage itself, rounded up to the two decimals policies
+// store.
+pub fn calculate_premium(
+    base_premium_rate: Decimal,
+    coverage_amount: Decimal,
+    trigger_probability: Decimal,
+    risk_loading: Decimal,
+) -> Decimal {
+    let rate = (base_premium_rate + trigger_probability * (Decimal::ONE + risk_loading))
+        .clamp(MIN_PREMIUM_RATE, Decimal::ONE);
+    (coverage_amount * rate).round_dp_with_strategy(2, RoundingStrategy::AwayFromZero)
+}
+
+pub struct QuoteEngine {
+    pool: Pool<Postgres>,
+    config: PricingConfig,
+}
+
+impl QuoteEngine {
+    pub fn new(pool: Pool<Postgres>, config: PricingConfig) -> Self {
+        Self { pool, config }
+    }
+
+    pub async fn quote(
+        &self,
+        user_id: i32,
+        request: &CreateQuoteRequest,
+        now: PrimitiveDateTime,
+    ) -> Result<PolicyQuote, PricingError> {
+        let template =
+            policy_queries::get_policy_template_by_id(&self.pool, request.policy_template_id)
+                .await?
+                .ok_or(PricingError::TemplateNotFound(request.policy_template_id))?;
+        if template.is_active == Some(false) {
+            return Err(PricingError::TemplateInactive(template.id));
+        }
+        validate_quote_request(&template, request, now)?;
+        let cell = CellIndex::from_str(&request.location_h3_index).map_err(|_| {
+            PricingError::InvalidRequest(format!("Invalid H3 index {}", request.location_h3_index))
+        })?;
+        let conditions = template_conditions(&template, &request.condition_overrides)?;
+
+        let station_ids = self.nearby_station_ids(cell, request).await?;
+        let weather_station_id = request
+            .weather_station_id
+            .clone()
+            .unwrap_or_else(|| station_ids[0].clone());
+        let history_start = now - Duration::days(self.config.lookback_days);
+        let mut readings_by_station = Vec::with_capacity(station_ids.len());
+        for station_id in &station_ids {
+            readings_by_station.push(
+                policy_queries::get_weather_data_by_station_and_date_range(
+                    &self.pool,
+                    station_id,
+                    &history_start,
+                    &now,
+                )
+                .await?,
+            );
+        }
+
+        // Replaying a year of history is CPU bound, so it is kept off the
+        // async workers
+        let duration = request.end_date - request.start_date;
+        let min_samples = self.config.min_historical_samples;
+        let estimate = tokio::task::spawn_blocking(move || {
+            estimate_trigger_probability(&conditions, duration, readings_by_station, min_samples)
+        })
+        .await??;
+        debug!(
+            "Template {} triggered in {} of {} historical windows near {}",
+            template.id, estimate.triggered, estimate.samples, cell
+        );
+
+        let premium_amount = calculate_premium(
+            template.base_premium_rate,
+            request.coverage_amount,
+            estimate
+                .probability
+                .unwrap_or(self.config.fallback_trigger_probability),
+            self.config.risk_loading,
+        );
+        let expires_at = now + Duration::seconds(self.config.quote_ttl_seconds);
+        let location_h3_index = cell.to_string();
+
+        let secret = self
+            .config
+            .signing_secret
+            .as_deref()
+            .ok_or(QuoteError::NotConfigured)?;
+        let quote_token = sign_quote(
+            &QuoteClaims {
+                user_id,
+                policy_template_id: template.id,
+                policy_type: template.policy_type,
+                location_h3_index: location_h3_index.clone(),
+                weather_station_id: Some(weather_station_id.clone()),
+                coverage_amount: request.coverage_amount,
+                premium_amount,
+                start_date: request.start_date.assume_utc().unix_timestamp(),
+                end_date: request.end_date.assume_utc().unix_timestamp(),
+                condition_overrides: request.condition_overrides.clone(),
+                iat: now.assume_utc().unix_timestamp(),
+                exp: expires_at.assume_utc().unix_timestamp(),
+            },
+            secret,
+        )?;
+
+        info!(
+            "Quoted premium {} for {} coverage on template {} for user {}",
+            premium_amount, request.coverage_amount, template.id, user_id
+        );
+
+        Ok(PolicyQuote {
+            quote_token,
+            policy_template_id: template.id,
+            policy_type: template.policy_type,
+            location_h3_index,
+            coverage_amount: request.coverage_amount,
+            premium_amount,
+            currency: "ETH".to_string(),
+            start_date: request.start_date,
+            end_date: request.end_date,
+            trigger_probability: estimate.probability,
+            historical_samples: estimate.samples,
+            weather_station_id,
+            weather_station_ids: station_ids,
+            expires_at,
+        })
+    }
+
+    // Stations in or around the requested cell, nearest first. A station the
+    // client asks for must be one of them, so a quote can't be priced from the
+    // history of a station somewhere else. Policies are evaluated against a
+    // station, so there has to be at least one.
+    async fn nearby_station_ids(
+        &self,
+        cell: CellIndex,
+        request: &CreateQuoteRequest,
+    ) -> Result<Vec<String>, PricingError> {
+        let station_cell = cell.parent(STATION_CELL_RESOLUTION).ok_or_else(|| {
+            PricingError::InvalidRequest(format!(
+                "H3 index {} is coarser than resolution {}",
+                cell,
+                u8::from(STATION_CELL_RESOLUTION)
+            ))
+        })?;
+        let cells: Vec<String> = station_cell
+            .grid_disk::<Vec<_>>(self.config.search_rings)
+            .into_iter()
+            .map(|c| c.to_string())
+            .collect();
+        let mut stations =
+            policy_queries::get_weather_stations_by_h3_indexes(&self.pool, &cells).await?;
+        stations.sort_by_cached_key(|station| {
+            let distance = CellIndex::from_str(&station.h3_index)
+                .ok()
+                .and_then(|c| station_cell.grid_distance(c).ok())
+                .unwrap_or(i32::MAX);
+            (distance, station.id.clone())
+        });
+        let station_ids: Vec<String> = stations.into_iter().map(|s| s.id).collect();
+
+        if station_ids.is_empty() {
+            return Err(PricingError::InvalidRequest(format!(
+                "No weather station near {}",
+                cell
+            )));
+        }
+        if let Some(station_id) = &request.weather_station_id
+            && !station_ids.contains(station_id)
+        {
+            return Err(PricingError::InvalidRequest(format!(
+                "Weather station {} is not near {}",
+                station_id, cell
+            )));
+        }
+        Ok(station_ids)
+    }
+}
+
+fn validate_quote_request(
+    template: &PolicyTemplate,
+    request: &CreateQuoteRequest,
+    now: PrimitiveDateTime,
+) -> Result<(), PricingError> {
+    if request.coverage_amount < template.min_coverage_amount
+        || request.coverage_amount > template.max_coverage_amount
+    {
+        return Err(PricingError::InvalidRequest(format!(
+            "Coverage amount must be between {} and {}",
+            template.min_coverage_amount, template.max_coverage_amount
+        )));
+    }
+    if request.end_date <= request.start_date {
+        return Err(PricingError::InvalidRequest(
+            "End date must be after start date".to_string(),
+        ));
+    }
+    if request.start_date < now - Duration::seconds(MAX_BACKDATE_SECONDS) {
+        return Err(PricingError::InvalidRequest(
+            "Start date is in the past".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{
+        ComparisonOperator, ConditionType, MeasurementPeriod, MeasurementUnit, PolicyType,
+    };
+    use time::{Date, Month};
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn datetime(month: Month, day: u8, hour: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2026, month, day).unwrap(),
+            Time::from_hms(hour, 0, 0).unwrap(),
+        )
+    }
+
+    fn rain_condition() -> PolicyCondition {
+        PolicyCondition {
+            id: 0,
+            policy_id: 0,
+            condition_type: ConditionType::Rainfall,
+            operator: ComparisonOperator::GreaterThan,
+            threshold_value: dec("10"),
+            measurement_unit: MeasurementUnit::Millimeters,
+            measurement_period: MeasurementPeriod::Daily,
+            consecutive_days: Some(1),
+            created_at: None,
+        }
+    }
+
+    fn reading(at: PrimitiveDateTime, mm: &str) -> WeatherData {
+        WeatherData {
+            id: 0,
+            station_id: "wxm_station_1".to_string(),
+            recorded_at: at,
+            temperature: None,
+            humidity: None,
+            precipitation: Some(dec(mm)),
+            wind_speed: None,
+            wind_direction: None,
+            atmospheric_pressure: None,
+            data_source: None,
+            raw_data: None,
+            quality_score: None,
+            created_at: None,
+        }
+    }
+
+    // One reading at noon for each of the first `days` days of March, with
+    // heavy rain on the given days
+    fn march_history(days: u8, rainy_days: &[u8]) -> Vec<WeatherData> {
+        (1..=days)
+            .map(|day| {
+                let mm = if rainy_days.contains(&day) { "25" } else { "1" };
+                reading(datetime(Month::March, day, 12), mm)
+            })
+            .collect()
+    }
+
+    fn template(default_conditions: serde_json::Value) -> PolicyTemplate {
+        PolicyTemplate {
+            id: 2,
+            template_name: "Rain Event Insurance".to_string(),
+            description: None,
+            policy_type: PolicyType::Rain,
+            default_conditions: Some(default_conditions),
+            min_coverage_amount: dec("0.50"),
+            max_coverage_amount: dec("5.00"),
+            base_premium_rate: dec("0.03"),
+            is_active: Some(true),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_template_conditions_are_parsed() {
+        let rain_template = template(serde_json::json!({
+            "conditions": [{"type": "rainfall", "operator": ">", "threshold": 10,
+                            "unit": "mm", "period": "daily", "consecutive_days": 1,
+                            "min_threshold": 5, "max_threshold": 50}]
+        }));
+        let conditions = template_conditions(&rain_template, &[]).unwrap();
+
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].condition_type, ConditionType::Rainfall);
+        assert_eq!(conditions[0].threshold_value, dec("10"));
+        assert_eq!(conditions[0].measurement_period, MeasurementPeriod::Daily);
+
+        let overrides = [PolicyConditionOverride {
+            condition_type: ConditionType::Rainfall,
+            operator: None,
+            threshold: Some(dec("20")),
+            consecutive_days: None,
+        }];
+        let conditions = template_conditions(&rain_template, &overrides).unwrap();
+        assert_eq!(conditions[0].threshold_value, dec("20"));
+
+        let overrides = [PolicyConditionOverride {
+            condition_type: ConditionType::WindSpeed,
+            operator: None,
+            threshold: Some(dec("20")),
+            consecutive_days: None,
+        }];
+        assert!(matches!(
+            template_conditions(&rain_template, &overrides),
+            Err(PricingError::InvalidRequest(_))
+        ));
+
+        assert!(matches!(
+            template_conditions(&template(serde_json::json!({"conditions": []})), &[]),
+            Err(PricingError::InvalidConditions(_))
+        ));
+        assert!(matches!(
+            template_conditions(
+                &template(serde_json::json!({"conditions": [{"type": "rainfall"}]})),
+                &[]
+            ),
+            Err(PricingError::InvalidConditions(_))
+        ));
+    }
+
+    #[test]
+    fn test_estimate_counts_triggering_windows() {
+        // 20 days of history give 17 complete three-day windows; rain on day
+        // 10 falls inside the three windows starting on days 8, 9 and 10
+        let estimate = estimate_trigger_probability(
+            &[rain_condition()],
+            Duration::days(3),
+            vec![march_history(20, &[10])],
+            10,
+        )
+        .unwrap();
+
+        assert_eq!(estimate.samples, 17);
+        assert_eq!(estimate.triggered, 3);
+        assert_eq!(estimate.probability, Some(dec("0.1765")));
+    }
+
+    #[test]
+    fn test_estimate_pools_stations_and_skips_gaps() {
+        // The second station reports every other day, so none of its windows
+        // have enough coverage
+        let sparse: Vec<WeatherData> = (1..=20)
+            .step_by(2)
+            .map(|day| reading(datetime(Month::March, day, 12), "25"))
+            .collect();
+
+        let estimate = estimate_trigger_probability(
+            &[rain_condition()],
+            Duration::days(3),
+            vec![march_history(20, &[]), sparse],
+            10,
+        )
+        .unwrap();
+
+        assert_eq!(estimate.samples, 17);
+        assert_eq!(estimate.triggered, 0);
+        assert_eq!(estimate.probability, Some(Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_estimate_requires_minimum_samples() {
+        let estimate = estimate_trigger_probability(
+            &[rain_condition()],
+            Duration::days(3),
+            vec![march_history(5, &[2])],
+            10,
+        )
+        .unwrap();
+
+        assert_eq!(estimate.samples, 2);
+        assert_eq!(estimate.probability, None);
+
+        let estimate =
+            estimate_trigger_probability(&[rain_condition()], Duration::days(3), vec![], 0)
+                .unwrap();
+        assert_eq!(estimate.samples, 0);
+        assert_eq!(estimate.probability, None);
+    }
+
+    #[test]
+    fn test_calculate_premium() {
+        // 0.03 + 0.2 * 1.2 = 0.27
+        assert_eq!(
+            calculate_premium(dec("0.03"), dec("5.00"), dec("0.2"), dec("0.2")),
+            dec("1.35")
+        );
+        // Never below the contract minimum of a tenth of the payout
+        assert_eq!(
+            calculate_premium(dec("0.03"), dec("5.00"), dec("0.01"), dec("0.2")),
+            dec("0.50")
+        );
+        // Rounded up to whole cents
+        assert_eq!(
+            calculate_premium(dec("0.05"), dec("1.23"), dec("0.1"), dec("0.2")),
+            dec("0.21")
+        );
+        // Never more than the coverage
+        assert_eq!(
+            calculate_premium(dec("0.05"), dec("2.00"), dec("0.9"), dec("0.2")),
+            dec("2.00")
+        );
+    }
+
+    #[test]
+    fn test_validate_quote_request() {
+        let template = template(serde_json::json!({"conditions": []}));
+        let now = datetime(Month::March, 1, 12);
+        let request = CreateQuoteRequest {
+            policy_template_id: 2,
+            location_h3_index: "872a1072bffffff".to_string(),
+            coverage_amount: dec("5.00"),
+            start_date: now,
+            end_date: datetime(Month::March, 31, 12),
+            weather_station_id: None,
+            condition_overrides: Vec::new(),
+        };
+        assert!(validate_quote_request(&template, &request, now).is_ok());
+
+        let mut invalid = request.clone();
+        invalid.coverage_amount = dec("5.01");
+        assert!(validate_quote_request(&template, &invalid, now).is_err());
+
+        let mut invalid = request.clone();
+        invalid.end_date = invalid.start_date;
+        assert!(validate_quote_request(&template, &invalid, now).is_err());
+
+        let mut invalid = request.clone();
+        invalid.start_date = datetime(Month::March, 1, 10);
+        assert!(validate_quote_request(&template, &invalid, now).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use crate::db::models::{CreateInsurancePolicyRequest, PolicyConditionOverride, PolicyType};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+// Error types for quote tokens
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum QuoteError {
+    #[error("A quote is required to create a policy")]
+    Missing,
+    #[error("Quote signing is not configured")]
+    NotConfigured,
+    #[error("Quote has expired")]
+    Expired,
+    #[error("Invalid quote: {0}")]
+    Invalid(String),
+    // `field` names the request field that differs from the quote
+    #[error("Policy does not match quote: {message}")]
+    Mismatch {
+        field: &'static str,
+        message: String,
+    },
+}
+
+// The terms a quote was priced for. Dates are unix timestamps.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QuoteClaims {
+    pub user_id: i32,
+    pub policy_template_id: i32,
+    pub policy_type: PolicyType,
+    pub location_h3_index: String,
+    #[serde(default)]
+    pub weather_station_id: Option<String>,
+    pub coverage_amount: Decimal,
+    pub premium_amount: Decimal,
+    pub start_date: i64,
+    pub end_date: i64,
+    #[serde(default)]
+    pub condition_overrides: Vec<PolicyConditionOverride>,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+pub fn sign_quote(claims: &QuoteClaims, secret: &str) -> Result<String, QuoteError> {
+    encode(
+        &Header::new(Algorithm::HS256),
+        claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .map_err(|e| QuoteError::Invalid(e.to_string()))
+}
+
+// Check a quote's signature and expiry, allowing `grace_seconds` past expiry
+pub fn verify_quote(
+    token: &str,
+    secret: &str,
+    grace_seconds: u64,
+) -> Result<QuoteClaims, QuoteError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.leeway = grace_seconds;
+
+    decode::<QuoteClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_ref()),
+        &validation,
+    )
+    .map(|data| data.claims)
+    .map_err(|e| match e.kind() {
+        ErrorKind::ExpiredSignature => QuoteError::Expired,
+        _ => QuoteError::Invalid(e.to_string()),
+    })
+}
+
+// Compare the policy a user asks to create with the quote they were given
+pub fn verify_policy_matches_quote(
+    quote: &QuoteClaims,
+    user_id: i32,
+    request: &CreateInsurancePolicyRequest,
+) -> Result<(), QuoteError> {
+    if quote.user_id != user_id {
+        return Err(QuoteError::Mismatch {
+            field: "quote_token",
+            message: "Quote was issued to another user".to_string(),
+        });
+    }
+    if request.policy_template_id != Some(quote.policy_template_id) {
+        return Err(QuoteError::Mismatch {
+            field: "policy_template_id",
+            message: format!(
+                "Policy template {:?} does not match quoted template {}",
+                request.policy_template_id, quote.policy_template_id
+            ),
+        });
+    }
+    if request.policy_type != quote.policy_type {
+        return Err(QuoteError::Mismatch {
+            field: "policy_type",
+            message: format!(
+                "Policy type {} does not match quoted type {}",
+                request.policy_type, quote.policy_type
+            ),
+        });
+    }
+    let h3_matches = request
+        .location_h3_index
+        .as_deref()
+        .is_some_and(|h3| h3.eq_ignore_ascii_case(&quote.location_h3_index));
+    if !h3_matches {
+        return Err(QuoteError::Mismatch {
+            field: "location_h3_index",
+            message: format!(
+                "Location does not match quoted H3 index {}",
+                quote.location_h3_index
+            ),
+        });
+    }
+    if request.weather_station_id != quote.weather_station_id {
+        return Err(QuoteError::Mismatch {
+            field: "weather_station_id",
+            message: format!(
+                "Weather station {:?} does not match quoted station {:?}",
+                request.weather_station_id, quote.weather_station_id
+            ),
+        });
+    }
+    if request.coverage_amount != quote.coverage_amount {
+        return Err(QuoteError::Mismatch {
+            field: "coverage_amount",
+            message: format!(
+                "Coverage amount {} does not match quoted coverage {}",
+                request.coverage_amount, quote.coverage_amount
+            ),
+        });
+    }
+    if request.premium_amount.round_dp(2) != quote.premium_amount.round_dp(2) {
+        return Err(QuoteError::Mismatch {
+            field: "premium_amount",
+            message: format!(
+                "Premium amount {} does not match quoted premium {}",
+                request.premium_amount, quote.premium_amount
+            ),
+        });
+    }
+    let start_date = request.start_date.assume_utc().unix_timestamp();
+    let end_date = request.end_date.assume_utc().unix_timestamp();
+    if start_date != quote.start_date || end_date != quote.end_date {
+        return Err(QuoteError::Mismatch {
+            field: if start_date != quote.start_date {
+                "start_date"
+            } else {
+                "end_date"
+            },
+            message: "Policy period does not match quoted period".to_string(),
+        });
+    }
+    if request.condition_overrides != quote.condition_overrides {
+        return Err(QuoteError::Mismatch {
+            field: "condition_overrides",
+            message: "Policy conditions do not match quoted conditions".to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::ConditionType;
+    use std::str::FromStr;
+    use time::{Date, Month, PrimitiveDateTime, Time};
+
+    const SECRET: &str = "test_quote_secret";
+
+    fn datetime(day: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2026, Month::March, day).unwrap(),
+            Time::MIDNIGHT,
+        )
+    }
+
+    fn now() -> i64 {
+        time::OffsetDateTime::now_utc().unix_timestamp()
+    }
+
+    fn claims() -> QuoteClaims {
+        QuoteClaims {
+            user_id: 7,
+            policy_template_id: 2,
+            policy_type: PolicyType::Rain,
+            location_h3_index: "872a1072bffffff".to_string(),
+            weather_station_id: Some("wxm_station_1".to_string()),
+            coverage_amount: Decimal::from_str("5.00").unwrap(),
+            premium_amount: Decimal::from_str("0.75").unwrap(),
+            start_date: datetime(1).assume_utc().unix_timestamp(),
+            end_date: datetime(31).assume_utc().unix_timestamp(),
+            condition_overrides: Vec::new(),
+            iat: now(),
+            exp: now() + 900,
+        }
+    }
+
+    fn policy_request() -> CreateInsurancePolicyRequest {
+        CreateInsurancePolicyRequest {
+            policy_template_id: Some(2),
+            policy_name: "Rain Event Insurance".to_string(),
+            policy_type: PolicyType::Rain,
+            location_latitude: Decimal::from_str("40.7128").unwrap(),
+            location_longitude: Decimal::from_str("-74.0060").unwrap(),
+            location_h3_index: Some("872A1072BFFFFFF".to_string()),
+            location_name: None,
+            coverage_amount: Decimal::from_str("5.00").unwrap(),
+            premium_amount: Decimal::from_str("0.7500000000000001").unwrap(),
+            currency: Some("ETH".to_string()),
+            start_date: datetime(1),
+            end_date: datetime(31),
+            weather_station_id: Some("wxm_station_1".to_string()),
+            smart_contract_address: None,
+            purchase_transaction_hash: None,
+            quote_token: None,
+            condition_overrides: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_quote_round_trip() {
+        let token = sign_quote(&claims(), SECRET).unwrap();
+        assert_eq!(verify_quote(&token, SECRET, 0).unwrap(), claims());
+    }
+
+    #[test]
+    fn test_quote_with_wrong_secret_is_rejected() {
+        let token = sign_quote(&claims(), SECRET).unwrap();
+        assert!(matches!(
+            verify_quote(&token, "another_secret", 0),
+            Err(QuoteError::Invalid(_))
+        ));
+        assert!(matches!(
+            verify_quote("not.a.quote", SECRET, 0),
+            Err(QuoteError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_expired_quote_is_rejected_after_grace() {
+        let mut expired = claims();
+        expired.exp = now() - 300;
+        let token = sign_quote(&expired, SECRET).unwrap();
+
+        assert_eq!(verify_quote(&token, SECRET, 0), Err(QuoteError::Expired));
+        assert!(verify_quote(&token, SECRET, 600).is_ok());
+    }
+
+    #[test]
+    fn test_policy_matching_quote() {
+        assert!(verify_policy_matches_quote(&claims(), 7, &policy_request()).is_ok());
+    }
+
+    #[test]
+    fn test_policy_not_matching_quote() {
+        // The field named by the mismatch
+        let mismatch = |request: CreateInsurancePolicyRequest, user_id| {
+            let result = verify_policy_matches_quote(&claims(), user_id, &request);
+            match result {
+                Err(QuoteError::Mismatch { field, .. }) => Some(field),
+                _ => None,
+            }
+        };
+
+        assert_eq!(mismatch(policy_request(), 8), Some("quote_token"));
+
+        let mut request = policy_request();
+        request.policy_template_id = Some(3);
+        assert_eq!(mismatch(request, 7), Some("policy_template_id"));
+
+        let mut request = policy_request();
+        request.location_h3_index = Some("872a10729ffffff".to_string());
+        assert_eq!(mismatch(request, 7), Some("location_h3_index"));
+
+        let mut request = policy_request();
+        request.weather_station_id = Some("wxm_station_2".to_string());
+        assert_eq!(mismatch(request, 7), Some("weather_station_id"));
+
+        let mut request = policy_request();
+        request.weather_station_id = None;
+        assert_eq!(mismatch(request, 7), Some("weather_station_id"));
+
+        let mut request = policy_request();
+        request.coverage_amount = Decimal::from_str("4.00").unwrap();
+        assert_eq!(mismatch(request, 7), Some("coverage_amount"));
+
+        let mut request = policy_request();
+        request.premium_amount = Decimal::from_str("0.50").unwrap();
+        assert_eq!(mismatch(request, 7), Some("premium_amount"));
+
+        let mut request = policy_request();
+        request.end_date = datetime(30);
+        assert_eq!(mismatch(request, 7), Some("end_date"));
+
+        let mut request = policy_request();
+        request.condition_overrides = vec![PolicyConditionOverride {
+            condition_type: ConditionType::Rainfall,
+            operator: None,
+            threshold: Some(Decimal::from_str("20").unwrap()),
+            consecutive_days: None,
+        }];
+        assert_eq!(mismatch(request, 7), Some("condition_overrides"));
+    }
+}
@@ -6,9 +6,11 @@ use testcontainers::{ContainerAsync, runners::AsyncRunner};
 use testcontainers_modules::postgres::Postgres;
 use tower_http::cors::CorsLayer;
 
-use crate::db::models::{CreateUser, User};
+use crate::db::models::{CreateInsurancePolicy, CreateUser, PolicyType, User};
 use crate::db::user_queries;
+use crate::mail::{AppMailer, Email, InMemoryMailer};
 use crate::web;
+use crate::web::rate_limit::{RateLimitBackend, RateLimitConfig, RateLimiter};
 
 pub struct TestDatabase {
     pub pool: Pool<SqlxPostgres>,
@@ -50,30 +52,78 @@ pub async fn create_test_db() -> TestDatabase {
 
 /// Create a test Axum app with test PostgreSQL database
 pub async fn create_test_app() -> (Router, TestDatabase) {
+    create_test_app_with_rate_limit(RateLimitConfig::default()).await
+}
+
+/// Create a test Axum app whose sign-in and sign-up routes are limited by an
+/// in-memory rate limiter with the given config. Clients are told apart by
+/// their X-Forwarded-For header.
+pub async fn create_test_app_with_rate_limit(
+    rate_limit_config: RateLimitConfig,
+) -> (Router, TestDatabase) {
+    let (app, test_db, _mailer) = build_test_app(rate_limit_config).await;
+    (app, test_db)
+}
+
+/// Create a test Axum app and the in-memory mailer it sends mail with
+pub async fn create_test_app_with_mailer() -> (Router, TestDatabase, InMemoryMailer) {
+    build_test_app(RateLimitConfig::default()).await
+}
+
+async fn build_test_app(
+    rate_limit_config: RateLimitConfig,
+) -> (Router, TestDatabase, InMemoryMailer) {
     // Set up test environment variables
     unsafe {
         env::set_var("JWT_SECRET", "test_jwt_secret_key_for_integration_tests");
+        env::set_var(
+            "QUOTE_SIGNING_SECRET",
+            "test_quote_secret_for_integration_tests",
+        );
     }
 
     let test_db = create_test_db().await;
     let cors = CorsLayer::permissive();
     let pool_extension = axum::extract::Extension(test_db.pool.clone());
 
+    let rate_limiter = RateLimiter::new(
+        test_db.pool.clone(),
+        RateLimitConfig {
+            backend: RateLimitBackend::InMemory,
+            trust_forwarded_for: true,
+            ..rate_limit_config
+        },
+    );
+    let rate_limit_extension = axum::extract::Extension(rate_limiter);
+    let mailer = InMemoryMailer::default();
+    let mailer_extension = axum::extract::Extension(AppMailer::InMemory(mailer.clone()));
+
     // Create your main router with database extension
     let main_router = Router::new()
         .route(
             "/createUser",
-            axum::routing::post(user_queries::create_user),
+            axum::routing::post(user_queries::create_user).layer(
+                axum::middleware::from_fn_with_state(
+                    "create_user",
+                    web::rate_limit::rate_limit_middleware,
+                ),
+            ),
         )
+        .layer(rate_limit_extension.clone())
+        .layer(mailer_extension.clone())
         .layer(pool_extension.clone());
 
     // Get the web routes router with database extension
-    let web_router = web::routes::app().await.layer(pool_extension.clone());
+    let web_router = web::routes::app()
+        .await
+        .layer(rate_limit_extension)
+        .layer(mailer_extension)
+        .layer(pool_extension.clone());
 
     // Merge the routers
     let app = main_router.merge(web_router).layer(cors);
 
-    (app, test_db)
+    (app, test_db, mailer)
 }
 
 /// Helper function to create a test user in the database
@@ -93,10 +143,10 @@ pub async fn create_test_user(
     let password_hash = bcrypt::hash(password, bcrypt::DEFAULT_COST)
         .map_err(|_| sqlx::Error::Protocol("Failed to hash password".into()))?;
 
-    // Insert user into PostgreSQL database using your existing query
+    // Insert an already verified user into PostgreSQL database
     let user = sqlx::query_as!(
         User,
-        "INSERT INTO users (name, email, password_hash) VALUES ($1, $2, $3) RETURNING id, name, email, password_hash, wallet_address, created_at, updated_at",
+        "INSERT INTO users (name, email, password_hash, email_verified_at) VALUES ($1, $2, $3, CURRENT_TIMESTAMP) RETURNING id, name, email, password_hash, wallet_address, email_verified_at, created_at, updated_at, role as \"role: _\"",
         create_user.name.trim(),
         create_user.email.trim().to_lowercase(),
         password_hash
@@ -107,14 +157,198 @@ pub async fn create_test_user(
     Ok(user)
 }
 
-/// Helper function to generate a valid JWT token for testing
-pub fn create_test_jwt(email: &str) -> String {
-    use crate::web::auth::encode_jwt;
+/// Helper function to create the owner of test policies. It has no usable
+/// password; use `create_test_user` for users that sign in.
+pub async fn create_test_user_for_policies(pool: &Pool<SqlxPostgres>) -> i32 {
+    let user = sqlx::query!(
+        "INSERT INTO users (name, email, password_hash) VALUES ($1, $2, $3) RETURNING id",
+        "Test User",
+        "test@policies.com",
+        "$2b$12$test_hash"
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create test user");
+
+    user.id
+}
+
+/// A rain policy in New York for June 2024 with a coverage of 1.00 and a
+/// premium of 0.10. Override fields with struct update syntax.
+pub fn test_policy_data(user_id: i32) -> CreateInsurancePolicy {
+    use rust_decimal::Decimal;
+    use time::{Date, Month, PrimitiveDateTime, Time};
+
+    let date = |month| {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2024, month, 1).unwrap(),
+            Time::MIDNIGHT,
+        )
+    };
+    CreateInsurancePolicy {
+        user_id,
+        policy_template_id: None,
+        policy_name: "Test Policy".to_string(),
+        policy_type: PolicyType::Rain,
+        location_latitude: Decimal::new(407128, 4),
+        location_longitude: Decimal::new(-740060, 4),
+        location_h3_index: None,
+        location_name: None,
+        coverage_amount: Decimal::new(100, 2),
+        premium_amount: Decimal::new(10, 2),
+        currency: None,
+        start_date: date(Month::June),
+        end_date: date(Month::July),
+        weather_station_id: None,
+        smart_contract_address: None,
+        purchase_transaction_hash: None,
+    }
+}
+
+/// Helper function to place a weather station at the center of the H3 cell
+/// 872a1072bffffff, the cell quotes are requested for in tests
+pub async fn create_test_weather_station(pool: &Pool<SqlxPostgres>, station_id: &str) {
+    use crate::db::models::CreateWeatherStation;
+    use h3o::{CellIndex, LatLng};
+    use rust_decimal::Decimal;
+
+    let cell = CellIndex::try_from(0x872a1072bffffff).unwrap();
+    let center = LatLng::from(cell);
+    crate::db::policy_queries::upsert_weather_station(
+        pool,
+        &CreateWeatherStation {
+            id: station_id.to_string(),
+            latitude: Decimal::from_f64_retain(center.lat()).unwrap().round_dp(8),
+            longitude: Decimal::from_f64_retain(center.lng()).unwrap().round_dp(8),
+            h3_index: cell.to_string(),
+        },
+    )
+    .await
+    .expect("Failed to create test weather station");
+}
+
+/// Helper function to start a session for a test user and return its access token
+pub async fn create_test_jwt(pool: &Pool<SqlxPostgres>, user: &User) -> String {
+    use crate::web::auth::start_session;
 
     unsafe {
         env::set_var("JWT_SECRET", "test_jwt_secret_key_for_integration_tests");
     }
-    encode_jwt(email.to_string()).expect("Failed to create test JWT")
+    start_session(pool, user)
+        .await
+        .expect("Failed to create test JWT")
+        .access_token
+}
+
+/// Start a mock WeatherXM API on a random local port and return its base URL.
+///
+/// Serves `GET /api/v1/stations/wxm_station_1/history?date=YYYY-MM-DD` with two
+/// observations for the requested day, and `GET /api/v1/stations` with that
+/// station in New York. Other stations return 404 and requests without the
+/// expected `X-API-KEY` header return 401.
+pub async fn spawn_mock_weatherxm_server(api_key: &'static str) -> String {
+    use axum::extract::{Path, Query};
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use std::collections::HashMap;
+
+    let handler = move |Path(station_id): Path<String>,
+                        Query(params): Query<HashMap<String, String>>,
+                        headers: HeaderMap| async move {
+        if headers.get("X-API-KEY").and_then(|v| v.to_str().ok()) != Some(api_key) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        if station_id != "wxm_station_1" {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+        let date = params.get("date").cloned().unwrap_or_default();
+        axum::Json(serde_json::json!({
+            "date": date,
+            "health": {"data_quality": {"score": 0.9}},
+            "observations": [
+                {
+                    "timestamp": format!("{}T06:00:00+00:00", date),
+                    "temperature": 12.5,
+                    "humidity": 70.0,
+                    "precipitation_accumulated": 0.4,
+                    "wind_speed": 3.0,
+                    "wind_direction": 180.0,
+                    "pressure": 1010.0
+                },
+                {
+                    "timestamp": format!("{}T18:00:00+00:00", date),
+                    "temperature": 20.0,
+                    "humidity": 55.0,
+                    "precipitation_accumulated": 2.4,
+                    "wind_speed": 6.5,
+                    "wind_direction": 200.0,
+                    "pressure": 1008.5
+                }
+            ]
+        }))
+        .into_response()
+    };
+
+    let stations = move |headers: HeaderMap| async move {
+        if headers.get("X-API-KEY").and_then(|v| v.to_str().ok()) != Some(api_key) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        axum::Json(serde_json::json!([
+            {
+                "id": "wxm_station_1",
+                "name": "Test Station",
+                "cellIndex": "872a1072cffffff",
+                "location": {"lat": 40.7128, "lon": -74.0060, "elevation": 10.0}
+            }
+        ]))
+        .into_response()
+    };
+
+    let app = Router::new()
+        .route(
+            "/api/v1/stations/{station_id}/history",
+            axum::routing::get(handler),
+        )
+        .route("/api/v1/stations", axum::routing::get(stations));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind mock WeatherXM server");
+    let address = listener
+        .local_addr()
+        .expect("Failed to read mock server address");
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.ok();
+    });
+
+    format!("http://{}", address)
+}
+
+/// The token in the link of the last email sent to the address
+pub fn mailed_token(mailer: &InMemoryMailer, to: &str) -> String {
+    let email = mailer
+        .sent()
+        .into_iter()
+        .rev()
+        .find(|email| email.to == to)
+        .unwrap_or_else(|| panic!("No email was sent to {}", to));
+    let (_, rest) = email
+        .body
+        .split_once("?token=")
+        .expect("Expected a link with a token in the email");
+    rest.split_whitespace().next().unwrap().to_string()
+}
+
+/// The emails sent so far, once there are at least `count`. Some mail is sent
+/// after the response, so tests have to wait for it.
+pub async fn wait_for_mail(mailer: &InMemoryMailer, count: usize) -> Vec<Email> {
+    for _ in 0..100 {
+        let sent = mailer.sent();
+        if sent.len() >= count {
+            return sent;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("Expected {} emails, got {}", count, mailer.sent().len());
 }
 
 /// Clean up test environment variables
@@ -141,7 +375,7 @@ mod integration_tests {
             .json(&serde_json::json!({
                 "name": "Test User",
                 "email": "test@example.com",
-                "password": "password123"
+                "password": "sunny-day-cover-42"
             }))
             .await;
 
@@ -149,7 +383,7 @@ mod integration_tests {
 
         let user: User = response.json();
         assert_eq!(user.name, "Test User");
-        assert_eq!(user.email, "test@example.com");
+        assert_eq!(user.email.as_deref(), Some("test@example.com"));
         assert!(user.id > 0);
 
         cleanup_test_env();
@@ -176,7 +410,7 @@ mod integration_tests {
             .json(&serde_json::json!({
                 "name": "Second User",
                 "email": "duplicate@example.com",
-                "password": "password456"
+                "password": "another-strong-pass"
             }))
             .await;
 
@@ -185,6 +419,39 @@ mod integration_tests {
         cleanup_test_env();
     }
 
+    #[tokio::test]
+    async fn test_create_user_rejects_weak_passwords() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+
+        for (password, code) in [
+            ("short1", "too_short"),
+            ("Password123", "common"),
+            ("weak.user.2026", "contains_email"),
+        ] {
+            let response = server
+                .post("/createUser")
+                .json(&serde_json::json!({
+                    "name": "Weak User",
+                    "email": "weak.user@example.com",
+                    "password": password
+                }))
+                .await;
+
+            response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+            let body: serde_json::Value = response.json();
+            assert_eq!(body["fields"][0]["field"], "password");
+            assert_eq!(body["fields"][0]["code"], code);
+        }
+
+        let user = user_queries::retrieve_user_by_email(&test_db.pool, "weak.user@example.com")
+            .await
+            .unwrap();
+        assert!(user.is_none());
+
+        cleanup_test_env();
+    }
+
     #[tokio::test]
     async fn test_create_user_missing_fields() {
         let (app, _test_db) = create_test_app().await;
@@ -264,6 +531,176 @@ mod integration_tests {
         cleanup_test_env();
     }
 
+    #[tokio::test]
+    async fn test_signin_rate_limited_by_ip_and_account() {
+        let (app, _test_db) = create_test_app_with_rate_limit(RateLimitConfig {
+            ip_capacity: 3,
+            ip_refill_per_minute: 1.0,
+            account_capacity: 2,
+            account_refill_per_minute: 1.0,
+            ..RateLimitConfig::default()
+        })
+        .await;
+        let server = TestServer::new(app).unwrap();
+        let sign_in = |ip: &'static str, email: &'static str| {
+            server
+                .post("/signin")
+                .add_header("x-forwarded-for", ip)
+                .json(&serde_json::json!({"email": email, "password": "guess"}))
+        };
+
+        // Unknown accounts still use up tokens
+        sign_in("10.0.0.1", "target@example.com")
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+        sign_in("10.0.0.2", "Target@Example.com")
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+
+        // The account's bucket is empty, whichever IP asks
+        let response = sign_in("10.0.0.3", "target@example.com").await;
+        response.assert_status(http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.header("retry-after"), "60");
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["retry_after_seconds"], 60);
+
+        // Spreading guesses over accounts runs out the IP's bucket
+        for email in ["a@example.com", "b@example.com"] {
+            sign_in("10.0.0.1", email)
+                .await
+                .assert_status(http::StatusCode::UNAUTHORIZED);
+        }
+        let response = sign_in("10.0.0.1", "c@example.com").await;
+        response.assert_status(http::StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.maybe_header("retry-after").is_some());
+
+        // Sign-up has buckets of its own
+        server
+            .post("/createUser")
+            .add_header("x-forwarded-for", "10.0.0.1")
+            .json(&serde_json::json!({
+                "name": "New User",
+                "email": "target@example.com",
+                "password": "storm-season-2026"
+            }))
+            .await
+            .assert_status_ok();
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_signin_rehashes_bcrypt_passwords() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Legacy User",
+            "legacy@example.com",
+            "legacy_password",
+        )
+        .await
+        .expect("Failed to create test user");
+        let stored_hash = |pool: PgPool| async move {
+            sqlx::query_scalar!("SELECT password_hash FROM users WHERE id = $1", user.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+                .unwrap()
+        };
+        assert!(stored_hash(test_db.pool.clone()).await.starts_with("$2"));
+
+        // A failed sign-in leaves the hash alone
+        server
+            .post("/signin")
+            .json(&serde_json::json!({
+                "email": "legacy@example.com",
+                "password": "wrong_password"
+            }))
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+        assert!(stored_hash(test_db.pool.clone()).await.starts_with("$2"));
+
+        let sign_in = || {
+            server.post("/signin").json(&serde_json::json!({
+                "email": "legacy@example.com",
+                "password": "legacy_password"
+            }))
+        };
+        sign_in().await.assert_status_ok();
+        let rehashed = stored_hash(test_db.pool.clone()).await;
+        assert!(rehashed.starts_with("$argon2id$"));
+
+        // The new hash keeps working and isn't replaced again
+        sign_in().await.assert_status_ok();
+        assert_eq!(stored_hash(test_db.pool.clone()).await, rehashed);
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_signin_locks_account_after_failed_attempts() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Locked User",
+            "locked@example.com",
+            "correct_password",
+        )
+        .await
+        .expect("Failed to create test user");
+        let sign_in = |password: &'static str| {
+            server.post("/signin").json(&serde_json::json!({
+                "email": "locked@example.com",
+                "password": password
+            }))
+        };
+
+        // The default config locks for a minute after 5 failures in a row
+        for _ in 0..5 {
+            sign_in("wrong_password")
+                .await
+                .assert_status(http::StatusCode::UNAUTHORIZED);
+        }
+
+        // Even the right password is refused while locked, with the same
+        // response as an unknown email
+        let response = sign_in("correct_password").await;
+        response.assert_status(http::StatusCode::UNAUTHORIZED);
+        assert!(response.maybe_header("retry-after").is_none());
+        let unknown = server
+            .post("/signin")
+            .json(&serde_json::json!({
+                "email": "nobody@example.com",
+                "password": "correct_password"
+            }))
+            .await;
+        unknown.assert_status(http::StatusCode::UNAUTHORIZED);
+        assert_eq!(response.as_bytes(), unknown.as_bytes());
+
+        // Once the lockout has passed a successful sign-in clears the failures
+        sqlx::query!(
+            "UPDATE login_lockouts SET locked_until = (NOW() AT TIME ZONE 'UTC') - INTERVAL '1 second'
+             WHERE user_id = $1",
+            user.id
+        )
+        .execute(&test_db.pool)
+        .await
+        .unwrap();
+        sign_in("correct_password").await.assert_status_ok();
+        let failures = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM login_lockouts WHERE user_id = $1",
+            user.id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        assert_eq!(failures, Some(0));
+
+        cleanup_test_env();
+    }
+
     #[tokio::test]
     async fn test_signin_user_not_found() {
         let (app, _test_db) = create_test_app().await;
@@ -298,7 +735,7 @@ mod integration_tests {
         .expect("Failed to create test user");
 
         // Generate a valid JWT token
-        let token = create_test_jwt(&user.email);
+        let token = create_test_jwt(&test_db.pool, &user).await;
 
         let response = server
             .get("/tokenvalid/")
@@ -321,7 +758,79 @@ mod integration_tests {
 
         let response = server.get("/tokenvalid/").await;
 
+        response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["code"], "missing_token");
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_protected_route_with_malformed_authorization_header() {
+        let (app, _test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+
+        for header in ["Bearer", "Bearer ", "Basic dXNlcjpwYXNz"] {
+            let response = server
+                .get("/tokenvalid/")
+                .add_header(http::header::AUTHORIZATION, header)
+                .await;
+            response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+            let body: serde_json::Value = response.json();
+            assert_eq!(body["code"], "malformed_token");
+        }
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_protected_route_with_token_cookie() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Cookie User",
+            "cookie@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let token = create_test_jwt(&test_db.pool, &user).await;
+
+        let response = server
+            .get("/tokenvalid/")
+            .add_header(http::header::COOKIE, format!("access_token={}", token))
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["email"], "cookie@example.com");
+
+        // A cookie alone can't sign out; a cross-site form could send it
+        let response = server
+            .post("/logout")
+            .add_header(http::header::COOKIE, format!("access_token={}", token))
+            .await;
         response.assert_status(axum::http::StatusCode::FORBIDDEN);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["code"], "missing_csrf_header");
+
+        // A signed out session's token is reported as revoked
+        server
+            .post("/logout")
+            .add_header(http::header::COOKIE, format!("access_token={}", token))
+            .add_header(
+                http::HeaderName::from_static("x-requested-with"),
+                "XMLHttpRequest",
+            )
+            .await
+            .assert_status(axum::http::StatusCode::NO_CONTENT);
+        let response = server
+            .get("/tokenvalid/")
+            .add_header(http::header::COOKIE, format!("access_token={}", token))
+            .await;
+        response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["code"], "revoked_token");
 
         cleanup_test_env();
     }
@@ -343,7 +852,7 @@ mod integration_tests {
 
     #[tokio::test]
     async fn test_complete_user_journey() {
-        let (app, _test_db) = create_test_app().await;
+        let (app, _test_db, mailer) = create_test_app_with_mailer().await;
         let server = TestServer::new(app).unwrap();
 
         // Step 1: Register a new user
@@ -352,18 +861,40 @@ mod integration_tests {
             .json(&serde_json::json!({
                 "name": "Journey User",
                 "email": "journey@example.com",
-                "password": "journey_password123"
+                "password": "long-trip-2026"
             }))
             .await;
 
         create_response.assert_status_ok();
+        let user: User = create_response.json();
+        assert!(user.email_verified_at.is_none());
+
+        // Step 2: Password sign-in waits until the email is verified
+        let unverified_response = server
+            .post("/signin")
+            .json(&serde_json::json!({
+                "email": "journey@example.com",
+                "password": "long-trip-2026"
+            }))
+            .await;
+        unverified_response.assert_status(http::StatusCode::FORBIDDEN);
+        let body: serde_json::Value = unverified_response.json();
+        assert_eq!(body["code"], "email_not_verified");
+
+        server
+            .post("/auth/verify-email")
+            .json(&serde_json::json!({
+                "token": mailed_token(&mailer, "journey@example.com")
+            }))
+            .await
+            .assert_status(http::StatusCode::NO_CONTENT);
 
-        // Step 2: Sign in with the new user
+        // Step 3: Sign in with the new user
         let signin_response = server
             .post("/signin")
             .json(&serde_json::json!({
                 "email": "journey@example.com",
-                "password": "journey_password123"
+                "password": "long-trip-2026"
             }))
             .await;
 
@@ -373,7 +904,7 @@ mod integration_tests {
             .as_str()
             .expect("Expected token in response");
 
-        // Step 3: Access protected route with the token
+        // Step 4: Access protected route with the token
         let protected_response = server
             .get("/tokenvalid/")
             .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
@@ -386,4 +917,1777 @@ mod integration_tests {
 
         cleanup_test_env();
     }
+
+    #[tokio::test]
+    async fn test_verify_email_tokens_are_single_use() {
+        let (app, test_db, mailer) = create_test_app_with_mailer().await;
+        let server = TestServer::new(app).unwrap();
+
+        let user: User = server
+            .post("/createUser")
+            .json(&serde_json::json!({
+                "name": "Verify User",
+                "email": "Verify@Example.com",
+                "password": "confirm-me-please"
+            }))
+            .await
+            .json();
+        let email = mailer.sent().pop().expect("Expected a verification email");
+        assert_eq!(email.to, "verify@example.com");
+        assert_eq!(email.subject, "Verify your email address");
+        assert!(
+            email
+                .body
+                .contains("http://localhost:5173/verify-email?token=")
+        );
+        let token = mailed_token(&mailer, "verify@example.com");
+
+        // Only the hash of the token is stored
+        let stored = sqlx::query_scalar!(
+            "SELECT token_hash FROM user_tokens WHERE user_id = $1",
+            user.id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        assert_ne!(stored, token);
+
+        // A verification token can't reset the password
+        server
+            .post("/auth/reset-password")
+            .json(&serde_json::json!({"token": token, "password": "new_password"}))
+            .await
+            .assert_status(http::StatusCode::BAD_REQUEST);
+
+        server
+            .post("/auth/verify-email")
+            .json(&serde_json::json!({"token": token}))
+            .await
+            .assert_status(http::StatusCode::NO_CONTENT);
+        let verified_at =
+            sqlx::query_scalar!("SELECT email_verified_at FROM users WHERE id = $1", user.id)
+                .fetch_one(&test_db.pool)
+                .await
+                .unwrap();
+        assert!(verified_at.is_some());
+
+        for token in [token.as_str(), "not-a-token"] {
+            let response = server
+                .post("/auth/verify-email")
+                .json(&serde_json::json!({"token": token}))
+                .await;
+            response.assert_status(http::StatusCode::BAD_REQUEST);
+            let body: serde_json::Value = response.json();
+            assert_eq!(body["error"], "Invalid or expired token");
+        }
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_flow() {
+        let (app, test_db, mailer) = create_test_app_with_mailer().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Forgetful User",
+            "forgetful@example.com",
+            "old_password",
+        )
+        .await
+        .expect("Failed to create test user");
+        let access_token = create_test_jwt(&test_db.pool, &user).await;
+
+        // Unknown accounts get the same answer, and no mail
+        server
+            .post("/auth/forgot-password")
+            .json(&serde_json::json!({"email": "nobody@example.com"}))
+            .await
+            .assert_status(http::StatusCode::ACCEPTED);
+        assert!(mailer.sent().is_empty());
+
+        // Asking twice only leaves the newest link working
+        for count in 1..=2 {
+            server
+                .post("/auth/forgot-password")
+                .json(&serde_json::json!({"email": "forgetful@example.com"}))
+                .await
+                .assert_status(http::StatusCode::ACCEPTED);
+            wait_for_mail(&mailer, count).await;
+        }
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 2);
+        assert!(sent.iter().all(|email| email.to == "forgetful@example.com"));
+        assert_eq!(sent[0].subject, "Reset your password");
+        let token = mailed_token(&mailer, "forgetful@example.com");
+        let (_, rest) = sent[0].body.split_once("?token=").unwrap();
+        let superseded_token = rest.split_whitespace().next().unwrap();
+        server
+            .post("/auth/reset-password")
+            .json(&serde_json::json!({"token": superseded_token, "password": "new_password"}))
+            .await
+            .assert_status(http::StatusCode::BAD_REQUEST);
+
+        server
+            .post("/auth/reset-password")
+            .json(&serde_json::json!({"token": token, "password": "  "}))
+            .await
+            .assert_status(http::StatusCode::BAD_REQUEST);
+        // Rejected passwords leave the token usable
+        let response = server
+            .post("/auth/reset-password")
+            .json(&serde_json::json!({"token": token, "password": "qwerty123"}))
+            .await;
+        response.assert_status(http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["code"], "common");
+        server
+            .post("/auth/reset-password")
+            .json(&serde_json::json!({"token": token, "password": "new_password"}))
+            .await
+            .assert_status(http::StatusCode::NO_CONTENT);
+
+        // Existing sessions are signed out
+        server
+            .get("/tokenvalid/")
+            .add_header(
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", access_token),
+            )
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+
+        let sign_in = |password: &'static str| {
+            server.post("/signin").json(&serde_json::json!({
+                "email": "forgetful@example.com",
+                "password": password
+            }))
+        };
+        sign_in("old_password")
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+        sign_in("new_password").await.assert_status_ok();
+
+        // The link only works once
+        server
+            .post("/auth/reset-password")
+            .json(&serde_json::json!({"token": token, "password": "another_password"}))
+            .await
+            .assert_status(http::StatusCode::BAD_REQUEST);
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_tokens_and_logout_revokes_session() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        create_test_user(
+            &test_db.pool,
+            "Session User",
+            "session@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+
+        let signin: serde_json::Value = server
+            .post("/signin")
+            .json(&serde_json::json!({
+                "email": "session@example.com",
+                "password": "password123"
+            }))
+            .await
+            .json();
+        let first_refresh = signin["refresh_token"].as_str().unwrap().to_string();
+
+        let response = server
+            .post("/token/refresh")
+            .json(&serde_json::json!({ "refresh_token": first_refresh }))
+            .await;
+        response.assert_status_ok();
+        let refreshed: serde_json::Value = response.json();
+        let token = refreshed["token"].as_str().unwrap().to_string();
+        let second_refresh = refreshed["refresh_token"].as_str().unwrap().to_string();
+        assert_ne!(second_refresh, first_refresh);
+        server
+            .get("/tokenvalid/")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await
+            .assert_status_ok();
+
+        let response = server
+            .post("/logout")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await;
+        response.assert_status(axum::http::StatusCode::NO_CONTENT);
+
+        // Both the access token and the refresh token stop working
+        server
+            .get("/tokenvalid/")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await
+            .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+        server
+            .post("/token/refresh")
+            .json(&serde_json::json!({ "refresh_token": second_refresh }))
+            .await
+            .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_reused_refresh_token_revokes_session() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        create_test_user(
+            &test_db.pool,
+            "Reuse User",
+            "reuse@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+
+        let signin: serde_json::Value = server
+            .post("/signin")
+            .json(&serde_json::json!({
+                "email": "reuse@example.com",
+                "password": "password123"
+            }))
+            .await
+            .json();
+        let stolen = signin["refresh_token"].as_str().unwrap().to_string();
+
+        let refreshed: serde_json::Value = server
+            .post("/token/refresh")
+            .json(&serde_json::json!({ "refresh_token": stolen }))
+            .await
+            .json();
+        let token = refreshed["token"].as_str().unwrap().to_string();
+
+        // The already exchanged token is presented again
+        server
+            .post("/token/refresh")
+            .json(&serde_json::json!({ "refresh_token": stolen }))
+            .await
+            .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+        server
+            .get("/tokenvalid/")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await
+            .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+        server
+            .post("/token/refresh")
+            .json(&serde_json::json!({ "refresh_token": "not-a-token" }))
+            .await
+            .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+        cleanup_test_env();
+    }
+
+    // A nonce from the server, signed into a SIWE message by `wallet`
+    async fn signed_siwe_message(
+        server: &TestServer,
+        wallet: &ethers::signers::LocalWallet,
+    ) -> serde_json::Value {
+        let nonce: serde_json::Value = server.get("/auth/nonce").await.json();
+        siwe_message_with_nonce(wallet, nonce["nonce"].as_str().unwrap())
+    }
+
+    fn siwe_message_with_nonce(
+        wallet: &ethers::signers::LocalWallet,
+        nonce: &str,
+    ) -> serde_json::Value {
+        use ethers::signers::Signer;
+
+        let message = format!(
+            "localhost:5173 wants you to sign in with your Ethereum account:\n{}\n\nURI: http://localhost:5173\nVersion: 1\nChain ID: 1\nNonce: {}\nIssued At: {}",
+            ethers::utils::to_checksum(&wallet.address(), None),
+            nonce,
+            chrono::Utc::now().to_rfc3339()
+        );
+        let signature = wallet
+            .sign_hash(ethers::utils::hash_message(&message))
+            .unwrap();
+        serde_json::json!({
+            "message": message,
+            "signature": format!("0x{}", signature)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_siwe_nonces_are_rate_limited() {
+        let (app, test_db) = create_test_app_with_rate_limit(RateLimitConfig {
+            ip_capacity: 2,
+            ip_refill_per_minute: 1.0,
+            ..RateLimitConfig::default()
+        })
+        .await;
+        let server = TestServer::new(app).unwrap();
+        let issue_nonce =
+            |ip: &'static str| server.get("/auth/nonce").add_header("x-forwarded-for", ip);
+
+        for _ in 0..2 {
+            issue_nonce("10.0.0.1").await.assert_status_ok();
+        }
+        issue_nonce("10.0.0.1")
+            .await
+            .assert_status(http::StatusCode::TOO_MANY_REQUESTS);
+        issue_nonce("10.0.0.2").await.assert_status_ok();
+
+        let issued = sqlx::query_scalar!("SELECT COUNT(*) FROM auth_nonces")
+            .fetch_one(&test_db.pool)
+            .await
+            .unwrap();
+        assert_eq!(issued, Some(3));
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_siwe_sign_in() {
+        use ethers::signers::{LocalWallet, Signer};
+
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let address = ethers::utils::to_checksum(&wallet.address(), None);
+
+        // The first sign-in creates a wallet-only account
+        let request = signed_siwe_message(&server, &wallet).await;
+        let response = server.post("/auth/siwe").json(&request).await;
+        response.assert_status_ok();
+        let signin: serde_json::Value = response.json();
+        let token = signin["token"].as_str().unwrap().to_string();
+        assert!(signin["refresh_token"].as_str().is_some());
+        let user: serde_json::Value = server
+            .get("/tokenvalid/")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await
+            .json();
+        assert_eq!(user["email"], serde_json::Value::Null);
+
+        // Each nonce signs in once
+        server
+            .post("/auth/siwe")
+            .json(&request)
+            .await
+            .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+        // Signing in again reaches the same account
+        let response = server
+            .post("/auth/siwe")
+            .json(&signed_siwe_message(&server, &wallet).await)
+            .await;
+        response.assert_status_ok();
+        let token = response.json::<serde_json::Value>()["token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        server
+            .get("/tokenvalid/")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await
+            .assert_status_ok();
+        let accounts = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM users WHERE wallet_address = $1",
+            address
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        assert_eq!(accounts, Some(1));
+
+        // A nonce the server never issued is refused
+        server
+            .post("/auth/siwe")
+            .json(&siwe_message_with_nonce(&wallet, "abcdef0123456789"))
+            .await
+            .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_link_wallets_with_signed_challenge() {
+        use ethers::signers::{LocalWallet, Signer};
+
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Wallet User",
+            "wallet@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let token = create_test_jwt(&test_db.pool, &user).await;
+        let first = LocalWallet::new(&mut rand::thread_rng());
+        let second = LocalWallet::new(&mut rand::thread_rng());
+        let link = |wallet: &LocalWallet, proof: serde_json::Value| {
+            let mut request = proof;
+            request["wallet_address"] = format!("{:?}", wallet.address()).into();
+            request
+        };
+
+        // A signature from another wallet proves nothing
+        let request = link(&first, signed_siwe_message(&server, &second).await);
+        server
+            .put("/user/wallet")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&request)
+            .await
+            .assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let request = link(&first, signed_siwe_message(&server, &first).await);
+        server
+            .put("/user/wallet")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&request)
+            .await
+            .assert_status_ok();
+        let response = server
+            .post("/user/wallets")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&link(&second, signed_siwe_message(&server, &second).await))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+
+        let wallets: Vec<serde_json::Value> = server
+            .get("/user/wallets")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await
+            .json();
+        assert_eq!(wallets.len(), 2);
+        // Adding a wallet leaves the primary one alone
+        let primary = user_queries::retrieve_user_by_id(&test_db.pool, user.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .wallet_address;
+        assert_eq!(
+            primary,
+            Some(ethers::utils::to_checksum(&first.address(), None))
+        );
+
+        // Another account cannot take the wallet over
+        let other = create_test_user(
+            &test_db.pool,
+            "Other User",
+            "other-wallet@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let other_token = create_test_jwt(&test_db.pool, &other).await;
+        server
+            .put("/user/wallet")
+            .add_header(
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", other_token),
+            )
+            .json(&link(&first, signed_siwe_message(&server, &first).await))
+            .await
+            .assert_status(axum::http::StatusCode::CONFLICT);
+
+        // Signing in with a linked wallet reaches the account it is linked to
+        let response = server
+            .post("/auth/siwe")
+            .json(&signed_siwe_message(&server, &second).await)
+            .await;
+        response.assert_status_ok();
+        let token = response.json::<serde_json::Value>()["token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let me: serde_json::Value = server
+            .get("/tokenvalid/")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await
+            .json();
+        assert_eq!(me["email"], "wallet@example.com");
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_update_profile_requires_reverification() {
+        let (app, test_db, mailer) = create_test_app_with_mailer().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Profile User",
+            "profile@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        create_test_user(
+            &test_db.pool,
+            "Taken User",
+            "taken@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let auth = format!("Bearer {}", create_test_jwt(&test_db.pool, &user).await);
+
+        let response = server
+            .get("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .await;
+        response.assert_status_ok();
+        let profile: serde_json::Value = response.json();
+        assert_eq!(profile["name"], "Profile User");
+        assert_eq!(profile["email"], "profile@example.com");
+        assert_eq!(profile["email_verified"], true);
+        assert_eq!(profile["has_password"], true);
+        assert!(profile.get("password_hash").is_none());
+
+        // Renaming needs no password and keeps the email verified
+        let response = server
+            .patch("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .json(&serde_json::json!({"name": "  Renamed User "}))
+            .await;
+        response.assert_status_ok();
+        let profile: serde_json::Value = response.json();
+        assert_eq!(profile["name"], "Renamed User");
+        assert_eq!(profile["email_verified"], true);
+        server
+            .patch("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .json(&serde_json::json!({"name": " "}))
+            .await
+            .assert_status(http::StatusCode::BAD_REQUEST);
+
+        // Changing the email needs the current password
+        let change_email = |email: &'static str, password: Option<&'static str>| {
+            server
+                .patch("/user")
+                .add_header(http::header::AUTHORIZATION, auth.clone())
+                .json(&serde_json::json!({"email": email, "current_password": password}))
+        };
+        let response = change_email("new@example.com", None).await;
+        response.assert_status(http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["field"], "current_password");
+        change_email("new@example.com", Some("wrong_password"))
+            .await
+            .assert_status(http::StatusCode::FORBIDDEN);
+        change_email("not-an-email", Some("password123"))
+            .await
+            .assert_status(http::StatusCode::BAD_REQUEST);
+        change_email("Taken@Example.com", Some("password123"))
+            .await
+            .assert_status(http::StatusCode::CONFLICT);
+        assert!(mailer.sent().is_empty());
+
+        let response = change_email("New@Example.com", Some("password123")).await;
+        response.assert_status_ok();
+        let profile: serde_json::Value = response.json();
+        assert_eq!(profile["email"], "new@example.com");
+        assert_eq!(profile["email_verified"], false);
+
+        // Until the new address is verified, the password can't sign in
+        let sign_in = |email: &'static str| {
+            server.post("/signin").json(&serde_json::json!({
+                "email": email,
+                "password": "password123"
+            }))
+        };
+        sign_in("profile@example.com")
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+        sign_in("new@example.com")
+            .await
+            .assert_status(http::StatusCode::FORBIDDEN);
+
+        server
+            .post("/auth/verify-email")
+            .json(&serde_json::json!({"token": mailed_token(&mailer, "new@example.com")}))
+            .await
+            .assert_status(http::StatusCode::NO_CONTENT);
+        sign_in("new@example.com").await.assert_status_ok();
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_change_password_signs_out_other_sessions() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Password User",
+            "password.user@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let current = format!("Bearer {}", create_test_jwt(&test_db.pool, &user).await);
+        let other = format!("Bearer {}", create_test_jwt(&test_db.pool, &user).await);
+
+        let change_password = |current_password: &'static str, new_password: &'static str| {
+            server
+                .post("/user/password")
+                .add_header(http::header::AUTHORIZATION, current.clone())
+                .json(&serde_json::json!({
+                    "current_password": current_password,
+                    "new_password": new_password
+                }))
+        };
+        change_password("wrong_password", "a-much-better-one")
+            .await
+            .assert_status(http::StatusCode::FORBIDDEN);
+        let response = change_password("password123", "qwerty123").await;
+        response.assert_status(http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["field"], "new_password");
+        assert_eq!(body["fields"][0]["code"], "common");
+
+        change_password("password123", "a-much-better-one")
+            .await
+            .assert_status(http::StatusCode::NO_CONTENT);
+
+        // Only the session that changed the password stays signed in
+        server
+            .get("/user")
+            .add_header(http::header::AUTHORIZATION, current.clone())
+            .await
+            .assert_status_ok();
+        server
+            .get("/user")
+            .add_header(http::header::AUTHORIZATION, other)
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+
+        let sign_in = |password: &'static str| {
+            server.post("/signin").json(&serde_json::json!({
+                "email": "password.user@example.com",
+                "password": password
+            }))
+        };
+        sign_in("password123")
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+        sign_in("a-much-better-one").await.assert_status_ok();
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_keeps_policies_and_claims() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Leaving User",
+            "leaving@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        sqlx::query!(
+            "INSERT INTO user_wallets (user_id, wallet_address)
+             VALUES ($1, '0x1234567890123456789012345678901234567890')",
+            user.id
+        )
+        .execute(&test_db.pool)
+        .await
+        .unwrap();
+        let policy_id = sqlx::query_scalar!(
+            "INSERT INTO insurance_policies
+                (user_id, policy_name, policy_type, location_latitude, location_longitude,
+                 coverage_amount, premium_amount, start_date, end_date)
+             VALUES ($1, 'Rain Event Insurance', 'rain', 40.7128, -74.0060, 3.00, 0.30,
+                     NOW() - INTERVAL '10 days', NOW() + INTERVAL '20 days')
+             RETURNING id",
+            user.id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO policy_claims (policy_id, claim_amount, trigger_date)
+             VALUES ($1, 3.00, NOW() - INTERVAL '2 days')",
+            policy_id
+        )
+        .execute(&test_db.pool)
+        .await
+        .unwrap();
+        let auth = format!("Bearer {}", create_test_jwt(&test_db.pool, &user).await);
+
+        let delete_account = |password: &'static str| {
+            server
+                .delete("/user")
+                .add_header(http::header::AUTHORIZATION, auth.clone())
+                .json(&serde_json::json!({"current_password": password}))
+        };
+        delete_account("wrong_password")
+            .await
+            .assert_status(http::StatusCode::FORBIDDEN);
+        delete_account("password123")
+            .await
+            .assert_status(http::StatusCode::NO_CONTENT);
+
+        // Signed out, and unable to sign in again
+        server
+            .get("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+        server
+            .post("/signin")
+            .json(&serde_json::json!({
+                "email": "leaving@example.com",
+                "password": "password123"
+            }))
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+
+        let anonymised = sqlx::query!(
+            "SELECT name, email, password_hash, wallet_address, deleted_at FROM users WHERE id = $1",
+            user.id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        assert_eq!(anonymised.name, "Deleted user");
+        assert!(anonymised.email.is_none());
+        assert!(anonymised.password_hash.is_none());
+        assert!(anonymised.wallet_address.is_none());
+        assert!(anonymised.deleted_at.is_some());
+        let wallets = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM user_wallets WHERE user_id = $1",
+            user.id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        assert_eq!(wallets, Some(0));
+
+        // The insurance history stays, and can't be removed with the user
+        let claims = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM policy_claims c
+             JOIN insurance_policies p ON p.id = c.policy_id
+             WHERE p.user_id = $1",
+            user.id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        assert_eq!(claims, Some(1));
+        assert!(
+            sqlx::query!("DELETE FROM users WHERE id = $1", user.id)
+                .execute(&test_db.pool)
+                .await
+                .is_err()
+        );
+
+        cleanup_test_env();
+    }
+
+    fn quote_request(coverage: &str, days: i64) -> serde_json::Value {
+        let start = time::OffsetDateTime::now_utc()
+            .replace_nanosecond(0)
+            .unwrap()
+            + time::Duration::hours(1);
+        let end = start + time::Duration::days(days);
+        let format = time::format_description::well_known::Rfc3339;
+        serde_json::json!({
+            "policy_template_id": 2,
+            "location_h3_index": "872a1072bffffff",
+            "coverage_amount": coverage,
+            "start_date": start.format(&format).unwrap(),
+            "end_date": end.format(&format).unwrap()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_wallet_only_account_needs_signed_message() {
+        use ethers::signers::LocalWallet;
+
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let other = LocalWallet::new(&mut rand::thread_rng());
+        let signin: serde_json::Value = server
+            .post("/auth/siwe")
+            .json(&signed_siwe_message(&server, &wallet).await)
+            .await
+            .json();
+        let auth = format!("Bearer {}", signin["token"].as_str().unwrap());
+
+        // An access token alone can't change the email or delete the account
+        let response = server
+            .patch("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .json(&serde_json::json!({"email": "wallet@example.com"}))
+            .await;
+        response.assert_status(http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["field"], "signed_message");
+        server
+            .delete("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .await
+            .assert_status(http::StatusCode::BAD_REQUEST);
+
+        // Nor can a message signed by a wallet that isn't the account's
+        server
+            .delete("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .json(&serde_json::json!({
+                "signed_message": signed_siwe_message(&server, &other).await
+            }))
+            .await
+            .assert_status(http::StatusCode::FORBIDDEN);
+
+        let change_email = serde_json::json!({
+            "email": "wallet@example.com",
+            "signed_message": signed_siwe_message(&server, &wallet).await
+        });
+        let response = server
+            .patch("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .json(&change_email)
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<serde_json::Value>()["email"],
+            "wallet@example.com"
+        );
+
+        // Each signed message is only good once
+        server
+            .patch("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .json(&serde_json::json!({
+                "email": "another@example.com",
+                "signed_message": change_email["signed_message"]
+            }))
+            .await
+            .assert_status(http::StatusCode::UNAUTHORIZED);
+        server
+            .delete("/user")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .json(&serde_json::json!({
+                "signed_message": signed_siwe_message(&server, &wallet).await
+            }))
+            .await
+            .assert_status(http::StatusCode::NO_CONTENT);
+        let deleted =
+            sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE deleted_at IS NOT NULL")
+                .fetch_one(&test_db.pool)
+                .await
+                .unwrap();
+        assert_eq!(deleted, Some(1));
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_create_quote_prices_from_nearby_history() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Quote User",
+            "quote@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let token = create_test_jwt(&test_db.pool, &user).await;
+
+        // A station in the same cell saw heavy rain on 6 of the last 60 days
+        create_test_weather_station(&test_db.pool, "wxm_station_1").await;
+        sqlx::query!(
+            "INSERT INTO weather_data (station_id, recorded_at, precipitation)
+             SELECT 'wxm_station_1',
+                    date_trunc('day', NOW() AT TIME ZONE 'UTC') - n * INTERVAL '1 day' + INTERVAL '12 hours',
+                    CASE WHEN n % 10 = 0 THEN 25 ELSE 1 END
+             FROM generate_series(1, 60) AS n"
+        )
+        .execute(&test_db.pool)
+        .await
+        .unwrap();
+
+        let mut request = quote_request("5.00", 1);
+        request["weather_station_id"] = serde_json::json!("wxm_station_1");
+        let response = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&request)
+            .await;
+
+        response.assert_status_ok();
+        let quote: crate::db::models::PolicyQuote = response.json();
+        assert_eq!(quote.weather_station_id, "wxm_station_1");
+        assert_eq!(quote.weather_station_ids, vec!["wxm_station_1"]);
+        // 59 complete one-day windows, 6 of them with more than 10mm of rain
+        assert_eq!(quote.historical_samples, 59);
+        assert_eq!(quote.trigger_probability, Some("0.1017".parse().unwrap()));
+        // 5.00 * (0.03 + 0.1017 * 1.2), rounded up
+        assert_eq!(quote.premium_amount, "0.77".parse().unwrap());
+
+        let claims = crate::pricing::verify_quote(
+            &quote.quote_token,
+            "test_quote_secret_for_integration_tests",
+            0,
+        )
+        .expect("Quote token should verify");
+        assert_eq!(claims.user_id, user.id);
+        assert_eq!(claims.weather_station_id.as_deref(), Some("wxm_station_1"));
+        assert_eq!(claims.premium_amount, quote.premium_amount);
+
+        // Without a station, the policy is evaluated against the nearest one
+        request["weather_station_id"] = serde_json::Value::Null;
+        let quote: crate::db::models::PolicyQuote = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&request)
+            .await
+            .json();
+        assert_eq!(quote.weather_station_id, "wxm_station_1");
+
+        // A station somewhere else can't be priced from
+        request["weather_station_id"] = serde_json::json!("wxm_station_far_away");
+        let response = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&request)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("is not near"));
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_create_quote_rejects_invalid_requests() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Quote User",
+            "quote@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let token = create_test_jwt(&test_db.pool, &user).await;
+
+        // Locations without a weather station can't be insured
+        let response = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&quote_request("5.00", 30))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("No weather station near"));
+        create_test_weather_station(&test_db.pool, "wxm_station_1").await;
+
+        // The rain template covers at most 5.00
+        let response = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&quote_request("50.00", 30))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let mut request = quote_request("5.00", 30);
+        request["condition_overrides"] =
+            serde_json::json!([{"condition_type": "rainfall", "consecutive_days": 0}]);
+        let response = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&request)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        // Overrides can't turn the rain template into one paying for dry days,
+        // or move its threshold out of range
+        for policy_override in [
+            serde_json::json!({"condition_type": "rainfall", "operator": "<"}),
+            serde_json::json!({"condition_type": "rainfall", "threshold": "0"}),
+        ] {
+            let mut request = quote_request("5.00", 30);
+            request["condition_overrides"] = serde_json::json!([policy_override]);
+            let response = server
+                .post("/quotes")
+                .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+                .json(&request)
+                .await;
+            response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        // Unknown enum values are rejected when the body is parsed
+        let mut request = quote_request("5.00", 30);
+        request["condition_overrides"] =
+            serde_json::json!([{"condition_type": "rainfall", "operator": "~"}]);
+        let response = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&request)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "Validation failed");
+        assert_eq!(
+            body["fields"][0]["field"],
+            "condition_overrides[0].operator"
+        );
+        assert_eq!(body["fields"][0]["code"], "invalid");
+        assert!(
+            body["fields"][0]["message"]
+                .as_str()
+                .unwrap()
+                .contains("Unknown operator: ~")
+        );
+
+        let mut request = quote_request("5.00", 30);
+        request["policy_template_id"] = serde_json::json!(999);
+        let response = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&request)
+            .await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+
+        let response = server
+            .post("/quotes")
+            .json(&quote_request("5.00", 30))
+            .await;
+        response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_create_policy_requires_matching_quote() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Quote User",
+            "quote@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        crate::db::wallet_queries::add_user_wallet(
+            &test_db.pool,
+            user.id,
+            "0x1234567890123456789012345678901234567890",
+        )
+        .await
+        .unwrap();
+        create_test_weather_station(&test_db.pool, "wxm_station_1").await;
+        let token = create_test_jwt(&test_db.pool, &user).await;
+
+        let quote_response = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&quote_request("5.00", 30))
+            .await;
+        quote_response.assert_status_ok();
+        let quote: serde_json::Value = quote_response.json();
+
+        let mut policy = serde_json::json!({
+            "policy_template_id": 2,
+            "policy_name": "Rain Event Insurance",
+            "policy_type": "rain",
+            "location_latitude": "40.7128",
+            "location_longitude": "-74.0060",
+            "location_h3_index": "872a1072bffffff",
+            "coverage_amount": "5.00",
+            "premium_amount": "0.60",
+            "start_date": quote["start_date"],
+            "end_date": quote["end_date"],
+            "weather_station_id": quote["weather_station_id"],
+            "purchase_transaction_hash": format!("0x{}", "ab".repeat(32))
+        });
+
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&policy)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["field"], "quote_token");
+        assert_eq!(body["fields"][0]["code"], "required");
+
+        policy["quote_token"] = quote["quote_token"].clone();
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&policy)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["field"], "premium_amount");
+        assert_eq!(body["fields"][0]["code"], "quote_mismatch");
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_purchase_backs_only_one_policy() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        unsafe {
+            env::set_var("BLOCKCHAIN_VERIFICATION_ENABLED", "false");
+            env::set_var(
+                "WEATHER_INSURANCE_CONTRACT_ADDRESS",
+                "0x2222222222222222222222222222222222222222",
+            );
+        }
+        let user = create_test_user(
+            &test_db.pool,
+            "Policy User",
+            "policy@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        crate::db::wallet_queries::add_user_wallet(
+            &test_db.pool,
+            user.id,
+            "0x1234567890123456789012345678901234567890",
+        )
+        .await
+        .unwrap();
+        create_test_weather_station(&test_db.pool, "wxm_station_1").await;
+        let token = create_test_jwt(&test_db.pool, &user).await;
+
+        let quote: serde_json::Value = server
+            .post("/quotes")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&quote_request("5.00", 30))
+            .await
+            .json();
+        let mut policy = serde_json::json!({
+            "policy_template_id": 2,
+            "policy_name": "Rain Event Insurance",
+            "policy_type": "rain",
+            "location_latitude": "40.7128",
+            "location_longitude": "-74.0060",
+            "location_h3_index": "872a1072bffffff",
+            "coverage_amount": "5.00",
+            "premium_amount": quote["premium_amount"],
+            "start_date": quote["start_date"],
+            "end_date": quote["end_date"],
+            "weather_station_id": quote["weather_station_id"],
+            "smart_contract_address": "0x3333333333333333333333333333333333333333",
+            "purchase_transaction_hash": format!("0x{}", "AB".repeat(32)),
+            "quote_token": quote["quote_token"]
+        });
+
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&policy)
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let created: serde_json::Value = response.json();
+        assert_eq!(
+            created["purchase_transaction_hash"],
+            format!("0x{}", "ab".repeat(32))
+        );
+        assert_eq!(
+            created["smart_contract_address"],
+            "0x2222222222222222222222222222222222222222"
+        );
+
+        // The same purchase spelled differently is still the same purchase
+        policy["purchase_transaction_hash"] = serde_json::json!("ab".repeat(32));
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&policy)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["field"], "purchase_transaction_hash");
+        assert_eq!(body["fields"][0]["code"], "already_used");
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_create_policy_validates_against_template() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Policy User",
+            "policy@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        crate::db::wallet_queries::add_user_wallet(
+            &test_db.pool,
+            user.id,
+            "0x1234567890123456789012345678901234567890",
+        )
+        .await
+        .unwrap();
+        let token = create_test_jwt(&test_db.pool, &user).await;
+
+        // The rain template covers between 0.50 and 5.00
+        let request = quote_request("50.00", 30);
+        let mut policy = serde_json::json!({
+            "policy_template_id": 2,
+            "policy_name": "Rain Event Insurance",
+            "policy_type": "drought",
+            "location_latitude": "40.7128",
+            "location_longitude": "-74.0060",
+            "location_h3_index": "872a1072bffffff",
+            "coverage_amount": "50.00",
+            "premium_amount": "1.00",
+            "start_date": request["end_date"],
+            "end_date": request["start_date"],
+            "purchase_transaction_hash": format!("0x{}", "ab".repeat(32))
+        });
+
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&policy)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "Validation failed");
+        let fields: Vec<(&str, &str)> = body["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| (f["field"].as_str().unwrap(), f["code"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("policy_type", "mismatch"),
+                ("coverage_amount", "out_of_range"),
+                ("premium_amount", "too_low"),
+                ("end_date", "before_start_date"),
+            ]
+        );
+
+        // Policy types outside the enum never reach the handler, but are
+        // reported like any other invalid field
+        policy["policy_type"] = serde_json::json!("flood");
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&policy)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"], "Validation failed");
+        assert_eq!(body["fields"][0]["field"], "policy_type");
+        assert_eq!(body["fields"][0]["code"], "invalid");
+        assert!(
+            body["fields"][0]["message"]
+                .as_str()
+                .unwrap()
+                .contains("Unknown policy type: flood")
+        );
+
+        // So is a body that isn't JSON at all
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .text("not json")
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["field"], "body");
+        assert_eq!(body["fields"][0]["code"], "invalid");
+
+        // Overrides must refer to a condition of the template
+        policy["policy_type"] = serde_json::json!("rain");
+        policy["coverage_amount"] = serde_json::json!("5.00");
+        policy["start_date"] = request["start_date"].clone();
+        policy["end_date"] = request["end_date"].clone();
+        policy["condition_overrides"] =
+            serde_json::json!([{"condition_type": "wind_speed", "threshold": "50"}]);
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&policy)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["field"], "condition_overrides");
+        assert_eq!(body["fields"][0]["code"], "invalid");
+
+        policy["condition_overrides"] = serde_json::json!([]);
+        sqlx::query!("UPDATE policy_templates SET is_active = false WHERE id = 2")
+            .execute(&test_db.pool)
+            .await
+            .unwrap();
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&policy)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["field"], "policy_template_id");
+        assert_eq!(body["fields"][0]["code"], "inactive");
+
+        policy["policy_template_id"] = serde_json::json!(999);
+        let response = server
+            .post("/policies")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .json(&policy)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["fields"][0]["code"], "not_found");
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_policy_refunds_unused_premium() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let owner = create_test_user(
+            &test_db.pool,
+            "Cancel User",
+            "cancel@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let other = create_test_user(
+            &test_db.pool,
+            "Other User",
+            "other@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+
+        // A third of the way through its period, with no on-chain policy
+        let policy_id = sqlx::query_scalar!(
+            "INSERT INTO insurance_policies
+                (user_id, policy_name, policy_type, location_latitude, location_longitude,
+                 coverage_amount, premium_amount, start_date, end_date)
+             VALUES ($1, 'Rain Event Insurance', 'rain', 40.7128, -74.0060, 3.00, 0.30,
+                     NOW() - INTERVAL '10 days', NOW() + INTERVAL '20 days')
+             RETURNING id",
+            owner.id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        let cancel_url = format!("/policies/{}/cancel", policy_id);
+
+        let response = server
+            .post(&cancel_url)
+            .add_header(
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", create_test_jwt(&test_db.pool, &other).await),
+            )
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+
+        let token = create_test_jwt(&test_db.pool, &owner).await;
+        let response = server
+            .post(&cancel_url)
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["policy"]["status"], "cancelled");
+        assert!(body["policy"]["refund_transaction_hash"].is_null());
+        // Two thirds of the 0.30 premium, less the default 5% fee
+        let refund: rust_decimal::Decimal =
+            body["refund"]["amount"].as_str().unwrap().parse().unwrap();
+        assert!(refund > "0.184".parse().unwrap() && refund <= "0.185".parse().unwrap());
+        assert_eq!(body["refund"]["fee"], "0.015");
+        let recorded: rust_decimal::Decimal = body["policy"]["refund_amount"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(recorded, refund);
+
+        let response = server
+            .post(&cancel_url)
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await;
+        response.assert_status(axum::http::StatusCode::CONFLICT);
+
+        let response = server
+            .post("/policies/99999/cancel")
+            .add_header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_get_policy_details() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let owner = create_test_user(
+            &test_db.pool,
+            "Detail User",
+            "detail@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let other = create_test_user(
+            &test_db.pool,
+            "Other User",
+            "other@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+
+        let policy_id = sqlx::query_scalar!(
+            "INSERT INTO insurance_policies
+                (user_id, policy_name, policy_type, location_latitude, location_longitude,
+                 coverage_amount, premium_amount, start_date, end_date, weather_station_id,
+                 blockchain_verified)
+             VALUES ($1, 'Rain Event Insurance', 'rain', 40.7128, -74.0060, 3.00, 0.30,
+                     NOW() - INTERVAL '10 days', NOW() + INTERVAL '20 days', 'detail_station',
+                     true)
+             RETURNING id",
+            owner.id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO policy_conditions
+                (policy_id, condition_type, operator, threshold_value, measurement_unit,
+                 measurement_period, consecutive_days)
+             VALUES ($1, 'rainfall', '>', 10, 'mm', 'daily', 1)",
+            policy_id
+        )
+        .execute(&test_db.pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO policy_claims (policy_id, claim_amount, trigger_date)
+             VALUES ($1, 3.00, NOW() - INTERVAL '2 days')",
+            policy_id
+        )
+        .execute(&test_db.pool)
+        .await
+        .unwrap();
+        // Three readings during coverage and one from before it started
+        sqlx::query!(
+            "INSERT INTO weather_data (station_id, recorded_at, precipitation)
+             SELECT 'detail_station', NOW() - n * INTERVAL '1 day', 12
+             FROM unnest(ARRAY[1, 2, 3, 15]) AS n"
+        )
+        .execute(&test_db.pool)
+        .await
+        .unwrap();
+
+        let url = format!("/policies/{}", policy_id);
+        let response = server
+            .get(&url)
+            .add_header(
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", create_test_jwt(&test_db.pool, &owner).await),
+            )
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["id"], policy_id);
+        assert_eq!(body["verification_status"], "verified");
+        assert_eq!(body["conditions"].as_array().unwrap().len(), 1);
+        assert_eq!(body["claims"].as_array().unwrap().len(), 1);
+        assert_eq!(body["weather_data"].as_array().unwrap().len(), 3);
+
+        let response = server
+            .get(&url)
+            .add_header(
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", create_test_jwt(&test_db.pool, &other).await),
+            )
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+
+        let response = server
+            .get("/policies/99999")
+            .add_header(
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", create_test_jwt(&test_db.pool, &owner).await),
+            )
+            .await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_list_policies_paginates_and_filters() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "List User",
+            "list@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+
+        // Three rain policies and one verified wind policy
+        sqlx::query!(
+            "INSERT INTO insurance_policies
+                (user_id, policy_name, policy_type, location_latitude, location_longitude,
+                 coverage_amount, premium_amount, start_date, end_date, blockchain_verified)
+             SELECT $1, 'Policy ' || n, CASE WHEN n = 4 THEN 'wind' ELSE 'rain' END,
+                    40.7128, -74.0060, n, 0.10, NOW(), NOW() + INTERVAL '30 days', n = 4
+             FROM generate_series(1, 4) AS n",
+            user.id
+        )
+        .execute(&test_db.pool)
+        .await
+        .unwrap();
+        let auth = format!("Bearer {}", create_test_jwt(&test_db.pool, &user).await);
+
+        let response = server
+            .get("/policies?sort=coverage_amount&order=asc&limit=3")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["total_count"], 4);
+        assert_eq!(body["policies"].as_array().unwrap().len(), 3);
+        assert_eq!(body["policies"][0]["policy_name"], "Policy 1");
+        let cursor = body["next_cursor"].as_str().unwrap().to_string();
+
+        let response = server
+            .get("/policies")
+            .add_query_param("sort", "coverage_amount")
+            .add_query_param("order", "asc")
+            .add_query_param("limit", 3)
+            .add_query_param("cursor", &cursor)
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["policies"].as_array().unwrap().len(), 1);
+        assert_eq!(body["policies"][0]["policy_name"], "Policy 4");
+        assert!(body["next_cursor"].is_null());
+
+        let response = server
+            .get("/policies?policy_type=wind&blockchain_verified=true")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["total_count"], 1);
+
+        let response = server
+            .get("/policies?limit=500")
+            .add_header(http::header::AUTHORIZATION, auth.clone())
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let response = server
+            .get("/policies")
+            .add_query_param("cursor", &cursor)
+            .add_header(http::header::AUTHORIZATION, auth)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        cleanup_test_env();
+    }
+
+    #[tokio::test]
+    async fn test_admin_api() {
+        let (app, test_db) = create_test_app().await;
+        let server = TestServer::new(app).unwrap();
+        let admin = create_test_user(
+            &test_db.pool,
+            "Admin User",
+            "admin@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        sqlx::query!("UPDATE users SET role = 'admin' WHERE id = $1", admin.id)
+            .execute(&test_db.pool)
+            .await
+            .unwrap();
+        let user = create_test_user(
+            &test_db.pool,
+            "Regular User",
+            "regular@example.com",
+            "password123",
+        )
+        .await
+        .expect("Failed to create test user");
+        let admin_auth = format!("Bearer {}", create_test_jwt(&test_db.pool, &admin).await);
+        let user_auth = format!("Bearer {}", create_test_jwt(&test_db.pool, &user).await);
+
+        let response = server
+            .get("/admin/policies")
+            .add_header(http::header::AUTHORIZATION, user_auth)
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+        let response = server.get("/admin/policies").await;
+        response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+        // Templates
+        let template = serde_json::json!({
+            "template_name": "Storm Cover",
+            "description": "Pays out on high winds",
+            "policy_type": "storm",
+            "default_conditions": {"conditions": [{
+                "type": "wind_speed",
+                "operator": ">",
+                "threshold": 90,
+                "unit": "km/h",
+                "period": "daily"
+            }]},
+            "min_coverage_amount": "1.00",
+            "max_coverage_amount": "10.00",
+            "base_premium_rate": "0.08"
+        });
+        let response = server
+            .post("/admin/policy-templates")
+            .add_header(http::header::AUTHORIZATION, admin_auth.clone())
+            .json(&template)
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let template_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+
+        let response = server
+            .put(&format!("/admin/policy-templates/{}", template_id))
+            .add_header(http::header::AUTHORIZATION, admin_auth.clone())
+            .json(&serde_json::json!({"min_coverage_amount": "50.00"}))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let response = server
+            .put(&format!("/admin/policy-templates/{}", template_id))
+            .add_header(http::header::AUTHORIZATION, admin_auth.clone())
+            .json(&serde_json::json!({"max_coverage_amount": "20.00"}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<serde_json::Value>()["max_coverage_amount"],
+            "20.00"
+        );
+
+        let response = server
+            .post(&format!(
+                "/admin/policy-templates/{}/deactivate",
+                template_id
+            ))
+            .add_header(http::header::AUTHORIZATION, admin_auth.clone())
+            .await;
+        response.assert_status_ok();
+        let active = crate::db::policy_queries::get_all_policy_templates(&test_db.pool)
+            .await
+            .unwrap();
+        assert!(active.iter().all(|t| t.id != template_id as i32));
+
+        // Policies and claims
+        let policy_id = sqlx::query_scalar!(
+            "INSERT INTO insurance_policies
+                (user_id, policy_name, policy_type, location_latitude, location_longitude,
+                 coverage_amount, premium_amount, start_date, end_date, status)
+             VALUES ($1, 'Rain Event Insurance', 'rain', 40.7128, -74.0060, 3.00, 0.30,
+                     NOW() - INTERVAL '10 days', NOW() + INTERVAL '20 days', 'claimed')
+             RETURNING id",
+            user.id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+        let claim_id = sqlx::query_scalar!(
+            "INSERT INTO policy_claims (policy_id, claim_amount, trigger_date)
+             VALUES ($1, 3.00, NOW()) RETURNING id",
+            policy_id
+        )
+        .fetch_one(&test_db.pool)
+        .await
+        .unwrap();
+
+        let response = server
+            .get("/admin/policies")
+            .add_header(http::header::AUTHORIZATION, admin_auth.clone())
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<serde_json::Value>()["total_count"], 1);
+
+        let reject_url = format!("/admin/claims/{}/reject", claim_id);
+        let response = server
+            .post(&reject_url)
+            .add_header(http::header::AUTHORIZATION, admin_auth.clone())
+            .json(&serde_json::json!({"rejection_reason": " "}))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let response = server
+            .post(&reject_url)
+            .add_header(http::header::AUTHORIZATION, admin_auth.clone())
+            .json(&serde_json::json!({"rejection_reason": "Station was offline"}))
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["claim_status"], "rejected");
+        assert_eq!(body["rejection_reason"], "Station was offline");
+        let response = server
+            .post(&format!("/admin/claims/{}/approve", claim_id))
+            .add_header(http::header::AUTHORIZATION, admin_auth.clone())
+            .await;
+        response.assert_status(axum::http::StatusCode::CONFLICT);
+
+        // Claimed is final for the lifecycle, but an admin can reopen it
+        let response = server
+            .post(&format!("/admin/policies/{}/status", policy_id))
+            .add_header(http::header::AUTHORIZATION, admin_auth.clone())
+            .json(&serde_json::json!({"status": "active", "reason": "Claim rejected"}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<serde_json::Value>()["actor"],
+            format!("admin:{}", admin.id)
+        );
+
+        let response = server
+            .get("/admin/audit-log")
+            .add_header(http::header::AUTHORIZATION, admin_auth)
+            .await;
+        response.assert_status_ok();
+        let actions: Vec<String> = response
+            .json::<Vec<serde_json::Value>>()
+            .iter()
+            .map(|entry| entry["action"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                "force_policy_status",
+                "reject_claim",
+                "deactivate_policy_template",
+                "update_policy_template",
+                "create_policy_template",
+            ]
+        );
+
+        cleanup_test_env();
+    }
 }
@@ -0,0 +1,533 @@
+use crate::db::models::{CreateWeatherData, CreateWeatherStation};
+use crate::db::policy_queries;
+use crate::weather::weatherxm_client::{
+    StationHistory, WeatherXmClient, WeatherXmError, WeatherXmObservation, WeatherXmStation,
+};
+use h3o::{CellIndex, LatLng, Resolution};
+use rust_decimal::Decimal;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{Pool, Postgres};
+use time::format_description::well_known::Rfc3339;
+use time::{Date, OffsetDateTime, UtcOffset};
+use tracing::{debug, error, info, warn};
+
+// WeatherXM reports wind speed in m/s, weather_data stores km/h
+const MS_TO_KMH: f64 = 3.6;
+
+// Stations are indexed by the cell they are in at the resolution policy
+// locations are given in
+pub const STATION_CELL_RESOLUTION: Resolution = Resolution::Seven;
+
+// Error types for weather ingestion
+#[derive(Debug, thiserror::Error)]
+pub enum IngestionError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("WeatherXM error: {0}")]
+    WeatherXm(#[from] WeatherXmError),
+}
+
+// Configuration for the ingestion worker
+#[derive(Debug, Clone)]
+pub struct IngestionConfig {
+    pub enabled: bool,
+    pub poll_interval_seconds: u64,
+    pub lookback_days: i64,
+    // Station locations change rarely, so they are synced less often than
+    // observations are polled
+    pub station_sync_interval_seconds: u64,
+}
+
+impl Default for IngestionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_seconds: 900,
+            lookback_days: 2,
+            station_sync_interval_seconds: 86400,
+        }
+    }
+}
+
+impl IngestionConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("WEATHER_INGESTION_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            poll_interval_seconds: std::env::var("WEATHER_INGESTION_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.poll_interval_seconds),
+            lookback_days: std::env::var("WEATHER_INGESTION_LOOKBACK_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.lookback_days),
+            station_sync_interval_seconds: std::env::var("WEATHER_STATION_SYNC_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.station_sync_interval_seconds),
+        }
+    }
+}
+
+// Summary of a single ingestion pass
+#[derive(Debug, Default)]
+pub struct IngestionReport {
+    pub stations_polled: usize,
+    pub observations_upserted: usize,
+    pub failed_stations: Vec<String>,
+}
+
+pub struct WeatherIngestionService<C: WeatherXmClient> {
+    pool: Pool<Postgres>,
+    client: C,
+    config: IngestionConfig,
+}
+
+impl<C: WeatherXmClient> WeatherIngestionService<C> {
+    pub fn new(pool: Pool<Postgres>, client: C, config: IngestionConfig) -> Self {
+        Self {
+            pool,
+            client,
+            config,
+        }
+    }
+
+    // Poll forever on the configured interval. Failures are logged and retried
+    // on the next tick rather than stopping the worker.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            self.config.poll_interval_seconds,
+        ));
+        let station_sync_interval =
+            std::time::Duration::from_secs(self.config.station_sync_interval_seconds);
+        let mut stations_synced_at: Option<std::time::Instant> = None;
+
+        loop {
+            interval.tick().await;
+            if stations_synced_at.is_none_or(|at| at.elapsed() >= station_sync_interval) {
+                match self.sync_stations().await {
+                    Ok(count) => {
+                        info!("Synced the locations of {} weather stations", count);
+                        stations_synced_at = Some(std::time::Instant::now());
+                    }
+                    Err(e) => error!("Weather station sync failed: {}", e),
+                }
+            }
+            let today = OffsetDateTime::now_utc().date();
+            match self.run_once(today).await {
+                Ok(report) => info!(
+                    "Weather ingestion pass complete: {} stations, {} observations, {} failures",
+                    report.stations_polled,
+                    report.observations_upserted,
+                    report.failed_stations.len()
+                ),
+                Err(e) => error!("Weather ingestion pass failed: {}", e),
+            }
+        }
+    }
+
+    // Store the location of every station WeatherXM lists. Stations with
+    // coordinates outside the valid range are skipped.
+    pub async fn sync_stations(&self) -> Result<usize, IngestionError> {
+        let stations = self.client.get_stations().await?;
+        let mut synced = 0;
+
+        for station in &stations {
+            let Some(station) = map_station(station) else {
+                warn!(
+                    "Skipping weather station {} with an invalid location",
+                    station.id
+                );
+                continue;
+            };
+            policy_queries::upsert_weather_station(&self.pool, &station).await?;
+            synced += 1;
+        }
+
+        Ok(synced)
+    }
+
+    // Ingest observations for every station referenced by an active policy
+    pub async fn run_once(&self, today: Date) -> Result<IngestionReport, IngestionError> {
+        let station_ids = policy_queries::get_active_weather_station_ids(&self.pool).await?;
+        let mut report = IngestionReport::default();
+
+        for station_id in station_ids {
+            report.stations_polled += 1;
+            match self.ingest_station(&station_id, today).await {
+                Ok(count) => report.observations_upserted += count,
+                Err(e) => {
+                    warn!(
+                        "Failed to ingest weather data for station {}: {}",
+                        station_id, e
+                    );
+                    report.failed_stations.push(station_id);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // Fetch and upsert every day from the last stored reading (or the lookback
+    // window, whichever is later) up to and including `today`. The last stored
+    // day is re-fetched because it was most likely only partially recorded.
+    pub async fn ingest_station(
+        &self,
+        station_id: &str,
+        today: Date,
+    ) -> Result<usize, IngestionError> {
+        let earliest = today - time::Duration::days(self.config.lookback_days);
+        let start =
+            match policy_queries::get_latest_weather_recorded_at(&self.pool, station_id).await? {
+                Some(latest) => latest.date().max(earliest),
+                None => earliest,
+            };
+
+        let mut upserted = 0;
+        let mut date = start;
+        while date <= today {
+            let history = self.client.get_station_history(station_id, date).await?;
+            let readings = map_station_history(station_id, &history)?;
+            debug!(
+                "Fetched {} observations for station {} on {}",
+                readings.len(),
+                station_id,
+                date
+            );
+
+            for reading in &readings {
+                policy_queries::insert_weather_data(&self.pool, reading).await?;
+            }
+            upserted += readings.len();
+
+            date = match date.next_day() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        Ok(upserted)
+    }
+}
+
+// Map one day of WeatherXM observations into weather_data rows.
+//
+// WeatherXM reports precipitation as a running daily total, so each row stores
+// the increase since the previous observation. Summing a day's rows therefore
+// yields that day's rainfall.
+pub fn map_station_history(
+    station_id: &str,
+    history: &StationHistory,
+) -> Result<Vec<CreateWeatherData>, WeatherXmError> {
+    let quality_score = history.quality_score();
+
+    let mut observations = history
+        .observations
+        .iter()
+        .map(|raw| {
+            let observation: WeatherXmObservation = serde_json::from_value(raw.clone())
+                .map_err(|e| WeatherXmError::ParseError(format!("Invalid observation: {}", e)))?;
+            let recorded_at = parse_timestamp(&observation.timestamp)?;
+            Ok((recorded_at, observation, raw))
+        })
+        .collect::<Result<Vec<_>, WeatherXmError>>()?;
+    observations.sort_by_key(|(recorded_at, _, _)| *recorded_at);
+
+    let mut previous_accumulated: Option<f64> = None;
+    let readings = observations
+        .into_iter()
+        .map(|(recorded_at, observation, raw)| {
+            let precipitation = observation.precipitation_accumulated.map(|total| {
+                // A drop in the running total means the station reset its counter
+                let increment = match previous_accumulated {
+                    Some(previous) if total >= previous => total - previous,
+                    _ => total,
+                };
+                previous_accumulated = Some(total);
+                increment
+            });
+
+            CreateWeatherData {
+                station_id: station_id.to_string(),
+                recorded_at,
+                temperature: observation.temperature.and_then(to_decimal),
+                humidity: observation.humidity.and_then(to_decimal),
+                precipitation: precipitation.and_then(to_decimal),
+                wind_speed: observation
+                    .wind_speed
+                    .and_then(|v| to_decimal(v * MS_TO_KMH)),
+                wind_direction: observation.wind_direction.and_then(to_decimal),
+                atmospheric_pressure: observation.pressure.and_then(to_decimal),
+                data_source: Some("weatherxm".to_string()),
+                raw_data: Some(raw.clone()),
+                quality_score,
+            }
+        })
+        .collect();
+
+    Ok(readings)
+}
+
+// The cell a station is in, or None if its coordinates are invalid
+pub fn station_cell(latitude: f64, longitude: f64) -> Option<CellIndex> {
+    LatLng::new(latitude, longitude)
+        .ok()
+        .map(|location| location.to_cell(STATION_CELL_RESOLUTION))
+}
+
+fn map_station(station: &WeatherXmStation) -> Option<CreateWeatherStation> {
+    let cell = station_cell(station.location.lat, station.location.lon)?;
+    Some(CreateWeatherStation {
+        id: station.id.clone(),
+        latitude: Decimal::from_f64_retain(station.location.lat)?.round_dp(8),
+        longitude: Decimal::from_f64_retain(station.location.lon)?.round_dp(8),
+        h3_index: cell.to_string(),
+    })
+}
+
+fn parse_timestamp(timestamp: &str) -> Result<PrimitiveDateTime, WeatherXmError> {
+    let parsed = OffsetDateTime::parse(timestamp, &Rfc3339).map_err(|e| {
+        WeatherXmError::ParseError(format!("Invalid timestamp '{}': {}", timestamp, e))
+    })?;
+    let utc = parsed.to_offset(UtcOffset::UTC);
+    Ok(PrimitiveDateTime::new(utc.date(), utc.time()))
+}
+
+// weather_data columns are DECIMAL(_, 2)
+fn to_decimal(value: f64) -> Option<Decimal> {
+    Decimal::from_f64_retain(value).map(|d| d.round_dp(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::CreateInsurancePolicy;
+    use crate::test_utils::{
+        create_test_db, create_test_user_for_policies, spawn_mock_weatherxm_server,
+        test_policy_data,
+    };
+    use crate::weather::weatherxm_client::{HttpWeatherXmClient, WeatherXmConfig};
+    use std::str::FromStr;
+
+    fn sample_history() -> StationHistory {
+        serde_json::from_value(serde_json::json!({
+            "date": "2024-07-01",
+            "health": {"data_quality": {"score": 0.874}},
+            "observations": [
+                {
+                    "timestamp": "2024-07-01T00:10:00+00:00",
+                    "temperature": 18.25,
+                    "humidity": 80.0,
+                    "precipitation_accumulated": 1.5,
+                    "wind_speed": 10.0,
+                    "wind_direction": 270.0,
+                    "pressure": 1012.3,
+                    "uv_index": 0
+                },
+                {
+                    "timestamp": "2024-07-01T00:05:00+00:00",
+                    "temperature": 18.5,
+                    "humidity": 79.0,
+                    "precipitation_accumulated": 1.0,
+                    "wind_speed": 5.0,
+                    "wind_direction": 265.0,
+                    "pressure": 1012.4
+                },
+                {
+                    "timestamp": "2024-07-01T02:15:00+02:00",
+                    "temperature": 18.0,
+                    "precipitation_accumulated": 4.0
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_map_station_history_orders_and_converts() {
+        let readings = map_station_history("wxm_station_1", &sample_history()).unwrap();
+
+        assert_eq!(readings.len(), 3);
+        assert!(
+            readings
+                .windows(2)
+                .all(|w| w[0].recorded_at <= w[1].recorded_at)
+        );
+
+        let first = &readings[0];
+        assert_eq!(first.station_id, "wxm_station_1");
+        assert_eq!(first.temperature, Some(Decimal::from_str("18.5").unwrap()));
+        assert_eq!(first.wind_speed, Some(Decimal::from_str("18.0").unwrap()));
+        assert_eq!(first.quality_score, Some(87));
+        assert_eq!(first.data_source, Some("weatherxm".to_string()));
+    }
+
+    #[test]
+    fn test_map_station_history_converts_accumulated_precipitation() {
+        let readings = map_station_history("wxm_station_1", &sample_history()).unwrap();
+
+        // Running totals 1.0 -> 1.5 -> 4.0 become increments 1.0, 0.5, 2.5
+        let precipitation: Vec<Decimal> = readings.iter().filter_map(|r| r.precipitation).collect();
+        assert_eq!(
+            precipitation,
+            vec![
+                Decimal::from_str("1.0").unwrap(),
+                Decimal::from_str("0.5").unwrap(),
+                Decimal::from_str("2.5").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_map_station_history_keeps_raw_payload() {
+        let readings = map_station_history("wxm_station_1", &sample_history()).unwrap();
+
+        let raw = readings[1].raw_data.as_ref().unwrap();
+        assert_eq!(raw["uv_index"], 0);
+        assert_eq!(raw["timestamp"], "2024-07-01T00:10:00+00:00");
+    }
+
+    #[test]
+    fn test_map_station_history_normalises_timezone() {
+        let readings = map_station_history("wxm_station_1", &sample_history()).unwrap();
+
+        assert_eq!(
+            readings[2].recorded_at,
+            PrimitiveDateTime::new(
+                Date::from_calendar_date(2024, time::Month::July, 1).unwrap(),
+                time::Time::from_hms(0, 15, 0).unwrap(),
+            )
+        );
+    }
+
+    #[test]
+    fn test_map_station_history_rejects_bad_timestamp() {
+        let history: StationHistory = serde_json::from_value(serde_json::json!({
+            "observations": [{"timestamp": "yesterday"}]
+        }))
+        .unwrap();
+
+        let result = map_station_history("wxm_station_1", &history);
+        assert!(matches!(result, Err(WeatherXmError::ParseError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_run_once_ingests_active_policy_stations() {
+        let test_db = create_test_db().await;
+        let base_url = spawn_mock_weatherxm_server("test_api_key").await;
+
+        let user_id = create_test_user_for_policies(&test_db.pool).await;
+
+        for station in ["wxm_station_1", "unknown_station"] {
+            policy_queries::create_insurance_policy(
+                &test_db.pool,
+                &CreateInsurancePolicy {
+                    policy_name: format!("Policy for {}", station),
+                    coverage_amount: Decimal::from_str("100.00").unwrap(),
+                    premium_amount: Decimal::from_str("5.00").unwrap(),
+                    start_date: PrimitiveDateTime::new(
+                        Date::from_calendar_date(2024, time::Month::January, 1).unwrap(),
+                        time::Time::from_hms(0, 0, 0).unwrap(),
+                    ),
+                    end_date: PrimitiveDateTime::new(
+                        Date::from_calendar_date(2024, time::Month::December, 31).unwrap(),
+                        time::Time::from_hms(23, 59, 59).unwrap(),
+                    ),
+                    weather_station_id: Some(station.to_string()),
+                    ..test_policy_data(user_id)
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let client = HttpWeatherXmClient::new(WeatherXmConfig {
+            api_url: base_url,
+            api_key: Some("test_api_key".to_string()),
+            timeout_seconds: 5,
+        })
+        .unwrap();
+        let service = WeatherIngestionService::new(
+            test_db.pool.clone(),
+            client,
+            IngestionConfig {
+                enabled: true,
+                poll_interval_seconds: 60,
+                lookback_days: 1,
+                ..IngestionConfig::default()
+            },
+        );
+
+        let today = Date::from_calendar_date(2024, time::Month::July, 2).unwrap();
+        let report = service.run_once(today).await.unwrap();
+
+        // The mock server returns 2 observations per day; lookback covers July 1 and 2
+        assert_eq!(report.stations_polled, 2);
+        assert_eq!(report.observations_upserted, 4);
+        assert_eq!(report.failed_stations, vec!["unknown_station".to_string()]);
+
+        let stored = policy_queries::get_weather_data_by_station_and_date_range(
+            &test_db.pool,
+            "wxm_station_1",
+            &PrimitiveDateTime::new(
+                Date::from_calendar_date(2024, time::Month::July, 1).unwrap(),
+                time::Time::MIDNIGHT,
+            ),
+            &PrimitiveDateTime::new(today, time::Time::from_hms(23, 59, 59).unwrap()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stored.len(), 4);
+        assert!(stored.iter().all(|r| r.raw_data.is_some()));
+
+        // A second pass re-fetches only the latest day and upserts in place
+        let report = service.run_once(today).await.unwrap();
+        assert_eq!(report.observations_upserted, 2);
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM weather_data")
+            .fetch_one(&test_db.pool)
+            .await
+            .unwrap();
+        assert_eq!(count, Some(4));
+    }
+
+    #[test]
+    fn test_station_cell() {
+        assert_eq!(
+            station_cell(40.7128, -74.0060).map(|cell| cell.to_string()),
+            Some("872a1072cffffff".to_string())
+        );
+        assert_eq!(station_cell(f64::NAN, 0.0), None);
+    }
+
+    #[tokio::test]
+    async fn test_sync_stations_stores_locations() {
+        let test_db = create_test_db().await;
+        let base_url = spawn_mock_weatherxm_server("test_api_key").await;
+        let client = HttpWeatherXmClient::new(WeatherXmConfig {
+            api_url: base_url,
+            api_key: Some("test_api_key".to_string()),
+            timeout_seconds: 5,
+        })
+        .unwrap();
+        let service =
+            WeatherIngestionService::new(test_db.pool.clone(), client, IngestionConfig::default());
+
+        assert_eq!(service.sync_stations().await.unwrap(), 1);
+        // Syncing again updates the station in place
+        assert_eq!(service.sync_stations().await.unwrap(), 1);
+
+        let stations = policy_queries::get_weather_stations_by_h3_indexes(
+            &test_db.pool,
+            &["872a1072cffffff".to_string()],
+        )
+        .await
+        .unwrap();
+        assert_eq!(stations.len(), 1);
+        assert_eq!(stations[0].id, "wxm_station_1");
+        assert_eq!(stations[0].latitude, Decimal::from_str("40.7128").unwrap());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod ingestion_service;
+pub mod weatherxm_client;
+
+pub use ingestion_service::*;
+pub use weatherxm_client::*;
@@ -0,0 +1,280 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use time::Date;
+
+// Error types for WeatherXM API access
+#[derive(Debug, thiserror::Error)]
+pub enum WeatherXmError {
+    #[error("Station not found: {0}")]
+    StationNotFound(String),
+    #[error("Unexpected response status {status}: {body}")]
+    UnexpectedStatus { status: u16, body: String },
+    #[error("Network error: {0}")]
+    NetworkError(String),
+    #[error("Parse error: {0}")]
+    ParseError(String),
+}
+
+// Configuration for the WeatherXM API client
+#[derive(Debug, Clone)]
+pub struct WeatherXmConfig {
+    pub api_url: String,
+    pub api_key: Option<String>,
+    pub timeout_seconds: u64,
+}
+
+impl Default for WeatherXmConfig {
+    fn default() -> Self {
+        Self {
+            api_url: "https://pro.weatherxm.com".to_string(),
+            api_key: None,
+            timeout_seconds: 30,
+        }
+    }
+}
+
+impl WeatherXmConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            api_url: std::env::var("WEATHERXM_API_URL").unwrap_or(defaults.api_url),
+            api_key: std::env::var("WEATHERXM_API_KEY").ok(),
+            timeout_seconds: std::env::var("WEATHERXM_TIMEOUT_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.timeout_seconds),
+        }
+    }
+}
+
+// One day of observations for a station, as returned by
+// GET /api/v1/stations/{station_id}/history?date=YYYY-MM-DD
+//
+// Observations are kept as raw JSON so the full payload can be stored in
+// weather_data.raw_data; see `WeatherXmObservation` for the typed view.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StationHistory {
+    pub date: Option<String>,
+    pub health: Option<serde_json::Value>,
+    #[serde(default)]
+    pub observations: Vec<serde_json::Value>,
+}
+
+impl StationHistory {
+    // Data quality score reported for the day, scaled from 0.0-1.0 to 0-100
+    pub fn quality_score(&self) -> Option<i32> {
+        self.health
+            .as_ref()?
+            .pointer("/data_quality/score")?
+            .as_f64()
+            .map(|score| (score * 100.0).round() as i32)
+    }
+}
+
+// Typed view of the observation fields we store. Units follow WeatherXM:
+// temperature in celsius, wind speed in m/s, precipitation in mm, pressure in hPa.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WeatherXmObservation {
+    pub timestamp: String,
+    pub temperature: Option<f64>,
+    pub humidity: Option<f64>,
+    pub precipitation_rate: Option<f64>,
+    pub precipitation_accumulated: Option<f64>,
+    pub wind_speed: Option<f64>,
+    pub wind_direction: Option<f64>,
+    pub pressure: Option<f64>,
+}
+
+// A station as listed by GET /api/v1/stations
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WeatherXmStation {
+    pub id: String,
+    pub location: WeatherXmLocation,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WeatherXmLocation {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+// Abstraction over the WeatherXM HTTP API so the ingestion worker can be
+// pointed at a mock server (or an in-process fake) in tests.
+pub trait WeatherXmClient: Send + Sync {
+    fn get_station_history(
+        &self,
+        station_id: &str,
+        date: Date,
+    ) -> impl Future<Output = Result<StationHistory, WeatherXmError>> + Send;
+
+    fn get_stations(
+        &self,
+    ) -> impl Future<Output = Result<Vec<WeatherXmStation>, WeatherXmError>> + Send;
+}
+
+pub struct HttpWeatherXmClient {
+    client: reqwest::Client,
+    config: WeatherXmConfig,
+}
+
+impl HttpWeatherXmClient {
+    pub fn new(config: WeatherXmConfig) -> Result<Self, WeatherXmError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .map_err(|e| {
+                WeatherXmError::NetworkError(format!("Failed to build HTTP client: {}", e))
+            })?;
+
+        Ok(Self { client, config })
+    }
+}
+
+impl WeatherXmClient for HttpWeatherXmClient {
+    async fn get_station_history(
+        &self,
+        station_id: &str,
+        date: Date,
+    ) -> Result<StationHistory, WeatherXmError> {
+        // Date's Display impl renders as YYYY-MM-DD, which is what the API expects
+        let date = date.to_string();
+        let url = format!(
+            "{}/api/v1/stations/{}/history",
+            self.config.api_url.trim_end_matches('/'),
+            station_id
+        );
+
+        let mut request = self.client.get(&url).query(&[("date", &date)]);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header("X-API-KEY", api_key);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            WeatherXmError::NetworkError(format!("Failed to fetch station history: {}", e))
+        })?;
+
+        match response.status() {
+            StatusCode::OK => response.json::<StationHistory>().await.map_err(|e| {
+                WeatherXmError::ParseError(format!("Invalid station history payload: {}", e))
+            }),
+            StatusCode::NOT_FOUND => Err(WeatherXmError::StationNotFound(station_id.to_string())),
+            status => Err(WeatherXmError::UnexpectedStatus {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            }),
+        }
+    }
+
+    async fn get_stations(&self) -> Result<Vec<WeatherXmStation>, WeatherXmError> {
+        let url = format!(
+            "{}/api/v1/stations",
+            self.config.api_url.trim_end_matches('/')
+        );
+
+        let mut request = self.client.get(&url);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header("X-API-KEY", api_key);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            WeatherXmError::NetworkError(format!("Failed to fetch stations: {}", e))
+        })?;
+
+        match response.status() {
+            StatusCode::OK => response.json::<Vec<WeatherXmStation>>().await.map_err(|e| {
+                WeatherXmError::ParseError(format!("Invalid station list payload: {}", e))
+            }),
+            status => Err(WeatherXmError::UnexpectedStatus {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::spawn_mock_weatherxm_server;
+
+    fn client_for(api_url: String, api_key: &str) -> HttpWeatherXmClient {
+        HttpWeatherXmClient::new(WeatherXmConfig {
+            api_url,
+            api_key: Some(api_key.to_string()),
+            timeout_seconds: 5,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_quality_score_scaling() {
+        let history: StationHistory = serde_json::from_value(serde_json::json!({
+            "health": {"data_quality": {"score": 0.955}},
+            "observations": []
+        }))
+        .unwrap();
+        assert_eq!(history.quality_score(), Some(96));
+
+        let history: StationHistory =
+            serde_json::from_value(serde_json::json!({"observations": []})).unwrap();
+        assert_eq!(history.quality_score(), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_station_history_success() {
+        let base_url = spawn_mock_weatherxm_server("test_api_key").await;
+        let client = client_for(base_url, "test_api_key");
+
+        let date = Date::from_calendar_date(2024, time::Month::July, 1).unwrap();
+        let history = client
+            .get_station_history("wxm_station_1", date)
+            .await
+            .unwrap();
+
+        assert_eq!(history.date, Some("2024-07-01".to_string()));
+        assert_eq!(history.observations.len(), 2);
+        assert_eq!(
+            history.observations[0]["timestamp"],
+            "2024-07-01T06:00:00+00:00"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_station_history_unknown_station() {
+        let base_url = spawn_mock_weatherxm_server("test_api_key").await;
+        let client = client_for(base_url, "test_api_key");
+
+        let date = Date::from_calendar_date(2024, time::Month::July, 1).unwrap();
+        let result = client.get_station_history("missing", date).await;
+
+        assert!(matches!(result, Err(WeatherXmError::StationNotFound(id)) if id == "missing"));
+    }
+
+    #[tokio::test]
+    async fn test_get_stations() {
+        let base_url = spawn_mock_weatherxm_server("test_api_key").await;
+        let client = client_for(base_url, "test_api_key");
+
+        let stations = client.get_stations().await.unwrap();
+
+        assert_eq!(stations.len(), 1);
+        assert_eq!(stations[0].id, "wxm_station_1");
+        assert_eq!(stations[0].location.lat, 40.7128);
+    }
+
+    #[tokio::test]
+    async fn test_get_station_history_bad_api_key() {
+        let base_url = spawn_mock_weatherxm_server("test_api_key").await;
+        let client = client_for(base_url, "wrong_key");
+
+        let date = Date::from_calendar_date(2024, time::Month::July, 1).unwrap();
+        let result = client.get_station_history("wxm_station_1", date).await;
+
+        assert!(matches!(
+            result,
+            Err(WeatherXmError::UnexpectedStatus { status: 401, .. })
+        ));
+    }
+}
@@ -0,0 +1,326 @@
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use serde_json::json;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{Pool, Postgres};
+use tracing::{error, info, warn};
+
+use crate::db::models::{
+    ForgotPasswordRequest, ResetPasswordRequest, UserTokenPurpose, VerifyEmailRequest,
+};
+use crate::db::{rate_limit_queries, session_queries, token_queries, user_queries};
+use crate::mail::{AppMailer, Email, Mailer, MailerError};
+use crate::web::auth::{generate_secret_token, hash_secret_token};
+use crate::web::password_hashing::PasswordHashError;
+use crate::web::validation::{PasswordPolicy, ValidationErrors, validate_password};
+
+// Email verification and password reset. Both mail the user a link with a
+// single-use token; only its hash is stored.
+
+// Configuration for the emails that verify an address or reset a password
+#[derive(Debug, Clone)]
+pub struct AccountEmailConfig {
+    // Frontend the links in emails point to
+    pub app_url: String,
+    pub verification_ttl_hours: i64,
+    pub password_reset_ttl_minutes: i64,
+    // Refuse password sign-in until the user has verified their email
+    pub require_verified_email: bool,
+}
+
+impl Default for AccountEmailConfig {
+    fn default() -> Self {
+        Self {
+            app_url: "http://localhost:5173".to_string(),
+            verification_ttl_hours: 24,
+            password_reset_ttl_minutes: 60,
+            require_verified_email: true,
+        }
+    }
+}
+
+impl AccountEmailConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            app_url: std::env::var("APP_URL").unwrap_or(defaults.app_url),
+            verification_ttl_hours: std::env::var("EMAIL_VERIFICATION_TTL_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.verification_ttl_hours),
+            password_reset_ttl_minutes: std::env::var("PASSWORD_RESET_TTL_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.password_reset_ttl_minutes),
+            require_verified_email: std::env::var("EMAIL_VERIFICATION_REQUIRED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.require_verified_email),
+        }
+    }
+
+    fn link(&self, path: &str, token: &str) -> String {
+        format!(
+            "{}/{}?token={}",
+            self.app_url.trim_end_matches('/'),
+            path,
+            token
+        )
+    }
+}
+
+// Error types for email verification and password reset
+#[derive(Debug, thiserror::Error)]
+pub enum AccountEmailError {
+    #[error("Invalid or expired token")]
+    InvalidToken,
+    #[error("Validation failed")]
+    Validation(ValidationErrors),
+    #[error("Failed to hash password: {0}")]
+    Hash(#[from] PasswordHashError),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Failed to send email: {0}")]
+    Mail(#[from] MailerError),
+}
+
+impl IntoResponse for AccountEmailError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            AccountEmailError::InvalidToken => StatusCode::BAD_REQUEST,
+            AccountEmailError::Validation(errors) => return errors.into_response(),
+            _ => {
+                error!("Account email flow failed: {}", self);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Something went wrong, please try again" })),
+                )
+                    .into_response();
+            }
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+fn now_utc() -> PrimitiveDateTime {
+    let now = time::OffsetDateTime::now_utc();
+    PrimitiveDateTime::new(now.date(), now.time())
+}
+
+// Mail the user a link confirming they receive mail at `email`
+pub async fn send_verification_email(
+    pool: &Pool<Postgres>,
+    mailer: &impl Mailer,
+    user_id: i32,
+    email: &str,
+) -> Result<(), AccountEmailError> {
+    let config = AccountEmailConfig::from_env();
+    let token = generate_secret_token();
+    token_queries::create_user_token(
+        pool,
+        user_id,
+        UserTokenPurpose::VerifyEmail,
+        &hash_secret_token(&token),
+        Some(email),
+        now_utc() + time::Duration::hours(config.verification_ttl_hours),
+    )
+    .await?;
+
+    mailer
+        .send(&Email {
+            to: email.to_string(),
+            subject: "Verify your email address".to_string(),
+            body: format!(
+                "Confirm this is your email address by opening the link below. \
+                 It expires in {} hours.\n\n{}\n\n\
+                 If you did not create a Weather Boyz account, you can ignore this email.",
+                config.verification_ttl_hours,
+                config.link("verify-email", &token)
+            ),
+        })
+        .await?;
+    info!("Sent verification email to user id: {}", user_id);
+    Ok(())
+}
+
+async fn send_password_reset_email(
+    pool: &Pool<Postgres>,
+    mailer: &impl Mailer,
+    user_id: i32,
+    email: &str,
+) -> Result<(), AccountEmailError> {
+    let config = AccountEmailConfig::from_env();
+    let token = generate_secret_token();
+    token_queries::create_user_token(
+        pool,
+        user_id,
+        UserTokenPurpose::ResetPassword,
+        &hash_secret_token(&token),
+        Some(email),
+        now_utc() + time::Duration::minutes(config.password_reset_ttl_minutes),
+    )
+    .await?;
+
+    mailer
+        .send(&Email {
+            to: email.to_string(),
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Choose a new password by opening the link below. \
+                 It expires in {} minutes and can only be used once.\n\n{}\n\n\
+                 If you did not ask to reset your password, you can ignore this email.",
+                config.password_reset_ttl_minutes,
+                config.link("reset-password", &token)
+            ),
+        })
+        .await?;
+    info!("Sent password reset email to user id: {}", user_id);
+    Ok(())
+}
+
+// POST /auth/verify-email
+pub async fn verify_email(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Json(request): Json<VerifyEmailRequest>,
+) -> Result<StatusCode, AccountEmailError> {
+    let mut tx = pool.begin().await?;
+    let token = token_queries::consume_user_token(
+        &mut *tx,
+        &hash_secret_token(&request.token),
+        UserTokenPurpose::VerifyEmail,
+    )
+    .await?
+    .ok_or(AccountEmailError::InvalidToken)?;
+    let email = token.email.ok_or(AccountEmailError::InvalidToken)?;
+
+    // The token is for an address the user has since changed
+    if !user_queries::mark_email_verified(&mut *tx, token.user_id, &email).await? {
+        return Err(AccountEmailError::InvalidToken);
+    }
+    tx.commit().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// POST /auth/forgot-password. Answers the same, and as quickly, whether or
+// not the account exists, so it can't be used to find out who has one: the
+// account is looked up and mailed after the response is sent.
+pub async fn forgot_password(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(mailer): Extension<AppMailer>,
+    Json(request): Json<ForgotPasswordRequest>,
+) -> StatusCode {
+    tokio::spawn(async move {
+        if let Err(e) = request_password_reset(&pool, &mailer, &request.email).await {
+            error!("Failed to handle password reset request: {}", e);
+        }
+    });
+    StatusCode::ACCEPTED
+}
+
+async fn request_password_reset(
+    pool: &Pool<Postgres>,
+    mailer: &AppMailer,
+    email: &str,
+) -> Result<(), AccountEmailError> {
+    let user = user_queries::retrieve_user_by_email(pool, email).await?;
+    match user {
+        Some(user) if user.password_hash.is_some() => {
+            if let Some(email) = &user.email
+                && let Err(e) = send_password_reset_email(pool, mailer, user.id, email).await
+            {
+                error!(
+                    "Failed to send password reset email to user id {}: {}",
+                    user.id, e
+                );
+            }
+        }
+        Some(user) => {
+            warn!(
+                "Password reset requested for user id {} who has no password",
+                user.id
+            );
+        }
+        None => {
+            info!("Password reset requested for unknown email");
+        }
+    }
+    Ok(())
+}
+
+// POST /auth/reset-password. Receiving the link proves the user owns the
+// address, so it also verifies their email. Every session is signed out.
+pub async fn reset_password(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, AccountEmailError> {
+    let mut tx = pool.begin().await?;
+    let token = token_queries::consume_user_token(
+        &mut *tx,
+        &hash_secret_token(&request.token),
+        UserTokenPurpose::ResetPassword,
+    )
+    .await?
+    .ok_or(AccountEmailError::InvalidToken)?;
+
+    // Checked once the token is known to be good, as the policy needs the
+    // account's email. Returning early rolls back, so the token stays usable.
+    validate_password(
+        &request.password,
+        token.email.as_deref(),
+        &PasswordPolicy::from_env(),
+    )
+    .map_err(AccountEmailError::Validation)?;
+    let password_hash = user_queries::hash_password(&request.password)?;
+
+    user_queries::update_password_hash(&mut *tx, token.user_id, &password_hash).await?;
+    if let Some(email) = &token.email {
+        user_queries::mark_email_verified(&mut *tx, token.user_id, email).await?;
+    }
+    session_queries::revoke_user_sessions(&mut *tx, token.user_id).await?;
+    tx.commit().await?;
+
+    if let Err(e) = rate_limit_queries::clear_failed_logins(&pool, token.user_id).await {
+        error!(
+            "Failed to clear failed sign-ins of user id {}: {}",
+            token.user_id, e
+        );
+    }
+    info!("Reset password of user id: {}", token.user_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_joins_app_url_and_token() {
+        let config = AccountEmailConfig {
+            app_url: "https://app.example.com/".to_string(),
+            ..AccountEmailConfig::default()
+        };
+
+        assert_eq!(
+            config.link("verify-email", "abc_123"),
+            "https://app.example.com/verify-email?token=abc_123"
+        );
+    }
+
+    #[test]
+    fn test_error_statuses() {
+        assert_eq!(
+            AccountEmailError::InvalidToken.into_response().status(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            AccountEmailError::Validation(ValidationErrors::single("password", "too_short", ""))
+                .into_response()
+                .status(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            AccountEmailError::Mail(MailerError::InvalidAddress("".to_string()))
+                .into_response()
+                .status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
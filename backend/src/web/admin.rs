@@ -0,0 +1,361 @@
+// Admin API. Every route is behind auth::admin_middleware, and every change is
+// written to the audit log in the same transaction as the change itself.
+use axum::{
+    Extension, Json,
+    extract::{Path, Query},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde_json::json;
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::db::models::{
+    AdminAuditEntry, ClaimRejection, ClaimStatus, CreateAdminAuditEntry, CreatePolicyTemplate,
+    ForcePolicyStatus, PolicyListQuery, UpdatePolicyTemplate, User,
+};
+use crate::db::{admin_queries, policy_queries};
+use crate::policies::{
+    Actor, LifecycleError, ListingError, apply_template_update, force_transition_policy_in,
+    list_all_policies, validate_template,
+};
+
+const AUDIT_LOG_LIMIT: i64 = 200;
+
+async fn audit(
+    conn: &mut PgConnection,
+    admin: &User,
+    action: &str,
+    target_type: &str,
+    target_id: i32,
+    details: serde_json::Value,
+) -> Result<AdminAuditEntry, sqlx::Error> {
+    admin_queries::create_audit_log_entry(
+        conn,
+        &CreateAdminAuditEntry {
+            admin_user_id: admin.id,
+            action: action.to_string(),
+            target_type: target_type.to_string(),
+            target_id,
+            details: Some(details),
+        },
+    )
+    .await
+}
+
+pub async fn create_policy_template(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(admin): Extension<User>,
+    Json(template_data): Json<CreatePolicyTemplate>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_template(&template_data) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let template = policy_queries::create_policy_template(&mut *tx, &template_data).await?;
+        audit(
+            &mut tx,
+            &admin,
+            "create_policy_template",
+            "policy_template",
+            template.id,
+            json!(template),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(template)
+    }
+    .await;
+
+    match result {
+        Ok(template) => (StatusCode::CREATED, Json(template)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to create policy template: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create policy template",
+            )
+                .into_response()
+        }
+    }
+}
+
+pub async fn update_policy_template(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(admin): Extension<User>,
+    Path(template_id): Path<i32>,
+    Json(update): Json<UpdatePolicyTemplate>,
+) -> impl IntoResponse {
+    let template = match policy_queries::get_policy_template_by_id(&pool, template_id).await {
+        Ok(Some(template)) => template,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Policy template not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch policy template {}: {}", template_id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update policy template",
+            )
+                .into_response();
+        }
+    };
+    let changes = json!(update);
+    let updated = apply_template_update(&template, update);
+    if let Err(e) = validate_template(&updated) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let template =
+            policy_queries::update_policy_template(&mut *tx, template_id, &updated).await?;
+        if template.is_some() {
+            audit(
+                &mut tx,
+                &admin,
+                "update_policy_template",
+                "policy_template",
+                template_id,
+                changes,
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(template)
+    }
+    .await;
+
+    match result {
+        Ok(Some(template)) => Json(template).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Policy template not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to update policy template {}: {}", template_id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update policy template",
+            )
+                .into_response()
+        }
+    }
+}
+
+pub async fn deactivate_policy_template(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(admin): Extension<User>,
+    Path(template_id): Path<i32>,
+) -> impl IntoResponse {
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let template =
+            policy_queries::set_policy_template_active(&mut *tx, template_id, false).await?;
+        if template.is_some() {
+            audit(
+                &mut tx,
+                &admin,
+                "deactivate_policy_template",
+                "policy_template",
+                template_id,
+                json!({}),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(template)
+    }
+    .await;
+
+    match result {
+        Ok(Some(template)) => Json(template).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Policy template not found").into_response(),
+        Err(e) => {
+            tracing::error!(
+                "Failed to deactivate policy template {}: {}",
+                template_id,
+                e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to deactivate policy template",
+            )
+                .into_response()
+        }
+    }
+}
+
+pub async fn list_policies(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Query(query): Query<PolicyListQuery>,
+) -> impl IntoResponse {
+    match list_all_policies(&pool, &query).await {
+        Ok(page) => Json(page).into_response(),
+        Err(ListingError::Database(e)) => {
+            tracing::error!("Failed to list policies: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch policies",
+            )
+                .into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+// Set a policy's status by hand, even where the lifecycle would not allow it
+pub async fn force_policy_status(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(admin): Extension<User>,
+    Path(policy_id): Path<i32>,
+    Json(request): Json<ForcePolicyStatus>,
+) -> impl IntoResponse {
+    if request.reason.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "A reason is required").into_response();
+    }
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let change = force_transition_policy_in(
+            &mut tx,
+            policy_id,
+            request.status,
+            Actor::Admin(admin.id),
+            request.reason.trim(),
+        )
+        .await?;
+        audit(
+            &mut tx,
+            &admin,
+            "force_policy_status",
+            "insurance_policy",
+            policy_id,
+            json!({
+                "from_status": change.from_status,
+                "to_status": change.to_status,
+                "reason": change.reason,
+            }),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok::<_, LifecycleError>(change)
+    }
+    .await;
+
+    match result {
+        Ok(change) => Json(change).into_response(),
+        Err(LifecycleError::PolicyNotFound(_)) => {
+            (StatusCode::NOT_FOUND, "Policy not found").into_response()
+        }
+        Err(LifecycleError::IllegalTransition { from, .. }) => {
+            (StatusCode::CONFLICT, format!("Policy is already {}", from)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to force status of policy {}: {}", policy_id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to change policy status",
+            )
+                .into_response()
+        }
+    }
+}
+
+pub async fn approve_claim(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(admin): Extension<User>,
+    Path(claim_id): Path<i32>,
+) -> impl IntoResponse {
+    review_claim(&pool, &admin, claim_id, ClaimStatus::Approved, None).await
+}
+
+pub async fn reject_claim(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(admin): Extension<User>,
+    Path(claim_id): Path<i32>,
+    Json(rejection): Json<ClaimRejection>,
+) -> impl IntoResponse {
+    let reason = rejection.rejection_reason.trim();
+    if reason.is_empty() {
+        return (StatusCode::BAD_REQUEST, "A rejection_reason is required").into_response();
+    }
+    review_claim(&pool, &admin, claim_id, ClaimStatus::Rejected, Some(reason)).await
+}
+
+// Error types for reviewing claims
+#[derive(Debug, thiserror::Error)]
+enum ReviewError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Claim not found")]
+    NotFound,
+    #[error("Only pending claims can be reviewed, this one is {0}")]
+    NotPending(ClaimStatus),
+}
+
+// Approve or reject a pending claim
+async fn review_claim(
+    pool: &Pool<Postgres>,
+    admin: &User,
+    claim_id: i32,
+    status: ClaimStatus,
+    rejection_reason: Option<&str>,
+) -> axum::response::Response {
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let claim = policy_queries::lock_claim(&mut *tx, claim_id)
+            .await?
+            .ok_or(ReviewError::NotFound)?;
+        let current = claim.claim_status.unwrap_or(ClaimStatus::Pending);
+        if current != ClaimStatus::Pending {
+            return Err(ReviewError::NotPending(current));
+        }
+
+        policy_queries::update_claim_status(&mut *tx, claim_id, status, rejection_reason).await?;
+        let action = match status {
+            ClaimStatus::Approved => "approve_claim",
+            _ => "reject_claim",
+        };
+        audit(
+            &mut tx,
+            admin,
+            action,
+            "policy_claim",
+            claim_id,
+            json!({
+                "policy_id": claim.policy_id,
+                "rejection_reason": rejection_reason,
+            }),
+        )
+        .await?;
+        let claim = policy_queries::lock_claim(&mut *tx, claim_id)
+            .await?
+            .ok_or(ReviewError::NotFound)?;
+        tx.commit().await?;
+        Ok(claim)
+    }
+    .await;
+
+    match result {
+        Ok(claim) => Json(claim).into_response(),
+        Err(ReviewError::NotFound) => (StatusCode::NOT_FOUND, "Claim not found").into_response(),
+        Err(e @ ReviewError::NotPending(_)) => {
+            (StatusCode::CONFLICT, e.to_string()).into_response()
+        }
+        Err(ReviewError::Database(e)) => {
+            tracing::error!("Failed to review claim {}: {}", claim_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to review claim").into_response()
+        }
+    }
+}
+
+pub async fn get_audit_log(Extension(pool): Extension<Pool<Postgres>>) -> impl IntoResponse {
+    match admin_queries::get_audit_log(&pool, AUDIT_LOG_LIMIT).await {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch admin audit log: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch audit log",
+            )
+                .into_response()
+        }
+    }
+}
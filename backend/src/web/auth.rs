@@ -6,23 +6,35 @@ use axum::{
     middleware::Next,
     response::IntoResponse,
 };
-use bcrypt::{DEFAULT_COST, hash, verify};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use bcrypt::{DEFAULT_COST, hash};
 use chrono::{Duration, Utc};
+use jsonwebtoken::errors::ErrorKind;
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, TokenData, Validation, decode, encode};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::types::time::PrimitiveDateTime;
 use sqlx::{Pool, Postgres};
 use tracing::{debug, error, info, warn};
 
-use crate::db::models::{SignInData, User};
-use crate::db::user_queries;
+use crate::db::models::{RefreshTokenRequest, SignInData, User, UserRole};
+use crate::db::{rate_limit_queries, session_queries, user_queries};
+use crate::web::account::AccountEmailConfig;
+use crate::web::password_hashing::{PasswordHashError, PasswordHashing};
+use crate::web::rate_limit::LoginLockoutConfig;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 // Define a structure for holding claims data used in JWT tokens
 pub struct Claims {
     pub exp: usize,    // Expiry time of the token
     pub iat: usize,    // Issued at time of the token
     pub email: String, // Email associated with the token
+    pub sub: String,   // Id of the user the token was issued to
+    pub jti: String,   // Unique id of this token
+    pub sid: i32,      // Session the token belongs to
 }
 
 #[derive(Serialize, Deserialize)]
@@ -30,19 +42,125 @@ pub struct SignInResponse {
     pub success: bool,
     pub message: String,
     pub token: String,
+    pub refresh_token: String,
 }
 
+// Configuration for access and refresh tokens
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    pub access_token_ttl_minutes: i64,
+    pub refresh_token_ttl_days: i64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            access_token_ttl_minutes: 120,
+            refresh_token_ttl_days: 30,
+        }
+    }
+}
+
+impl SessionConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            access_token_ttl_minutes: std::env::var("ACCESS_TOKEN_TTL_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.access_token_ttl_minutes),
+            refresh_token_ttl_days: std::env::var("REFRESH_TOKEN_TTL_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.refresh_token_ttl_days),
+        }
+    }
+}
+
+// The tokens handed out when a session starts or is refreshed
+pub struct SessionTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+// Name of the cookie an access token can be sent in instead of the
+// Authorization header
+pub const ACCESS_TOKEN_COOKIE: &str = "access_token";
+
+// Header a request authenticated by the cookie must carry unless its method
+// is safe. A cross-site form can't set it, and a cross-site script can only
+// set it after a CORS preflight, which fails for requests with cookies.
+pub const CSRF_HEADER: &str = "x-requested-with";
+
+// What went wrong authenticating a request, so clients can tell whether to
+// refresh their token or sign in again
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthErrorCode {
+    // No token in the Authorization header or the cookie
+    MissingToken,
+    // Not a `Bearer` token, or not a JWT at all
+    MalformedToken,
+    // A JWT we did not sign
+    InvalidToken,
+    // Past its expiry; the refresh token may still get a new one
+    ExpiredToken,
+    // Its session was revoked or has ended, or the user no longer exists
+    RevokedToken,
+    // Signed in, but not allowed to use this route
+    Forbidden,
+    // Sent with the cookie, changing state, and without the CSRF header
+    MissingCsrfHeader,
+    Internal,
+}
+
+// An authentication failure, returned as a JSON body:
+// {"error": "<message>", "code": "<code>"}
 #[derive(Debug)]
 pub struct AuthError {
+    pub code: AuthErrorCode,
     pub message: String,
     pub status_code: StatusCode,
 }
+
+impl AuthError {
+    pub fn new(code: AuthErrorCode, message: impl Into<String>) -> Self {
+        let status_code = match code {
+            AuthErrorCode::Forbidden | AuthErrorCode::MissingCsrfHeader => StatusCode::FORBIDDEN,
+            AuthErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNAUTHORIZED,
+        };
+        Self {
+            code,
+            message: message.into(),
+            status_code,
+        }
+    }
+
+    fn internal() -> Self {
+        Self::new(AuthErrorCode::Internal, "Authentication failed")
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response<Body> {
+        (
+            self.status_code,
+            Json(json!({
+                "error": self.message,
+                "code": self.code,
+            })),
+        )
+            .into_response()
+    }
+}
+
 // Function to handle sign-in requests with comprehensive error handling
 pub async fn sign_in_handler(
     Extension(pool): Extension<Pool<Postgres>>,
     Json(user_data): Json<SignInData>, // JSON payload containing sign-in data
-) -> Result<Json<SignInResponse>, StatusCode> {
-    // Return type is a JSON-wrapped SignInResponse or an HTTP status code
+) -> Result<Json<SignInResponse>, Response<Body>> {
+    // Return type is a JSON-wrapped SignInResponse or an error response
 
     info!("Sign-in attempt received for email: {}", user_data.email);
     debug!("Sign-in request payload: {:?}", user_data);
@@ -50,12 +168,12 @@ pub async fn sign_in_handler(
     // Validate input data
     if user_data.email.is_empty() {
         error!("Sign-in failed: Empty email provided");
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
 
     if user_data.password.is_empty() {
         error!("Sign-in failed: Empty password provided");
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
 
     // Attempt to retrieve user information based on the provided email
@@ -70,20 +188,48 @@ pub async fn sign_in_handler(
                 "Sign-in failed: User not found for email: {}",
                 user_data.email
             );
-            return Err(StatusCode::UNAUTHORIZED); // User not found, return unauthorized status
+            return Err(StatusCode::UNAUTHORIZED.into_response()); // User not found, return unauthorized status
         }
         Err(e) => {
             error!(
                 "Sign-in failed: Database error while retrieving user {}: {:?}",
                 user_data.email, e
             );
-            return Err(StatusCode::INTERNAL_SERVER_ERROR); // Database error
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()); // Database error
         }
     };
 
+    // Refuse to check passwords of an account locked by failed sign-ins. The
+    // response is the same as for an unknown email, so a lockout does not
+    // reveal that the account exists.
+    match rate_limit_queries::get_login_locked_until(&pool, user.id).await {
+        Ok(Some(locked_until)) => {
+            warn!(
+                "Sign-in failed: account {} is locked until {}",
+                user_data.email, locked_until
+            );
+            return Err(StatusCode::UNAUTHORIZED.into_response());
+        }
+        Ok(None) => {}
+        Err(e) => {
+            error!(
+                "Sign-in failed: Database error while checking lockout of {}: {:?}",
+                user_data.email, e
+            );
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    }
+
     // Verify the password provided against the stored hash
     info!("Verifying password for user: {}", user_data.email);
-    let password_verification = verify_password(&user_data.password, &user.password_hash);
+    let Some(password_hash) = &user.password_hash else {
+        error!(
+            "Sign-in failed: User {} has no password and signs in with their wallet",
+            user_data.email
+        );
+        return Err(StatusCode::UNAUTHORIZED.into_response());
+    };
+    let password_verification = verify_password(&user_data.password, password_hash);
     match password_verification {
         Ok(is_valid) => {
             if is_valid {
@@ -91,12 +237,26 @@ pub async fn sign_in_handler(
                     "Password verification successful for user: {}",
                     user_data.email
                 );
+                rehash_password_if_outdated(&pool, user.id, &user_data.password, password_hash)
+                    .await;
+                if let Err(e) = rate_limit_queries::clear_failed_logins(&pool, user.id).await {
+                    error!(
+                        "Failed to clear failed sign-ins of {}: {:?}",
+                        user_data.email, e
+                    );
+                }
             } else {
                 error!(
                     "Sign-in failed: Invalid password for user: {}",
                     user_data.email
                 );
-                return Err(StatusCode::UNAUTHORIZED); // Password verification failed, return unauthorized status
+                if let Err(e) = record_failed_login(&pool, user.id).await {
+                    error!(
+                        "Failed to record failed sign-in of {}: {:?}",
+                        user_data.email, e
+                    );
+                }
+                return Err(StatusCode::UNAUTHORIZED.into_response()); // Password verification failed, return unauthorized status
             }
         }
         Err(e) => {
@@ -104,47 +264,226 @@ pub async fn sign_in_handler(
                 "Sign-in failed: Password verification error for user {}: {:?}",
                 user_data.email, e
             );
-            return Err(StatusCode::INTERNAL_SERVER_ERROR); // Handle bcrypt errors
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()); // Handle hashing errors
         }
     }
 
-    // Generate a JWT token for the authenticated user
-    info!("Generating JWT token for user: {}", user_data.email);
-    let token = match encode_jwt(user.email.clone()) {
-        Ok(token) => {
-            info!(
-                "JWT token generated successfully for user: {}",
-                user_data.email
-            );
-            token
+    if user.email_verified_at.is_none() && AccountEmailConfig::from_env().require_verified_email {
+        warn!(
+            "Sign-in failed: email of user {} is not verified",
+            user_data.email
+        );
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "Verify your email address before signing in",
+                "code": "email_not_verified",
+            })),
+        )
+            .into_response());
+    }
+
+    // Start a session and generate its tokens for the authenticated user
+    info!("Starting session for user: {}", user_data.email);
+    let tokens = match start_session(&pool, &user).await {
+        Ok(tokens) => {
+            info!("Session started successfully for user: {}", user_data.email);
+            tokens
         }
         Err(e) => {
             error!(
-                "Sign-in failed: JWT encoding error for user {}: {:?}",
+                "Sign-in failed: could not start a session for user {}: {:?}",
                 user_data.email, e
             );
-            return Err(StatusCode::INTERNAL_SERVER_ERROR); // Handle JWT encoding errors
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
         }
     };
 
     info!("Sign-in successful for user: {}", user_data.email);
-    // Return the response as a JSON object with success, message, and token
+    // Return the response as a JSON object with success, message, and tokens
     Ok(Json(SignInResponse {
         success: true,
         message: "Login successful".to_string(),
+        token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+    }))
+}
+
+// Replace a hash made by an older hasher or with older settings. If that
+// fails the old hash keeps working, and we try again on the next sign-in.
+async fn rehash_password_if_outdated(
+    pool: &Pool<Postgres>,
+    user_id: i32,
+    password: &str,
+    password_hash: &str,
+) {
+    let hashing = match PasswordHashing::from_env() {
+        Ok(hashing) => hashing,
+        Err(e) => {
+            error!("Invalid password hashing settings: {:?}", e);
+            return;
+        }
+    };
+    if !hashing.needs_rehash(password_hash) {
+        return;
+    }
+    let new_hash = match hashing.hash(password) {
+        Ok(new_hash) => new_hash,
+        Err(e) => {
+            error!("Failed to rehash password of user id {}: {:?}", user_id, e);
+            return;
+        }
+    };
+    match user_queries::replace_password_hash(pool, user_id, password_hash, &new_hash).await {
+        Ok(true) => info!("Rehashed password of user id {}", user_id),
+        // The password was changed since it was checked; keep the new one
+        Ok(false) => {}
+        Err(e) => error!(
+            "Failed to save rehashed password of user id {}: {:?}",
+            user_id, e
+        ),
+    }
+}
+
+// Count a failed sign-in, locking the account once there have been too many
+async fn record_failed_login(pool: &Pool<Postgres>, user_id: i32) -> Result<(), sqlx::Error> {
+    let failed_attempts = rate_limit_queries::record_failed_login(pool, user_id).await?;
+    if let Some(duration) = LoginLockoutConfig::from_env().lockout_duration(failed_attempts) {
+        rate_limit_queries::lock_login(pool, user_id, now_utc() + duration).await?;
+    }
+    Ok(())
+}
+
+fn now_utc() -> PrimitiveDateTime {
+    let now = time::OffsetDateTime::now_utc();
+    PrimitiveDateTime::new(now.date(), now.time())
+}
+
+// 32 random bytes, URL-safe base64 encoded
+pub fn generate_secret_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Refresh tokens and the tokens mailed to users are only stored hashed. They
+// are random, so a fast hash is enough.
+pub fn hash_secret_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+// Record a new session for the user and issue its first tokens
+pub async fn start_session(
+    pool: &Pool<Postgres>,
+    user: &User,
+) -> Result<SessionTokens, StatusCode> {
+    let config = SessionConfig::from_env();
+    let refresh_token = generate_secret_token();
+    let expires_at = now_utc() + time::Duration::days(config.refresh_token_ttl_days);
+
+    let session = session_queries::create_session(
+        pool,
+        user.id,
+        &hash_secret_token(&refresh_token),
+        expires_at,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to create session for user {}: {}", user.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(SessionTokens {
+        access_token: encode_jwt(user.email.clone().unwrap_or_default(), user.id, session.id)?,
+        refresh_token,
+    })
+}
+
+// Exchange a refresh token for a new access token and a new refresh token.
+// Presenting a refresh token that was already exchanged means it has leaked,
+// so the whole session is revoked.
+pub async fn refresh_session(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Json(request): Json<RefreshTokenRequest>,
+) -> Result<Json<SignInResponse>, StatusCode> {
+    let database_error = |e: sqlx::Error| {
+        error!("Token refresh failed: database error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let presented_hash = hash_secret_token(&request.refresh_token);
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    let session = session_queries::lock_session_by_refresh_token_hash(&mut *tx, &presented_hash)
+        .await
+        .map_err(database_error)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if session.refresh_token_hash != presented_hash {
+        warn!(
+            "Refresh token of session {} was reused, revoking the session",
+            session.id
+        );
+        session_queries::revoke_session(&mut *tx, session.id)
+            .await
+            .map_err(database_error)?;
+        tx.commit().await.map_err(database_error)?;
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    if session.revoked_at.is_some() || session.expires_at <= now_utc() {
+        debug!("Refresh attempted on ended session {}", session.id);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let user = user_queries::retrieve_user_by_id(&mut *tx, session.user_id)
+        .await
+        .map_err(database_error)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let refresh_token = generate_secret_token();
+    session_queries::rotate_refresh_token(&mut *tx, session.id, &hash_secret_token(&refresh_token))
+        .await
+        .map_err(database_error)?;
+    let token = encode_jwt(user.email.clone().unwrap_or_default(), user.id, session.id)?;
+    tx.commit().await.map_err(database_error)?;
+
+    info!(
+        "Refreshed session {} for user {}",
+        session.id,
+        user.identifier()
+    );
+    Ok(Json(SignInResponse {
+        success: true,
+        message: "Token refreshed".to_string(),
         token,
+        refresh_token,
     }))
 }
 
+// Revoke the session of the token the request was made with
+pub async fn logout(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+) -> StatusCode {
+    match session_queries::revoke_session(&pool, claims.sid).await {
+        Ok(_) => {
+            info!("User {} signed out of session {}", claims.email, claims.sid);
+            StatusCode::NO_CONTENT
+        }
+        Err(e) => {
+            error!("Failed to revoke session {}: {}", claims.sid, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
 // Legacy function name for backward compatibility
 pub async fn sign_in(
     Extension(pool): Extension<Pool<Postgres>>,
     Json(user_data): Json<SignInData>,
-) -> Result<Json<SignInResponse>, StatusCode> {
+) -> Result<Json<SignInResponse>, Response<Body>> {
     sign_in_handler(Extension(pool), Json(user_data)).await
 }
 
-pub fn encode_jwt(email: String) -> Result<String, StatusCode> {
+pub fn encode_jwt(email: String, user_id: i32, session_id: i32) -> Result<String, StatusCode> {
     debug!("Encoding JWT token for email: {}", email);
 
     let secret = std::env::var("JWT_SECRET").map_err(|_| {
@@ -153,10 +492,20 @@ pub fn encode_jwt(email: String) -> Result<String, StatusCode> {
     })?;
 
     let now = Utc::now();
-    let expire: chrono::TimeDelta = Duration::hours(2);
+    let expire: chrono::TimeDelta =
+        Duration::minutes(SessionConfig::from_env().access_token_ttl_minutes);
     let exp: usize = (now + expire).timestamp() as usize;
     let iat: usize = now.timestamp() as usize;
-    let claim = Claims { iat, exp, email };
+    let mut jti = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut jti);
+    let claim = Claims {
+        iat,
+        exp,
+        email,
+        sub: user_id.to_string(),
+        jti: hex::encode(jti),
+        sid: session_id,
+    };
 
     let result = encode(
         &Header::default(),
@@ -176,64 +525,145 @@ pub fn encode_jwt(email: String) -> Result<String, StatusCode> {
     }
 }
 
-pub fn decode_jwt(jwt_token: String) -> Result<TokenData<Claims>, StatusCode> {
+pub fn decode_jwt(jwt_token: String) -> Result<TokenData<Claims>, AuthError> {
     let secret = std::env::var("JWT_SECRET").map_err(|_| {
         error!("JWT_SECRET environment variable not set");
-        StatusCode::INTERNAL_SERVER_ERROR
+        AuthError::internal()
     })?;
 
-    let result: Result<TokenData<Claims>, StatusCode> = decode(
+    decode(
         &jwt_token,
         &DecodingKey::from_secret(secret.as_ref()),
         &Validation::default(),
     )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
-    result
+    .map_err(|e| match e.kind() {
+        ErrorKind::ExpiredSignature => {
+            AuthError::new(AuthErrorCode::ExpiredToken, "Token has expired")
+        }
+        ErrorKind::InvalidSignature
+        | ErrorKind::InvalidAlgorithm
+        | ErrorKind::ImmatureSignature => {
+            AuthError::new(AuthErrorCode::InvalidToken, "Token is not valid")
+        }
+        _ => AuthError::new(AuthErrorCode::MalformedToken, "Token is malformed"),
+    })
+}
+
+// The access token of a request: from an `Authorization: Bearer <token>`
+// header, or else from the access token cookie. A header that is present
+// but malformed is an error rather than a reason to try the cookie. Browsers
+// send the cookie with cross-site requests too, so it is only accepted for
+// unsafe methods along with the CSRF header.
+fn extract_token(method: &http::Method, headers: &http::HeaderMap) -> Result<String, AuthError> {
+    if let Some(header) = headers.get(http::header::AUTHORIZATION) {
+        let malformed = || {
+            AuthError::new(
+                AuthErrorCode::MalformedToken,
+                "Authorization header must be `Bearer <token>`",
+            )
+        };
+        let header = header.to_str().map_err(|_| malformed())?;
+        let mut parts = header.split_whitespace();
+        return match (parts.next(), parts.next(), parts.next()) {
+            (Some(scheme), Some(token), None) if scheme.eq_ignore_ascii_case("Bearer") => {
+                Ok(token.to_string())
+            }
+            _ => Err(malformed()),
+        };
+    }
+
+    let token = headers
+        .get_all(http::header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, value)| *name == ACCESS_TOKEN_COOKIE && !value.is_empty())
+        .map(|(_, value)| value.to_string())
+        .ok_or_else(|| AuthError::new(AuthErrorCode::MissingToken, "No access token provided"))?;
+
+    if !method.is_safe() && !headers.contains_key(CSRF_HEADER) {
+        return Err(AuthError::new(
+            AuthErrorCode::MissingCsrfHeader,
+            "Requests authenticated by cookie must send the X-Requested-With header",
+        ));
+    }
+    Ok(token)
 }
 
 pub async fn authorization_middleware(
     mut req: Request,
     next: Next,
-) -> Result<Response<Body>, StatusCode> {
-    let auth_header = req.headers_mut().get(http::header::AUTHORIZATION);
-    let auth_header = match auth_header {
-        Some(header) => header.to_str().map_err(|_| StatusCode::FORBIDDEN)?,
-        None => {
-            return Err(StatusCode::FORBIDDEN);
-        }
-    };
-    let mut header = auth_header.split_whitespace();
-    let (bearer, token) = (header.next(), header.next());
-    let token_data = match decode_jwt(token.unwrap().to_string()) {
-        Ok(data) => data,
-        Err(_) => {
-            return Err(StatusCode::UNAUTHORIZED);
-        }
-    };
+) -> Result<Response<Body>, AuthError> {
+    let token = extract_token(req.method(), req.headers())?;
+    let token_data = decode_jwt(token)?;
 
     // Get the database pool from request extensions
     let pool = req
         .extensions()
         .get::<Pool<Postgres>>()
-        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+        .ok_or_else(AuthError::internal)?;
+
+    // Tokens stop working as soon as their session is revoked
+    let user_id: i32 = token_data.claims.sub.parse().map_err(|_| {
+        AuthError::new(
+            AuthErrorCode::MalformedToken,
+            "Token has an invalid subject",
+        )
+    })?;
+    let revoked = || AuthError::new(AuthErrorCode::RevokedToken, "Session has ended");
+    match session_queries::get_active_session(pool, token_data.claims.sid, user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            debug!("Rejected token for ended session {}", token_data.claims.sid);
+            return Err(revoked());
+        }
+        Err(e) => {
+            error!("Failed to look up session {}: {}", token_data.claims.sid, e);
+            return Err(AuthError::internal());
+        }
+    }
 
     // Fetch the user details from the database
-    let current_user =
-        match user_queries::retrieve_user_by_email(pool, &token_data.claims.email).await {
-            Ok(Some(user)) => user,
-            Ok(None) => {
-                return Err(StatusCode::UNAUTHORIZED);
-            }
-            Err(_) => {
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        };
+    let current_user = match user_queries::retrieve_user_by_id(pool, user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return Err(revoked());
+        }
+        Err(e) => {
+            error!("Failed to look up user {}: {}", user_id, e);
+            return Err(AuthError::internal());
+        }
+    };
     req.extensions_mut().insert(current_user);
+    req.extensions_mut().insert(token_data.claims);
     Ok(next.run(req).await)
 }
 
-pub fn verify_password(password: &str, hash: &str) -> Result<bool, bcrypt::BcryptError> {
-    verify(password, hash)
+// Lets only admins through. Layered inside authorization_middleware, which
+// puts the current user in the request extensions.
+pub async fn admin_middleware(req: Request, next: Next) -> Result<Response<Body>, AuthError> {
+    let user = req.extensions().get::<User>().ok_or_else(|| {
+        error!("admin_middleware ran without authorization_middleware");
+        AuthError::internal()
+    })?;
+    if user.role != UserRole::Admin {
+        warn!(
+            "User {} with role {} tried to use the admin API",
+            user.identifier(),
+            user.role
+        );
+        return Err(AuthError::new(
+            AuthErrorCode::Forbidden,
+            "Only admins can use this route",
+        ));
+    }
+
+    Ok(next.run(req).await)
+}
+
+pub fn verify_password(password: &str, hash: &str) -> Result<bool, PasswordHashError> {
+    PasswordHashing::from_env()?.verify(password, hash)
 }
 
 #[cfg(test)]
@@ -309,7 +739,7 @@ mod tests {
         let email = "test@example.com".to_string();
 
         // Test encoding
-        let token_result = encode_jwt(email.clone());
+        let token_result = encode_jwt(email.clone(), 7, 3);
         assert!(token_result.is_ok());
 
         let token = token_result.unwrap();
@@ -337,11 +767,14 @@ mod tests {
         let email = "claims@test.com".to_string();
         let before_encoding = Utc::now().timestamp() as usize;
 
-        let token = encode_jwt(email.clone()).unwrap();
+        let token = encode_jwt(email.clone(), 7, 3).unwrap();
         let token_data = decode_jwt(token).unwrap();
 
-        // Check email claim
+        // Check email, user and session claims
         assert_eq!(token_data.claims.email, email);
+        assert_eq!(token_data.claims.sub, "7");
+        assert_eq!(token_data.claims.sid, 3);
+        assert_eq!(token_data.claims.jti.len(), 32);
 
         // Check issued at time (should be around now)
         assert!(token_data.claims.iat >= before_encoding);
@@ -360,7 +793,7 @@ mod tests {
         }
 
         let email = "test@example.com".to_string();
-        let result = encode_jwt(email);
+        let result = encode_jwt(email, 7, 3);
 
         // Should fail without JWT_SECRET
         assert!(result.is_err());
@@ -377,7 +810,9 @@ mod tests {
         let result = decode_jwt(invalid_token);
 
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), StatusCode::INTERNAL_SERVER_ERROR);
+        let error = result.unwrap_err();
+        assert_eq!(error.code, AuthErrorCode::MalformedToken);
+        assert_eq!(error.status_code, StatusCode::UNAUTHORIZED);
 
         unsafe {
             env::remove_var("JWT_SECRET");
@@ -396,7 +831,10 @@ mod tests {
 
         // Should fail without JWT_SECRET
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            result.unwrap_err().status_code,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
     }
 
     #[test]
@@ -406,7 +844,7 @@ mod tests {
         }
 
         let empty_email = "".to_string();
-        let token_result = encode_jwt(empty_email.clone());
+        let token_result = encode_jwt(empty_email.clone(), 7, 3);
 
         // Should succeed with empty email (validation happens elsewhere)
         assert!(token_result.is_ok());
@@ -420,4 +858,143 @@ mod tests {
             env::remove_var("JWT_SECRET");
         }
     }
+
+    #[test]
+    fn test_decode_expired_and_foreign_tokens() {
+        unsafe {
+            env::set_var("JWT_SECRET", "test_secret_for_expired_tokens");
+        }
+
+        let claims = |exp: usize| Claims {
+            exp,
+            iat: 0,
+            email: "expired@example.com".to_string(),
+            sub: "7".to_string(),
+            jti: "0".repeat(32),
+            sid: 3,
+        };
+        let expired = encode(
+            &Header::default(),
+            &claims(1),
+            &EncodingKey::from_secret("test_secret_for_expired_tokens".as_ref()),
+        )
+        .unwrap();
+        assert_eq!(
+            decode_jwt(expired).unwrap_err().code,
+            AuthErrorCode::ExpiredToken
+        );
+
+        let foreign = encode(
+            &Header::default(),
+            &claims(usize::MAX / 2),
+            &EncodingKey::from_secret("some_other_secret".as_ref()),
+        )
+        .unwrap();
+        assert_eq!(
+            decode_jwt(foreign).unwrap_err().code,
+            AuthErrorCode::InvalidToken
+        );
+
+        unsafe {
+            env::remove_var("JWT_SECRET");
+        }
+    }
+
+    #[test]
+    fn test_extract_token() {
+        let headers = |pairs: &[(http::HeaderName, &str)]| {
+            let mut headers = http::HeaderMap::new();
+            for (name, value) in pairs {
+                headers.append(name.clone(), value.parse().unwrap());
+            }
+            headers
+        };
+        let get = http::Method::GET;
+        let code = |headers: http::HeaderMap| extract_token(&get, &headers).unwrap_err().code;
+
+        assert_eq!(
+            extract_token(
+                &get,
+                &headers(&[(http::header::AUTHORIZATION, "Bearer abc.def")])
+            )
+            .unwrap(),
+            "abc.def"
+        );
+        assert_eq!(
+            extract_token(
+                &get,
+                &headers(&[(http::header::AUTHORIZATION, "bearer abc.def")])
+            )
+            .unwrap(),
+            "abc.def"
+        );
+        assert_eq!(
+            extract_token(
+                &get,
+                &headers(&[(http::header::COOKIE, "theme=dark; access_token=abc.def")])
+            )
+            .unwrap(),
+            "abc.def"
+        );
+
+        assert_eq!(code(headers(&[])), AuthErrorCode::MissingToken);
+        assert_eq!(
+            code(headers(&[(http::header::COOKIE, "access_token=")])),
+            AuthErrorCode::MissingToken
+        );
+        assert_eq!(
+            code(headers(&[(http::header::AUTHORIZATION, "Bearer")])),
+            AuthErrorCode::MalformedToken
+        );
+        assert_eq!(
+            code(headers(&[(
+                http::header::AUTHORIZATION,
+                "Basic dXNlcjpwYXNz"
+            )])),
+            AuthErrorCode::MalformedToken
+        );
+        assert_eq!(
+            code(headers(&[(http::header::AUTHORIZATION, "Bearer a b")])),
+            AuthErrorCode::MalformedToken
+        );
+        // A bad header is not bypassed by a cookie
+        assert_eq!(
+            code(headers(&[
+                (http::header::AUTHORIZATION, "Token abc.def"),
+                (http::header::COOKIE, "access_token=abc.def"),
+            ])),
+            AuthErrorCode::MalformedToken
+        );
+
+        // The cookie only changes state along with the CSRF header
+        let post = http::Method::POST;
+        assert_eq!(
+            extract_token(
+                &post,
+                &headers(&[(http::header::COOKIE, "access_token=abc.def")])
+            )
+            .unwrap_err()
+            .code,
+            AuthErrorCode::MissingCsrfHeader
+        );
+        assert_eq!(
+            extract_token(
+                &post,
+                &headers(&[
+                    (http::header::COOKIE, "access_token=abc.def"),
+                    (http::HeaderName::from_static(CSRF_HEADER), "XMLHttpRequest")
+                ])
+            )
+            .unwrap(),
+            "abc.def"
+        );
+        assert_eq!(
+            extract_token(
+                &post,
+                &headers(&[(http::header::AUTHORIZATION, "Bearer abc.def")])
+            )
+            .unwrap(),
+            "abc.def"
+        );
+    }
 }
@@ -0,0 +1,166 @@
+use crate::db::{rate_limit_queries, session_queries};
+use crate::web::rate_limit::RateLimitConfig;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{Pool, Postgres};
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+// Configuration for the cleanup worker, which removes sign-in and rate limit
+// state that is no longer needed
+#[derive(Debug, Clone)]
+pub struct CleanupConfig {
+    pub enabled: bool,
+    pub interval_seconds: u64,
+    // Rate limit buckets unused for this long are removed. None keeps them.
+    pub bucket_idle_time: Option<time::Duration>,
+}
+
+impl Default for CleanupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_seconds: 3600,
+            bucket_idle_time: RateLimitConfig::default().refill_duration(),
+        }
+    }
+}
+
+impl CleanupConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("CLEANUP_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            interval_seconds: std::env::var("CLEANUP_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.interval_seconds),
+            // Full buckets are the same as missing ones
+            bucket_idle_time: RateLimitConfig::from_env().refill_duration(),
+        }
+    }
+}
+
+// Summary of a single cleanup pass
+#[derive(Debug, Default)]
+pub struct CleanupReport {
+    pub expired_nonces: u64,
+    pub idle_buckets: u64,
+}
+
+pub struct Cleanup {
+    pool: Pool<Postgres>,
+    config: CleanupConfig,
+}
+
+impl Cleanup {
+    pub fn new(pool: Pool<Postgres>, config: CleanupConfig) -> Self {
+        Self { pool, config }
+    }
+
+    // Clean up on the configured interval until the task is dropped
+    pub async fn run(self) {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(self.config.interval_seconds));
+
+        loop {
+            interval.tick().await;
+            let now = OffsetDateTime::now_utc();
+            let as_of = PrimitiveDateTime::new(now.date(), now.time());
+            match self.run_once(as_of).await {
+                Ok(report) => info!(
+                    "Cleanup pass complete: {} expired nonces and {} idle rate limit buckets removed",
+                    report.expired_nonces, report.idle_buckets
+                ),
+                Err(e) => error!("Cleanup pass failed: {}", e),
+            }
+        }
+    }
+
+    // Remove the sign-in nonces that expired before `as_of`, and the rate
+    // limit buckets that have been idle for the configured time
+    pub async fn run_once(&self, as_of: PrimitiveDateTime) -> Result<CleanupReport, sqlx::Error> {
+        let mut report = CleanupReport {
+            expired_nonces: session_queries::delete_expired_auth_nonces(&self.pool, as_of).await?,
+            ..CleanupReport::default()
+        };
+        if let Some(idle_time) = self.config.bucket_idle_time {
+            report.idle_buckets =
+                rate_limit_queries::delete_idle_rate_limit_buckets(&self.pool, as_of - idle_time)
+                    .await?;
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_db;
+    use time::Duration;
+
+    #[tokio::test]
+    async fn test_run_once_removes_expired_nonces() {
+        let test_db = create_test_db().await;
+        let pool = &test_db.pool;
+        let now = OffsetDateTime::now_utc();
+        let as_of = PrimitiveDateTime::new(now.date(), now.time());
+
+        session_queries::create_auth_nonce(pool, "expired0", as_of - Duration::minutes(1))
+            .await
+            .unwrap();
+        session_queries::create_auth_nonce(pool, "current0", as_of + Duration::minutes(5))
+            .await
+            .unwrap();
+
+        let cleanup = Cleanup::new(pool.clone(), CleanupConfig::default());
+        let report = cleanup.run_once(as_of).await.unwrap();
+        assert_eq!(report.expired_nonces, 1);
+
+        // The nonce still in date can still be used
+        assert!(
+            session_queries::consume_auth_nonce(pool, "current0")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !session_queries::consume_auth_nonce(pool, "expired0")
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_once_removes_idle_rate_limit_buckets() {
+        let test_db = create_test_db().await;
+        let pool = &test_db.pool;
+        let now = OffsetDateTime::now_utc();
+        let as_of = PrimitiveDateTime::new(now.date(), now.time());
+
+        for (key, last_used) in [
+            ("signin:ip:10.0.0.1", as_of - Duration::minutes(10)),
+            ("signin:ip:10.0.0.2", as_of - Duration::minutes(1)),
+        ] {
+            rate_limit_queries::lock_rate_limit_bucket(pool, key, 0.0, last_used)
+                .await
+                .unwrap();
+        }
+
+        let cleanup = Cleanup::new(
+            pool.clone(),
+            CleanupConfig {
+                bucket_idle_time: Some(Duration::minutes(5)),
+                ..CleanupConfig::default()
+            },
+        );
+        let report = cleanup.run_once(as_of).await.unwrap();
+        assert_eq!(report.idle_buckets, 1);
+
+        let keys = sqlx::query_scalar!("SELECT key FROM rate_limit_buckets")
+            .fetch_all(pool)
+            .await
+            .unwrap();
+        assert_eq!(keys, vec!["signin:ip:10.0.0.2"]);
+    }
+}
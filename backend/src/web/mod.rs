@@ -1,3 +1,11 @@
+pub mod account;
+pub mod admin;
 pub mod auth;
+pub mod cleanup;
+pub mod password_hashing;
+pub mod profile;
+pub mod rate_limit;
 pub mod routes;
 pub mod services;
+pub mod siwe;
+pub mod validation;
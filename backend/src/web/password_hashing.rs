@@ -0,0 +1,311 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::rngs::OsRng;
+
+// Password hashing. New passwords are hashed with the configured hasher, but
+// hashes from any hasher we have used are still accepted, and replaced the
+// next time their owner signs in.
+
+// Error types for password hashing
+#[derive(Debug, thiserror::Error)]
+pub enum PasswordHashError {
+    #[error("bcrypt error: {0}")]
+    Bcrypt(#[from] bcrypt::BcryptError),
+    #[error("Argon2 error: {0}")]
+    Argon2(String),
+    #[error("Unrecognized password hash")]
+    UnknownHash,
+}
+
+impl From<argon2::password_hash::Error> for PasswordHashError {
+    fn from(e: argon2::password_hash::Error) -> Self {
+        PasswordHashError::Argon2(e.to_string())
+    }
+}
+
+impl From<argon2::Error> for PasswordHashError {
+    fn from(e: argon2::Error) -> Self {
+        PasswordHashError::Argon2(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordHashAlgorithm {
+    Argon2id,
+    Bcrypt,
+}
+
+// Configuration for hashing new passwords
+#[derive(Debug, Clone)]
+pub struct PasswordHashConfig {
+    pub algorithm: PasswordHashAlgorithm,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub bcrypt_cost: u32,
+}
+
+impl Default for PasswordHashConfig {
+    fn default() -> Self {
+        // The OWASP recommended Argon2id settings
+        Self {
+            algorithm: PasswordHashAlgorithm::Argon2id,
+            argon2_memory_kib: 19456,
+            argon2_iterations: 2,
+            argon2_parallelism: 1,
+            bcrypt_cost: bcrypt::DEFAULT_COST,
+        }
+    }
+}
+
+impl PasswordHashConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            algorithm: match std::env::var("PASSWORD_HASH_ALGORITHM").as_deref() {
+                Ok("argon2id") => PasswordHashAlgorithm::Argon2id,
+                Ok("bcrypt") => PasswordHashAlgorithm::Bcrypt,
+                _ => defaults.algorithm,
+            },
+            argon2_memory_kib: std::env::var("ARGON2_MEMORY_KIB")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.argon2_memory_kib),
+            argon2_iterations: std::env::var("ARGON2_ITERATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.argon2_iterations),
+            argon2_parallelism: std::env::var("ARGON2_PARALLELISM")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.argon2_parallelism),
+            bcrypt_cost: std::env::var("BCRYPT_COST")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.bcrypt_cost),
+        }
+    }
+}
+
+// One way of hashing passwords
+pub trait PasswordHasher: Send + Sync {
+    // Whether the hash was made by this kind of hasher
+    fn recognizes(&self, hash: &str) -> bool;
+    fn hash(&self, password: &str) -> Result<String, PasswordHashError>;
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, PasswordHashError>;
+    // Whether a recognized hash was made with other settings than this
+    // hasher uses now
+    fn is_outdated(&self, hash: &str) -> bool;
+}
+
+pub struct Argon2idHasher {
+    params: Params,
+}
+
+impl Argon2idHasher {
+    pub fn new(config: &PasswordHashConfig) -> Result<Self, PasswordHashError> {
+        let params = Params::new(
+            config.argon2_memory_kib,
+            config.argon2_iterations,
+            config.argon2_parallelism,
+            None,
+        )?;
+        Ok(Self { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl PasswordHasher for Argon2idHasher {
+    fn recognizes(&self, hash: &str) -> bool {
+        hash.starts_with("$argon2")
+    }
+
+    fn hash(&self, password: &str) -> Result<String, PasswordHashError> {
+        let salt = SaltString::generate(&mut OsRng);
+        Ok(self
+            .argon2()
+            .hash_password(password.as_bytes(), &salt)?
+            .to_string())
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, PasswordHashError> {
+        let parsed = PasswordHash::new(hash)?;
+        // A truncated hash would otherwise just look like a wrong password
+        if parsed.hash.is_none() || parsed.salt.is_none() {
+            return Err(PasswordHashError::Argon2("Hash is incomplete".to_string()));
+        }
+        // The parameters come from the hash, so older settings still verify
+        match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+            Ok(()) => Ok(true),
+            Err(argon2::password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn is_outdated(&self, hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return true;
+        };
+        let Ok(params) = Params::try_from(&parsed) else {
+            return true;
+        };
+        parsed.algorithm != Algorithm::Argon2id.ident()
+            || parsed.version != Some(Version::V0x13.into())
+            || params.m_cost() != self.params.m_cost()
+            || params.t_cost() != self.params.t_cost()
+            || params.p_cost() != self.params.p_cost()
+    }
+}
+
+pub struct BcryptHasher {
+    cost: u32,
+}
+
+impl BcryptHasher {
+    pub fn new(config: &PasswordHashConfig) -> Self {
+        Self {
+            cost: config.bcrypt_cost,
+        }
+    }
+}
+
+impl PasswordHasher for BcryptHasher {
+    fn recognizes(&self, hash: &str) -> bool {
+        ["$2a$", "$2b$", "$2x$", "$2y$"]
+            .iter()
+            .any(|prefix| hash.starts_with(prefix))
+    }
+
+    fn hash(&self, password: &str) -> Result<String, PasswordHashError> {
+        Ok(bcrypt::hash(password, self.cost)?)
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, PasswordHashError> {
+        Ok(bcrypt::verify(password, hash)?)
+    }
+
+    fn is_outdated(&self, hash: &str) -> bool {
+        // "$2b$12$..." was hashed with cost 12
+        hash.get(4..6).and_then(|cost| cost.parse::<u32>().ok()) != Some(self.cost)
+    }
+}
+
+// Hashes new passwords with the configured hasher and verifies hashes made
+// by any of the hashers
+pub struct PasswordHashing {
+    current: Box<dyn PasswordHasher>,
+    others: Vec<Box<dyn PasswordHasher>>,
+}
+
+impl PasswordHashing {
+    pub fn new(config: &PasswordHashConfig) -> Result<Self, PasswordHashError> {
+        let argon2id: Box<dyn PasswordHasher> = Box::new(Argon2idHasher::new(config)?);
+        let bcrypt: Box<dyn PasswordHasher> = Box::new(BcryptHasher::new(config));
+        let (current, others) = match config.algorithm {
+            PasswordHashAlgorithm::Argon2id => (argon2id, vec![bcrypt]),
+            PasswordHashAlgorithm::Bcrypt => (bcrypt, vec![argon2id]),
+        };
+        Ok(Self { current, others })
+    }
+
+    pub fn from_env() -> Result<Self, PasswordHashError> {
+        Self::new(&PasswordHashConfig::from_env())
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, PasswordHashError> {
+        self.current.hash(password)
+    }
+
+    pub fn verify(&self, password: &str, hash: &str) -> Result<bool, PasswordHashError> {
+        std::iter::once(&self.current)
+            .chain(&self.others)
+            .find(|hasher| hasher.recognizes(hash))
+            .ok_or(PasswordHashError::UnknownHash)?
+            .verify(password, hash)
+    }
+
+    // Whether the hash should be replaced with one from the current hasher
+    // and settings, which needs the password so is done on sign-in
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        !self.current.recognizes(hash) || self.current.is_outdated(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Small settings so the tests run quickly
+    fn config(algorithm: PasswordHashAlgorithm) -> PasswordHashConfig {
+        PasswordHashConfig {
+            algorithm,
+            argon2_memory_kib: 1024,
+            argon2_iterations: 1,
+            argon2_parallelism: 1,
+            bcrypt_cost: 4,
+        }
+    }
+
+    #[test]
+    fn test_new_hashes_use_argon2id() {
+        let hashing = PasswordHashing::new(&config(PasswordHashAlgorithm::Argon2id)).unwrap();
+        let hash = hashing.hash("correct horse battery").unwrap();
+
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert_ne!(hash, hashing.hash("correct horse battery").unwrap());
+        assert!(hashing.verify("correct horse battery", &hash).unwrap());
+        assert!(!hashing.verify("wrong horse battery", &hash).unwrap());
+        assert!(!hashing.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn test_bcrypt_hashes_verify_and_need_rehash() {
+        let hashing = PasswordHashing::new(&config(PasswordHashAlgorithm::Argon2id)).unwrap();
+        let bcrypt_hash = bcrypt::hash("correct horse battery", 4).unwrap();
+
+        assert!(
+            hashing
+                .verify("correct horse battery", &bcrypt_hash)
+                .unwrap()
+        );
+        assert!(!hashing.verify("wrong horse battery", &bcrypt_hash).unwrap());
+        assert!(hashing.needs_rehash(&bcrypt_hash));
+    }
+
+    #[test]
+    fn test_changed_settings_need_rehash() {
+        let old = PasswordHashing::new(&config(PasswordHashAlgorithm::Argon2id)).unwrap();
+        let hash = old.hash("correct horse battery").unwrap();
+
+        // Hashes made with older settings still verify
+        let mut stronger = config(PasswordHashAlgorithm::Argon2id);
+        stronger.argon2_iterations = 2;
+        let new = PasswordHashing::new(&stronger).unwrap();
+        assert!(new.verify("correct horse battery", &hash).unwrap());
+        assert!(new.needs_rehash(&hash));
+
+        // Going back to bcrypt replaces Argon2id hashes too
+        let bcrypt = PasswordHashing::new(&config(PasswordHashAlgorithm::Bcrypt)).unwrap();
+        assert!(bcrypt.verify("correct horse battery", &hash).unwrap());
+        assert!(bcrypt.needs_rehash(&hash));
+        let bcrypt_hash = bcrypt.hash("correct horse battery").unwrap();
+        assert!(bcrypt_hash.starts_with("$2b$04$"));
+        assert!(!bcrypt.needs_rehash(&bcrypt_hash));
+        assert!(bcrypt.needs_rehash(&bcrypt::hash("correct horse battery", 5).unwrap()));
+    }
+
+    #[test]
+    fn test_unknown_hashes_are_errors() {
+        let hashing = PasswordHashing::new(&config(PasswordHashAlgorithm::Argon2id)).unwrap();
+
+        assert!(matches!(
+            hashing.verify("password", "not_a_valid_hash"),
+            Err(PasswordHashError::UnknownHash)
+        ));
+        assert!(hashing.verify("password", "$argon2id$v=19$m=abc").is_err());
+        assert!(hashing.verify("password", "$2b$04$short").is_err());
+    }
+}
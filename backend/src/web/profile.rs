@@ -0,0 +1,309 @@
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use serde_json::json;
+use sqlx::{PgConnection, Pool, Postgres};
+use tracing::{error, info, warn};
+
+use crate::db::models::{
+    ChangePasswordRequest, DeleteAccountRequest, SignedSiweMessage, UpdateProfileRequest, User,
+    UserProfile,
+};
+use crate::db::{session_queries, token_queries, user_queries, wallet_queries};
+use crate::mail::AppMailer;
+use crate::web::account::send_verification_email;
+use crate::web::auth::{Claims, verify_password};
+use crate::web::password_hashing::PasswordHashError;
+use crate::web::siwe::{self, SignedMessageError};
+use crate::web::validation::{FieldError, PasswordPolicy, ValidationErrors, validate_password};
+
+// The signed-in user's management of their own account. Changing the email,
+// changing the password and deleting the account need the current password,
+// or for wallet-only accounts a freshly signed SIWE message, so a stolen
+// access token can't be used to take the account over.
+
+// Error types for profile management
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("Validation failed")]
+    Validation(ValidationErrors),
+    #[error("Current password is incorrect")]
+    WrongPassword,
+    #[error("Account has no password")]
+    NoPassword,
+    #[error(transparent)]
+    SignedMessage(#[from] SignedMessageError),
+    #[error("The message was not signed by one of your wallets")]
+    WrongSigner,
+    #[error("Email already exists")]
+    EmailTaken,
+    #[error("Failed to hash password: {0}")]
+    Hash(#[from] PasswordHashError),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl IntoResponse for ProfileError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            ProfileError::Validation(errors) => return errors.into_response(),
+            ProfileError::SignedMessage(e) => return e.into_response(),
+            ProfileError::WrongPassword | ProfileError::WrongSigner => StatusCode::FORBIDDEN,
+            ProfileError::NoPassword => StatusCode::BAD_REQUEST,
+            ProfileError::EmailTaken => StatusCode::CONFLICT,
+            _ => {
+                error!("Profile update failed: {}", self);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Something went wrong, please try again" })),
+                )
+                    .into_response();
+            }
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+// Check the password the user gave against their current one
+fn confirm_password(user: &User, password: Option<&str>) -> Result<(), ProfileError> {
+    let Some(hash) = &user.password_hash else {
+        return Err(ProfileError::NoPassword);
+    };
+    let Some(password) = password else {
+        return Err(ProfileError::Validation(ValidationErrors::single(
+            "current_password",
+            "required",
+            "Current password is required",
+        )));
+    };
+    if !verify_password(password, hash)? {
+        warn!(
+            "Wrong current password given for user {}",
+            user.identifier()
+        );
+        return Err(ProfileError::WrongPassword);
+    }
+    Ok(())
+}
+
+// Check that the request comes from the account's owner. Accounts with a
+// password give it; wallet-only accounts have none, and sign a fresh SIWE
+// message with one of their wallets, whose nonce is spent when `tx` commits.
+async fn confirm_owner(
+    tx: &mut PgConnection,
+    user: &User,
+    password: Option<&str>,
+    signed_message: Option<&SignedSiweMessage>,
+) -> Result<(), ProfileError> {
+    if user.password_hash.is_some() {
+        return confirm_password(user, password);
+    }
+    let Some(signed_message) = signed_message else {
+        return Err(ProfileError::Validation(ValidationErrors::single(
+            "signed_message",
+            "required",
+            "A message signed by your wallet is required",
+        )));
+    };
+
+    let signer = siwe::verify_signed_message(&mut *tx, signed_message).await?;
+    let wallets = wallet_queries::get_user_wallets(&mut *tx, user.id).await?;
+    if !wallets
+        .iter()
+        .any(|wallet| wallet.wallet_address.eq_ignore_ascii_case(&signer))
+    {
+        warn!(
+            "Message signed by {} is not from a wallet of user {}",
+            signer,
+            user.identifier()
+        );
+        return Err(ProfileError::WrongSigner);
+    }
+    Ok(())
+}
+
+pub(crate) fn is_unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db_error) if db_error.code().as_deref() == Some("23505"))
+}
+
+// GET /user
+pub async fn get_profile(Extension(current_user): Extension<User>) -> Json<UserProfile> {
+    Json(current_user.into())
+}
+
+// PATCH /user. A new email has to be verified again, and links mailed to the
+// old one stop working.
+pub async fn update_profile(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(mailer): Extension<AppMailer>,
+    Extension(current_user): Extension<User>,
+    Json(request): Json<UpdateProfileRequest>,
+) -> Result<Json<UserProfile>, ProfileError> {
+    let name = request.name.as_deref().unwrap_or(&current_user.name).trim();
+    if name.is_empty() {
+        return Err(ProfileError::Validation(ValidationErrors::single(
+            "name",
+            "required",
+            "Name cannot be empty",
+        )));
+    }
+
+    let email = match request.email.as_deref() {
+        Some(email) => {
+            let email = email.trim().to_lowercase();
+            if !email.contains('@') {
+                return Err(ProfileError::Validation(ValidationErrors::single(
+                    "email",
+                    "invalid",
+                    "Email address is not valid",
+                )));
+            }
+            Some(email)
+        }
+        None => current_user.email.clone(),
+    };
+    let email_changed = email != current_user.email;
+
+    let mut tx = pool.begin().await?;
+    if email_changed {
+        confirm_owner(
+            &mut tx,
+            &current_user,
+            request.current_password.as_deref(),
+            request.signed_message.as_ref(),
+        )
+        .await?;
+    }
+    let user =
+        match user_queries::update_user_profile(&mut *tx, current_user.id, name, email.as_deref())
+            .await
+        {
+            Ok(user) => user,
+            Err(e) if is_unique_violation(&e) => return Err(ProfileError::EmailTaken),
+            Err(e) => return Err(e.into()),
+        };
+    if email_changed {
+        token_queries::delete_user_tokens(&mut *tx, user.id).await?;
+    }
+    tx.commit().await?;
+
+    if email_changed && let Some(email) = &user.email {
+        info!("User id {} changed their email", user.id);
+        if let Err(e) = send_verification_email(&pool, &mailer, user.id, email).await {
+            error!(
+                "Failed to send verification email to user id {}: {}",
+                user.id, e
+            );
+        }
+    }
+    Ok(Json(user.into()))
+}
+
+// POST /user/password. Every other session is signed out; the one the
+// request was made with stays signed in.
+pub async fn change_password(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(current_user): Extension<User>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, ProfileError> {
+    confirm_password(&current_user, Some(&request.current_password))?;
+    validate_password(
+        &request.new_password,
+        current_user.email.as_deref(),
+        &PasswordPolicy::from_env(),
+    )
+    .map_err(|errors| {
+        // The policy names the field "password"
+        ProfileError::Validation(ValidationErrors::new(
+            errors
+                .fields
+                .into_iter()
+                .map(|field| FieldError {
+                    field: "new_password".to_string(),
+                    ..field
+                })
+                .collect(),
+        ))
+    })?;
+    let password_hash = user_queries::hash_password(&request.new_password)?;
+
+    let mut tx = pool.begin().await?;
+    user_queries::update_password_hash(&mut *tx, current_user.id, &password_hash).await?;
+    session_queries::revoke_other_user_sessions(&mut *tx, current_user.id, claims.sid).await?;
+    tx.commit().await?;
+
+    info!("User {} changed their password", current_user.identifier());
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// DELETE /user. The account is anonymised and signed out everywhere. Its
+// policies and claims are kept for the audit trail.
+pub async fn delete_account(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(current_user): Extension<User>,
+    request: Option<Json<DeleteAccountRequest>>,
+) -> Result<StatusCode, ProfileError> {
+    let Json(request) = request.unwrap_or_default();
+
+    let mut tx = pool.begin().await?;
+    confirm_owner(
+        &mut tx,
+        &current_user,
+        request.current_password.as_deref(),
+        request.signed_message.as_ref(),
+    )
+    .await?;
+    session_queries::revoke_user_sessions(&mut *tx, current_user.id).await?;
+    token_queries::delete_user_tokens(&mut *tx, current_user.id).await?;
+    wallet_queries::delete_user_wallets(&mut *tx, current_user.id).await?;
+    user_queries::anonymize_user(&mut *tx, current_user.id).await?;
+    tx.commit().await?;
+
+    info!("Deleted account of user id: {}", current_user.id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::UserRole;
+
+    fn user(password_hash: Option<String>) -> User {
+        User {
+            id: 1,
+            name: "Test User".to_string(),
+            email: Some("test@example.com".to_string()),
+            password_hash,
+            wallet_address: None,
+            email_verified_at: None,
+            created_at: None,
+            updated_at: None,
+            role: UserRole::User,
+        }
+    }
+
+    #[test]
+    fn test_confirm_password() {
+        let hash = bcrypt::hash("current-password", 4).unwrap();
+        let user = user(Some(hash));
+
+        assert!(confirm_password(&user, Some("current-password")).is_ok());
+        assert!(matches!(
+            confirm_password(&user, Some("wrong-password")),
+            Err(ProfileError::WrongPassword)
+        ));
+        assert!(matches!(
+            confirm_password(&user, None),
+            Err(ProfileError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_confirm_password_without_one() {
+        let user = user(None);
+
+        assert!(matches!(
+            confirm_password(&user, Some("anything")),
+            Err(ProfileError::NoPassword)
+        ));
+    }
+}
@@ -0,0 +1,513 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{ConnectInfo, Extension, Json, Request, State},
+    http::{HeaderValue, Response, StatusCode, header},
+    middleware::Next,
+    response::IntoResponse,
+};
+use serde_json::json;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{Pool, Postgres};
+use tracing::{error, warn};
+
+use crate::db::models::RateLimitBucket;
+use crate::db::rate_limit_queries;
+
+// Rate limiting of the routes that take a password, so they can't be used to
+// guess passwords. Each request takes a token from a bucket for the client's
+// IP and one for the account named in the request. Buckets refill steadily up
+// to their capacity; a request that finds either empty gets a 429.
+
+// Largest request body read to find the account being signed in to
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+// Where buckets are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitBackend {
+    // Shared by every instance of the backend
+    Postgres,
+    // Per process, for tests and single-instance deployments
+    InMemory,
+}
+
+// Configuration for rate limiting
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub backend: RateLimitBackend,
+    pub ip_capacity: u32,
+    pub ip_refill_per_minute: f64,
+    pub account_capacity: u32,
+    pub account_refill_per_minute: f64,
+    // Take the client IP from X-Forwarded-For. Only safe behind a proxy that
+    // sets the header itself.
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            backend: RateLimitBackend::Postgres,
+            ip_capacity: 30,
+            ip_refill_per_minute: 10.0,
+            account_capacity: 10,
+            account_refill_per_minute: 2.0,
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("RATE_LIMIT_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            backend: match std::env::var("RATE_LIMIT_BACKEND").as_deref() {
+                Ok("memory") => RateLimitBackend::InMemory,
+                Ok("postgres") => RateLimitBackend::Postgres,
+                _ => defaults.backend,
+            },
+            ip_capacity: std::env::var("RATE_LIMIT_IP_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.ip_capacity),
+            ip_refill_per_minute: std::env::var("RATE_LIMIT_IP_REFILL_PER_MINUTE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.ip_refill_per_minute),
+            account_capacity: std::env::var("RATE_LIMIT_ACCOUNT_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.account_capacity),
+            account_refill_per_minute: std::env::var("RATE_LIMIT_ACCOUNT_REFILL_PER_MINUTE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.account_refill_per_minute),
+            trust_forwarded_for: std::env::var("RATE_LIMIT_TRUST_FORWARDED_FOR")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.trust_forwarded_for),
+        }
+    }
+
+    // How long until an emptied bucket is full again. A bucket unused for that
+    // long is the same as a missing one, which is created full. None if
+    // buckets never refill.
+    pub fn refill_duration(&self) -> Option<time::Duration> {
+        let ip = BucketLimit::new(self.ip_capacity, self.ip_refill_per_minute);
+        let account = BucketLimit::new(self.account_capacity, self.account_refill_per_minute);
+        [ip, account]
+            .into_iter()
+            .map(|limit| {
+                (limit.refill_per_second > 0.0)
+                    .then(|| time::Duration::seconds_f64(limit.capacity / limit.refill_per_second))
+            })
+            .try_fold(time::Duration::ZERO, |longest, refill| {
+                refill.map(|refill| longest.max(refill))
+            })
+    }
+}
+
+// Configuration for locking accounts after repeated failed sign-ins
+#[derive(Debug, Clone)]
+pub struct LoginLockoutConfig {
+    // Failed sign-ins in a row before the account is locked
+    pub threshold: i32,
+    // Length of the first lockout, doubled by every further failure
+    pub base_lockout_seconds: i64,
+    pub max_lockout_seconds: i64,
+}
+
+impl Default for LoginLockoutConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 5,
+            base_lockout_seconds: 60,
+            max_lockout_seconds: 3600,
+        }
+    }
+}
+
+impl LoginLockoutConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            threshold: std::env::var("LOGIN_LOCKOUT_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.threshold),
+            base_lockout_seconds: std::env::var("LOGIN_LOCKOUT_BASE_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.base_lockout_seconds),
+            max_lockout_seconds: std::env::var("LOGIN_LOCKOUT_MAX_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_lockout_seconds),
+        }
+    }
+
+    // How long to lock an account after this many failed sign-ins in a row
+    pub fn lockout_duration(&self, failed_attempts: i32) -> Option<time::Duration> {
+        if failed_attempts < self.threshold {
+            return None;
+        }
+        let doublings = (failed_attempts - self.threshold).min(32) as u32;
+        let seconds = self
+            .base_lockout_seconds
+            .saturating_mul(1i64 << doublings)
+            .min(self.max_lockout_seconds);
+        Some(time::Duration::seconds(seconds))
+    }
+}
+
+fn now_utc() -> PrimitiveDateTime {
+    let now = time::OffsetDateTime::now_utc();
+    PrimitiveDateTime::new(now.date(), now.time())
+}
+
+// Size and refill rate of a bucket
+#[derive(Debug, Clone, Copy)]
+struct BucketLimit {
+    capacity: f64,
+    refill_per_second: f64,
+}
+
+impl BucketLimit {
+    fn new(capacity: u32, refill_per_minute: f64) -> Self {
+        Self {
+            capacity: capacity as f64,
+            refill_per_second: refill_per_minute / 60.0,
+        }
+    }
+}
+
+// Refill the bucket for the time since it was last used, then take a token.
+// Err holds the seconds until there will be a token to take.
+fn take_token(
+    bucket: &mut RateLimitBucket,
+    limit: BucketLimit,
+    now: PrimitiveDateTime,
+) -> Result<(), u64> {
+    let elapsed = (now - bucket.updated_at).as_seconds_f64().max(0.0);
+    bucket.tokens = (bucket.tokens + elapsed * limit.refill_per_second).min(limit.capacity);
+    bucket.updated_at = now;
+
+    if bucket.tokens >= 1.0 {
+        bucket.tokens -= 1.0;
+        return Ok(());
+    }
+    if limit.refill_per_second <= 0.0 {
+        return Err(u64::MAX);
+    }
+    let seconds = ((1.0 - bucket.tokens) / limit.refill_per_second).ceil();
+    Err((seconds as u64).max(1))
+}
+
+#[derive(Clone)]
+enum RateLimitStore {
+    Postgres(Pool<Postgres>),
+    InMemory(Arc<Mutex<HashMap<String, RateLimitBucket>>>),
+}
+
+// Token buckets in front of the sign-in and sign-up routes. Added to the
+// router as an extension and used through `rate_limit_middleware`.
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: RateLimitStore,
+    config: RateLimitConfig,
+}
+
+impl RateLimiter {
+    pub fn new(pool: Pool<Postgres>, config: RateLimitConfig) -> Self {
+        let store = match config.backend {
+            RateLimitBackend::Postgres => RateLimitStore::Postgres(pool),
+            RateLimitBackend::InMemory => RateLimitStore::InMemory(Arc::default()),
+        };
+        Self { store, config }
+    }
+
+    // Take a token from the client's and the account's bucket for the route.
+    // Err holds the seconds until both have a token again.
+    pub async fn check(&self, route: &str, ip: &str, account: Option<&str>) -> Result<(), u64> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let mut buckets = vec![(
+            format!("{}:ip:{}", route, ip),
+            BucketLimit::new(self.config.ip_capacity, self.config.ip_refill_per_minute),
+        )];
+        if let Some(account) = account {
+            buckets.push((
+                format!("{}:account:{}", route, account),
+                BucketLimit::new(
+                    self.config.account_capacity,
+                    self.config.account_refill_per_minute,
+                ),
+            ));
+        }
+
+        let mut retry_after = None;
+        for (key, limit) in buckets {
+            match self.take(&key, limit).await {
+                Ok(Ok(())) => {}
+                Ok(Err(seconds)) => {
+                    warn!("Rate limited {} for {}s", key, seconds);
+                    retry_after = retry_after.max(Some(seconds));
+                }
+                // Failing closed would let a database outage lock everyone out
+                Err(e) => error!("Failed to check rate limit bucket {}: {}", key, e),
+            }
+        }
+        retry_after.map_or(Ok(()), Err)
+    }
+
+    async fn take(&self, key: &str, limit: BucketLimit) -> Result<Result<(), u64>, sqlx::Error> {
+        let now = now_utc();
+        match &self.store {
+            RateLimitStore::Postgres(pool) => {
+                let mut tx = pool.begin().await?;
+                let mut bucket =
+                    rate_limit_queries::lock_rate_limit_bucket(&mut *tx, key, limit.capacity, now)
+                        .await?;
+                let result = take_token(&mut bucket, limit, now);
+                rate_limit_queries::save_rate_limit_bucket(&mut *tx, key, &bucket).await?;
+                tx.commit().await?;
+                Ok(result)
+            }
+            RateLimitStore::InMemory(buckets) => {
+                let mut buckets = buckets.lock().unwrap_or_else(|e| e.into_inner());
+                let bucket = buckets.entry(key.to_string()).or_insert(RateLimitBucket {
+                    tokens: limit.capacity,
+                    updated_at: now,
+                });
+                Ok(take_token(bucket, limit, now))
+            }
+        }
+    }
+
+    // The IP the request came from
+    fn client_ip(&self, req: &Request) -> String {
+        if self.config.trust_forwarded_for
+            && let Some(forwarded_for) = req
+                .headers()
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(',').next())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        {
+            return forwarded_for.to_string();
+        }
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+// A 429 telling the client how long to wait before trying again
+fn too_many_requests(message: &str, retry_after_seconds: u64) -> Response<Body> {
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(json!({
+            "error": message,
+            "retry_after_seconds": retry_after_seconds,
+        })),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_seconds));
+    response
+}
+
+// The lowercased email in a JSON request body, if there is one
+fn account_from_body(body: &[u8]) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let email = value.get("email")?.as_str()?.trim().to_lowercase();
+    (!email.is_empty()).then_some(email)
+}
+
+// Middleware rate limiting a route by client IP and by the email in the
+// request body. The state names the route's buckets.
+pub async fn rate_limit_middleware(
+    State(route): State<&'static str>,
+    Extension(limiter): Extension<RateLimiter>,
+    req: Request,
+    next: Next,
+) -> Response<Body> {
+    let ip = limiter.client_ip(&req);
+    let (parts, body) = req.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_BODY_BYTES).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+    let account = account_from_body(&bytes);
+
+    if let Err(retry_after) = limiter.check(route, &ip, account.as_deref()).await {
+        return too_many_requests("Too many requests, try again later", retry_after);
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(tokens: f64, updated_at: PrimitiveDateTime) -> RateLimitBucket {
+        RateLimitBucket { tokens, updated_at }
+    }
+
+    #[test]
+    fn test_take_token_until_empty() {
+        let now = now_utc();
+        let limit = BucketLimit::new(2, 6.0);
+        let mut bucket = bucket(2.0, now);
+
+        assert_eq!(take_token(&mut bucket, limit, now), Ok(()));
+        assert_eq!(take_token(&mut bucket, limit, now), Ok(()));
+        // 6 a minute is one every 10 seconds
+        assert_eq!(take_token(&mut bucket, limit, now), Err(10));
+        assert_eq!(bucket.tokens, 0.0);
+    }
+
+    #[test]
+    fn test_take_token_refills_up_to_capacity() {
+        let now = now_utc();
+        let limit = BucketLimit::new(3, 6.0);
+
+        let mut half_empty = bucket(0.0, now - time::Duration::seconds(15));
+        assert_eq!(take_token(&mut half_empty, limit, now), Ok(()));
+        assert!((half_empty.tokens - 0.5).abs() < 1e-9);
+        assert_eq!(take_token(&mut half_empty, limit, now), Err(5));
+
+        let mut idle = bucket(0.0, now - time::Duration::hours(1));
+        assert_eq!(take_token(&mut idle, limit, now), Ok(()));
+        assert_eq!(idle.tokens, 2.0);
+        assert_eq!(idle.updated_at, now);
+    }
+
+    #[test]
+    fn test_refill_duration() {
+        let config = RateLimitConfig {
+            ip_capacity: 30,
+            ip_refill_per_minute: 10.0,
+            account_capacity: 10,
+            account_refill_per_minute: 2.0,
+            ..RateLimitConfig::default()
+        };
+        assert_eq!(config.refill_duration(), Some(time::Duration::minutes(5)));
+
+        let never_refills = RateLimitConfig {
+            account_refill_per_minute: 0.0,
+            ..config
+        };
+        assert_eq!(never_refills.refill_duration(), None);
+    }
+
+    #[test]
+    fn test_lockout_duration_doubles_up_to_max() {
+        let config = LoginLockoutConfig {
+            threshold: 3,
+            base_lockout_seconds: 60,
+            max_lockout_seconds: 600,
+        };
+
+        assert_eq!(config.lockout_duration(2), None);
+        assert_eq!(
+            config.lockout_duration(3),
+            Some(time::Duration::seconds(60))
+        );
+        assert_eq!(
+            config.lockout_duration(4),
+            Some(time::Duration::seconds(120))
+        );
+        assert_eq!(
+            config.lockout_duration(6),
+            Some(time::Duration::seconds(480))
+        );
+        assert_eq!(
+            config.lockout_duration(7),
+            Some(time::Duration::seconds(600))
+        );
+        assert_eq!(
+            config.lockout_duration(500),
+            Some(time::Duration::seconds(600))
+        );
+    }
+
+    #[test]
+    fn test_account_from_body() {
+        assert_eq!(
+            account_from_body(br#"{"email": " Alice@Example.com ", "password": "x"}"#),
+            Some("alice@example.com".to_string())
+        );
+        assert_eq!(account_from_body(br#"{"email": ""}"#), None);
+        assert_eq!(account_from_body(br#"{"name": "alice"}"#), None);
+        assert_eq!(account_from_body(b"not json"), None);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_limiter_checks_ip_and_account() {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+        let limiter = RateLimiter::new(
+            pool,
+            RateLimitConfig {
+                backend: RateLimitBackend::InMemory,
+                ip_capacity: 3,
+                account_capacity: 2,
+                ..RateLimitConfig::default()
+            },
+        );
+
+        assert!(
+            limiter
+                .check("signin", "1.2.3.4", Some("a@x.com"))
+                .await
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .check("signin", "1.2.3.4", Some("a@x.com"))
+                .await
+                .is_ok()
+        );
+        // The account is out of tokens, even from another IP
+        assert!(
+            limiter
+                .check("signin", "5.6.7.8", Some("a@x.com"))
+                .await
+                .is_err()
+        );
+        // The first IP has a token left for other accounts, then runs out
+        assert!(
+            limiter
+                .check("signin", "1.2.3.4", Some("b@x.com"))
+                .await
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .check("signin", "1.2.3.4", Some("c@x.com"))
+                .await
+                .is_err()
+        );
+        // Routes have their own buckets
+        assert!(
+            limiter
+                .check("create_user", "1.2.3.4", Some("a@x.com"))
+                .await
+                .is_ok()
+        );
+    }
+}
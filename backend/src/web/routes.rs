@@ -1,10 +1,12 @@
-use crate::{web::auth, web::services};
+use crate::{
+    web::account, web::admin, web::auth, web::profile, web::rate_limit, web::services, web::siwe,
+};
 use axum::{
     Router,
     extract::Request,
     middleware,
     response::Response,
-    routing::{get, post, put},
+    routing::{get, patch, post, put},
 };
 use std::future::Future;
 use std::pin::Pin;
@@ -24,9 +26,56 @@ async fn logging_middleware(req: Request, next: axum::middleware::Next) -> Respo
     response
 }
 
+// Routes for admins only. route_layer runs the last layer added first, so the
+// user is authenticated before their role is checked.
+fn admin_routes() -> Router {
+    Router::new()
+        .route("/policy-templates", post(admin::create_policy_template))
+        .route("/policy-templates/{id}", put(admin::update_policy_template))
+        .route(
+            "/policy-templates/{id}/deactivate",
+            post(admin::deactivate_policy_template),
+        )
+        .route("/policies", get(admin::list_policies))
+        .route("/policies/{id}/status", post(admin::force_policy_status))
+        .route("/claims/{id}/approve", post(admin::approve_claim))
+        .route("/claims/{id}/reject", post(admin::reject_claim))
+        .route("/audit-log", get(admin::get_audit_log))
+        .route_layer(middleware::from_fn(auth::admin_middleware))
+        .route_layer(middleware::from_fn(auth::authorization_middleware))
+}
+
 pub async fn app() -> Router {
     Router::new()
-        .route("/signin", post(auth::sign_in))
+        .route(
+            "/signin",
+            post(auth::sign_in).layer(middleware::from_fn_with_state(
+                "signin",
+                rate_limit::rate_limit_middleware,
+            )),
+        )
+        .route("/token/refresh", post(auth::refresh_session))
+        .route(
+            "/auth/nonce",
+            get(siwe::issue_nonce).layer(middleware::from_fn_with_state(
+                "auth_nonce",
+                rate_limit::rate_limit_middleware,
+            )),
+        )
+        .route("/auth/siwe", post(siwe::siwe_sign_in))
+        .route("/auth/verify-email", post(account::verify_email))
+        .route(
+            "/auth/forgot-password",
+            post(account::forgot_password).layer(middleware::from_fn_with_state(
+                "forgot_password",
+                rate_limit::rate_limit_middleware,
+            )),
+        )
+        .route("/auth/reset-password", post(account::reset_password))
+        .route(
+            "/logout",
+            post(auth::logout).layer(middleware::from_fn(auth::authorization_middleware)),
+        )
         .route(
             "/tokenvalid/",
             get(services::hello).layer(middleware::from_fn(auth::authorization_middleware)),
@@ -46,10 +95,55 @@ pub async fn app() -> Router {
             get(services::get_user_policies)
                 .layer(middleware::from_fn(auth::authorization_middleware)),
         )
+        .route(
+            "/policies/{id}",
+            get(services::get_user_policy)
+                .layer(middleware::from_fn(auth::authorization_middleware)),
+        )
+        .route(
+            "/policies/{id}/cancel",
+            post(services::cancel_user_policy)
+                .layer(middleware::from_fn(auth::authorization_middleware)),
+        )
+        .route(
+            "/quotes",
+            post(services::create_quote).layer(middleware::from_fn(auth::authorization_middleware)),
+        )
+        .route(
+            "/user",
+            get(profile::get_profile).layer(middleware::from_fn(auth::authorization_middleware)),
+        )
+        // Routes that take the current password are rate limited like sign-in
+        .route(
+            "/user",
+            patch(profile::update_profile)
+                .delete(profile::delete_account)
+                .layer(middleware::from_fn_with_state(
+                    "user",
+                    rate_limit::rate_limit_middleware,
+                ))
+                .layer(middleware::from_fn(auth::authorization_middleware)),
+        )
+        .route(
+            "/user/password",
+            post(profile::change_password)
+                .layer(middleware::from_fn_with_state(
+                    "change_password",
+                    rate_limit::rate_limit_middleware,
+                ))
+                .layer(middleware::from_fn(auth::authorization_middleware)),
+        )
         .route(
             "/user/wallet",
             put(services::update_wallet_address)
                 .layer(middleware::from_fn(auth::authorization_middleware)),
         )
+        .route(
+            "/user/wallets",
+            get(services::get_user_wallets)
+                .post(services::add_user_wallet)
+                .layer(middleware::from_fn(auth::authorization_middleware)),
+        )
+        .nest("/admin", admin_routes())
         .layer(middleware::from_fn(logging_middleware))
 }
@@ -1,21 +1,39 @@
 // This file contains all exposed services for the backend
-use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use axum::{
+    Extension, Json,
+    extract::{Path, Query},
+    http::StatusCode,
+    response::IntoResponse,
+};
 use serde::{Deserialize, Serialize};
 use sqlx::Pool;
 use sqlx::Postgres;
 
-use crate::blockchain::{BlockchainConfig, BlockchainService};
+use crate::blockchain::{
+    BlockchainConfig, BlockchainService, normalize_transaction_hash, pay_refund,
+};
 use crate::db::models::{
-    CreateInsurancePolicy, CreateInsurancePolicyRequest, InsurancePolicy, PolicyTemplate, User,
+    CreateInsurancePolicy, CreateInsurancePolicyRequest, CreateQuoteRequest, InsurancePolicy,
+    PolicyListQuery, PolicyStatus, PolicyTemplate, SignedSiweMessage, User, UserWallet,
+};
+use crate::db::{policy_queries, user_queries, wallet_queries};
+use crate::policies::{
+    Actor, CancellationConfig, LifecycleError, ListingError, PolicyCancellation, can_transition,
+    cancel_policy, list_user_policies, policy_conditions, pro_rata_refund,
 };
-use crate::db::{policy_queries, user_queries};
+use crate::pricing::{
+    PricingConfig, PricingError, QuoteEngine, QuoteError, verify_policy_matches_quote, verify_quote,
+};
+use crate::web::profile::is_unique_violation;
+use crate::web::siwe::{self, SignedMessageError};
+use crate::web::validation::{JsonBody, ValidationErrors, validate_policy_purchase};
 use crate::web::{
     auth::{self},
     services,
 };
 #[derive(Serialize, Deserialize)]
 struct UserResponse {
-    email: String,
+    email: Option<String>,
     name: String,
 }
 
@@ -35,7 +53,7 @@ pub async fn get_policy_templates(
             tracing::info!(
                 "Retrieved {} policy templates for user {}",
                 templates.len(),
-                current_user.email
+                current_user.identifier()
             );
             Json(templates).into_response()
         }
@@ -53,46 +71,135 @@ pub async fn get_policy_templates(
 pub async fn create_policy(
     Extension(pool): Extension<Pool<Postgres>>,
     Extension(current_user): Extension<User>,
-    Json(request_data): Json<CreateInsurancePolicyRequest>,
+    JsonBody(request_data): JsonBody<CreateInsurancePolicyRequest>,
 ) -> impl IntoResponse {
     tracing::info!(
         "Creating policy '{}' for user {}",
         request_data.policy_name,
-        current_user.email
+        current_user.identifier()
     );
 
-    // Validate that user has a wallet address
-    let user_wallet_address = match &current_user.wallet_address {
-        Some(addr) => addr,
-        None => {
-            tracing::warn!(
-                "User {} attempted to create policy without wallet address",
-                current_user.email
-            );
-            return (
-                StatusCode::BAD_REQUEST,
-                "User wallet address not found. Please connect your wallet first.",
-            )
-                .into_response();
-        }
-    };
+    // Validate that user has a verified wallet to have paid from
+    let user_wallet_addresses: Vec<String> =
+        match wallet_queries::get_user_wallets(&pool, current_user.id).await {
+            Ok(wallets) => wallets.into_iter().map(|w| w.wallet_address).collect(),
+            Err(e) => {
+                tracing::error!(
+                    "Failed to fetch wallets for user {}: {}",
+                    current_user.identifier(),
+                    e
+                );
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create policy")
+                    .into_response();
+            }
+        };
+    if user_wallet_addresses.is_empty() {
+        tracing::warn!(
+            "User {} attempted to create policy without wallet address",
+            current_user.identifier()
+        );
+        return ValidationErrors::single(
+            "wallet_address",
+            "required",
+            "User wallet address not found. Please connect your wallet first.",
+        )
+        .into_response();
+    }
 
     // Validate that transaction hash is provided
     let tx_hash = match &request_data.purchase_transaction_hash {
-        Some(hash) => hash,
+        Some(hash) => match normalize_transaction_hash(hash) {
+            Ok(hash) => hash,
+            Err(e) => {
+                return ValidationErrors::single(
+                    "purchase_transaction_hash",
+                    "invalid",
+                    e.to_string(),
+                )
+                .into_response();
+            }
+        },
         None => {
             tracing::warn!(
                 "Policy creation attempted without transaction hash for user {}",
-                current_user.email
+                current_user.identifier()
             );
-            return (
-                StatusCode::BAD_REQUEST,
+            return ValidationErrors::single(
+                "purchase_transaction_hash",
+                "required",
                 "Purchase transaction hash is required",
             )
+            .into_response();
+        }
+    };
+
+    // Check the purchase against the limits of its template
+    let template_id = match request_data.policy_template_id {
+        Some(id) => id,
+        None => {
+            return ValidationErrors::single(
+                "policy_template_id",
+                "required",
+                "Policy template is required",
+            )
+            .into_response();
+        }
+    };
+    let template = match policy_queries::get_policy_template_by_id(&pool, template_id).await {
+        Ok(Some(template)) => template,
+        Ok(None) => {
+            return ValidationErrors::single(
+                "policy_template_id",
+                "not_found",
+                format!("Policy template {} not found", template_id),
+            )
+            .into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch policy template {}: {}", template_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create policy").into_response();
+        }
+    };
+    if let Err(errors) = validate_policy_purchase(&request_data, &template) {
+        tracing::warn!(
+            "Policy purchase by user {} failed validation: {:?}",
+            current_user.identifier(),
+            errors.fields
+        );
+        return errors.into_response();
+    }
+    let conditions = match policy_conditions(&template, &request_data.condition_overrides) {
+        Ok(conditions) => conditions,
+        Err(e) if e.is_override_error() => {
+            return ValidationErrors::single("condition_overrides", "invalid", e.to_string())
                 .into_response();
         }
+        Err(e) => {
+            tracing::error!(
+                "Failed to build conditions from template {}: {}",
+                template_id,
+                e
+            );
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create policy").into_response();
+        }
     };
 
+    // The policy must match the quote its premium was priced with
+    let pricing_config = PricingConfig::from_env();
+    if let Err(e) = check_policy_quote(&pricing_config, current_user.id, &request_data) {
+        tracing::warn!(
+            "Quote check failed for user {}: {}",
+            current_user.identifier(),
+            e
+        );
+        return match quote_error_field(&e) {
+            Some((field, code)) => {
+                ValidationErrors::single(field, code, e.to_string()).into_response()
+            }
+            None => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
+    }
+
     // Perform blockchain verification
     let blockchain_config = get_blockchain_config();
     let blockchain_service = match BlockchainService::new(blockchain_config) {
@@ -108,21 +215,22 @@ pub async fn create_policy(
     };
 
     let verification_result = match blockchain_service
-        .verify_policy_transaction(tx_hash, user_wallet_address, &request_data)
+        .verify_policy_transaction(&tx_hash, &user_wallet_addresses, &request_data, &conditions)
         .await
     {
         Ok(result) => result,
         Err(e) => {
             tracing::error!(
                 "Blockchain verification failed for user {}: {}",
-                current_user.email,
+                current_user.identifier(),
                 e
             );
-            return (
-                StatusCode::BAD_REQUEST,
+            return ValidationErrors::single(
+                "purchase_transaction_hash",
+                "verification_failed",
                 format!("Blockchain verification failed: {}", e),
             )
-                .into_response();
+            .into_response();
         }
     };
 
@@ -132,22 +240,24 @@ pub async fn create_policy(
             .unwrap_or("Verification failed".to_string());
         tracing::warn!(
             "Blockchain verification failed for user {}: {}",
-            current_user.email,
+            current_user.identifier(),
             error_msg
         );
-        return (
-            StatusCode::BAD_REQUEST,
+        return ValidationErrors::single(
+            "purchase_transaction_hash",
+            "verification_failed",
             format!("Blockchain verification failed: {}", error_msg),
         )
-            .into_response();
+        .into_response();
     }
 
     tracing::info!(
         "Blockchain verification successful for user {}",
-        current_user.email
+        current_user.identifier()
     );
 
-    // Convert request struct to database struct with user_id from JWT and verification data
+    // Convert request struct to database struct with user_id from JWT and verification data.
+    // The contract and hash are the ones verified, not the client's spelling of them.
     let policy_data = CreateInsurancePolicy {
         user_id: current_user.id,
         policy_template_id: request_data.policy_template_id,
@@ -163,29 +273,42 @@ pub async fn create_policy(
         start_date: request_data.start_date,
         end_date: request_data.end_date,
         weather_station_id: request_data.weather_station_id,
-        smart_contract_address: request_data.smart_contract_address,
-        purchase_transaction_hash: request_data.purchase_transaction_hash,
+        smart_contract_address: Some(blockchain_service.contract_address()),
+        purchase_transaction_hash: Some(tx_hash),
     };
 
-    match policy_queries::create_insurance_policy_with_verification(
+    match policy_queries::create_insurance_policy_with_conditions(
         &pool,
         &policy_data,
         &verification_result,
+        &conditions,
     )
     .await
     {
         Ok(policy) => {
             tracing::info!(
                 "Successfully created verified policy with id: {} for user {}",
-                policy.id,
-                current_user.email
+                policy.policy.id,
+                current_user.identifier()
             );
             (StatusCode::CREATED, Json(policy)).into_response()
         }
+        Err(e) if is_unique_violation(&e) => {
+            tracing::warn!(
+                "User {} submitted a purchase that already backs a policy",
+                current_user.identifier()
+            );
+            ValidationErrors::single(
+                "purchase_transaction_hash",
+                "already_used",
+                "This purchase has already been used for a policy",
+            )
+            .into_response()
+        }
         Err(e) => {
             tracing::error!(
                 "Failed to create policy for user {}: {}",
-                current_user.email,
+                current_user.identifier(),
                 e
             );
             (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create policy").into_response()
@@ -193,8 +316,76 @@ pub async fn create_policy(
     }
 }
 
+fn check_policy_quote(
+    config: &PricingConfig,
+    user_id: i32,
+    request: &CreateInsurancePolicyRequest,
+) -> Result<(), QuoteError> {
+    let token = request.quote_token.as_deref().ok_or(QuoteError::Missing)?;
+    let secret = config
+        .signing_secret
+        .as_deref()
+        .ok_or(QuoteError::NotConfigured)?;
+    let quote = verify_quote(token, secret, config.acceptance_grace_seconds)?;
+    verify_policy_matches_quote(&quote, user_id, request)
+}
+
+// The request field and validation code a quote error is reported under.
+// None for errors that are not the client's fault.
+fn quote_error_field(error: &QuoteError) -> Option<(&'static str, &'static str)> {
+    match error {
+        QuoteError::Missing => Some(("quote_token", "required")),
+        QuoteError::Expired => Some(("quote_token", "expired")),
+        QuoteError::Invalid(_) => Some(("quote_token", "invalid")),
+        QuoteError::Mismatch { field, .. } => Some((field, "quote_mismatch")),
+        QuoteError::NotConfigured => None,
+    }
+}
+
+pub async fn create_quote(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(current_user): Extension<User>,
+    JsonBody(request_data): JsonBody<CreateQuoteRequest>,
+) -> impl IntoResponse {
+    tracing::info!(
+        "Quoting template {} for user {}",
+        request_data.policy_template_id,
+        current_user.identifier()
+    );
+
+    let now = time::OffsetDateTime::now_utc();
+    let now = time::PrimitiveDateTime::new(now.date(), now.time());
+    let engine = QuoteEngine::new(pool, PricingConfig::from_env());
+    match engine.quote(current_user.id, &request_data, now).await {
+        Ok(quote) => Json(quote).into_response(),
+        Err(e) => {
+            let status = match e {
+                PricingError::TemplateNotFound(_) => StatusCode::NOT_FOUND,
+                PricingError::TemplateInactive(_) | PricingError::InvalidRequest(_) => {
+                    StatusCode::BAD_REQUEST
+                }
+                PricingError::Database(_)
+                | PricingError::InvalidConditions(_)
+                | PricingError::Evaluation(_)
+                | PricingError::Estimation(_)
+                | PricingError::Signing(_) => {
+                    tracing::error!(
+                        "Failed to quote template {} for user {}: {}",
+                        request_data.policy_template_id,
+                        current_user.identifier(),
+                        e
+                    );
+                    return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create quote")
+                        .into_response();
+                }
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
 // Helper function to get blockchain configuration from environment
-fn get_blockchain_config() -> BlockchainConfig {
+pub fn get_blockchain_config() -> BlockchainConfig {
     BlockchainConfig {
         rpc_url: std::env::var("ETHEREUM_RPC_URL")
             .unwrap_or_else(|_| "http://localhost:8545".to_string()),
@@ -206,28 +397,31 @@ fn get_blockchain_config() -> BlockchainConfig {
             .unwrap_or_else(|_| "30".to_string())
             .parse()
             .unwrap_or(30),
+        owner_private_key: std::env::var("PRIVATE_KEY").ok(),
     }
 }
 
 pub async fn get_user_policies(
     Extension(pool): Extension<Pool<Postgres>>,
     Extension(current_user): Extension<User>,
+    Query(query): Query<PolicyListQuery>,
 ) -> impl IntoResponse {
-    tracing::info!("Fetching policies for user {}", current_user.email);
+    tracing::info!("Fetching policies for user {}", current_user.identifier());
 
-    match policy_queries::get_policies_by_user_id(&pool, current_user.id).await {
-        Ok(policies) => {
+    match list_user_policies(&pool, current_user.id, &query).await {
+        Ok(page) => {
             tracing::info!(
-                "Retrieved {} policies for user {}",
-                policies.len(),
-                current_user.email
+                "Retrieved {} of {} policies for user {}",
+                page.policies.len(),
+                page.total_count,
+                current_user.identifier()
             );
-            Json(policies).into_response()
+            Json(page).into_response()
         }
-        Err(e) => {
+        Err(ListingError::Database(e)) => {
             tracing::error!(
                 "Failed to fetch policies for user {}: {}",
-                current_user.email,
+                current_user.identifier(),
                 e
             );
             (
@@ -236,37 +430,248 @@ pub async fn get_user_policies(
             )
                 .into_response()
         }
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
     }
 }
 
+// Roughly a month of hourly readings
+const POLICY_DETAIL_READINGS: i64 = 720;
+
+pub async fn get_user_policy(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(current_user): Extension<User>,
+    Path(policy_id): Path<i32>,
+) -> impl IntoResponse {
+    tracing::info!(
+        "Fetching policy {} for user {}",
+        policy_id,
+        current_user.identifier()
+    );
+
+    let now = time::OffsetDateTime::now_utc();
+    let now = time::PrimitiveDateTime::new(now.date(), now.time());
+    match policy_queries::get_policy_details(&pool, policy_id, now, POLICY_DETAIL_READINGS).await {
+        Ok(Some(details)) if details.policy.user_id == current_user.id => {
+            Json(details).into_response()
+        }
+        Ok(Some(_)) => {
+            tracing::warn!(
+                "User {} tried to read policy {} owned by another user",
+                current_user.identifier(),
+                policy_id
+            );
+            (StatusCode::FORBIDDEN, "You do not own this policy").into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "Policy not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch policy {}: {}", policy_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch policy").into_response()
+        }
+    }
+}
+
+pub async fn cancel_user_policy(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(current_user): Extension<User>,
+    Path(policy_id): Path<i32>,
+) -> impl IntoResponse {
+    tracing::info!(
+        "Cancelling policy {} for user {}",
+        policy_id,
+        current_user.identifier()
+    );
+
+    let policy = match policy_queries::get_policy_by_id(&pool, policy_id).await {
+        Ok(Some(policy)) => policy,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Policy not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch policy {}: {}", policy_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to cancel policy").into_response();
+        }
+    };
+    if policy.user_id != current_user.id {
+        tracing::warn!(
+            "User {} tried to cancel policy {} owned by user {}",
+            current_user.identifier(),
+            policy_id,
+            policy.user_id
+        );
+        return (StatusCode::FORBIDDEN, "You do not own this policy").into_response();
+    }
+    let status = policy.status.unwrap_or(PolicyStatus::Active);
+    if !can_transition(status, PolicyStatus::Cancelled) {
+        return (
+            StatusCode::CONFLICT,
+            format!("A {} policy cannot be cancelled", status),
+        )
+            .into_response();
+    }
+
+    let now = time::OffsetDateTime::now_utc();
+    let now = time::PrimitiveDateTime::new(now.date(), now.time());
+    let refund = pro_rata_refund(&policy, now, &CancellationConfig::from_env());
+
+    // Policies bought on chain are cancelled and refunded by the contract,
+    // which only the owner can do. Check that is possible before cancelling.
+    let blockchain_service = match policy.blockchain_policy_id {
+        Some(_) => match BlockchainService::new(get_blockchain_config()) {
+            Ok(service) if service.can_send_refunds() => Some(service),
+            Ok(_) => {
+                tracing::error!("Cannot refund policy {}: no owner key", policy_id);
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Refunds are currently unavailable",
+                )
+                    .into_response();
+            }
+            Err(e) => {
+                tracing::error!("Failed to initialize blockchain service: {}", e);
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Refunds are currently unavailable",
+                )
+                    .into_response();
+            }
+        },
+        None => None,
+    };
+
+    let mut cancelled =
+        match cancel_policy(&pool, policy_id, Actor::User(current_user.id), &refund, now).await {
+            Ok(policy) => policy,
+            Err(LifecycleError::IllegalTransition { from, .. }) => {
+                return (
+                    StatusCode::CONFLICT,
+                    format!("A {} policy cannot be cancelled", from),
+                )
+                    .into_response();
+            }
+            Err(e) => {
+                tracing::error!("Failed to cancel policy {}: {}", policy_id, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to cancel policy")
+                    .into_response();
+            }
+        };
+
+    // The policy stays cancelled if the refund cannot be sent. Its
+    // refund_amount is recorded without a transaction hash, and the refund
+    // processor sends it later.
+    let mut status = StatusCode::OK;
+    if let Some(blockchain_service) = blockchain_service {
+        match pay_refund(&pool, &blockchain_service, &cancelled).await {
+            Ok(tx_hash) => cancelled.refund_transaction_hash = tx_hash,
+            Err(e) => {
+                tracing::error!(
+                    "Policy {} was cancelled but its refund failed, it will be retried: {}",
+                    policy_id,
+                    e
+                );
+                status = StatusCode::ACCEPTED;
+            }
+        }
+    }
+
+    tracing::info!(
+        "Cancelled policy {} for user {} with a refund of {}",
+        policy_id,
+        current_user.identifier(),
+        refund.amount
+    );
+    (
+        status,
+        Json(PolicyCancellation {
+            policy: cancelled,
+            refund,
+        }),
+    )
+        .into_response()
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct UpdateWalletAddressRequest {
     wallet_address: String,
+    // A SIWE message signed by the wallet, proving the user owns it
+    #[serde(flatten)]
+    proof: SignedSiweMessage,
 }
 
+// Error types for linking wallets
+#[derive(Debug, thiserror::Error)]
+enum LinkWalletError {
+    #[error("Invalid wallet address format")]
+    InvalidAddress,
+    #[error(transparent)]
+    SignedMessage(#[from] SignedMessageError),
+    #[error("The message was signed by {0}, not by the wallet being linked")]
+    WrongSigner(String),
+    #[error("Wallet is linked to another account")]
+    LinkedToAnotherUser,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl IntoResponse for LinkWalletError {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            LinkWalletError::SignedMessage(e) => e.into_response(),
+            LinkWalletError::LinkedToAnotherUser => {
+                (StatusCode::CONFLICT, self.to_string()).into_response()
+            }
+            LinkWalletError::Database(e) => {
+                tracing::error!("Failed to link wallet: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to link wallet").into_response()
+            }
+            _ => (StatusCode::BAD_REQUEST, self.to_string()).into_response(),
+        }
+    }
+}
+
+// Link a wallet the user has proven they own. It becomes their primary wallet
+// if `make_primary` is set or they have none yet.
+async fn link_wallet(
+    pool: &Pool<Postgres>,
+    current_user: &User,
+    request: &UpdateWalletAddressRequest,
+    make_primary: bool,
+) -> Result<(UserWallet, User), LinkWalletError> {
+    if !is_valid_ethereum_address(&request.wallet_address) {
+        return Err(LinkWalletError::InvalidAddress);
+    }
+
+    let mut tx = pool.begin().await?;
+    let signer = siwe::verify_signed_message(&mut *tx, &request.proof).await?;
+    if !signer.eq_ignore_ascii_case(&request.wallet_address) {
+        return Err(LinkWalletError::WrongSigner(signer));
+    }
+    let wallet = wallet_queries::add_user_wallet(&mut *tx, current_user.id, &signer)
+        .await?
+        .ok_or(LinkWalletError::LinkedToAnotherUser)?;
+    let user = if make_primary || current_user.wallet_address.is_none() {
+        user_queries::update_user_wallet_address(&mut *tx, current_user.id, &signer).await?
+    } else {
+        current_user.clone()
+    };
+    tx.commit().await?;
+
+    Ok((wallet, user))
+}
+
+// Link a wallet and make it the user's primary wallet
 pub async fn update_wallet_address(
     Extension(pool): Extension<Pool<Postgres>>,
     Extension(current_user): Extension<User>,
     Json(request): Json<UpdateWalletAddressRequest>,
 ) -> impl IntoResponse {
-    tracing::info!("Updating wallet address for user {}", current_user.email);
-
-    // Validate wallet address format (basic Ethereum address validation)
-    if !is_valid_ethereum_address(&request.wallet_address) {
-        tracing::warn!(
-            "Invalid wallet address format provided by user {}",
-            current_user.email
-        );
-        return (StatusCode::BAD_REQUEST, "Invalid wallet address format").into_response();
-    }
+    tracing::info!(
+        "Updating wallet address for user {}",
+        current_user.identifier()
+    );
 
-    match user_queries::update_user_wallet_address(&pool, current_user.id, &request.wallet_address)
-        .await
-    {
-        Ok(updated_user) => {
+    match link_wallet(&pool, &current_user, &request, true).await {
+        Ok((_, updated_user)) => {
             tracing::info!(
                 "Successfully updated wallet address for user {}",
-                current_user.email
+                current_user.identifier()
             );
             Json(UserResponse {
                 email: updated_user.email,
@@ -275,16 +680,48 @@ pub async fn update_wallet_address(
             .into_response()
         }
         Err(e) => {
-            tracing::error!(
+            tracing::warn!(
                 "Failed to update wallet address for user {}: {}",
-                current_user.email,
+                current_user.identifier(),
                 e
             );
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to update wallet address",
-            )
-                .into_response()
+            e.into_response()
+        }
+    }
+}
+
+pub async fn get_user_wallets(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(current_user): Extension<User>,
+) -> impl IntoResponse {
+    match wallet_queries::get_user_wallets(&pool, current_user.id).await {
+        Ok(wallets) => Json(wallets).into_response(),
+        Err(e) => {
+            tracing::error!(
+                "Failed to fetch wallets for user {}: {}",
+                current_user.identifier(),
+                e
+            );
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch wallets").into_response()
+        }
+    }
+}
+
+// Link another wallet without changing the primary one
+pub async fn add_user_wallet(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Extension(current_user): Extension<User>,
+    Json(request): Json<UpdateWalletAddressRequest>,
+) -> impl IntoResponse {
+    match link_wallet(&pool, &current_user, &request, false).await {
+        Ok((wallet, _)) => (StatusCode::CREATED, Json(wallet)).into_response(),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to link wallet for user {}: {}",
+                current_user.identifier(),
+                e
+            );
+            e.into_response()
         }
     }
 }
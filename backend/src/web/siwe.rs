@@ -0,0 +1,531 @@
+// Sign-In With Ethereum (EIP-4361). The client fetches a nonce, has the wallet
+// sign a message containing it, and exchanges the message and signature for
+// the same tokens as an email and password sign-in.
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use chrono::{DateTime, FixedOffset, Utc};
+use ethers::types::{Address, Signature};
+use ethers::utils::to_checksum;
+use rand::RngCore;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{PgExecutor, Pool, Postgres};
+use std::str::FromStr;
+use tracing::{error, info, warn};
+
+use crate::db::models::SignedSiweMessage;
+use crate::db::{session_queries, user_queries, wallet_queries};
+use crate::web::auth::{SignInResponse, start_session};
+
+const PREAMBLE_SUFFIX: &str = " wants you to sign in with your Ethereum account:";
+const MIN_NONCE_LENGTH: usize = 8;
+
+// Error types for Sign-In With Ethereum
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum SiweError {
+    #[error("Malformed SIWE message: {0}")]
+    Malformed(String),
+    #[error("Message is for another domain")]
+    DomainMismatch,
+    #[error("Message is for another chain")]
+    ChainIdMismatch,
+    #[error("Message has expired")]
+    Expired,
+    #[error("Message is not valid yet")]
+    NotYetValid,
+    #[error("Signature does not match the message's address")]
+    InvalidSignature,
+}
+
+// Configuration for SIWE sign-in
+#[derive(Debug, Clone)]
+pub struct SiweConfig {
+    // The domain messages must be issued for, normally the frontend's host
+    pub domain: String,
+    // When set, messages must be for this chain
+    pub chain_id: Option<u64>,
+    pub nonce_ttl_seconds: i64,
+}
+
+impl Default for SiweConfig {
+    fn default() -> Self {
+        Self {
+            domain: "localhost:5173".to_string(),
+            chain_id: None,
+            nonce_ttl_seconds: 600,
+        }
+    }
+}
+
+impl SiweConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            domain: std::env::var("SIWE_DOMAIN").unwrap_or(defaults.domain),
+            chain_id: std::env::var("SIWE_CHAIN_ID")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .or(defaults.chain_id),
+            nonce_ttl_seconds: std::env::var("SIWE_NONCE_TTL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.nonce_ttl_seconds),
+        }
+    }
+}
+
+// A parsed EIP-4361 message
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiweMessage {
+    pub domain: String,
+    pub address: Address,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: DateTime<FixedOffset>,
+    pub expiration_time: Option<DateTime<FixedOffset>>,
+    pub not_before: Option<DateTime<FixedOffset>>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+fn malformed(reason: &str) -> SiweError {
+    SiweError::Malformed(reason.to_string())
+}
+
+fn parse_timestamp(value: &str, field: &str) -> Result<DateTime<FixedOffset>, SiweError> {
+    DateTime::parse_from_rfc3339(value)
+        .map_err(|_| SiweError::Malformed(format!("{} is not an RFC 3339 timestamp", field)))
+}
+
+impl FromStr for SiweMessage {
+    type Err = SiweError;
+
+    fn from_str(message: &str) -> Result<Self, Self::Err> {
+        let mut lines = message.split('\n').peekable();
+
+        let domain = lines
+            .next()
+            .and_then(|line| line.strip_suffix(PREAMBLE_SUFFIX))
+            .filter(|domain| !domain.is_empty())
+            .ok_or_else(|| malformed("missing preamble"))?
+            .to_string();
+
+        // Addresses must be EIP-55 checksummed
+        let address_line = lines.next().ok_or_else(|| malformed("missing address"))?;
+        let address = Address::from_str(address_line).map_err(|_| malformed("invalid address"))?;
+        if to_checksum(&address, None) != address_line {
+            return Err(malformed("address is not EIP-55 checksummed"));
+        }
+        if lines.next() != Some("") {
+            return Err(malformed("expected an empty line after the address"));
+        }
+
+        // The statement is optional. Older messages leave an empty line in
+        // its place.
+        let statement = match lines.peek() {
+            Some(line) if line.starts_with("URI: ") => None,
+            Some(&"") => {
+                lines.next();
+                None
+            }
+            Some(line) => {
+                let statement = line.to_string();
+                lines.next();
+                if lines.next() != Some("") {
+                    return Err(malformed("expected an empty line after the statement"));
+                }
+                Some(statement)
+            }
+            None => return Err(malformed("missing URI")),
+        };
+
+        let mut required = |name: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .and_then(|line| line.strip_prefix(": "))
+                .map(str::to_string)
+                .ok_or_else(|| SiweError::Malformed(format!("missing {}", name)))
+        };
+        let uri = required("URI")?;
+        let version = required("Version")?;
+        let chain_id = required("Chain ID")?
+            .parse()
+            .map_err(|_| malformed("invalid Chain ID"))?;
+        let nonce = required("Nonce")?;
+        let issued_at = parse_timestamp(&required("Issued At")?, "Issued At")?;
+
+        if version != "1" {
+            return Err(malformed("unsupported Version"));
+        }
+        if nonce.len() < MIN_NONCE_LENGTH || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(malformed("invalid Nonce"));
+        }
+
+        let mut optional = |name: &str| {
+            let value = lines
+                .peek()
+                .and_then(|line| line.strip_prefix(name))
+                .and_then(|line| line.strip_prefix(": "))
+                .map(str::to_string);
+            if value.is_some() {
+                lines.next();
+            }
+            value
+        };
+        let expiration_time = optional("Expiration Time")
+            .map(|value| parse_timestamp(&value, "Expiration Time"))
+            .transpose()?;
+        let not_before = optional("Not Before")
+            .map(|value| parse_timestamp(&value, "Not Before"))
+            .transpose()?;
+        let request_id = optional("Request ID");
+
+        let mut resources = Vec::new();
+        if lines.peek() == Some(&"Resources:") {
+            lines.next();
+            while let Some(resource) = lines.peek().and_then(|line| line.strip_prefix("- ")) {
+                resources.push(resource.to_string());
+                lines.next();
+            }
+        }
+
+        // Wallets may add a trailing newline
+        if lines.any(|line| !line.is_empty()) {
+            return Err(malformed("unexpected content after the message"));
+        }
+
+        Ok(Self {
+            domain,
+            address,
+            statement,
+            uri,
+            version,
+            chain_id,
+            nonce,
+            issued_at,
+            expiration_time,
+            not_before,
+            request_id,
+            resources,
+        })
+    }
+}
+
+impl SiweMessage {
+    // Check that the message is meant for us and valid now, and that
+    // `signature` is the message's address signing `message` (EIP-191)
+    pub fn verify(
+        &self,
+        message: &str,
+        signature: &str,
+        config: &SiweConfig,
+        now: DateTime<Utc>,
+    ) -> Result<(), SiweError> {
+        if self.domain != config.domain {
+            return Err(SiweError::DomainMismatch);
+        }
+        if let Some(chain_id) = config.chain_id
+            && self.chain_id != chain_id
+        {
+            return Err(SiweError::ChainIdMismatch);
+        }
+        if let Some(expiration_time) = self.expiration_time
+            && expiration_time <= now
+        {
+            return Err(SiweError::Expired);
+        }
+        if let Some(not_before) = self.not_before
+            && not_before > now
+        {
+            return Err(SiweError::NotYetValid);
+        }
+
+        let signature = Signature::from_str(signature).map_err(|_| SiweError::InvalidSignature)?;
+        match signature.recover(message) {
+            Ok(signer) if signer == self.address => Ok(()),
+            _ => Err(SiweError::InvalidSignature),
+        }
+    }
+}
+
+fn now_utc() -> PrimitiveDateTime {
+    let now = time::OffsetDateTime::now_utc();
+    PrimitiveDateTime::new(now.date(), now.time())
+}
+
+// 16 random bytes, hex encoded, as EIP-4361 nonces must be alphanumeric
+fn generate_nonce() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+// Issue a nonce to put in a SIWE message
+pub async fn issue_nonce(Extension(pool): Extension<Pool<Postgres>>) -> impl IntoResponse {
+    let config = SiweConfig::from_env();
+    let expires_at = now_utc() + time::Duration::seconds(config.nonce_ttl_seconds);
+
+    match session_queries::create_auth_nonce(&pool, &generate_nonce(), expires_at).await {
+        Ok(nonce) => Json(nonce).into_response(),
+        Err(e) => {
+            error!("Failed to issue sign-in nonce: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to issue nonce").into_response()
+        }
+    }
+}
+
+// Error types for requests carrying a signed SIWE message
+#[derive(Debug, thiserror::Error)]
+pub enum SignedMessageError {
+    #[error(transparent)]
+    Siwe(#[from] SiweError),
+    #[error("Invalid or expired nonce")]
+    InvalidNonce,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl IntoResponse for SignedMessageError {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            SignedMessageError::Siwe(SiweError::Malformed(_)) => {
+                (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+            }
+            SignedMessageError::Database(e) => {
+                error!("Failed to check signed message: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to check signed message",
+                )
+                    .into_response()
+            }
+            _ => (StatusCode::UNAUTHORIZED, self.to_string()).into_response(),
+        }
+    }
+}
+
+// Check a signed message and use up its nonce, returning the checksummed
+// address that signed it. The nonce is only spent if the caller's
+// transaction commits.
+pub async fn verify_signed_message(
+    executor: impl PgExecutor<'_>,
+    request: &SignedSiweMessage,
+) -> Result<String, SignedMessageError> {
+    let message = SiweMessage::from_str(&request.message)?;
+    message.verify(
+        &request.message,
+        &request.signature,
+        &SiweConfig::from_env(),
+        Utc::now(),
+    )?;
+    if !session_queries::consume_auth_nonce(executor, &message.nonce).await? {
+        return Err(SignedMessageError::InvalidNonce);
+    }
+
+    Ok(to_checksum(&message.address, None))
+}
+
+// Sign in with a signed SIWE message. The wallet's account is created on
+// first sign-in.
+pub async fn siwe_sign_in(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Json(request): Json<SignedSiweMessage>,
+) -> impl IntoResponse {
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let wallet_address = verify_signed_message(&mut *tx, &request).await?;
+        let user =
+            match user_queries::retrieve_user_by_wallet_address(&mut *tx, &wallet_address).await? {
+                Some(user) => user,
+                None => {
+                    let name = format!("{}...{}", &wallet_address[..6], &wallet_address[38..]);
+                    let user =
+                        user_queries::create_wallet_user(&mut *tx, &name, &wallet_address).await?;
+                    wallet_queries::add_user_wallet(&mut *tx, user.id, &wallet_address).await?;
+                    user
+                }
+            };
+        tx.commit().await?;
+        Ok::<_, SignedMessageError>(user)
+    }
+    .await;
+
+    let user = match result {
+        Ok(user) => user,
+        Err(e) => {
+            warn!("SIWE sign-in failed: {}", e);
+            return e.into_response();
+        }
+    };
+
+    match start_session(&pool, &user).await {
+        Ok(tokens) => {
+            info!("SIWE sign-in successful for {}", user.identifier());
+            Json(SignInResponse {
+                success: true,
+                message: "Login successful".to_string(),
+                token: tokens.access_token,
+                refresh_token: tokens.refresh_token,
+            })
+            .into_response()
+        }
+        Err(status) => status.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::utils::hash_message;
+
+    const ISSUED_AT: &str = "2026-10-17T12:00:00Z";
+
+    fn wallet() -> LocalWallet {
+        "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap()
+    }
+
+    fn message_for(address: Address, extra: &str) -> String {
+        format!(
+            "localhost:5173 wants you to sign in with your Ethereum account:\n\
+             {}\n\
+             \n\
+             Sign in to Weather Insurance\n\
+             \n\
+             URI: http://localhost:5173\n\
+             Version: 1\n\
+             Chain ID: 1\n\
+             Nonce: 32891756abcdef12\n\
+             Issued At: {}{}",
+            to_checksum(&address, None),
+            ISSUED_AT,
+            extra
+        )
+    }
+
+    fn sign(wallet: &LocalWallet, message: &str) -> String {
+        let signature = wallet.sign_hash(hash_message(message)).unwrap();
+        format!("0x{}", signature)
+    }
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap().into()
+    }
+
+    #[test]
+    fn test_parse_message() {
+        let wallet = wallet();
+        let text = message_for(
+            wallet.address(),
+            "\nExpiration Time: 2026-10-17T12:10:00Z\nResources:\n- https://example.com/terms",
+        );
+        let message = SiweMessage::from_str(&text).unwrap();
+
+        assert_eq!(message.domain, "localhost:5173");
+        assert_eq!(message.address, wallet.address());
+        assert_eq!(
+            message.statement.as_deref(),
+            Some("Sign in to Weather Insurance")
+        );
+        assert_eq!(message.uri, "http://localhost:5173");
+        assert_eq!(message.chain_id, 1);
+        assert_eq!(message.nonce, "32891756abcdef12");
+        assert_eq!(message.issued_at, at(ISSUED_AT));
+        assert_eq!(
+            message.expiration_time,
+            Some(at("2026-10-17T12:10:00Z").into())
+        );
+        assert_eq!(message.resources, vec!["https://example.com/terms"]);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_messages() {
+        let address = wallet().address();
+        let text = message_for(address, "");
+
+        // Lowercase addresses are not checksummed
+        let lowercase = text.replace(&to_checksum(&address, None), &format!("{:?}", address));
+        assert!(matches!(
+            SiweMessage::from_str(&lowercase),
+            Err(SiweError::Malformed(_))
+        ));
+        assert!(matches!(
+            SiweMessage::from_str(&text.replace("Version: 1", "Version: 2")),
+            Err(SiweError::Malformed(_))
+        ));
+        assert!(matches!(
+            SiweMessage::from_str(&text.replace("32891756abcdef12", "short")),
+            Err(SiweError::Malformed(_))
+        ));
+        assert!(matches!(
+            SiweMessage::from_str(&text.replace("Nonce", "Once")),
+            Err(SiweError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let wallet = wallet();
+        let text = message_for(wallet.address(), "");
+        let message = SiweMessage::from_str(&text).unwrap();
+        let config = SiweConfig::default();
+        let now = at(ISSUED_AT);
+
+        assert_eq!(
+            message.verify(&text, &sign(&wallet, &text), &config, now),
+            Ok(())
+        );
+
+        // Signed by another wallet
+        let other = LocalWallet::new(&mut rand::thread_rng());
+        assert_eq!(
+            message.verify(&text, &sign(&other, &text), &config, now),
+            Err(SiweError::InvalidSignature)
+        );
+        assert_eq!(
+            message.verify(&text, "0x1234", &config, now),
+            Err(SiweError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_verify_domain_chain_and_time() {
+        let wallet = wallet();
+        let text = message_for(
+            wallet.address(),
+            "\nExpiration Time: 2026-10-17T12:10:00Z\nNot Before: 2026-10-17T12:00:00Z",
+        );
+        let message = SiweMessage::from_str(&text).unwrap();
+        let signature = sign(&wallet, &text);
+        let config = SiweConfig::default();
+
+        let other_domain = SiweConfig {
+            domain: "example.com".to_string(),
+            ..SiweConfig::default()
+        };
+        assert_eq!(
+            message.verify(&text, &signature, &other_domain, at(ISSUED_AT)),
+            Err(SiweError::DomainMismatch)
+        );
+        let other_chain = SiweConfig {
+            chain_id: Some(137),
+            ..SiweConfig::default()
+        };
+        assert_eq!(
+            message.verify(&text, &signature, &other_chain, at(ISSUED_AT)),
+            Err(SiweError::ChainIdMismatch)
+        );
+        assert_eq!(
+            message.verify(&text, &signature, &config, at("2026-10-17T11:59:59Z")),
+            Err(SiweError::NotYetValid)
+        );
+        assert_eq!(
+            message.verify(&text, &signature, &config, at("2026-10-17T12:10:00Z")),
+            Err(SiweError::Expired)
+        );
+    }
+}
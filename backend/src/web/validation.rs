@@ -0,0 +1,411 @@
+// Validation of policy purchases against the template they were bought from,
+// of new passwords against the password policy, and of request bodies
+use axum::{
+    Json,
+    extract::{FromRequest, Request, rejection::JsonRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::db::models::{CreateInsurancePolicyRequest, PolicyTemplate};
+use crate::pricing::MIN_PREMIUM_RATE;
+
+// A problem with a single request field
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+// All problems found with a request, returned as a 400 JSON body:
+// {"error": "Validation failed", "fields": [{"field", "code", "message"}]}
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ValidationErrors {
+    pub error: String,
+    pub fields: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn new(fields: Vec<FieldError>) -> Self {
+        Self {
+            error: "Validation failed".to_string(),
+            fields,
+        }
+    }
+
+    pub fn single(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self::new(vec![FieldError::new(field, code, message)])
+    }
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, Json(self)).into_response()
+    }
+}
+
+// A JSON request body. Bodies that don't deserialize are rejected with the
+// same structured errors as failed validation, naming the field that could
+// not be read, rather than axum's plain-text 422.
+pub struct JsonBody<T>(pub T);
+
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ValidationErrors;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(request, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            Err(rejection) => Err(json_rejection_errors(&rejection)),
+        }
+    }
+}
+
+fn json_rejection_errors(rejection: &JsonRejection) -> ValidationErrors {
+    use std::error::Error;
+
+    // Data errors carry the path of the field serde failed on
+    let path_error = rejection
+        .source()
+        .and_then(|e| e.source())
+        .and_then(|e| e.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>());
+    match path_error {
+        Some(e) if e.path().iter().next().is_some() => {
+            ValidationErrors::single(&e.path().to_string(), "invalid", e.inner().to_string())
+        }
+        Some(e) => ValidationErrors::single("body", "invalid", e.inner().to_string()),
+        None => ValidationErrors::single("body", "invalid", rejection.body_text()),
+    }
+}
+
+// Check a policy purchase against its template's limits
+pub fn validate_policy_purchase(
+    request: &CreateInsurancePolicyRequest,
+    template: &PolicyTemplate,
+) -> Result<(), ValidationErrors> {
+    let mut fields = Vec::new();
+
+    if template.is_active == Some(false) {
+        fields.push(FieldError::new(
+            "policy_template_id",
+            "inactive",
+            format!("Policy template {} is not active", template.id),
+        ));
+    }
+    if request.policy_type != template.policy_type {
+        fields.push(FieldError::new(
+            "policy_type",
+            "mismatch",
+            format!(
+                "Policy type must be {} for template {}",
+                template.policy_type, template.id
+            ),
+        ));
+    }
+    if request.coverage_amount < template.min_coverage_amount
+        || request.coverage_amount > template.max_coverage_amount
+    {
+        fields.push(FieldError::new(
+            "coverage_amount",
+            "out_of_range",
+            format!(
+                "Coverage amount must be between {} and {}",
+                template.min_coverage_amount, template.max_coverage_amount
+            ),
+        ));
+    }
+    let min_premium = request.coverage_amount * MIN_PREMIUM_RATE;
+    if request.premium_amount < min_premium {
+        fields.push(FieldError::new(
+            "premium_amount",
+            "too_low",
+            format!(
+                "Premium amount must be at least {}",
+                min_premium.normalize()
+            ),
+        ));
+    }
+    if request.start_date >= request.end_date {
+        fields.push(FieldError::new(
+            "end_date",
+            "before_start_date",
+            "End date must be after start date",
+        ));
+    }
+
+    if fields.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationErrors::new(fields))
+    }
+}
+
+// Rules new passwords have to follow
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    // Bounds the work of hashing a password
+    pub max_length: usize,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            min_length: std::env::var("PASSWORD_MIN_LENGTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.min_length),
+            max_length: std::env::var("PASSWORD_MAX_LENGTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_length),
+        }
+    }
+}
+
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+fn is_common_password(password: &str) -> bool {
+    let password = password.to_lowercase();
+    COMMON_PASSWORDS
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .any(|common| common == password)
+}
+
+// Whether the password is, or is built from, the account's email address
+fn contains_email(password: &str, email: &str) -> bool {
+    let password = password.to_lowercase();
+    let email = email.trim().to_lowercase();
+    if email.is_empty() {
+        return false;
+    }
+    let local_part = email.split('@').next().unwrap_or_default();
+    password.contains(&email) || (local_part.chars().count() >= 3 && password.contains(local_part))
+}
+
+// Check a new password against the policy. `email` is the address of the
+// account the password is for, when it has one.
+pub fn validate_password(
+    password: &str,
+    email: Option<&str>,
+    policy: &PasswordPolicy,
+) -> Result<(), ValidationErrors> {
+    let length = password.chars().count();
+    let error = if password.trim().chars().count() < policy.min_length {
+        Some((
+            "too_short",
+            format!(
+                "Password must be at least {} characters long",
+                policy.min_length
+            ),
+        ))
+    } else if length > policy.max_length {
+        Some((
+            "too_long",
+            format!(
+                "Password must be at most {} characters long",
+                policy.max_length
+            ),
+        ))
+    } else if is_common_password(password) {
+        Some((
+            "common",
+            "Password is too common, choose one that is harder to guess".to_string(),
+        ))
+    } else if email.is_some_and(|email| contains_email(password, email)) {
+        Some((
+            "contains_email",
+            "Password must not contain your email address".to_string(),
+        ))
+    } else {
+        None
+    };
+
+    match error {
+        Some((code, message)) => Err(ValidationErrors::single("password", code, message)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::PolicyType;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+    use time::{Date, Month, PrimitiveDateTime, Time};
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn datetime(day: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2026, Month::March, day).unwrap(),
+            Time::MIDNIGHT,
+        )
+    }
+
+    fn template() -> PolicyTemplate {
+        PolicyTemplate {
+            id: 2,
+            template_name: "Rain Event Insurance".to_string(),
+            description: None,
+            policy_type: PolicyType::Rain,
+            default_conditions: None,
+            min_coverage_amount: dec("0.50"),
+            max_coverage_amount: dec("5.00"),
+            base_premium_rate: dec("0.03"),
+            is_active: Some(true),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn policy_request() -> CreateInsurancePolicyRequest {
+        CreateInsurancePolicyRequest {
+            policy_template_id: Some(2),
+            policy_name: "Rain Event Insurance".to_string(),
+            policy_type: PolicyType::Rain,
+            location_latitude: dec("40.7128"),
+            location_longitude: dec("-74.0060"),
+            location_h3_index: Some("872a1072bffffff".to_string()),
+            location_name: None,
+            coverage_amount: dec("5.00"),
+            premium_amount: dec("0.75"),
+            currency: Some("ETH".to_string()),
+            start_date: datetime(1),
+            end_date: datetime(31),
+            weather_station_id: None,
+            smart_contract_address: None,
+            purchase_transaction_hash: None,
+            quote_token: None,
+            condition_overrides: Vec::new(),
+        }
+    }
+
+    fn codes(result: Result<(), ValidationErrors>) -> Vec<(String, String)> {
+        result
+            .unwrap_err()
+            .fields
+            .into_iter()
+            .map(|f| (f.field, f.code))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_purchase() {
+        assert!(validate_policy_purchase(&policy_request(), &template()).is_ok());
+
+        // The minimum premium is exactly a tenth of the coverage
+        let mut request = policy_request();
+        request.premium_amount = dec("0.50");
+        assert!(validate_policy_purchase(&request, &template()).is_ok());
+    }
+
+    #[test]
+    fn test_purchase_outside_template_limits() {
+        let mut request = policy_request();
+        request.coverage_amount = dec("50.00");
+        request.premium_amount = dec("1.00");
+        request.policy_type = PolicyType::Drought;
+        request.end_date = datetime(1);
+
+        assert_eq!(
+            codes(validate_policy_purchase(&request, &template())),
+            vec![
+                ("policy_type".to_string(), "mismatch".to_string()),
+                ("coverage_amount".to_string(), "out_of_range".to_string()),
+                ("premium_amount".to_string(), "too_low".to_string()),
+                ("end_date".to_string(), "before_start_date".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_purchase_from_inactive_template() {
+        let mut inactive = template();
+        inactive.is_active = Some(false);
+
+        assert_eq!(
+            codes(validate_policy_purchase(&policy_request(), &inactive)),
+            vec![("policy_template_id".to_string(), "inactive".to_string())]
+        );
+    }
+
+    fn password_code(password: &str, email: Option<&str>) -> Option<String> {
+        validate_password(password, email, &PasswordPolicy::default())
+            .err()
+            .map(|errors| {
+                assert_eq!(errors.fields.len(), 1);
+                assert_eq!(errors.fields[0].field, "password");
+                errors.fields[0].code.clone()
+            })
+    }
+
+    #[test]
+    fn test_password_policy() {
+        assert_eq!(password_code("correct horse battery", None), None);
+        assert_eq!(
+            password_code("Tr0ub4dor&3", Some("alice@example.com")),
+            None
+        );
+
+        assert_eq!(password_code("short", None).as_deref(), Some("too_short"));
+        // Surrounding whitespace doesn't count towards the length
+        assert_eq!(
+            password_code("  abc12  ", None).as_deref(),
+            Some("too_short")
+        );
+        assert_eq!(
+            password_code(&"a".repeat(129), None).as_deref(),
+            Some("too_long")
+        );
+        assert_eq!(
+            password_code("Password123", None).as_deref(),
+            Some("common")
+        );
+        // Entries far down the list count as much as the first ones
+        assert_eq!(
+            password_code("Bulldog2021", None).as_deref(),
+            Some("common")
+        );
+        assert_eq!(password_code("rainbow23", None).as_deref(), Some("common"));
+        assert_eq!(
+            password_code("alice@example.com", Some("Alice@Example.com")).as_deref(),
+            Some("contains_email")
+        );
+        assert_eq!(
+            password_code("alice-2026-rocks", Some("alice@example.com")).as_deref(),
+            Some("contains_email")
+        );
+        // Very short local parts are too likely to appear by chance
+        assert_eq!(password_code("jollyroger42", Some("jo@example.com")), None);
+    }
+}
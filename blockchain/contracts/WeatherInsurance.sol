@@ -16,8 +16,13 @@ contract WeatherInsurance {
     }
 
     mapping(uint => Policy) public policies;
+    mapping(uint => uint) public premiums;
     uint public policyCount = 0;
 
+    event PolicyCreated(uint indexed policyId, address indexed user, uint payout, uint startTime, uint endTime);
+    event PolicyTriggered(uint indexed policyId, uint payout, bool triggered);
+    event PolicyCancelled(uint indexed policyId, address indexed user, uint refund);
+
     constructor() {
         owner = msg.sender;
     }
@@ -26,7 +31,7 @@ contract WeatherInsurance {
         require(duration > 0, "Duration must be greater than 0");
         require(payout > 0, "Payout must be greater than 0");
         require(msg.value >= payout / 10, "Premium too low");
-        require(bytes(eventType).length > 0, "Event type cannot be empty");
+        require(isSupportedEventType(eventType), "Unsupported event type");
         require(bytes(h3HexId).length > 0, "H3 hex ID cannot be empty");
 
         policies[policyCount] = Policy(
@@ -39,6 +44,8 @@ contract WeatherInsurance {
             eventType,
             h3HexId
         );
+        premiums[policyCount] = msg.value;
+        emit PolicyCreated(policyCount, msg.sender, payout, block.timestamp, block.timestamp + duration);
         policyCount++;
     }
 
@@ -47,13 +54,52 @@ contract WeatherInsurance {
         require(!p.paid, "Already settled");
         require(msg.sender == owner, "Only owner (oracle) can trigger");
 
-        if (
-            keccak256(bytes(p.eventType)) == keccak256("TEMP_BELOW") &&
-            observed < p.threshold
-        ) {
+        bool triggered;
+        if (paysBelowThreshold(p.eventType)) {
+            triggered = observed < p.threshold;
+        } else {
+            triggered = observed > p.threshold;
+        }
+        if (triggered) {
             payable(p.user).transfer(p.payout);
         }
 
         p.paid = true;
+        emit PolicyTriggered(policyId, triggered ? p.payout : 0, triggered);
+    }
+
+    // Settle an unpaid policy without a payout and return `refund` of its
+    // premium to the buyer. The refund is worked out off chain (the unused
+    // share of the premium less the cancellation fee) and capped here at the
+    // premium paid.
+    function cancel(uint policyId, uint refund) public {
+        Policy storage p = policies[policyId];
+        require(msg.sender == owner, "Only owner can cancel");
+        require(p.user != address(0), "Unknown policy");
+        require(!p.paid, "Already settled");
+        require(refund <= premiums[policyId], "Refund exceeds premium");
+
+        p.paid = true;
+        if (refund > 0) {
+            payable(p.user).transfer(refund);
+        }
+        emit PolicyCancelled(policyId, p.user, refund);
+    }
+
+    // TEMP_BELOW and RAIN_BELOW pay when the observation is below the
+    // threshold, TEMP_ABOVE, RAIN_ABOVE and WIND_ABOVE when it is above
+    function isSupportedEventType(string memory eventType) public pure returns (bool) {
+        return paysBelowThreshold(eventType) ||
+            isEventType(eventType, "TEMP_ABOVE") ||
+            isEventType(eventType, "RAIN_ABOVE") ||
+            isEventType(eventType, "WIND_ABOVE");
+    }
+
+    function paysBelowThreshold(string memory eventType) internal pure returns (bool) {
+        return isEventType(eventType, "TEMP_BELOW") || isEventType(eventType, "RAIN_BELOW");
+    }
+
+    function isEventType(string memory eventType, string memory expected) internal pure returns (bool) {
+        return keccak256(bytes(eventType)) == keccak256(bytes(expected));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,159 @@
+const { loadFixture } = require("@nomicfoundation/hardhat-toolbox/network-helpers");
+const { anyValue } = require("@nomicfoundation/hardhat-chai-matchers/withArgs");
+const { expect } = require("chai");
+
+describe("WeatherInsurance", function () {
+  const ONE_DAY = 24 * 60 * 60;
+  const PAYOUT = ethers.parseEther("1");
+  const PREMIUM = ethers.parseEther("0.2");
+  const H3 = "872a1072bffffff";
+
+  async function deployFixture() {
+    const [owner, buyer, other] = await ethers.getSigners();
+    const WeatherInsurance = await ethers.getContractFactory("WeatherInsurance");
+    const insurance = await WeatherInsurance.deploy();
+
+    return { insurance, owner, buyer, other };
+  }
+
+  async function boughtPolicyFixture() {
+    const fixture = await deployFixture();
+    await fixture.insurance
+      .connect(fixture.buyer)
+      .buyPolicy(30 * ONE_DAY, PAYOUT, 10, "RAIN_ABOVE", H3, { value: PREMIUM });
+    return fixture;
+  }
+
+  describe("buyPolicy", function () {
+    it("Should record the premium and emit PolicyCreated", async function () {
+      const { insurance, buyer } = await loadFixture(deployFixture);
+
+      await expect(
+        insurance
+          .connect(buyer)
+          .buyPolicy(30 * ONE_DAY, PAYOUT, 10, "RAIN_ABOVE", H3, { value: PREMIUM })
+      )
+        .to.emit(insurance, "PolicyCreated")
+        .withArgs(0, buyer.address, PAYOUT, anyValue, anyValue);
+
+      expect(await insurance.premiums(0)).to.equal(PREMIUM);
+      expect(await insurance.policyCount()).to.equal(1);
+    });
+
+    it("Should accept every supported event type", async function () {
+      const { insurance, buyer } = await loadFixture(deployFixture);
+
+      for (const eventType of [
+        "TEMP_BELOW",
+        "TEMP_ABOVE",
+        "RAIN_BELOW",
+        "RAIN_ABOVE",
+        "WIND_ABOVE",
+      ]) {
+        expect(await insurance.isSupportedEventType(eventType)).to.equal(true);
+        await expect(
+          insurance
+            .connect(buyer)
+            .buyPolicy(ONE_DAY, PAYOUT, 10, eventType, H3, { value: PREMIUM })
+        ).not.to.be.reverted;
+      }
+    });
+
+    it("Should reject unsupported event types", async function () {
+      const { insurance, buyer } = await loadFixture(deployFixture);
+
+      for (const eventType of ["rainfall", "WIND_BELOW", "rain_above", ""]) {
+        expect(await insurance.isSupportedEventType(eventType)).to.equal(false);
+        await expect(
+          insurance
+            .connect(buyer)
+            .buyPolicy(ONE_DAY, PAYOUT, 10, eventType, H3, { value: PREMIUM })
+        ).to.be.revertedWith("Unsupported event type");
+      }
+    });
+  });
+
+  describe("trigger", function () {
+    it("Should pay below-threshold policies when the observation is below", async function () {
+      const { insurance, owner, buyer } = await loadFixture(deployFixture);
+      // Payouts come out of the premiums held by the contract
+      await insurance
+        .connect(buyer)
+        .buyPolicy(ONE_DAY, PREMIUM, 10, "RAIN_BELOW", H3, { value: PREMIUM });
+
+      await expect(insurance.connect(owner).trigger(0, 9))
+        .to.emit(insurance, "PolicyTriggered")
+        .withArgs(0, PREMIUM, true);
+    });
+
+    it("Should not pay above-threshold policies when the observation is below", async function () {
+      const { insurance, owner } = await loadFixture(boughtPolicyFixture);
+
+      await expect(insurance.connect(owner).trigger(0, 9))
+        .to.emit(insurance, "PolicyTriggered")
+        .withArgs(0, 0, false);
+    });
+  });
+
+  describe("cancel", function () {
+    it("Should only let the owner cancel", async function () {
+      const { insurance, buyer, other } = await loadFixture(boughtPolicyFixture);
+
+      await expect(insurance.connect(buyer).cancel(0, 0)).to.be.revertedWith(
+        "Only owner can cancel"
+      );
+      await expect(insurance.connect(other).cancel(0, 0)).to.be.revertedWith(
+        "Only owner can cancel"
+      );
+    });
+
+    it("Should refund the buyer and emit PolicyCancelled", async function () {
+      const { insurance, owner, buyer } = await loadFixture(boughtPolicyFixture);
+      const refund = ethers.parseEther("0.15");
+
+      await expect(insurance.connect(owner).cancel(0, refund))
+        .to.emit(insurance, "PolicyCancelled")
+        .withArgs(0, buyer.address, refund)
+        .and.to.changeEtherBalance(buyer, refund);
+      expect((await insurance.policies(0)).paid).to.equal(true);
+    });
+
+    it("Should cap the refund at the premium paid", async function () {
+      const { insurance, owner } = await loadFixture(boughtPolicyFixture);
+
+      await expect(
+        insurance.connect(owner).cancel(0, PREMIUM + 1n)
+      ).to.be.revertedWith("Refund exceeds premium");
+      await expect(insurance.connect(owner).cancel(0, PREMIUM)).not.to.be.reverted;
+    });
+
+    it("Should reject settling a policy twice", async function () {
+      const { insurance, owner } = await loadFixture(boughtPolicyFixture);
+      await insurance.connect(owner).cancel(0, 0);
+
+      await expect(insurance.connect(owner).cancel(0, 0)).to.be.revertedWith(
+        "Already settled"
+      );
+      await expect(insurance.connect(owner).trigger(0, 100)).to.be.revertedWith(
+        "Already settled"
+      );
+    });
+
+    it("Should reject cancelling a triggered policy", async function () {
+      const { insurance, owner } = await loadFixture(boughtPolicyFixture);
+      await insurance.connect(owner).trigger(0, 0);
+
+      await expect(insurance.connect(owner).cancel(0, 0)).to.be.revertedWith(
+        "Already settled"
+      );
+    });
+
+    it("Should reject unknown policies", async function () {
+      const { insurance, owner } = await loadFixture(deployFixture);
+
+      await expect(insurance.connect(owner).cancel(7, 0)).to.be.revertedWith(
+        "Unknown policy"
+      );
+    });
+  });
+});
@@ -0,0 +1,152 @@
+// Event types understood by the WeatherInsurance contract and the mapping
+// from policy template types to them. Shared by the backend, which verifies
+// purchases, and the oracle, which settles claims, so both agree with the
+// contract on how a policy pays out.
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContractEventType {
+    TempBelow,
+    TempAbove,
+    RainAbove,
+    RainBelow,
+    WindAbove,
+}
+
+pub const ALL_EVENT_TYPES: [ContractEventType; 5] = [
+    ContractEventType::TempBelow,
+    ContractEventType::TempAbove,
+    ContractEventType::RainAbove,
+    ContractEventType::RainBelow,
+    ContractEventType::WindAbove,
+];
+
+impl ContractEventType {
+    // The string passed to buyPolicy and stored in the contract
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TempBelow => "TEMP_BELOW",
+            Self::TempAbove => "TEMP_ABOVE",
+            Self::RainAbove => "RAIN_ABOVE",
+            Self::RainBelow => "RAIN_BELOW",
+            Self::WindAbove => "WIND_ABOVE",
+        }
+    }
+
+    // Whether the contract pays when the observation is above the threshold
+    // rather than below it
+    pub fn pays_above_threshold(&self) -> bool {
+        matches!(self, Self::TempAbove | Self::RainAbove | Self::WindAbove)
+    }
+
+    // Mirrors the comparison made by the contract's trigger function
+    pub fn is_triggered(&self, observed: i64, threshold: i64) -> bool {
+        if self.pays_above_threshold() {
+            observed > threshold
+        } else {
+            observed < threshold
+        }
+    }
+}
+
+impl fmt::Display for ContractEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownEventType(pub String);
+
+impl fmt::Display for UnknownEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown contract event type: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownEventType {}
+
+impl FromStr for ContractEventType {
+    type Err = UnknownEventType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ALL_EVENT_TYPES
+            .into_iter()
+            .find(|event_type| event_type.as_str() == s)
+            .ok_or_else(|| UnknownEventType(s.to_string()))
+    }
+}
+
+// The contract event type a policy of the given template type is bought with
+pub fn event_type_for_policy_type(policy_type: &str) -> Option<ContractEventType> {
+    match policy_type {
+        "drought" => Some(ContractEventType::RainBelow),
+        "rain" => Some(ContractEventType::RainAbove),
+        "temperature" | "freeze" => Some(ContractEventType::TempBelow),
+        "heat" => Some(ContractEventType::TempAbove),
+        "wind" | "storm" => Some(ContractEventType::WindAbove),
+        _ => None,
+    }
+}
+
+// Postgres advisory lock held while a transaction from the contract owner is
+// signed and broadcast. The oracle's triggers and the backend's refunds are
+// both sent from the owner key, so without it they can take the same nonce.
+pub const OWNER_NONCE_LOCK: i64 = 0x5745_4154_4845_5201;
+
+// Hold the contract owner's nonce lock until the end of the transaction
+pub async fn lock_owner_nonce(executor: impl sqlx::PgExecutor<'_>) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(OWNER_NONCE_LOCK)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_policy_types_are_mapped() {
+        assert_eq!(
+            event_type_for_policy_type("drought"),
+            Some(ContractEventType::RainBelow)
+        );
+        assert_eq!(
+            event_type_for_policy_type("rain"),
+            Some(ContractEventType::RainAbove)
+        );
+        assert_eq!(
+            event_type_for_policy_type("temperature"),
+            Some(ContractEventType::TempBelow)
+        );
+        assert_eq!(
+            event_type_for_policy_type("wind"),
+            Some(ContractEventType::WindAbove)
+        );
+        assert_eq!(event_type_for_policy_type("earthquake"), None);
+    }
+
+    #[test]
+    fn test_event_types_round_trip() {
+        for event_type in ALL_EVENT_TYPES {
+            assert_eq!(event_type.as_str().parse(), Ok(event_type));
+        }
+        assert!("rainfall".parse::<ContractEventType>().is_err());
+    }
+
+    #[test]
+    fn test_trigger_direction_matches_contract() {
+        assert!(ContractEventType::TempBelow.is_triggered(-3, -2));
+        assert!(!ContractEventType::TempBelow.is_triggered(-2, -2));
+        assert!(ContractEventType::RainBelow.is_triggered(4, 5));
+        assert!(ContractEventType::RainAbove.is_triggered(11, 10));
+        assert!(!ContractEventType::RainAbove.is_triggered(10, 10));
+        assert!(ContractEventType::TempAbove.is_triggered(36, 35));
+        assert!(ContractEventType::WindAbove.is_triggered(61, 60));
+        assert!(!ContractEventType::WindAbove.is_triggered(59, 60));
+    }
+}
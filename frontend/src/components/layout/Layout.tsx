@@ -5,6 +5,8 @@ import AvailablePolicies from '../../pages/AvailablePolicies';
 import MyPolicies from '../../pages/MyPolicies';
 import Login from '../../pages/Login';
 import Register from '../../pages/Register';
+import VerifyEmail from '../../pages/VerifyEmail';
+import ResetPassword from '../../pages/ResetPassword';
 import { useWallet } from '../../context/WalletContext';
 import { useAuth } from '../../context/AuthContext';
 
@@ -27,6 +29,9 @@ const Layout = () => {
       <Routes>
         <Route path="/login" element={<Login />} />
         <Route path="/register" element={<Register />} />
+        <Route path="/verify-email" element={<VerifyEmail />} />
+        <Route path="/forgot-password" element={<ResetPassword />} />
+        <Route path="/reset-password" element={<ResetPassword />} />
         <Route path="*" element={<Navigate to="/login" replace />} />
       </Routes>
     );
@@ -39,6 +44,7 @@ const Layout = () => {
       <main className="container mx-auto px-4 py-8">
         <Routes>
           <Route path="/" element={<Home />} />
+          <Route path="/verify-email" element={<VerifyEmail />} />
           <Route
             path="/available-policies"
             element={isConnected ? <AvailablePolicies /> : <Navigate to="/" />}
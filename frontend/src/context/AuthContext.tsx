@@ -1,7 +1,11 @@
 import { createContext, useContext, useState, useEffect, useRef, type ReactNode } from 'react';
 import { isTokenExpired } from '../utils/auth';
 import { useNotifications } from './NotificationContext';
-import { validateTokenWithServer } from '../services/authService';
+import {
+  logoutSession,
+  refreshSession,
+  validateTokenWithServer,
+} from '../services/authService';
 
 interface User {
   id: string;
@@ -12,7 +16,7 @@ interface User {
 interface AuthContextType {
   isAuthenticated: boolean;
   user: User | null;
-  login: (token: string, userData?: User) => void;
+  login: (token: string, userData?: User, refreshToken?: string) => void;
   logout: () => void;
   loading: boolean;
 }
@@ -46,8 +50,8 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
     
     if (token && userData) {
       try {
-        // Check if token is expired
-        if (isTokenExpired(token)) {
+        // Check if token is expired and cannot be refreshed
+        if (isTokenExpired(token) && !localStorage.getItem('refreshToken')) {
           console.log('Token expired, logging out user');
           localStorage.removeItem('authToken');
           localStorage.removeItem('userData');
@@ -102,8 +106,10 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
 
     // First check client-side expiry (fast check)
     if (isTokenExpired(token)) {
-      console.log('⏰ Client-side token expiry detected');
-      handleTokenExpiry();
+      console.log('⏰ Client-side token expiry detected, refreshing session');
+      if (!(await refreshSession())) {
+        handleTokenExpiry();
+      }
       return;
     }
 
@@ -113,8 +119,10 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
     console.log('📡 Server validation response:', { isValid });
     
     if (!isValid) {
-      console.log('❌ Server says token is invalid, logging out');
-      handleTokenExpiry();
+      console.log('❌ Server says token is invalid, refreshing session');
+      if (!(await refreshSession())) {
+        handleTokenExpiry();
+      }
     } else {
       console.log('✅ Token is valid');
     }
@@ -162,8 +170,11 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
     };
   };
 
-  const login = (token: string, userData?: User) => {
+  const login = (token: string, userData?: User, refreshToken?: string) => {
     localStorage.setItem('authToken', token);
+    if (refreshToken) {
+      localStorage.setItem('refreshToken', refreshToken);
+    }
     if (userData) {
       localStorage.setItem('userData', JSON.stringify(userData));
     }
@@ -177,7 +188,9 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
   };
 
   const logout = () => {
+    logoutSession();
     localStorage.removeItem('authToken');
+    localStorage.removeItem('refreshToken');
     localStorage.removeItem('userData');
     localStorage.removeItem('walletAddress');
     setIsAuthenticated(false);
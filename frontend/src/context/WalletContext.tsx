@@ -83,7 +83,10 @@ export const WalletProvider = ({ children }: { children: ReactNode }) => {
       const token = localStorage.getItem('authToken');
       if (token) {
         try {
-          await updateWalletAddress(walletAddress);
+          const { chainId } = await provider.getNetwork();
+          await updateWalletAddress(walletAddress, Number(chainId), (message) =>
+            signer.signMessage(message)
+          );
           console.log('✅ Wallet address synced with backend');
         } catch (error) {
           console.warn('⚠️ Failed to sync wallet address with backend:', error);
@@ -7,12 +7,13 @@ import type { PolicyTemplate } from '../types';
 import {
   fetchPolicyTemplates,
   createPolicy,
+  createQuote,
   type CreatePolicyRequest,
 } from '../services/policyService';
 import { useWallet } from '../context/WalletContext';
 import { useNotifications } from '../context/NotificationContext';
 import { BrowserProvider, parseEther } from 'ethers';
-import { getContract } from '../utils/contract';
+import { EVENT_TYPE_BY_POLICY_TYPE, getContract } from '../utils/contract';
 
 const AvailablePolicies = () => {
   const [currentStep, setCurrentStep] = useState(1); // Start on step 1 for location selection
@@ -72,15 +73,34 @@ const AvailablePolicies = () => {
       const provider = new BrowserProvider(window.ethereum);
       const contract = await getContract(provider);
 
+      // Price the policy for the next 30 days
+      const startDate = new Date();
+      const endDate = new Date();
+      endDate.setDate(startDate.getDate() + 30);
+      const quote = await createQuote({
+        policy_template_id: template.id,
+        location_h3_index: String(locationData?.h3Index),
+        coverage_amount: template.max_coverage_amount,
+        start_date: startDate.toISOString(),
+        end_date: endDate.toISOString(),
+      });
+
       // Convert values to appropriate types
-      const duration = BigInt(30 * 24 * 60 * 60); // Convert to BigInt
-      const payout = parseEther(template.max_coverage_amount);
+      const duration = BigInt(
+        Math.round(
+          (Date.parse(quote.end_date) - Date.parse(quote.start_date)) / 1000
+        )
+      );
+      const payout = parseEther(quote.coverage_amount);
       const threshold = BigInt(
         template.default_conditions.conditions[0].threshold
       );
-      const eventType = String(template.default_conditions.conditions[0].type);
+      const eventType = EVENT_TYPE_BY_POLICY_TYPE[template.policy_type];
+      if (!eventType) {
+        throw new Error(`Unsupported policy type: ${template.policy_type}`);
+      }
       const h3HexId = String(locationData?.h3Index);
-      const premium = payout / BigInt(10);
+      const premium = parseEther(quote.premium_amount);
 
       console.log('Purchasing policy with the following details:', {
         duration,
@@ -112,10 +132,6 @@ const AvailablePolicies = () => {
 
       // Create policy in database after successful blockchain transaction
       try {
-        const startDate = new Date();
-        const endDate = new Date();
-        endDate.setDate(startDate.getDate() + 30); // 30 days from now
-
         const policyData: CreatePolicyRequest = {
           policy_template_id: template.id,
           policy_name: `${template.template_name} - ${locationData?.latitude}, ${locationData?.longitude}`,
@@ -124,13 +140,15 @@ const AvailablePolicies = () => {
           location_longitude: parseFloat(locationData?.longitude || '0'),
           location_h3_index: locationData?.h3Index,
           location_name: `${locationData?.latitude}, ${locationData?.longitude}`,
-          coverage_amount: parseFloat(template.max_coverage_amount),
-          premium_amount: parseFloat(template.max_coverage_amount) * 0.1, // 10% premium
-          currency: 'ETH',
-          start_date: startDate.toISOString(),
-          end_date: endDate.toISOString(),
+          coverage_amount: parseFloat(quote.coverage_amount),
+          premium_amount: parseFloat(quote.premium_amount),
+          currency: quote.currency,
+          start_date: quote.start_date,
+          end_date: quote.end_date,
+          weather_station_id: quote.weather_station_id,
           smart_contract_address: await contract.getAddress(),
           purchase_transaction_hash: tx.hash,
+          quote_token: quote.quote_token,
         };
 
         const createdPolicy = await createPolicy(policyData);
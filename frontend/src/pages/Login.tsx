@@ -66,6 +66,30 @@ const Login = () => {
         body: JSON.stringify(formData),
       });
 
+      if (response.status === 429) {
+        const retryAfter = response.headers.get('Retry-After') ?? '60';
+        const message = `Too many sign-in attempts. Please try again in ${retryAfter} seconds.`;
+        setError(message);
+        addNotification({
+          type: 'error',
+          title: 'Login Failed',
+          message,
+        });
+        return;
+      }
+
+      if (response.status === 403) {
+        const message =
+          'Please verify your email address using the link we sent you before signing in.';
+        setError(message);
+        addNotification({
+          type: 'error',
+          title: 'Email Not Verified',
+          message,
+        });
+        return;
+      }
+
       const data: LoginResponse = await response.json();
 
       if (response.ok) {
@@ -77,10 +101,14 @@ const Login = () => {
         });
         // Use the AuthContext to handle login
         if (data.token) {
-          login(data.token, {
-            id: 'user-' + Date.now(),
-            email: formData.email,
-          });
+          login(
+            data.token,
+            {
+              id: 'user-' + Date.now(),
+              email: formData.email,
+            },
+            data.refresh_token
+          );
           // Redirect to home page after successful login
           setTimeout(() => {
             navigate('/');
@@ -219,6 +247,14 @@ const Login = () => {
 
           <div className="text-center">
             <p className="text-sm text-gray-600">
+              <Link
+                to="/forgot-password"
+                className="font-medium text-orange-600 hover:text-orange-500"
+              >
+                Forgot your password?
+              </Link>
+            </p>
+            <p className="mt-2 text-sm text-gray-600">
               Don't have an account?{' '}
               <Link
                 to="/register"
@@ -43,7 +43,7 @@ const MyPolicies = () => {
       try {
         setLoading(true);
         const userPolicies = await fetchUserPolicies();
-        setPolicies(userPolicies);
+        setPolicies(userPolicies.policies);
       } catch (err) {
         setError(err instanceof Error ? err.message : 'Failed to load policies');
       } finally {
@@ -49,8 +49,8 @@ const Register = () => {
       setError('Password is required');
       return false;
     }
-    if (formData.password.length < 6) {
-      setError('Password must be at least 6 characters long');
+    if (formData.password.trim().length < 8) {
+      setError('Password must be at least 8 characters long');
       return false;
     }
     if (formData.password !== confirmPassword) {
@@ -81,7 +81,9 @@ const Register = () => {
       const data: RegisterResponse = await response.json();
 
       if (response.ok) {
-        setSuccess('Account created successfully!');
+        setSuccess(
+          'Account created! Check your email for a link to verify your address before signing in.'
+        );
         // Use the AuthContext to handle login if token is provided
         if (data.token) {
           login(data.token, {
@@ -97,11 +99,16 @@ const Register = () => {
           // If no token provided, redirect to login page
           setTimeout(() => {
             navigate('/login');
-          }, 1000);
+          }, 3000);
         }
         console.log('Registration successful:', data);
       } else {
-        setError(data.message || 'Registration failed. Please try again.');
+        setError(
+          data.fields?.[0]?.message ||
+            data.error ||
+            data.message ||
+            'Registration failed. Please try again.'
+        );
       }
     } catch (err) {
       setError('Network error. Please check your connection and try again.');
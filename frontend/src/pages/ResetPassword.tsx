@@ -0,0 +1,153 @@
+import { useState } from 'react';
+import { Link, useNavigate, useSearchParams } from 'react-router-dom';
+import { requestPasswordReset, resetPassword } from '../services/authService';
+
+// Asks for a reset link, or sets a new password when opened from one
+const ResetPassword = () => {
+  const navigate = useNavigate();
+  const [searchParams] = useSearchParams();
+  const token = searchParams.get('token');
+  const [email, setEmail] = useState('');
+  const [password, setPassword] = useState('');
+  const [confirmPassword, setConfirmPassword] = useState('');
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState<string>('');
+  const [success, setSuccess] = useState<string>('');
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError('');
+    setSuccess('');
+
+    if (token) {
+      if (password.trim().length < 8) {
+        setError('Password must be at least 8 characters long');
+        return;
+      }
+      if (password !== confirmPassword) {
+        setError('Passwords do not match');
+        return;
+      }
+    } else if (!email.includes('@')) {
+      setError('Please enter a valid email address');
+      return;
+    }
+
+    setIsLoading(true);
+    try {
+      if (token) {
+        await resetPassword(token, password);
+        setSuccess('Your password has been changed. Redirecting to sign in...');
+        setTimeout(() => {
+          navigate('/login');
+        }, 2000);
+      } else {
+        await requestPasswordReset(email);
+        setSuccess(
+          'If an account exists for that email, we have sent it a link to reset the password.'
+        );
+      }
+    } catch (err) {
+      setError((err as Error).message);
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  const inputClassName =
+    'appearance-none rounded-md relative block w-full px-3 py-2 border border-gray-300 placeholder-gray-500 text-gray-900 focus:outline-none focus:ring-orange-500 focus:border-orange-500 focus:z-10 sm:text-sm';
+
+  return (
+    <div className="min-h-screen flex items-center justify-center bg-gray-50 py-12 px-4 sm:px-6 lg:px-8">
+      <div className="max-w-md w-full space-y-8">
+        <div>
+          <h2 className="mt-6 text-center text-3xl font-extrabold text-gray-900">
+            {token ? 'Choose a new password' : 'Reset your password'}
+          </h2>
+          {!token && (
+            <p className="mt-2 text-center text-sm text-gray-600">
+              Enter your email and we will send you a link to reset your
+              password
+            </p>
+          )}
+        </div>
+
+        <form className="mt-8 space-y-4" onSubmit={handleSubmit}>
+          {token ? (
+            <>
+              <input
+                name="password"
+                type="password"
+                autoComplete="new-password"
+                required
+                className={inputClassName}
+                placeholder="New password"
+                value={password}
+                onChange={(e) => setPassword(e.target.value)}
+                disabled={isLoading}
+              />
+              <input
+                name="confirmPassword"
+                type="password"
+                autoComplete="new-password"
+                required
+                className={inputClassName}
+                placeholder="Confirm new password"
+                value={confirmPassword}
+                onChange={(e) => setConfirmPassword(e.target.value)}
+                disabled={isLoading}
+              />
+            </>
+          ) : (
+            <input
+              name="email"
+              type="email"
+              autoComplete="email"
+              required
+              className={inputClassName}
+              placeholder="Email address"
+              value={email}
+              onChange={(e) => setEmail(e.target.value)}
+              disabled={isLoading}
+            />
+          )}
+
+          {error && (
+            <div className="rounded-md bg-red-50 p-4">
+              <h3 className="text-sm font-medium text-red-800">{error}</h3>
+            </div>
+          )}
+
+          {success && (
+            <div className="rounded-md bg-green-50 p-4">
+              <h3 className="text-sm font-medium text-green-800">{success}</h3>
+            </div>
+          )}
+
+          <button
+            type="submit"
+            disabled={isLoading}
+            className="group relative w-full flex justify-center py-2 px-4 border border-transparent text-sm font-medium rounded-md text-white bg-orange-500 hover:bg-orange-600 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-orange-500 disabled:opacity-50 disabled:cursor-not-allowed"
+          >
+            {isLoading
+              ? 'Please wait...'
+              : token
+                ? 'Change password'
+                : 'Send reset link'}
+          </button>
+
+          <p className="text-center text-sm text-gray-600">
+            <Link
+              to="/login"
+              className="font-medium text-orange-600 hover:text-orange-500"
+            >
+              Back to sign in
+            </Link>
+          </p>
+        </form>
+      </div>
+    </div>
+  );
+};
+
+export default ResetPassword;
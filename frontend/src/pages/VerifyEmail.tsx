@@ -0,0 +1,71 @@
+import { useEffect, useRef, useState } from 'react';
+import { Link, useSearchParams } from 'react-router-dom';
+import { verifyEmail } from '../services/authService';
+
+// Landing page of the link in the verification email
+const VerifyEmail = () => {
+  const [searchParams] = useSearchParams();
+  const token = searchParams.get('token');
+  const [status, setStatus] = useState<'verifying' | 'verified' | 'failed'>(
+    'verifying'
+  );
+  const [error, setError] = useState<string>('');
+  // Tokens are single use, so only send it once even if the effect reruns
+  const sent = useRef(false);
+
+  useEffect(() => {
+    if (sent.current) return;
+    sent.current = true;
+
+    if (!token) {
+      setStatus('failed');
+      setError('This link is missing its verification token.');
+      return;
+    }
+    verifyEmail(token)
+      .then(() => setStatus('verified'))
+      .catch((err: Error) => {
+        setStatus('failed');
+        setError(err.message);
+      });
+  }, [token]);
+
+  return (
+    <div className="min-h-screen flex items-center justify-center bg-gray-50 py-12 px-4 sm:px-6 lg:px-8">
+      <div className="max-w-md w-full space-y-8 text-center">
+        <h2 className="mt-6 text-3xl font-extrabold text-gray-900">
+          Verify your email
+        </h2>
+
+        {status === 'verifying' && (
+          <p className="text-sm text-gray-600">Verifying your email address...</p>
+        )}
+
+        {status === 'verified' && (
+          <div className="rounded-md bg-green-50 p-4">
+            <h3 className="text-sm font-medium text-green-800">
+              Your email address is verified. You can now sign in.
+            </h3>
+          </div>
+        )}
+
+        {status === 'failed' && (
+          <div className="rounded-md bg-red-50 p-4">
+            <h3 className="text-sm font-medium text-red-800">{error}</h3>
+          </div>
+        )}
+
+        <p className="text-sm text-gray-600">
+          <Link
+            to="/login"
+            className="font-medium text-orange-600 hover:text-orange-500"
+          >
+            Go to sign in
+          </Link>
+        </p>
+      </div>
+    </div>
+  );
+};
+
+export default VerifyEmail;
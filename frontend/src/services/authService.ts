@@ -1,3 +1,6 @@
+import { getAddress } from 'ethers';
+import type { LoginResponse } from '../types';
+
 const API_URL = import.meta.env.VITE_API_URL || 'http://localhost:6969';
 
 export const validateTokenWithServer = async (): Promise<boolean> => {
@@ -31,8 +34,64 @@ export const validateTokenWithServer = async (): Promise<boolean> => {
   }
 };
 
+// Exchange the stored refresh token for new tokens. Refresh tokens rotate, so
+// both are replaced. Returns false when the session has ended.
+export const refreshSession = async (): Promise<boolean> => {
+  const refreshToken = localStorage.getItem('refreshToken');
+  if (!refreshToken) {
+    return false;
+  }
+
+  try {
+    const response = await fetch(`${API_URL}/token/refresh`, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify({ refresh_token: refreshToken }),
+    });
+
+    if (!response.ok) {
+      localStorage.removeItem('refreshToken');
+      return false;
+    }
+
+    const data = await response.json();
+    localStorage.setItem('authToken', data.token);
+    localStorage.setItem('refreshToken', data.refresh_token);
+    return true;
+  } catch (error) {
+    console.warn('🌐 Session refresh failed due to network error:', error);
+    return false;
+  }
+};
+
+// Revoke the current session on the server
+export const logoutSession = async (): Promise<void> => {
+  const token = localStorage.getItem('authToken');
+  if (!token) {
+    return;
+  }
+
+  try {
+    await fetch(`${API_URL}/logout`, {
+      method: 'POST',
+      headers: {
+        Authorization: `Bearer ${token}`,
+        'Content-Type': 'application/json',
+      },
+    });
+  } catch (error) {
+    console.warn('🌐 Logout request failed:', error);
+  }
+};
+
+// Link the wallet to the account. The wallet signs a challenge to prove the
+// user owns it.
 export const updateWalletAddress = async (
-  walletAddress: string
+  walletAddress: string,
+  chainId: number,
+  signMessage: (message: string) => Promise<string>
 ): Promise<void> => {
   const token = localStorage.getItem('authToken');
   if (!token) {
@@ -41,13 +100,23 @@ export const updateWalletAddress = async (
 
   try {
     console.log('📝 Updating wallet address:', walletAddress);
+    const message = await buildSiweMessage(
+      walletAddress,
+      chainId,
+      'Link this wallet to your Weather Insurance account'
+    );
+    const signature = await signMessage(message);
     const response = await fetch(`${API_URL}/user/wallet`, {
       method: 'PUT',
       headers: {
         Authorization: `Bearer ${token}`,
         'Content-Type': 'application/json',
       },
-      body: JSON.stringify({ wallet_address: walletAddress }),
+      body: JSON.stringify({
+        wallet_address: walletAddress,
+        message,
+        signature,
+      }),
     });
 
     console.log('📡 Wallet update response:', {
@@ -69,3 +138,109 @@ export const updateWalletAddress = async (
     throw error;
   }
 };
+
+// A nonce from the server, in an EIP-4361 message for the wallet to sign
+const buildSiweMessage = async (
+  address: string,
+  chainId: number,
+  statement: string
+): Promise<string> => {
+  const nonceResponse = await fetch(`${API_URL}/auth/nonce`);
+  if (!nonceResponse.ok) {
+    throw new Error(`Failed to get sign-in nonce: ${nonceResponse.status}`);
+  }
+  const { nonce } = await nonceResponse.json();
+
+  return [
+    `${window.location.host} wants you to sign in with your Ethereum account:`,
+    getAddress(address),
+    '',
+    statement,
+    '',
+    `URI: ${window.location.origin}`,
+    'Version: 1',
+    `Chain ID: ${chainId}`,
+    `Nonce: ${nonce}`,
+    `Issued At: ${new Date().toISOString()}`,
+  ].join('\n');
+};
+
+// Sign in with the connected wallet (EIP-4361). `signMessage` asks the wallet
+// to sign the message, for example an ethers signer's signMessage.
+export const signInWithEthereum = async (
+  address: string,
+  chainId: number,
+  signMessage: (message: string) => Promise<string>
+): Promise<LoginResponse> => {
+  const message = await buildSiweMessage(
+    address,
+    chainId,
+    'Sign in to Weather Insurance'
+  );
+  const signature = await signMessage(message);
+
+  const response = await fetch(`${API_URL}/auth/siwe`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+    },
+    body: JSON.stringify({ message, signature }),
+  });
+  if (!response.ok) {
+    const errorText = await response.text();
+    throw new Error(`Wallet sign-in failed: ${response.status} - ${errorText}`);
+  }
+  return response.json();
+};
+
+const postJson = (path: string, body: unknown) =>
+  fetch(`${API_URL}${path}`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+    },
+    body: JSON.stringify(body),
+  });
+
+// The error message of a failed request, if the server sent one
+const errorMessage = async (response: Response, fallback: string) => {
+  const data = await response.json().catch(() => null);
+  // Validation errors say what is wrong with the field
+  return data?.fields?.[0]?.message || data?.error || fallback;
+};
+
+// Confirm the email address with the token from the verification link
+export const verifyEmail = async (token: string): Promise<void> => {
+  const response = await postJson('/auth/verify-email', { token });
+  if (!response.ok) {
+    throw new Error(
+      await errorMessage(response, 'This link is invalid or has expired.')
+    );
+  }
+};
+
+// Ask for a password reset link. The server answers the same whether or not
+// the account exists.
+export const requestPasswordReset = async (email: string): Promise<void> => {
+  const response = await postJson('/auth/forgot-password', { email });
+  if (!response.ok) {
+    throw new Error(
+      response.status === 429
+        ? 'Too many requests. Please try again later.'
+        : await errorMessage(response, 'Could not send a reset link.')
+    );
+  }
+};
+
+// Set a new password with the token from the reset link
+export const resetPassword = async (
+  token: string,
+  password: string
+): Promise<void> => {
+  const response = await postJson('/auth/reset-password', { token, password });
+  if (!response.ok) {
+    throw new Error(
+      await errorMessage(response, 'This link is invalid or has expired.')
+    );
+  }
+};
@@ -1,3 +1,5 @@
+import type { InsurancePolicy, PolicyDetails, PolicyPage } from '../types';
+
 export interface CreatePolicyRequest {
   policy_template_id?: number;
   policy_name: string;
@@ -14,6 +16,50 @@ export interface CreatePolicyRequest {
   weather_station_id?: string;
   smart_contract_address?: string;
   purchase_transaction_hash: string;
+  quote_token: string;
+  condition_overrides?: PolicyConditionOverride[];
+}
+
+// Replaces fields of the template condition with the same condition_type
+export interface PolicyConditionOverride {
+  condition_type: string;
+  operator?: string;
+  threshold?: string;
+  consecutive_days?: number;
+}
+
+export interface CreateQuoteRequest {
+  policy_template_id: number;
+  location_h3_index: string;
+  coverage_amount: string;
+  start_date: string;
+  end_date: string;
+  weather_station_id?: string;
+  condition_overrides?: PolicyConditionOverride[];
+}
+
+export interface PolicyQuote {
+  quote_token: string;
+  policy_template_id: number;
+  policy_type: string;
+  location_h3_index: string;
+  coverage_amount: string;
+  premium_amount: string;
+  currency: string;
+  start_date: string;
+  end_date: string;
+  trigger_probability?: string;
+  historical_samples: number;
+  // Station the policy will be evaluated against
+  weather_station_id: string;
+  weather_station_ids: string[];
+  expires_at: string;
+}
+
+export interface PolicyRefund {
+  unused_premium: string;
+  fee: string;
+  amount: string;
 }
 
 const API_URL = import.meta.env.VITE_API_URL || 'http://localhost:6969';
@@ -46,6 +92,38 @@ export const fetchPolicyTemplates = async () => {
   }
 };
 
+export const createQuote = async (
+  quoteData: CreateQuoteRequest
+): Promise<PolicyQuote> => {
+  const token = localStorage.getItem('authToken');
+  if (!token) {
+    throw new Error('No authentication token found');
+  }
+
+  try {
+    const response = await fetch(`${API_URL}/quotes`, {
+      method: 'POST',
+      headers: {
+        Authorization: `Bearer ${token}`,
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify(quoteData),
+    });
+
+    if (!response.ok) {
+      const errorText = await response.text();
+      throw new Error(
+        `Failed to create quote: ${response.status} ${response.statusText} - ${errorText}`
+      );
+    }
+
+    return await response.json();
+  } catch (error) {
+    console.error('Error creating quote:', error);
+    throw error;
+  }
+};
+
 export const createPolicy = async (policyData: CreatePolicyRequest) => {
   const token = localStorage.getItem('authToken');
   if (!token) {
@@ -76,14 +154,36 @@ export const createPolicy = async (policyData: CreatePolicyRequest) => {
   }
 };
 
-export const fetchUserPolicies = async () => {
+export interface PolicyListParams {
+  status?: string;
+  policy_type?: string;
+  from?: string;
+  to?: string;
+  h3_prefix?: string;
+  blockchain_verified?: boolean;
+  sort?: 'created_at' | 'end_date' | 'coverage_amount';
+  order?: 'asc' | 'desc';
+  limit?: number;
+  cursor?: string;
+}
+
+export const fetchUserPolicies = async (
+  params: PolicyListParams = {}
+): Promise<PolicyPage> => {
   const token = localStorage.getItem('authToken');
   if (!token) {
     throw new Error('No authentication token found');
   }
 
+  const query = new URLSearchParams();
+  Object.entries(params).forEach(([key, value]) => {
+    if (value !== undefined) {
+      query.set(key, String(value));
+    }
+  });
+
   try {
-    const response = await fetch(`${API_URL}/policies`, {
+    const response = await fetch(`${API_URL}/policies?${query}`, {
       method: 'GET',
       headers: {
         Authorization: `Bearer ${token}`,
@@ -103,3 +203,63 @@ export const fetchUserPolicies = async () => {
     throw error;
   }
 };
+
+export const cancelPolicy = async (policyId: number) => {
+  const token = localStorage.getItem('authToken');
+  if (!token) {
+    throw new Error('No authentication token found');
+  }
+
+  try {
+    const response = await fetch(`${API_URL}/policies/${policyId}/cancel`, {
+      method: 'POST',
+      headers: {
+        Authorization: `Bearer ${token}`,
+        'Content-Type': 'application/json',
+      },
+    });
+
+    if (!response.ok) {
+      const errorText = await response.text();
+      throw new Error(
+        `Failed to cancel policy: ${response.status} ${response.statusText} - ${errorText}`
+      );
+    }
+
+    return (await response.json()) as {
+      policy: InsurancePolicy;
+      refund: PolicyRefund;
+    };
+  } catch (error) {
+    console.error('Error cancelling policy:', error);
+    throw error;
+  }
+};
+
+export const fetchPolicy = async (policyId: number): Promise<PolicyDetails> => {
+  const token = localStorage.getItem('authToken');
+  if (!token) {
+    throw new Error('No authentication token found');
+  }
+
+  try {
+    const response = await fetch(`${API_URL}/policies/${policyId}`, {
+      method: 'GET',
+      headers: {
+        Authorization: `Bearer ${token}`,
+        'Content-Type': 'application/json',
+      },
+    });
+
+    if (!response.ok) {
+      throw new Error(
+        `Failed to fetch policy: ${response.status} ${response.statusText}`
+      );
+    }
+
+    return await response.json();
+  } catch (error) {
+    console.error('Error fetching policy:', error);
+    throw error;
+  }
+};
@@ -20,6 +20,7 @@ export interface LoginResponse {
   success: boolean;
   message: string;
   token?: string;
+  refresh_token?: string;
 }
 
 export interface RegisterFormData {
@@ -28,10 +29,19 @@ export interface RegisterFormData {
   password: string;
 }
 
+// A problem with one field of a request the backend rejected
+export interface FieldError {
+  field: string;
+  code: string;
+  message: string;
+}
+
 export interface RegisterResponse {
   success: boolean;
   message: string;
   token?: string;
+  error?: string;
+  fields?: FieldError[];
 }
 
 export interface PolicyTemplate {
@@ -71,6 +81,64 @@ export interface InsurancePolicy {
   verification_timestamp?: string;
   blockchain_block_number?: number;
   verification_error_message?: string;
+  blockchain_policy_id?: number;
+  cancelled_at?: string | number[];
+  refund_amount?: string;
+  refund_transaction_hash?: string;
   created_at?: string;
   updated_at?: string;
-}
\ No newline at end of file
+}
+
+export interface PolicyCondition {
+  id: number;
+  policy_id: number;
+  condition_type: string;
+  operator: string;
+  threshold_value: string;
+  measurement_unit: string;
+  measurement_period: string;
+  consecutive_days?: number;
+}
+
+export interface PolicyClaim {
+  id: number;
+  policy_id: number;
+  claim_amount: string;
+  claim_status?: string;
+  trigger_date: string | number[];
+  payout_transaction_hash?: string;
+  rejection_reason?: string;
+}
+
+export interface PolicyStatusChange {
+  id: number;
+  from_status: string;
+  to_status: string;
+  actor: string;
+  reason?: string;
+  created_at?: string | number[];
+}
+
+export interface WeatherReading {
+  id: number;
+  station_id: string;
+  recorded_at: string | number[];
+  temperature?: string;
+  humidity?: string;
+  precipitation?: string;
+  wind_speed?: string;
+}
+
+export interface PolicyDetails extends InsurancePolicy {
+  verification_status: 'verified' | 'pending' | 'failed';
+  conditions: PolicyCondition[];
+  claims: PolicyClaim[];
+  status_history: PolicyStatusChange[];
+  weather_data: WeatherReading[];
+}
+
+export interface PolicyPage {
+  policies: InsurancePolicy[];
+  total_count: number;
+  next_cursor: string | null;
+}
@@ -19,6 +19,18 @@ const CONTRACT_ABI = [
   },
 ];
 
+// Contract event type for each policy template type. Keep in sync with
+// event_type_for_policy_type in contract-events/src/lib.rs.
+export const EVENT_TYPE_BY_POLICY_TYPE: Record<string, string> = {
+  drought: 'RAIN_BELOW',
+  rain: 'RAIN_ABOVE',
+  temperature: 'TEMP_BELOW',
+  freeze: 'TEMP_BELOW',
+  heat: 'TEMP_ABOVE',
+  wind: 'WIND_ABOVE',
+  storm: 'WIND_ABOVE',
+};
+
 export const getContract = async (provider: BrowserProvider) => {
   try {
     const signer = await provider.getSigner();
@@ -0,0 +1,122 @@
+use rust_decimal::Decimal;
+use sqlx::{Pool, Postgres};
+use tracing::debug;
+
+// An approved claim awaiting on-chain settlement, joined with the policy
+// fields needed to locate it in the contract
+#[derive(Debug, Clone)]
+pub struct PayableClaim {
+    pub claim_id: i32,
+    pub policy_id: i32,
+    pub policy_type: String,
+    pub verification_data: Option<serde_json::Value>,
+    pub payout_transaction_hash: Option<String>,
+    pub payout_attempts: i32,
+    pub coverage_amount: Decimal,
+    pub location_h3_index: Option<String>,
+    pub purchase_transaction_hash: Option<String>,
+    pub blockchain_policy_id: Option<i64>,
+}
+
+// Approved claims without a confirmed payout. Claims whose attempts are
+// exhausted are left for manual review.
+pub async fn get_payable_claims(
+    pool: &Pool<Postgres>,
+    max_attempts: i32,
+) -> Result<Vec<PayableClaim>, sqlx::Error> {
+    debug!("Fetching approved claims awaiting payout");
+
+    sqlx::query_as!(
+        PayableClaim,
+        "SELECT c.id AS claim_id, c.policy_id, p.policy_type, c.verification_data,
+                c.payout_transaction_hash, c.payout_attempts,
+                p.coverage_amount, p.location_h3_index, p.purchase_transaction_hash,
+                p.blockchain_policy_id
+         FROM policy_claims c
+         JOIN insurance_policies p ON p.id = c.policy_id
+         WHERE c.claim_status = 'approved'
+           AND c.payout_block_number IS NULL
+           AND c.payout_attempts < $1
+         ORDER BY c.id",
+        max_attempts
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Record the hash of a signed payout before it is broadcast, so a restart
+// checks that transaction instead of sending a second one. Returns false if
+// another submission is already recorded for the claim.
+pub async fn record_payout_submission(
+    pool: &Pool<Postgres>,
+    claim_id: i32,
+    transaction_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    debug!(
+        "Recording payout submission {} for claim {}",
+        transaction_hash, claim_id
+    );
+
+    let result = sqlx::query!(
+        "UPDATE policy_claims
+         SET payout_transaction_hash = $2, payout_submitted_at = CURRENT_TIMESTAMP,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND payout_transaction_hash IS NULL",
+        claim_id,
+        transaction_hash
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+pub async fn record_payout_confirmed(
+    pool: &Pool<Postgres>,
+    claim_id: i32,
+    transaction_hash: &str,
+    block_number: i32,
+) -> Result<(), sqlx::Error> {
+    debug!(
+        "Recording payout {} in block {} for claim {}",
+        transaction_hash, block_number, claim_id
+    );
+
+    sqlx::query!(
+        "UPDATE policy_claims
+         SET claim_status = 'paid', payout_transaction_hash = $2, payout_block_number = $3,
+             payout_last_error = NULL, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1",
+        claim_id,
+        transaction_hash,
+        block_number
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Count a failed attempt and clear any submission that is known not to have
+// been mined, making the claim eligible for another attempt
+pub async fn record_payout_failure(
+    pool: &Pool<Postgres>,
+    claim_id: i32,
+    error: &str,
+) -> Result<(), sqlx::Error> {
+    debug!("Recording payout failure for claim {}: {}", claim_id, error);
+
+    sqlx::query!(
+        "UPDATE policy_claims
+         SET payout_attempts = payout_attempts + 1, payout_last_error = $2,
+             payout_transaction_hash = NULL, payout_submitted_at = NULL,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1",
+        claim_id,
+        error
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use ethers::types::Address;
+
+// Error types for oracle configuration
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Missing environment variable: {0}")]
+    Missing(String),
+    #[error("Invalid value for {0}: {1}")]
+    Invalid(String, String),
+}
+
+// Configuration for the settlement daemon. The private key belongs to the
+// contract owner, which is the only account allowed to call `trigger`.
+#[derive(Clone)]
+pub struct OracleConfig {
+    pub rpc_url: String,
+    pub private_key: String,
+    pub contract_address: Address,
+    pub chain_id: u64,
+    pub poll_interval_seconds: u64,
+    pub confirmations: usize,
+    pub receipt_timeout_seconds: u64,
+    pub max_attempts: i32,
+}
+
+impl OracleConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(Self {
+            rpc_url: required("RPC_URL")?,
+            private_key: required("PRIVATE_KEY")?,
+            contract_address: parse("CONTRACT_ADDRESS", required("CONTRACT_ADDRESS")?)?,
+            chain_id: parse("CHAIN_ID", required("CHAIN_ID")?)?,
+            poll_interval_seconds: optional("ORACLE_POLL_INTERVAL_SECONDS", 60)?,
+            confirmations: optional("ORACLE_CONFIRMATIONS", 1)?,
+            receipt_timeout_seconds: optional("ORACLE_RECEIPT_TIMEOUT_SECONDS", 300)?,
+            max_attempts: optional("ORACLE_MAX_ATTEMPTS", 5)?,
+        })
+    }
+}
+
+fn required(name: &str) -> Result<String, ConfigError> {
+    std::env::var(name).map_err(|_| ConfigError::Missing(name.to_string()))
+}
+
+fn optional<T: std::str::FromStr>(name: &str, default: T) -> Result<T, ConfigError> {
+    match std::env::var(name) {
+        Ok(value) => parse(name, value),
+        Err(_) => Ok(default),
+    }
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: String) -> Result<T, ConfigError> {
+    // Allow trailing comments such as `CHAIN_ID=11155111 # Sepolia`
+    let value = value.split('#').next().unwrap_or_default().trim();
+    value
+        .parse()
+        .map_err(|_| ConfigError::Invalid(name.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_strips_trailing_comment() {
+        let chain_id: u64 = parse("CHAIN_ID", "11155111 # e.g., Sepolia".to_string()).unwrap();
+        assert_eq!(chain_id, 11155111);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_address() {
+        let result: Result<Address, _> = parse("CONTRACT_ADDRESS", "0xnotanaddress".to_string());
+        assert!(matches!(result, Err(ConfigError::Invalid(name, _)) if name == "CONTRACT_ADDRESS"));
+    }
+}
@@ -0,0 +1,14 @@
+use ethers::prelude::*;
+
+// WeatherInsurance contract ABI, limited to what the oracle needs
+abigen!(
+    WeatherInsurance,
+    r#"[
+        function buyPolicy(uint256 duration, uint256 payout, int256 threshold, string memory eventType, string memory h3HexId) external payable
+        function trigger(uint256 policyId, int256 observed) external
+        function policies(uint256) external view returns (address user, uint256 payout, uint256 startTime, uint256 endTime, bool paid, int256 threshold, string memory eventType, string memory h3HexId)
+        function policyCount() external view returns (uint256)
+        function owner() external view returns (address)
+        function isSupportedEventType(string memory eventType) external pure returns (bool)
+    ]"#,
+);
@@ -1,51 +1,47 @@
-// This is just starter code.
+mod claims;
+mod config;
+mod contract;
+mod settlement;
 
+#[cfg(test)]
+mod test_utils;
 
-use ethers::prelude::*;
-use std::sync::Arc;
-use dotenv::dotenv;
-use std::env;
-
-abigen!(
-    WeatherInsurance,
-    r#"[
-        function triggerPayout(uint256, int256, uint256) external
-    ]"#,
-);
+use sqlx::postgres::PgPoolOptions;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    dotenv().ok();
-
-    // Environment
-    let private_key = env::var("PRIVATE_KEY")?;
-    let rpc_url = env::var("RPC_URL")?;
-    let contract_addr: Address = env::var("CONTRACT_ADDRESS")?.parse()?;
-    let chain_id: u64 = env::var("CHAIN_ID")?.parse()?;
-
-    // Wallet + Provider
-    let wallet: LocalWallet = private_key.parse::<LocalWallet>()?.with_chain_id(chain_id);
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let client = SignerMiddleware::new(provider, wallet);
-    let client = Arc::new(client);
-
-    // Contract instance
-    let contract = WeatherInsurance::new(contract_addr, client.clone());
-
-    // Simulate oracle reading
-    let policy_id: u64 = 0;
-    let observed_temp: i64 = -5;
-    let obs_time: u64 = 1712345678;
-
-    let tx = contract
-        .trigger_payout(policy_id, observed_temp, obs_time)
-        .send()
-        .await?;
-
-    println!("TX sent: {:?}", tx.tx_hash());
-
-    let receipt = tx.await?;
-    println!("Receipt: {:?}", receipt);
-
-    Ok(())
-}
\ No newline at end of file
+async fn main() {
+    dotenvy::dotenv().ok();
+    tracing_subscriber::fmt::init();
+    tracing::info!("Starting weather-boyz settlement oracle...");
+
+    let config = match config::OracleConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("Invalid oracle configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = match PgPoolOptions::new().connect(&database_url).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            tracing::error!("Failed to connect to database: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let service = match settlement::SettlementService::new(pool, config) {
+        Ok(service) => service,
+        Err(e) => {
+            tracing::error!("Failed to initialize settlement service: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = service.verify_owner().await {
+        tracing::error!("{}", e);
+        std::process::exit(1);
+    }
+
+    service.run().await;
+}
@@ -0,0 +1,750 @@
+use crate::claims::{self, PayableClaim};
+use crate::config::OracleConfig;
+use crate::contract::WeatherInsurance;
+use contract_events::{ContractEventType, event_type_for_policy_type};
+use ethers::prelude::*;
+use ethers::utils::{keccak256, parse_ether};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use sqlx::{Pool, Postgres};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+pub type OracleClient = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+// Error types for claim settlement
+#[derive(Debug, thiserror::Error)]
+pub enum SettlementError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("Contract error: {0}")]
+    Contract(String),
+    #[error("Invalid claim: {0}")]
+    InvalidClaim(String),
+    #[error("Policy not found on chain: {0}")]
+    PolicyNotFound(String),
+    #[error("Policy {0} is already settled on chain")]
+    AlreadySettled(U256),
+    #[error("Payout transaction failed: {0}")]
+    TransactionFailed(String),
+}
+
+// What happened to a single claim during a settlement pass
+#[derive(Debug, PartialEq)]
+pub enum SettlementOutcome {
+    Paid {
+        transaction_hash: H256,
+        block_number: u64,
+    },
+    Pending(H256),
+    Skipped,
+}
+
+// Summary of a single settlement pass
+#[derive(Debug, Default)]
+pub struct SettlementReport {
+    pub claims_checked: usize,
+    pub claims_paid: usize,
+    pub claims_pending: usize,
+    pub failed_claims: Vec<i32>,
+}
+
+// State of a payout transaction recorded by an earlier attempt
+enum SubmittedPayout {
+    Mined(Box<TransactionReceipt>),
+    Unconfirmed,
+    Dropped,
+}
+
+pub struct SettlementService {
+    pool: Pool<Postgres>,
+    client: Arc<OracleClient>,
+    contract: WeatherInsurance<OracleClient>,
+    config: OracleConfig,
+}
+
+impl SettlementService {
+    pub fn new(pool: Pool<Postgres>, config: OracleConfig) -> Result<Self, SettlementError> {
+        let provider = Provider::<Http>::try_from(config.rpc_url.as_str())
+            .map_err(|e| SettlementError::Network(format!("Invalid RPC URL: {}", e)))?;
+        let wallet = config
+            .private_key
+            .parse::<LocalWallet>()
+            .map_err(|e| SettlementError::Contract(format!("Invalid private key: {}", e)))?
+            .with_chain_id(config.chain_id);
+        let client = Arc::new(SignerMiddleware::new(provider, wallet));
+        let contract = WeatherInsurance::new(config.contract_address, client.clone());
+
+        Ok(Self {
+            pool,
+            client,
+            contract,
+            config,
+        })
+    }
+
+    // `trigger` is owner-only, so refuse to start with any other key
+    pub async fn verify_owner(&self) -> Result<(), SettlementError> {
+        let owner = self
+            .contract
+            .owner()
+            .call()
+            .await
+            .map_err(|e| SettlementError::Contract(format!("Failed to read owner: {}", e)))?;
+        let oracle = self.client.address();
+
+        if owner != oracle {
+            return Err(SettlementError::Contract(format!(
+                "Oracle wallet {:?} is not the contract owner {:?}",
+                oracle, owner
+            )));
+        }
+        Ok(())
+    }
+
+    // Settle claims on the configured interval until the task is dropped
+    pub async fn run(self) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.poll_interval_seconds));
+
+        loop {
+            interval.tick().await;
+            match self.run_once().await {
+                Ok(report) => info!(
+                    "Settlement pass complete: {} checked, {} paid, {} pending, {} failures",
+                    report.claims_checked,
+                    report.claims_paid,
+                    report.claims_pending,
+                    report.failed_claims.len()
+                ),
+                Err(e) => error!("Settlement pass failed: {}", e),
+            }
+        }
+    }
+
+    // Attempt every payable claim once. A failure on one claim is recorded
+    // against it and does not stop the others.
+    pub async fn run_once(&self) -> Result<SettlementReport, SettlementError> {
+        let claims = claims::get_payable_claims(&self.pool, self.config.max_attempts).await?;
+        let mut report = SettlementReport::default();
+
+        for claim in claims {
+            report.claims_checked += 1;
+            match self.settle_claim(&claim).await {
+                Ok(SettlementOutcome::Paid { .. }) => report.claims_paid += 1,
+                Ok(SettlementOutcome::Pending(_)) => report.claims_pending += 1,
+                Ok(SettlementOutcome::Skipped) => {}
+                Err(e) => {
+                    warn!(
+                        "Failed to settle claim {} (attempt {}): {}",
+                        claim.claim_id,
+                        claim.payout_attempts + 1,
+                        e
+                    );
+                    report.failed_claims.push(claim.claim_id);
+                    // A database error may have hidden a successful payout, so
+                    // keep any recorded transaction for the next pass to check
+                    if !matches!(e, SettlementError::Database(_)) {
+                        claims::record_payout_failure(&self.pool, claim.claim_id, &e.to_string())
+                            .await?;
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    pub async fn settle_claim(
+        &self,
+        claim: &PayableClaim,
+    ) -> Result<SettlementOutcome, SettlementError> {
+        // Resume an earlier submission rather than sending a second payout
+        if let Some(hash) = &claim.payout_transaction_hash {
+            let tx_hash = H256::from_str(hash).map_err(|_| {
+                SettlementError::InvalidClaim(format!("Invalid payout transaction hash {}", hash))
+            })?;
+            return match self.check_submitted(tx_hash).await? {
+                SubmittedPayout::Mined(receipt) => self.confirm(claim, &receipt).await,
+                SubmittedPayout::Unconfirmed => Ok(SettlementOutcome::Pending(tx_hash)),
+                SubmittedPayout::Dropped => Err(SettlementError::TransactionFailed(format!(
+                    "Payout transaction {:?} was dropped",
+                    tx_hash
+                ))),
+            };
+        }
+
+        let verification_data = claim.verification_data.as_ref().ok_or_else(|| {
+            SettlementError::InvalidClaim(format!(
+                "Claim {} has no verification data",
+                claim.claim_id
+            ))
+        })?;
+        let observed = observed_value(verification_data)?;
+        let policy_id = self.resolve_policy_id(claim).await?;
+
+        let (_, _, _, _, paid, threshold, event_type, _) = self
+            .contract
+            .policies(policy_id)
+            .call()
+            .await
+            .map_err(|e| SettlementError::Contract(e.to_string()))?;
+        if paid {
+            return Err(SettlementError::AlreadySettled(policy_id));
+        }
+        check_trigger(&claim.policy_type, &event_type, observed, threshold)?;
+
+        info!(
+            "Triggering on-chain policy {} for claim {} with observed value {}",
+            policy_id, claim.claim_id, observed
+        );
+        let Some(tx_hash) = self
+            .submit_trigger(claim.claim_id, policy_id, observed)
+            .await?
+        else {
+            debug!("Claim {} was submitted by another worker", claim.claim_id);
+            return Ok(SettlementOutcome::Skipped);
+        };
+
+        let pending = PendingTransaction::new(tx_hash, self.client.provider())
+            .confirmations(self.config.confirmations);
+        match tokio::time::timeout(
+            Duration::from_secs(self.config.receipt_timeout_seconds),
+            pending,
+        )
+        .await
+        {
+            Ok(Ok(Some(receipt))) => self.confirm(claim, &receipt).await,
+            Ok(Ok(None)) => Err(SettlementError::TransactionFailed(format!(
+                "Payout transaction {:?} was dropped",
+                tx_hash
+            ))),
+            // The transaction may still be mined; the next pass checks it
+            Ok(Err(e)) => {
+                warn!("Error waiting for payout {:?}: {}", tx_hash, e);
+                Ok(SettlementOutcome::Pending(tx_hash))
+            }
+            Err(_) => Ok(SettlementOutcome::Pending(tx_hash)),
+        }
+    }
+
+    // Sign the trigger call locally and record its hash before broadcasting,
+    // so a crash between the two steps cannot lead to a duplicate payout. The
+    // backend sends refunds from the same key, so the owner nonce lock is held
+    // from filling in the nonce until the transaction is broadcast.
+    async fn submit_trigger(
+        &self,
+        claim_id: i32,
+        policy_id: U256,
+        observed: I256,
+    ) -> Result<Option<H256>, SettlementError> {
+        let mut nonce_lock = self.pool.begin().await?;
+        contract_events::lock_owner_nonce(&mut *nonce_lock).await?;
+
+        let mut tx = self.contract.trigger(policy_id, observed).tx;
+        self.client
+            .fill_transaction(&mut tx, None)
+            .await
+            .map_err(|e| SettlementError::Contract(e.to_string()))?;
+        let signature = self
+            .client
+            .signer()
+            .sign_transaction(&tx)
+            .await
+            .map_err(|e| SettlementError::Contract(e.to_string()))?;
+        let raw = tx.rlp_signed(&signature);
+        let tx_hash = H256::from(keccak256(&raw));
+
+        if !claims::record_payout_submission(&self.pool, claim_id, &format!("{:?}", tx_hash))
+            .await?
+        {
+            return Ok(None);
+        }
+
+        self.client
+            .send_raw_transaction(raw)
+            .await
+            .map_err(|e| SettlementError::Network(e.to_string()))?;
+        nonce_lock.commit().await?;
+        info!(
+            "Payout transaction {:?} sent for claim {}",
+            tx_hash, claim_id
+        );
+
+        Ok(Some(tx_hash))
+    }
+
+    async fn check_submitted(&self, tx_hash: H256) -> Result<SubmittedPayout, SettlementError> {
+        let receipt = self
+            .client
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(|e| SettlementError::Network(e.to_string()))?;
+
+        if let Some(receipt) = receipt {
+            let mined_in = receipt.block_number.unwrap_or_default();
+            let latest = self
+                .client
+                .get_block_number()
+                .await
+                .map_err(|e| SettlementError::Network(e.to_string()))?;
+            let confirmations = latest.saturating_sub(mined_in).as_u64() + 1;
+            if confirmations < self.config.confirmations as u64 {
+                return Ok(SubmittedPayout::Unconfirmed);
+            }
+            return Ok(SubmittedPayout::Mined(Box::new(receipt)));
+        }
+
+        let in_mempool = self
+            .client
+            .get_transaction(tx_hash)
+            .await
+            .map_err(|e| SettlementError::Network(e.to_string()))?
+            .is_some();
+        if in_mempool {
+            Ok(SubmittedPayout::Unconfirmed)
+        } else {
+            Ok(SubmittedPayout::Dropped)
+        }
+    }
+
+    async fn confirm(
+        &self,
+        claim: &PayableClaim,
+        receipt: &TransactionReceipt,
+    ) -> Result<SettlementOutcome, SettlementError> {
+        let tx_hash = receipt.transaction_hash;
+        if receipt.status != Some(U64::one()) {
+            return Err(SettlementError::TransactionFailed(format!(
+                "Payout transaction {:?} reverted",
+                tx_hash
+            )));
+        }
+
+        let block_number = receipt.block_number.unwrap_or_default().as_u64();
+        let block_column = i32::try_from(block_number).map_err(|_| {
+            SettlementError::TransactionFailed(format!(
+                "Block number {} out of range",
+                block_number
+            ))
+        })?;
+        claims::record_payout_confirmed(
+            &self.pool,
+            claim.claim_id,
+            &format!("{:?}", tx_hash),
+            block_column,
+        )
+        .await?;
+        info!(
+            "Claim {} paid in transaction {:?} (block {})",
+            claim.claim_id, tx_hash, block_number
+        );
+
+        Ok(SettlementOutcome::Paid {
+            transaction_hash: tx_hash,
+            block_number,
+        })
+    }
+
+    // Find the contract policy created by the claim's purchase transaction.
+    // Policies verified by the backend record the id from the PolicyCreated
+    // event. For older rows, the candidates are the sequential ids allocated
+    // in the purchase block; among those, match on buyer, location and payout.
+    async fn resolve_policy_id(&self, claim: &PayableClaim) -> Result<U256, SettlementError> {
+        if let Some(policy_id) = claim.blockchain_policy_id {
+            return u64::try_from(policy_id).map(U256::from).map_err(|_| {
+                SettlementError::InvalidClaim(format!("Invalid on-chain policy id {}", policy_id))
+            });
+        }
+
+        let purchase_hash = claim.purchase_transaction_hash.as_deref().ok_or_else(|| {
+            SettlementError::InvalidClaim(format!(
+                "Policy {} has no purchase transaction",
+                claim.policy_id
+            ))
+        })?;
+        let purchase_hash = H256::from_str(purchase_hash).map_err(|_| {
+            SettlementError::InvalidClaim(format!(
+                "Invalid purchase transaction hash {}",
+                purchase_hash
+            ))
+        })?;
+        let h3_index = claim.location_h3_index.as_deref().ok_or_else(|| {
+            SettlementError::InvalidClaim(format!("Policy {} has no H3 index", claim.policy_id))
+        })?;
+        let payout = parse_ether(claim.coverage_amount.to_string()).map_err(|e| {
+            SettlementError::InvalidClaim(format!("Invalid coverage amount: {}", e))
+        })?;
+
+        let purchase = self
+            .client
+            .get_transaction(purchase_hash)
+            .await
+            .map_err(|e| SettlementError::Network(e.to_string()))?
+            .ok_or_else(|| {
+                SettlementError::PolicyNotFound(format!("Purchase {:?} not found", purchase_hash))
+            })?;
+        let block = purchase.block_number.ok_or_else(|| {
+            SettlementError::PolicyNotFound(format!("Purchase {:?} is not mined", purchase_hash))
+        })?;
+
+        let first_id = if block.is_zero() {
+            U256::zero()
+        } else {
+            self.policy_count_at(block - 1).await?
+        };
+        let end_id = self.policy_count_at(block).await?;
+
+        let mut settled_match = None;
+        let mut id = first_id;
+        while id < end_id {
+            let (user, policy_payout, _, _, paid, _, _, h3_hex_id) = self
+                .contract
+                .policies(id)
+                .call()
+                .await
+                .map_err(|e| SettlementError::Contract(e.to_string()))?;
+            if user == purchase.from && policy_payout == payout && h3_hex_id == h3_index {
+                if !paid {
+                    return Ok(id);
+                }
+                settled_match.get_or_insert(id);
+            }
+            id += U256::one();
+        }
+
+        settled_match.ok_or_else(|| {
+            SettlementError::PolicyNotFound(format!(
+                "No policy for purchase {:?} in block {}",
+                purchase_hash, block
+            ))
+        })
+    }
+
+    async fn policy_count_at(&self, block: U64) -> Result<U256, SettlementError> {
+        self.contract
+            .policy_count()
+            .block(block)
+            .call()
+            .await
+            .map_err(|e| SettlementError::Contract(e.to_string()))
+    }
+}
+
+// The on-chain observation for a claim. The contract compares an integer
+// observation with the integer threshold recorded at purchase, so the
+// measured value is rounded away from the threshold, which keeps a strict
+// comparison against an integer threshold exact.
+pub fn observed_value(verification_data: &serde_json::Value) -> Result<I256, SettlementError> {
+    let condition = verification_data
+        .get("conditions")
+        .and_then(|c| c.get(0))
+        .ok_or_else(|| SettlementError::InvalidClaim("No evaluated conditions".to_string()))?;
+    let observed = decimal_field(condition, "observed_value")?;
+    let threshold = decimal_field(condition, "normalized_threshold")?;
+
+    let rounded = if observed < threshold {
+        observed.floor()
+    } else {
+        observed.ceil()
+    };
+    let value = rounded.to_i64().ok_or_else(|| {
+        SettlementError::InvalidClaim(format!("Observed value {} out of range", observed))
+    })?;
+
+    Ok(I256::from(value))
+}
+
+// Make sure the contract will pay for the observation before triggering,
+// since a trigger that does not meet the policy's condition settles it
+// without a payout
+pub fn check_trigger(
+    policy_type: &str,
+    event_type: &str,
+    observed: I256,
+    threshold: I256,
+) -> Result<ContractEventType, SettlementError> {
+    let event_type = ContractEventType::from_str(event_type)
+        .map_err(|e| SettlementError::InvalidClaim(e.to_string()))?;
+    if event_type_for_policy_type(policy_type) != Some(event_type) {
+        return Err(SettlementError::InvalidClaim(format!(
+            "On-chain event type {} does not match policy type {}",
+            event_type, policy_type
+        )));
+    }
+
+    let (observed, threshold) = match (i64::try_from(observed), i64::try_from(threshold)) {
+        (Ok(observed), Ok(threshold)) => (observed, threshold),
+        _ => {
+            return Err(SettlementError::InvalidClaim(format!(
+                "Observed value {} or threshold {} out of range",
+                observed, threshold
+            )));
+        }
+    };
+    if !event_type.is_triggered(observed, threshold) {
+        return Err(SettlementError::InvalidClaim(format!(
+            "Observed value {} does not trigger {} with threshold {}",
+            observed, event_type, threshold
+        )));
+    }
+
+    Ok(event_type)
+}
+
+// Decimals are serialized as strings, but accept plain numbers too
+fn decimal_field(value: &serde_json::Value, field: &str) -> Result<Decimal, SettlementError> {
+    let parsed = match value.get(field) {
+        Some(serde_json::Value::String(s)) => Decimal::from_str(s).ok(),
+        Some(serde_json::Value::Number(n)) => Decimal::from_str(&n.to_string()).ok(),
+        _ => None,
+    };
+    parsed.ok_or_else(|| SettlementError::InvalidClaim(format!("Missing or invalid {}", field)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn verification(
+        observed: serde_json::Value,
+        threshold: serde_json::Value,
+    ) -> serde_json::Value {
+        json!({
+            "evaluated_at": "2026-07-01T00:00:00",
+            "conditions": [{
+                "condition_id": 1,
+                "normalized_threshold": threshold,
+                "observed_value": observed
+            }],
+            "readings": []
+        })
+    }
+
+    #[test]
+    fn test_observed_value_rounds_up_above_threshold() {
+        let data = verification(json!("10.40"), json!("10"));
+        assert_eq!(observed_value(&data).unwrap(), I256::from(11));
+    }
+
+    #[test]
+    fn test_observed_value_rounds_down_below_threshold() {
+        let data = verification(json!("-2.40"), json!("-2"));
+        assert_eq!(observed_value(&data).unwrap(), I256::from(-3));
+    }
+
+    #[test]
+    fn test_observed_value_keeps_integers() {
+        let data = verification(json!("13.00"), json!("10"));
+        assert_eq!(observed_value(&data).unwrap(), I256::from(13));
+    }
+
+    #[test]
+    fn test_observed_value_accepts_numbers() {
+        let data = verification(json!(3.2), json!(5));
+        assert_eq!(observed_value(&data).unwrap(), I256::from(3));
+    }
+
+    #[test]
+    fn test_check_trigger_uses_event_direction() {
+        let check = |policy_type, event_type, observed: i64, threshold: i64| {
+            check_trigger(
+                policy_type,
+                event_type,
+                I256::from(observed),
+                I256::from(threshold),
+            )
+        };
+
+        assert_eq!(
+            check("drought", "RAIN_BELOW", 3, 5).unwrap(),
+            ContractEventType::RainBelow
+        );
+        assert_eq!(
+            check("rain", "RAIN_ABOVE", 11, 10).unwrap(),
+            ContractEventType::RainAbove
+        );
+        assert_eq!(
+            check("wind", "WIND_ABOVE", 70, 60).unwrap(),
+            ContractEventType::WindAbove
+        );
+        assert!(matches!(
+            check("rain", "RAIN_ABOVE", 9, 10),
+            Err(SettlementError::InvalidClaim(_))
+        ));
+        assert!(matches!(
+            check("temperature", "TEMP_BELOW", -2, -2),
+            Err(SettlementError::InvalidClaim(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_trigger_rejects_mismatched_event_types() {
+        // Policies bought before the contract supported event types
+        assert!(matches!(
+            check_trigger("rain", "rainfall", I256::from(11), I256::from(10)),
+            Err(SettlementError::InvalidClaim(_))
+        ));
+        assert!(matches!(
+            check_trigger("drought", "RAIN_ABOVE", I256::from(11), I256::from(10)),
+            Err(SettlementError::InvalidClaim(_))
+        ));
+    }
+
+    #[test]
+    fn test_observed_value_requires_conditions() {
+        let data = json!({"conditions": []});
+        assert!(matches!(
+            observed_value(&data),
+            Err(SettlementError::InvalidClaim(_))
+        ));
+
+        let data = verification(json!(null), json!("10"));
+        assert!(matches!(
+            observed_value(&data),
+            Err(SettlementError::InvalidClaim(_))
+        ));
+    }
+
+    // The tests below need a local node and the backend database; see
+    // test_utils for setup
+
+    async fn claim_by_id(service: &SettlementService, claim_id: i32) -> PayableClaim {
+        claims::get_payable_claims(&service.pool, service.config.max_attempts)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|c| c.claim_id == claim_id)
+            .expect("Claim should be payable")
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_settle_claim_pays_out_on_chain() {
+        use crate::test_utils::*;
+
+        let owner = test_client(OWNER_PRIVATE_KEY).await;
+        let buyer = test_client(BUYER_PRIVATE_KEY).await;
+        let contract_address = deploy_contract(owner).await;
+        let purchase =
+            buy_policy(buyer.clone(), contract_address, "1", -2, "872a1072bffffff").await;
+
+        let pool = test_pool().await;
+        let claim_id = insert_approved_claim(
+            &pool,
+            "1.00",
+            "872a1072bffffff",
+            purchase,
+            verification(json!("-4.50"), json!("-2")),
+        )
+        .await;
+
+        let service = SettlementService::new(pool.clone(), test_config(contract_address).await)
+            .expect("Failed to create settlement service");
+        service
+            .verify_owner()
+            .await
+            .expect("Oracle should own the contract");
+        let balance_before = buyer.get_balance(buyer.address(), None).await.unwrap();
+
+        let claim = claim_by_id(&service, claim_id).await;
+        let outcome = service
+            .settle_claim(&claim)
+            .await
+            .expect("Failed to settle claim");
+        let SettlementOutcome::Paid {
+            transaction_hash,
+            block_number,
+        } = outcome
+        else {
+            panic!("Expected payout, got {:?}", outcome);
+        };
+
+        let balance_after = buyer.get_balance(buyer.address(), None).await.unwrap();
+        assert_eq!(balance_after - balance_before, parse_ether("1").unwrap());
+
+        let (_, _, _, _, paid, _, _, _) = service
+            .contract
+            .policies(U256::zero())
+            .call()
+            .await
+            .unwrap();
+        assert!(paid);
+
+        let row = sqlx::query!(
+            "SELECT claim_status, payout_transaction_hash, payout_block_number FROM policy_claims WHERE id = $1",
+            claim_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.claim_status, Some("paid".to_string()));
+        assert_eq!(
+            row.payout_transaction_hash,
+            Some(format!("{:?}", transaction_hash))
+        );
+        assert_eq!(row.payout_block_number, Some(block_number as i32));
+
+        // A paid claim is no longer picked up
+        let payable = claims::get_payable_claims(&pool, 5).await.unwrap();
+        assert!(payable.iter().all(|c| c.claim_id != claim_id));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_settle_claim_retries_dropped_submission() {
+        use crate::test_utils::*;
+
+        let owner = test_client(OWNER_PRIVATE_KEY).await;
+        let buyer = test_client(BUYER_PRIVATE_KEY).await;
+        let contract_address = deploy_contract(owner).await;
+        let purchase = buy_policy(buyer, contract_address, "2", -2, "872a1072affffff").await;
+
+        let pool = test_pool().await;
+        let claim_id = insert_approved_claim(
+            &pool,
+            "2.00",
+            "872a1072affffff",
+            purchase,
+            verification(json!("-3.00"), json!("-2")),
+        )
+        .await;
+
+        // Simulate a crash after recording a submission that never reached the node
+        let never_sent = format!("{:?}", H256::repeat_byte(0xab));
+        assert!(
+            claims::record_payout_submission(&pool, claim_id, &never_sent)
+                .await
+                .unwrap()
+        );
+
+        let service = SettlementService::new(pool.clone(), test_config(contract_address).await)
+            .expect("Failed to create settlement service");
+        let report = service.run_once().await.unwrap();
+        assert!(report.failed_claims.contains(&claim_id));
+
+        let claim = claim_by_id(&service, claim_id).await;
+        assert_eq!(claim.payout_transaction_hash, None);
+        assert_eq!(claim.payout_attempts, 1);
+
+        let outcome = service
+            .settle_claim(&claim)
+            .await
+            .expect("Failed to settle claim");
+        assert!(matches!(outcome, SettlementOutcome::Paid { .. }));
+
+        // The contract rejects a second trigger for the same policy
+        let (_, _, _, _, paid, _, _, _) = service
+            .contract
+            .policies(U256::zero())
+            .call()
+            .await
+            .unwrap();
+        assert!(paid);
+    }
+}
@@ -0,0 +1,195 @@
+// Helpers for tests that run against a local Hardhat or anvil node and the
+// backend database. Start a node with `npx hardhat node` (or `anvil`), apply
+// the backend migrations, then run `cargo test -- --ignored`.
+use crate::config::OracleConfig;
+use crate::contract::WeatherInsurance;
+use ethers::abi::Abi;
+use ethers::prelude::*;
+use ethers::utils::parse_ether;
+use sqlx::{PgPool, Pool, Postgres};
+use std::sync::Arc;
+
+// Default Hardhat/anvil development accounts #0 and #1
+pub const OWNER_PRIVATE_KEY: &str =
+    "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+pub const BUYER_PRIVATE_KEY: &str =
+    "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+
+const CONTRACT_ARTIFACT: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../blockchain/artifacts/contracts/WeatherInsurance.sol/WeatherInsurance.json"
+);
+
+// What the tests expect the compiled contract to have
+const CONTRACT_FUNCTIONS: [&str; 7] = [
+    "buyPolicy",
+    "trigger",
+    "cancel",
+    "policies",
+    "premiums",
+    "policyCount",
+    "isSupportedEventType",
+];
+const CONTRACT_EVENTS: [&str; 3] = ["PolicyCreated", "PolicyTriggered", "PolicyCancelled"];
+
+pub type TestClient = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+pub fn test_rpc_url() -> String {
+    std::env::var("ORACLE_TEST_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8545".to_string())
+}
+
+pub async fn test_pool() -> Pool<Postgres> {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    PgPool::connect(&url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+pub async fn test_client(private_key: &str) -> Arc<TestClient> {
+    let provider =
+        Provider::<Http>::try_from(test_rpc_url()).expect("Failed to create test provider");
+    let chain_id = provider
+        .get_chainid()
+        .await
+        .expect("Local node is not reachable");
+    let wallet = private_key
+        .parse::<LocalWallet>()
+        .expect("Invalid test private key")
+        .with_chain_id(chain_id.as_u64());
+    Arc::new(SignerMiddleware::new(provider, wallet))
+}
+
+/// Deploy a fresh WeatherInsurance contract from the Hardhat build artifact
+pub async fn deploy_contract(owner: Arc<TestClient>) -> Address {
+    let artifact: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(CONTRACT_ARTIFACT).expect("Missing contract artifact"),
+    )
+    .expect("Invalid contract artifact");
+    let abi: Abi = serde_json::from_value(artifact["abi"].clone()).expect("Invalid contract ABI");
+    // The artifact is committed, so it can fall behind WeatherInsurance.sol.
+    // Testing against an older contract would pass for the wrong reasons.
+    for function in CONTRACT_FUNCTIONS {
+        assert!(
+            abi.function(function).is_ok(),
+            "Contract artifact has no `{}`; run `npx hardhat compile` in blockchain/",
+            function
+        );
+    }
+    for event in CONTRACT_EVENTS {
+        assert!(
+            abi.event(event).is_ok(),
+            "Contract artifact has no `{}` event; run `npx hardhat compile` in blockchain/",
+            event
+        );
+    }
+    let bytecode: Bytes = artifact["bytecode"]
+        .as_str()
+        .expect("Missing contract bytecode")
+        .parse()
+        .expect("Invalid contract bytecode");
+
+    let contract = ContractFactory::new(abi, bytecode, owner)
+        .deploy(())
+        .expect("Failed to build deployment")
+        .send()
+        .await
+        .expect("Failed to deploy contract");
+    contract.address()
+}
+
+/// Buy a TEMP_BELOW policy as the given client, paying the minimum premium
+pub async fn buy_policy(
+    buyer: Arc<TestClient>,
+    contract_address: Address,
+    payout_eth: &str,
+    threshold: i64,
+    h3_hex_id: &str,
+) -> H256 {
+    let contract = WeatherInsurance::new(contract_address, buyer);
+    let payout = parse_ether(payout_eth).expect("Invalid payout");
+    let call = contract
+        .buy_policy(
+            U256::from(30 * 24 * 60 * 60),
+            payout,
+            I256::from(threshold),
+            "TEMP_BELOW".to_string(),
+            h3_hex_id.to_string(),
+        )
+        .value(payout / 10);
+    let pending = call.send().await.expect("Failed to send buyPolicy");
+    let receipt = pending
+        .await
+        .expect("Failed to await buyPolicy")
+        .expect("buyPolicy was dropped");
+    receipt.transaction_hash
+}
+
+/// Insert a user, an insurance policy and an approved claim for it
+pub async fn insert_approved_claim(
+    pool: &Pool<Postgres>,
+    coverage_eth: &str,
+    h3_index: &str,
+    purchase_transaction_hash: H256,
+    verification_data: serde_json::Value,
+) -> i32 {
+    let email = format!("oracle_{:?}@example.com", purchase_transaction_hash);
+    let user_id = sqlx::query_scalar!(
+        "INSERT INTO users (name, email, password_hash) VALUES ('Oracle Test', $1, 'x') RETURNING id",
+        email
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to insert test user");
+
+    let coverage: rust_decimal::Decimal = coverage_eth.parse().expect("Invalid coverage");
+    let policy_id = sqlx::query_scalar!(
+        "INSERT INTO insurance_policies
+            (user_id, policy_name, policy_type, location_latitude, location_longitude,
+             location_h3_index, coverage_amount, premium_amount, start_date, end_date,
+             status, purchase_transaction_hash)
+         VALUES ($1, 'Freeze Protection', 'temperature', 40.7128, -74.0060, $2, $3, $4,
+                 CURRENT_TIMESTAMP - INTERVAL '10 days', CURRENT_TIMESTAMP + INTERVAL '20 days',
+                 'claimed', $5)
+         RETURNING id",
+        user_id,
+        h3_index,
+        coverage,
+        coverage / rust_decimal::Decimal::TEN,
+        format!("{:?}", purchase_transaction_hash)
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to insert test policy");
+
+    sqlx::query_scalar!(
+        "INSERT INTO policy_claims
+            (policy_id, claim_amount, claim_status, trigger_date, verification_data, approved_at)
+         VALUES ($1, $2, 'approved', CURRENT_TIMESTAMP, $3, CURRENT_TIMESTAMP)
+         RETURNING id",
+        policy_id,
+        coverage,
+        verification_data
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to insert test claim")
+}
+
+pub async fn test_config(contract_address: Address) -> OracleConfig {
+    let client = test_client(OWNER_PRIVATE_KEY).await;
+    let chain_id = client
+        .get_chainid()
+        .await
+        .expect("Local node is not reachable");
+
+    OracleConfig {
+        rpc_url: test_rpc_url(),
+        private_key: OWNER_PRIVATE_KEY.to_string(),
+        contract_address,
+        chain_id: chain_id.as_u64(),
+        poll_interval_seconds: 1,
+        confirmations: 1,
+        receipt_timeout_seconds: 30,
+        max_attempts: 5,
+    }
+}